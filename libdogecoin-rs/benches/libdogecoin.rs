@@ -0,0 +1,95 @@
+//! Benchmarks for the hot paths of the bindings.
+//!
+//! Run with `cargo bench -p libdogecoin-rs`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libdogecoin_rs::{DogeWallet, HdWallet, Message, Mnemonic, QrCode};
+
+const PHRASE: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn keypair_generation(c: &mut Criterion) {
+    c.bench_function("keypair/new", |b| {
+        b.iter(|| DogeWallet::new(black_box(false)).unwrap())
+    });
+    c.bench_function("keypair/generate_batch_1000", |b| {
+        b.iter(|| DogeWallet::generate_batch(black_box(1000), false).unwrap())
+    });
+    c.bench_function("keypair/hd_master", |b| {
+        b.iter(|| HdWallet::new(black_box(false)).unwrap())
+    });
+}
+
+fn bip44_derivation(c: &mut Criterion) {
+    let wallet = HdWallet::new(false).unwrap();
+    let mut index = 0u32;
+    c.bench_function("bip44/derive_address", |b| {
+        b.iter(|| {
+            index = index.wrapping_add(1);
+            wallet.derive_address(0, black_box(index), false).unwrap()
+        })
+    });
+    let mut session = wallet.derivation_session().unwrap();
+    c.bench_function("bip44/session_derive_address", |b| {
+        b.iter(|| {
+            index = index.wrapping_add(1);
+            session.derive_address(0, black_box(index), false).unwrap()
+        })
+    });
+    c.bench_function("bip44/account_xpub", |b| {
+        b.iter(|| wallet.account_xpub(black_box(0)).unwrap())
+    });
+}
+
+fn mnemonic_to_seed(c: &mut Criterion) {
+    c.bench_function("mnemonic/to_seed_uncached", |b| {
+        b.iter(|| {
+            Mnemonic::from_phrase(PHRASE)
+                .to_seed(black_box(""))
+                .unwrap()
+        })
+    });
+
+    let mnemonic = Mnemonic::from_phrase(PHRASE);
+    c.bench_function("mnemonic/to_seed_cached", |b| {
+        b.iter(|| mnemonic.to_seed(black_box("")).unwrap())
+    });
+    c.bench_function("mnemonic/derive_address", |b| {
+        b.iter(|| mnemonic.derive_address(0, black_box(0), "", false).unwrap())
+    });
+}
+
+fn signing(c: &mut Criterion) {
+    let wallet = DogeWallet::new(false).unwrap();
+    c.bench_function("message/sign", |b| {
+        b.iter(|| Message::sign(wallet.private_key(), black_box("hello doge")).unwrap())
+    });
+
+    let signature = Message::sign(wallet.private_key(), "hello doge").unwrap();
+    c.bench_function("message/verify", |b| {
+        b.iter(|| Message::verify(&signature, black_box("hello doge"), wallet.address()))
+    });
+}
+
+fn qr_generation(c: &mut Criterion) {
+    let address = DogeWallet::new(false).unwrap().address().to_string();
+    c.bench_function("qr/bits", |b| {
+        b.iter(|| QrCode::to_bits(black_box(&address)).unwrap())
+    });
+    c.bench_function("qr/svg", |b| {
+        b.iter(|| QrCode::to_svg(black_box(&address), 4).unwrap())
+    });
+    c.bench_function("qr/png_bytes", |b| {
+        b.iter(|| QrCode::to_png_bytes(black_box(&address), 4).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    keypair_generation,
+    bip44_derivation,
+    mnemonic_to_seed,
+    signing,
+    qr_generation
+);
+criterion_main!(benches);
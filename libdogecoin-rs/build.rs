@@ -0,0 +1,20 @@
+use std::env;
+
+/// libdogecoin releases `ffi::sys` has wrappers for.
+const SUPPORTED_VERSIONS: &[&str] = &["0.1"];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Set by libdogecoin-sys (`links = "dogecoin"`) from the vendored sources.
+    let version = env::var("DEP_DOGECOIN_VERSION")
+        .expect("libdogecoin-sys did not report the vendored libdogecoin version");
+
+    let values = SUPPORTED_VERSIONS
+        .iter()
+        .map(|v| format!("\"{v}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!("cargo:rustc-check-cfg=cfg(libdogecoin, values({values}))");
+    println!("cargo:rustc-cfg=libdogecoin=\"{version}\"");
+}
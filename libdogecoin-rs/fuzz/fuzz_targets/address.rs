@@ -0,0 +1,18 @@
+#![no_main]
+
+use libdogecoin_rs::extkey::ExtendedKey;
+use libdogecoin_rs::{AddressUtils, OutputKind};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let diagnosed = AddressUtils::diagnose(text).is_ok();
+    // libdogecoin and the Rust decoder must agree on P2PKH addresses.
+    if AddressUtils::is_valid_p2pkh(text) {
+        assert!(diagnosed);
+    }
+    let _ = OutputKind::from_address(text);
+    let _ = ExtendedKey::parse(text);
+});
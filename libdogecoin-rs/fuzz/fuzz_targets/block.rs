@@ -0,0 +1,9 @@
+#![no_main]
+
+use libdogecoin_rs::{Block, BlockHeader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = BlockHeader::from_bytes(data);
+    let _ = Block::from_bytes(data);
+});
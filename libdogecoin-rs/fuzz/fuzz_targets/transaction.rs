@@ -0,0 +1,13 @@
+#![no_main]
+
+use libdogecoin_rs::transaction::{DecodedTransaction, Transaction};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tx) = Transaction::from_bytes(data) {
+        // Only canonical encodings parse, so they must round-trip exactly.
+        assert_eq!(tx.to_bytes(), data);
+        let decoded = DecodedTransaction::from_transaction(&tx, false);
+        assert_eq!(decoded.outputs.len(), tx.outputs.len());
+    }
+});
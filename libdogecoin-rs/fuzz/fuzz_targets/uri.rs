@@ -0,0 +1,15 @@
+#![no_main]
+
+use libdogecoin_rs::{Koinu, PaymentUri};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Some(uri) = PaymentUri::parse(text) {
+        // Whatever parses must survive a round-trip.
+        assert_eq!(PaymentUri::parse(&uri.to_string()), Some(uri));
+    }
+    let _ = text.parse::<Koinu>();
+});
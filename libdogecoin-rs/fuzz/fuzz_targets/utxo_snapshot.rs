@@ -0,0 +1,8 @@
+#![no_main]
+
+use libdogecoin_rs::UtxoSnapshot;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = UtxoSnapshot::from_bytes(data);
+});
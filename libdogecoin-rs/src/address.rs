@@ -1,7 +1,74 @@
 //! Address utilities (validation and network detection).
 
-use crate::sys;
+use crate::ffi::{checked_cstring, is_base58_byte, string_from_buf, sys, MAX_ADDRESS_LEN};
+use crate::hash::{hash160, sha256d};
 use std::ffi::CString;
+use zeroize::Zeroizing;
+
+/// Base58 version byte of mainnet P2PKH addresses ("D...").
+pub const P2PKH_VERSION_MAINNET: u8 = 0x1e;
+/// Base58 version byte of testnet P2PKH addresses ("n..." / "m...").
+pub const P2PKH_VERSION_TESTNET: u8 = 0x71;
+/// Base58 version byte of mainnet P2SH addresses ("9..." / "A...").
+pub const P2SH_VERSION_MAINNET: u8 = 0x16;
+/// Base58 version byte of testnet P2SH addresses ("2...").
+pub const P2SH_VERSION_TESTNET: u8 = 0xc4;
+/// Base58 version byte of mainnet WIF private keys ("Q...").
+pub const WIF_VERSION_MAINNET: u8 = 0x9e;
+/// Base58 version byte of testnet WIF private keys ("c...").
+pub const WIF_VERSION_TESTNET: u8 = 0xf1;
+
+/// Largest Base58Check payload handled by the helpers below.
+const BASE58_MAX_PAYLOAD: usize = 128;
+
+/// Base58 alphabet used by Dogecoin addresses and keys.
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Length of a decoded address: version byte, hash160 and checksum.
+const ADDRESS_PAYLOAD_LEN: usize = 25;
+
+/// Why an address failed validation.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("address is empty")]
+    Empty,
+
+    #[error("address is {0} characters, too long to be valid")]
+    TooLong(usize),
+
+    #[error("invalid character {character:?} at position {position}")]
+    InvalidCharacter { position: usize, character: char },
+
+    #[error("decodes to {length} bytes, expected 25")]
+    WrongLength { length: usize },
+
+    #[error("checksum mismatch (typo or truncated address)")]
+    BadChecksum,
+
+    #[error("unknown version byte 0x{0:02x}")]
+    UnknownVersion(u8),
+
+    #[error("address is for {found:?}, expected {expected:?}")]
+    WrongNetwork {
+        expected: AddressNetwork,
+        found: AddressNetwork,
+    },
+}
+
+/// Script type an address pays to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    P2pkh,
+    P2sh,
+}
+
+/// The decoded contents of a valid address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressInfo {
+    pub network: AddressNetwork,
+    pub kind: AddressKind,
+    pub hash: [u8; 20],
+}
 
 /// Address network classification based on base58 prefix.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,14 +88,14 @@ impl AddressUtils {
             return false;
         }
 
-        let c_address = match CString::new(address) {
-            Ok(s) => s,
-            Err(_) => return false,
+        let Ok(c_address) = checked_cstring(address, MAX_ADDRESS_LEN, is_base58_byte, "address")
+        else {
+            return false;
         };
 
         // `verifyP2pkhAddress` expects `len` to be a buffer size. Using a value >= 25 is fine.
         let len = address.len().saturating_add(1);
-        let result = unsafe { sys::verifyP2pkhAddress(c_address.as_ptr() as *mut i8, len) };
+        let result = unsafe { sys::verifyP2pkhAddress(c_address.as_ptr(), len) };
         result == 1
     }
 
@@ -46,18 +113,190 @@ impl AddressUtils {
         };
 
         // These libdogecoin functions expect a fixed-size char buffer, but the bindings accept a pointer.
-        let is_test = unsafe { sys::isTestnetFromB58Prefix(c_address.as_ptr() as *const i8) };
+        let is_test = unsafe { sys::isTestnetFromB58Prefix(c_address.as_ptr().cast()) };
         if is_test != 0 {
             return AddressNetwork::Testnet;
         }
 
-        let is_main = unsafe { sys::isMainnetFromB58Prefix(c_address.as_ptr() as *const i8) };
+        let is_main = unsafe { sys::isMainnetFromB58Prefix(c_address.as_ptr().cast()) };
         if is_main != 0 {
             return AddressNetwork::Mainnet;
         }
 
         AddressNetwork::Unknown
     }
+
+    /// Decode an address, explaining why it is invalid.
+    ///
+    /// Unlike [`AddressUtils::is_valid_p2pkh`], this tells apart the usual
+    /// failure modes (a mistyped character, a truncated address, a checksum
+    /// mismatch, an unknown version), which helps when answering "why was my
+    /// address rejected?".
+    pub fn diagnose(address: &str) -> Result<AddressInfo, AddressError> {
+        if address.is_empty() {
+            return Err(AddressError::Empty);
+        }
+        // Beyond this many characters the payload exceeds 25 bytes; stop
+        // before the quadratic decode.
+        if address.len() > 2 * ADDRESS_PAYLOAD_LEN {
+            return Err(AddressError::TooLong(address.len()));
+        }
+        let payload = base58_decode(address)?;
+        if payload.len() != ADDRESS_PAYLOAD_LEN {
+            return Err(AddressError::WrongLength {
+                length: payload.len(),
+            });
+        }
+        let (body, checksum) = payload.split_at(ADDRESS_PAYLOAD_LEN - 4);
+        if sha256d(body)[..4] != *checksum {
+            return Err(AddressError::BadChecksum);
+        }
+
+        let (network, kind) = match body[0] {
+            P2PKH_VERSION_MAINNET => (AddressNetwork::Mainnet, AddressKind::P2pkh),
+            P2PKH_VERSION_TESTNET => (AddressNetwork::Testnet, AddressKind::P2pkh),
+            P2SH_VERSION_MAINNET => (AddressNetwork::Mainnet, AddressKind::P2sh),
+            P2SH_VERSION_TESTNET => (AddressNetwork::Testnet, AddressKind::P2sh),
+            version => return Err(AddressError::UnknownVersion(version)),
+        };
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(&body[1..]);
+        Ok(AddressInfo {
+            network,
+            kind,
+            hash,
+        })
+    }
+
+    /// Like [`AddressUtils::diagnose`], also requiring a specific network.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    pub fn diagnose_for(address: &str, is_testnet: bool) -> Result<AddressInfo, AddressError> {
+        let info = Self::diagnose(address)?;
+        let expected = if is_testnet {
+            AddressNetwork::Testnet
+        } else {
+            AddressNetwork::Mainnet
+        };
+        if info.network != expected {
+            return Err(AddressError::WrongNetwork {
+                expected,
+                found: info.network,
+            });
+        }
+        Ok(info)
+    }
+
+    /// Get the P2PKH address of a WIF private key, on the key's network.
+    ///
+    /// # Returns
+    /// `None` if the key is not a valid mainnet or testnet WIF key.
+    pub fn address_from_wif(privkey_wif: &str) -> Option<String> {
+        // Version byte, key, and a 0x01 suffix for compressed keys.
+        let payload = Zeroizing::new(base58check_decode(privkey_wif)?);
+        let compressed = match payload.len() {
+            34 if payload[33] == 0x01 => true,
+            33 => false,
+            _ => return None,
+        };
+        let version = match payload[0] {
+            WIF_VERSION_MAINNET => P2PKH_VERSION_MAINNET,
+            WIF_VERSION_TESTNET => P2PKH_VERSION_TESTNET,
+            _ => return None,
+        };
+
+        let _ecc = crate::context::ensure_ecc_started();
+        let mut pubkey = [0u8; 65];
+        let mut len = if compressed { 33 } else { 65 };
+        unsafe {
+            sys::dogecoin_ecc_get_pubkey(
+                payload[1..33].as_ptr(),
+                pubkey.as_mut_ptr(),
+                &mut len,
+                compressed,
+            )
+        };
+        // The key is left zeroed if the secret is out of range.
+        if pubkey[0] == 0 {
+            return None;
+        }
+        let mut address = vec![version];
+        address.extend_from_slice(&hash160(&pubkey[..len]));
+        base58check_encode(&address)
+    }
+}
+
+/// Decode Base58 without checksum verification.
+fn base58_decode(encoded: &str) -> Result<Vec<u8>, AddressError> {
+    // Big-endian base-256 accumulator.
+    let mut bytes: Vec<u8> = Vec::with_capacity(encoded.len());
+    for (position, character) in encoded.chars().enumerate() {
+        let digit = BASE58_ALPHABET
+            .iter()
+            .position(|&c| c as char == character)
+            .ok_or(AddressError::InvalidCharacter {
+                position,
+                character,
+            })?;
+        let mut carry = digit as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    // Each leading '1' encodes a leading zero byte.
+    let zeros = encoded.bytes().take_while(|&c| c == b'1').count();
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes);
+    Ok(out)
+}
+
+/// Base58Check-encode a payload (version byte(s) followed by data).
+pub(crate) fn base58check_encode(payload: &[u8]) -> Option<String> {
+    if payload.len() > BASE58_MAX_PAYLOAD {
+        return None;
+    }
+    // Every 4 payload+checksum bytes expand to at most 6 characters. The
+    // payload may be a private key, so the buffer is wiped.
+    let mut out = Zeroizing::new(vec![0u8; (payload.len() + 4) * 2 + 1]);
+    let written = unsafe {
+        sys::dogecoin_base58_encode_check(
+            payload.as_ptr(),
+            payload.len(),
+            out.as_mut_ptr().cast(),
+            out.len(),
+        )
+    };
+    if written == 0 {
+        return None;
+    }
+    string_from_buf(&out).ok()
+}
+
+/// Decode a Base58Check string, verifying its checksum.
+///
+/// # Returns
+/// The payload without the checksum.
+pub(crate) fn base58check_decode(encoded: &str) -> Option<Vec<u8>> {
+    // Every payload byte takes at most two Base58 characters.
+    let max_len = 2 * (BASE58_MAX_PAYLOAD + 4);
+    let c_encoded = checked_cstring(encoded, max_len, is_base58_byte, "Base58 string").ok()?;
+    let mut out = vec![0u8; BASE58_MAX_PAYLOAD + 4];
+    // The returned length includes the trailing 4-byte checksum.
+    let len = unsafe {
+        sys::dogecoin_base58_decode_check(c_encoded.as_ptr(), out.as_mut_ptr(), out.len())
+    };
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| (4..out.len()).contains(len))?;
+    out.truncate(len - 4);
+    Some(out)
 }
 
 #[cfg(test)]
@@ -75,9 +314,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_address_from_wif() {
+        let vectors = [
+            (
+                "QPQmT9kzbSxpLSwstsa2vdmRxndVPMccZuct3Cko3wFD6koynPPQ",
+                "DGCpYmJ5YxarfGajqQnfmyrz8CW9NAe6hS",
+            ),
+            (
+                "6JK5BTTHDnkusAxUquh7Kx16f1pgxpGKUr5WEe6qx3KdGv7NcNo",
+                "D7VnXMvzjQwJFVZTXDNhDKnv1gDhjgmHF9",
+            ),
+            (
+                "cfY6uYff8D1EDQ3doALA15crcrJdg67x5aKhUuWeoeoHUEPA5NiY",
+                "nfFtGn2zUw3aYF9vsES82PTHN4tSPYApQJ",
+            ),
+            (
+                "95erphhvx6ti4GC262D3VZmNfUwHvGCzntxSV5CFTRspESdbYX4",
+                "nWYrFNfufPQ28U8eZ329TjPDFYbzkgpdYi",
+            ),
+        ];
+        for (wif, address) in vectors {
+            assert_eq!(
+                AddressUtils::address_from_wif(wif).as_deref(),
+                Some(address)
+            );
+        }
+        assert_eq!(
+            AddressUtils::address_from_wif("DGCpYmJ5YxarfGajqQnfmyrz8CW9NAe6hS"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let info = AddressUtils::diagnose("DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViY").unwrap();
+        assert_eq!(info.network, AddressNetwork::Mainnet);
+        assert_eq!(info.kind, AddressKind::P2pkh);
+        assert_eq!(
+            AddressUtils::diagnose_for("DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViY", true),
+            Err(AddressError::WrongNetwork {
+                expected: AddressNetwork::Testnet,
+                found: AddressNetwork::Mainnet,
+            })
+        );
+
+        assert_eq!(AddressUtils::diagnose(""), Err(AddressError::Empty));
+        assert_eq!(
+            AddressUtils::diagnose("DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9Vi0"),
+            Err(AddressError::InvalidCharacter {
+                position: 33,
+                character: '0',
+            })
+        );
+        assert_eq!(
+            AddressUtils::diagnose("DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViZ"),
+            Err(AddressError::BadChecksum)
+        );
+        assert!(matches!(
+            AddressUtils::diagnose("DNHC2u4MhhdhC8EubHXi9A2RLUMm"),
+            Err(AddressError::WrongLength { .. })
+        ));
+    }
+
+    #[test]
+    fn test_base58_decode() {
+        assert_eq!(base58_decode("").unwrap(), Vec::<u8>::new());
+        assert_eq!(base58_decode("11").unwrap(), vec![0, 0]);
+        assert_eq!(base58_decode("5Q").unwrap(), vec![0xff]);
+        assert_eq!(base58_decode("1LUw").unwrap(), vec![0, 0x01, 0x00, 0x00]);
+    }
+
     #[test]
     fn test_address_validation_rejects_garbage() {
         assert!(!AddressUtils::is_valid_p2pkh("not-an-address"));
+        assert!(!AddressUtils::is_valid_p2pkh(&"D".repeat(10_000)));
+        assert_eq!(
+            AddressUtils::diagnose(&"D".repeat(10_000)),
+            Err(AddressError::TooLong(10_000))
+        );
+        assert_eq!(base58check_decode(&"z".repeat(1_000)), None);
         assert_eq!(
             AddressUtils::network("not-an-address"),
             AddressNetwork::Unknown
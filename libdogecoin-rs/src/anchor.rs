@@ -0,0 +1,571 @@
+//! Chain-anchored timestamps for signing and payment records.
+//!
+//! An [`AnchoredRecord`] stores the SHA-256 digest of a record together with
+//! the [`ChainAnchor`] (height, hash and time) of the chain tip when the
+//! record was made. Because the block hash could not be known in advance,
+//! this shows the record was made *after* that block.
+//!
+//! Showing a record existed *before* a block needs the record on chain:
+//! put [`commitment`] in an `OP_RETURN` output (see
+//! [`DogeTransaction::add_data_output`]), and once it confirms keep an
+//! [`InclusionProof`]. The proof links the transaction to a block header
+//! through its merkle branch, and [`HeaderChain`] links that header to any
+//! later block.
+//!
+//! Headers are checked for linkage only, not proof of work; compare the
+//! chain's tip hash with a trusted node before relying on a proof.
+//!
+//! For plain document notarization, [`commit`] pays for and broadcasts the
+//! commitment to a document hash from a [`DogeWallet`], and [`verify`]
+//! later looks the transaction up through a [`TransactionLookup`] (such as
+//! a node over RPC) and reports its confirmations.
+//!
+//! [`DogeTransaction::add_data_output`]: crate::DogeTransaction::add_data_output
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::anchor::{AnchoredRecord, ChainAnchor};
+//! use libdogecoin_rs::BlockHeader;
+//!
+//! # let tip = BlockHeader { version: 1, prev_blockhash: [0; 32], merkle_root: [0; 32], time: 1_700_000_000, bits: 0, nonce: 0 };
+//! let record = b"invoice 42 paid";
+//! let anchored = AnchoredRecord::new(record, ChainAnchor::from_header(5_000_000, &tip));
+//! assert!(anchored.matches(record));
+//! ```
+
+use crate::address::{AddressError, AddressNetwork, AddressUtils};
+use crate::block::{BlockHeader, MerkleBranch};
+use crate::coinselect::{
+    SelectError, DUST_LIMIT, P2PKH_INPUT_SIZE, P2PKH_OUTPUT_SIZE, TX_OVERHEAD_SIZE,
+};
+use crate::hash::sha256;
+use crate::koinu::Koinu;
+use crate::offline::{OfflineError, UnsignedTransaction};
+use crate::pipeline::Broadcaster;
+use crate::script::{NullDataTemplate, OutputKind, P2pkhTemplate, ScriptTemplate};
+use crate::transaction::{Transaction, TxIn, TxOut};
+use crate::utxo::Utxo;
+use crate::wallet::DogeWallet;
+use std::collections::HashMap;
+
+/// Prefix of the `OP_RETURN` payload built by [`commitment`].
+pub const COMMITMENT_TAG: &[u8; 4] = b"DANC";
+
+/// Errors from checking an anchor or inclusion proof.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum AnchorError {
+    #[error("header chain does not cover height {0}")]
+    UnknownHeight(u32),
+
+    #[error("block at height {0} does not match the anchor")]
+    HashMismatch(u32),
+
+    #[error("header at height {0} does not follow its parent")]
+    BrokenChain(u32),
+
+    #[error("transaction does not commit to the record")]
+    NotCommitted,
+
+    #[error("merkle branch does not lead to the block's merkle root")]
+    NotInBlock,
+
+    #[error("invalid wallet address: {0}")]
+    InvalidWallet(AddressError),
+
+    #[error(transparent)]
+    Select(#[from] SelectError),
+
+    #[error(transparent)]
+    Sign(#[from] OfflineError),
+
+    #[error("broadcast failed: {0}")]
+    Broadcast(String),
+
+    #[error("transaction {0} not found")]
+    NotFound(String),
+
+    #[error("lookup failed: {0}")]
+    Lookup(String),
+}
+
+/// A block the chain had reached when a record was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainAnchor {
+    pub height: u32,
+    /// Block hash in display byte order.
+    pub block_hash: [u8; 32],
+    /// Block timestamp, seconds since the Unix epoch.
+    pub time: u32,
+}
+
+impl ChainAnchor {
+    /// Anchor to a block.
+    ///
+    /// # Arguments
+    /// * `height` - Height of the block, e.g. from `getblockcount`.
+    /// * `header` - The block's header.
+    pub fn from_header(height: u32, header: &BlockHeader) -> Self {
+        ChainAnchor {
+            height,
+            block_hash: header.hash(),
+            time: header.time,
+        }
+    }
+
+    /// Check the anchored block is in `chain`.
+    pub fn verify(&self, chain: &HeaderChain) -> Result<(), AnchorError> {
+        let header = chain
+            .header_at(self.height)
+            .ok_or(AnchorError::UnknownHeight(self.height))?;
+        if header.hash() != self.block_hash {
+            return Err(AnchorError::HashMismatch(self.height));
+        }
+        Ok(())
+    }
+}
+
+/// A record digest with the chain tip at the time it was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnchoredRecord {
+    /// SHA-256 of the record.
+    pub digest: [u8; 32],
+    pub anchor: ChainAnchor,
+}
+
+impl AnchoredRecord {
+    /// Anchor a record, e.g. a signed transaction or payment receipt.
+    ///
+    /// Only the digest is kept; store the record itself alongside.
+    pub fn new(record: &[u8], anchor: ChainAnchor) -> Self {
+        AnchoredRecord {
+            digest: sha256(record),
+            anchor,
+        }
+    }
+
+    /// Whether `record` is the anchored record.
+    pub fn matches(&self, record: &[u8]) -> bool {
+        sha256(record) == self.digest
+    }
+}
+
+/// Build the `OP_RETURN` payload committing to `record`: [`COMMITMENT_TAG`]
+/// followed by the record's SHA-256.
+pub fn commitment(record: &[u8]) -> Vec<u8> {
+    hash_commitment(&sha256(record))
+}
+
+/// Build the `OP_RETURN` payload for a document already hashed with
+/// SHA-256; the same as [`commitment`] of the document.
+pub fn hash_commitment(document_hash: &[u8; 32]) -> Vec<u8> {
+    let mut payload = COMMITMENT_TAG.to_vec();
+    payload.extend_from_slice(document_hash);
+    payload
+}
+
+/// Looks up transactions by txid, e.g. a node with `-txindex`.
+pub trait TransactionLookup {
+    /// # Returns
+    /// The transaction and its confirmations (0 while in the mempool), or
+    /// `None` if it is unknown.
+    fn transaction(&self, txid: &str) -> Result<Option<(Transaction, u32)>, String>;
+}
+
+impl<F: Fn(&str) -> Result<Option<(Transaction, u32)>, String>> TransactionLookup for F {
+    fn transaction(&self, txid: &str) -> Result<Option<(Transaction, u32)>, String> {
+        self(txid)
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl TransactionLookup for crate::rpc::DogeRpcClient {
+    fn transaction(&self, txid: &str) -> Result<Option<(Transaction, u32)>, String> {
+        use crate::rpc::RpcError;
+
+        #[derive(serde::Deserialize)]
+        struct VerboseTransaction {
+            hex: String,
+            #[serde(default)]
+            confirmations: u32,
+        }
+
+        let result: Result<VerboseTransaction, _> =
+            self.call("getrawtransaction", serde_json::json!([txid, true]));
+        match result {
+            Ok(verbose) => Transaction::from_hex(&verbose.hex)
+                .map(|tx| Some((tx, verbose.confirmations)))
+                .ok_or_else(|| "node returned an invalid transaction".to_string()),
+            // RPC_INVALID_ADDRESS_OR_KEY: no such transaction.
+            Err(RpcError::Remote(e)) if e.code == -5 => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Pay for and broadcast a commitment to a document hash.
+///
+/// Inputs are taken largest first from the wallet's outputs; any change
+/// worth keeping goes back to the wallet's address.
+///
+/// # Arguments
+/// * `document_hash` - SHA-256 of the document.
+/// * `wallet` - The paying wallet.
+/// * `utxos` - The wallet's spendable outputs; outputs paying other
+///   scripts are ignored.
+/// * `fee_rate` - Fee rate in koinu per byte.
+/// * `broadcaster` - Sends the signed transaction.
+///
+/// # Returns
+/// The txid of the anchoring transaction, for [`verify`].
+pub fn commit(
+    document_hash: &[u8; 32],
+    wallet: &DogeWallet,
+    utxos: &[Utxo],
+    fee_rate: u64,
+    broadcaster: &dyn Broadcaster,
+) -> Result<String, AnchorError> {
+    let info = AddressUtils::diagnose(wallet.address()).map_err(AnchorError::InvalidWallet)?;
+    let is_testnet = info.network == AddressNetwork::Testnet;
+    let wallet_script = P2pkhTemplate
+        .build(&OutputKind::P2pkh(info.hash))
+        .expect("P2PKH scripts build");
+    let data_script = NullDataTemplate
+        .build(&OutputKind::NullData(vec![hash_commitment(document_hash)]))
+        .expect("data scripts build");
+
+    let mut pool: Vec<&Utxo> = utxos
+        .iter()
+        .filter(|u| u.script_pubkey == wallet_script)
+        .collect();
+    pool.sort_by_key(|u| std::cmp::Reverse(u.amount));
+    // Value, script length and script.
+    let data_size = 9 + data_script.len() as u64;
+    let fee_for = |inputs: usize| {
+        fee_rate.saturating_mul(
+            TX_OVERHEAD_SIZE + inputs as u64 * P2PKH_INPUT_SIZE + data_size + P2PKH_OUTPUT_SIZE,
+        )
+    };
+    let mut total = 0u64;
+    let mut count = 0;
+    while count == 0 || total < fee_for(count) {
+        let Some(utxo) = pool.get(count) else {
+            return Err(SelectError::InsufficientFunds {
+                available: Koinu(total),
+                needed: Koinu(fee_for(count.max(1))),
+            }
+            .into());
+        };
+        total = total.saturating_add(utxo.amount);
+        count += 1;
+    }
+    let chosen = &pool[..count];
+
+    let change = total - fee_for(count);
+    let mut outputs = vec![TxOut {
+        value: 0,
+        script_pubkey: data_script,
+    }];
+    if change >= DUST_LIMIT.0 {
+        outputs.push(TxOut {
+            value: change,
+            script_pubkey: wallet_script,
+        });
+    }
+    let tx = Transaction {
+        version: 1,
+        inputs: chosen
+            .iter()
+            .map(|u| TxIn {
+                previous_output: u.outpoint,
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            })
+            .collect(),
+        outputs,
+        lock_time: 0,
+    };
+
+    let spent: Vec<Utxo> = chosen.iter().map(|&u| u.clone()).collect();
+    let unsigned = UnsignedTransaction::new(is_testnet, &tx.to_hex(), &spent)?;
+    let keys = HashMap::from([(
+        wallet.address().to_string(),
+        wallet.private_key().to_string(),
+    )]);
+    let signed = unsigned.sign(&keys)?;
+    broadcaster
+        .broadcast(&signed)
+        .map_err(AnchorError::Broadcast)?;
+    let txid = Transaction::from_hex(&signed)
+        .ok_or(OfflineError::InvalidTransaction)?
+        .txid_hex();
+    Ok(txid)
+}
+
+/// Check that a transaction commits to a document hash.
+///
+/// # Arguments
+/// * `txid` - The anchoring transaction, as returned by [`commit`].
+/// * `expected_hash` - SHA-256 of the document.
+/// * `lookup` - Where to find the transaction.
+///
+/// # Returns
+/// The transaction's confirmations; 0 means it is not yet mined.
+pub fn verify(
+    txid: &str,
+    expected_hash: &[u8; 32],
+    lookup: &dyn TransactionLookup,
+) -> Result<u32, AnchorError> {
+    let (tx, confirmations) = lookup
+        .transaction(txid)
+        .map_err(AnchorError::Lookup)?
+        .ok_or_else(|| AnchorError::NotFound(txid.to_string()))?;
+    if !tx.txid_hex().eq_ignore_ascii_case(txid) {
+        return Err(AnchorError::Lookup(format!(
+            "lookup returned another transaction for {txid}"
+        )));
+    }
+    if !commits_to(&tx, &hash_commitment(expected_hash)) {
+        return Err(AnchorError::NotCommitted);
+    }
+    Ok(confirmations)
+}
+
+/// Whether an `OP_RETURN` output of `tx` pushes `payload`.
+fn commits_to(tx: &Transaction, payload: &[u8]) -> bool {
+    tx.outputs.iter().any(|out| {
+        matches!(
+            NullDataTemplate.parse(&out.script_pubkey),
+            Some(OutputKind::NullData(pushes)) if pushes.iter().any(|p| p == payload)
+        )
+    })
+}
+
+/// Consecutive block headers starting at a known height.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderChain {
+    start_height: u32,
+    headers: Vec<BlockHeader>,
+}
+
+impl HeaderChain {
+    /// Start a chain at the block `header` at `height`.
+    pub fn new(height: u32, header: BlockHeader) -> Self {
+        HeaderChain {
+            start_height: height,
+            headers: vec![header],
+        }
+    }
+
+    /// Append the next header.
+    ///
+    /// # Returns
+    /// An error if `header` does not build on the current tip.
+    pub fn push(&mut self, header: BlockHeader) -> Result<&mut Self, AnchorError> {
+        if header.prev_blockhash != self.tip().hash() {
+            return Err(AnchorError::BrokenChain(self.tip_height() + 1));
+        }
+        self.headers.push(header);
+        Ok(self)
+    }
+
+    pub fn start_height(&self) -> u32 {
+        self.start_height
+    }
+
+    pub fn tip_height(&self) -> u32 {
+        self.start_height + (self.headers.len() - 1) as u32
+    }
+
+    pub fn tip(&self) -> &BlockHeader {
+        self.headers.last().expect("chain has a header")
+    }
+
+    /// Get the header at `height`, if the chain covers it.
+    pub fn header_at(&self, height: u32) -> Option<&BlockHeader> {
+        let offset = height.checked_sub(self.start_height)?;
+        self.headers.get(offset as usize)
+    }
+}
+
+/// Proof that a transaction committing to a record was mined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub transaction: Transaction,
+    /// Branch from the transaction to the block's merkle root, from
+    /// [`Block::merkle_branch`](crate::Block::merkle_branch).
+    pub branch: MerkleBranch,
+    /// Height of the block that mined the transaction.
+    pub height: u32,
+}
+
+impl InclusionProof {
+    /// Check that `record` existed before the block at `height`.
+    ///
+    /// # Arguments
+    /// * `record` - The record the transaction should commit to.
+    /// * `chain` - Headers covering `height` and up to the block the claim
+    ///   is made against.
+    ///
+    /// # Returns
+    /// The anchor of the mining block; the record existed before it, and so
+    /// before every later block in `chain`.
+    pub fn verify(&self, record: &[u8], chain: &HeaderChain) -> Result<ChainAnchor, AnchorError> {
+        if !commits_to(&self.transaction, &commitment(record)) {
+            return Err(AnchorError::NotCommitted);
+        }
+        let header = chain
+            .header_at(self.height)
+            .ok_or(AnchorError::UnknownHeight(self.height))?;
+        if self.branch.root(&self.transaction.txid()) != header.merkle_root {
+            return Err(AnchorError::NotInBlock);
+        }
+        Ok(ChainAnchor::from_header(self.height, header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::transaction::TxOut;
+
+    fn header(prev: &BlockHeader, merkle_root: [u8; 32]) -> BlockHeader {
+        BlockHeader {
+            prev_blockhash: prev.hash(),
+            merkle_root,
+            time: prev.time + 60,
+            ..*prev
+        }
+    }
+
+    fn genesis() -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            prev_blockhash: [0; 32],
+            merkle_root: [0; 32],
+            time: 1_700_000_000,
+            bits: 0x1e0f_fff0,
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn test_anchor_and_chain() {
+        let first = genesis();
+        let mut chain = HeaderChain::new(100, first);
+        chain.push(header(&first, [1; 32])).unwrap();
+        assert_eq!(
+            chain.push(header(&first, [2; 32])).err(),
+            Some(AnchorError::BrokenChain(102))
+        );
+        assert_eq!(chain.tip_height(), 101);
+
+        let record = AnchoredRecord::new(b"receipt", ChainAnchor::from_header(101, chain.tip()));
+        assert!(record.matches(b"receipt"));
+        assert!(!record.matches(b"receipt2"));
+        assert_eq!(record.anchor.verify(&chain), Ok(()));
+
+        let stale = ChainAnchor::from_header(100, chain.tip());
+        assert_eq!(stale.verify(&chain), Err(AnchorError::HashMismatch(100)));
+        assert_eq!(
+            ChainAnchor {
+                height: 99,
+                ..stale
+            }
+            .verify(&chain),
+            Err(AnchorError::UnknownHeight(99))
+        );
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        let record = b"signed withdrawal 7";
+        let coinbase = Transaction {
+            version: 1,
+            inputs: Vec::new(),
+            outputs: vec![TxOut {
+                value: 1,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        };
+        let mut committing = coinbase.clone();
+        committing.outputs.push(TxOut {
+            value: 0,
+            script_pubkey: NullDataTemplate
+                .build(&OutputKind::NullData(vec![commitment(record)]))
+                .unwrap(),
+        });
+        let mut block = Block {
+            header: genesis(),
+            auxpow: None,
+            transactions: vec![coinbase, committing.clone()],
+        };
+        block.header.merkle_root = block
+            .merkle_branch(0)
+            .unwrap()
+            .root(&block.transactions[0].txid());
+
+        let mut chain = HeaderChain::new(200, block.header);
+        chain.push(header(&block.header, [3; 32])).unwrap();
+        let proof = InclusionProof {
+            transaction: committing,
+            branch: block.merkle_branch(1).unwrap(),
+            height: 200,
+        };
+        let anchor = proof.verify(record, &chain).unwrap();
+        assert_eq!(anchor.block_hash, block.hash());
+
+        assert_eq!(
+            proof.verify(b"other", &chain),
+            Err(AnchorError::NotCommitted)
+        );
+        let moved = InclusionProof {
+            height: 201,
+            ..proof.clone()
+        };
+        assert_eq!(moved.verify(record, &chain), Err(AnchorError::NotInBlock));
+    }
+
+    #[test]
+    fn test_verify_document_hash() {
+        let hash = sha256(b"contract.pdf");
+        let tx = Transaction {
+            version: 1,
+            inputs: Vec::new(),
+            outputs: vec![TxOut {
+                value: 0,
+                script_pubkey: NullDataTemplate
+                    .build(&OutputKind::NullData(vec![hash_commitment(&hash)]))
+                    .unwrap(),
+            }],
+            lock_time: 0,
+        };
+        let txid = tx.txid_hex();
+        let lookup = |id: &str| Ok((id == txid).then(|| (tx.clone(), 3)));
+
+        assert_eq!(verify(&txid, &hash, &lookup), Ok(3));
+        assert_eq!(
+            verify(&txid, &sha256(b"other.pdf"), &lookup),
+            Err(AnchorError::NotCommitted)
+        );
+        assert_eq!(
+            verify("00", &hash, &lookup),
+            Err(AnchorError::NotFound("00".to_string()))
+        );
+        assert_eq!(hash_commitment(&hash), commitment(b"contract.pdf"));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_commit_needs_funds() {
+        let wallet = DogeWallet::from_parts("unused", "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC");
+        let broadcaster = |_: &str| -> Result<(), String> { panic!("nothing to send") };
+        assert!(matches!(
+            commit(&[0; 32], &wallet, &[], 1_000, &broadcaster),
+            Err(AnchorError::Select(SelectError::InsufficientFunds { .. }))
+        ));
+    }
+}
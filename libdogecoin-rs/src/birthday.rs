@@ -0,0 +1,122 @@
+//! Wallet birthdays.
+//!
+//! A wallet cannot have history older than its seed, so a restore or rescan
+//! can start at the wallet's birthday instead of the genesis block. New HD
+//! wallets record one (see [`HdWallet::birthday`](crate::HdWallet::birthday));
+//! store it next to the seed and pass it back with
+//! [`HdWallet::with_birthday`](crate::HdWallet::with_birthday) on restore.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::birthday::Birthday;
+//!
+//! // Created on 2024-01-01 at block ~5,030,000.
+//! let birthday = Birthday::from_time(1_704_067_200);
+//! let start = birthday.scan_start_height(false);
+//! assert!(start > 4_000_000 && start < 5_030_000);
+//! ```
+
+use crate::consensus::TARGET_SPACING;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Timestamp of the mainnet genesis block.
+pub const GENESIS_TIME_MAINNET: u64 = 1_386_325_540;
+
+/// Timestamp of the testnet genesis block.
+pub const GENESIS_TIME_TESTNET: u64 = 1_391_503_289;
+
+/// Blocks scanned before the birthday, to allow for clock skew and reorgs.
+pub const SCAN_MARGIN_BLOCKS: u32 = 24 * 60 * 60 / TARGET_SPACING;
+
+/// Average block spacing assumed when estimating a height from a time.
+///
+/// Deliberately slower than the real average, so the estimate stays below
+/// the true height and no wallet history is skipped.
+const CONSERVATIVE_SPACING: u64 = 70;
+
+/// When a wallet was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Birthday {
+    /// Unix time of creation.
+    pub time: u64,
+    /// Chain height at creation, if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub height: Option<u32>,
+}
+
+impl Birthday {
+    /// A birthday of right now.
+    pub fn now() -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::from_time(time)
+    }
+
+    /// A birthday known only by time.
+    pub fn from_time(time: u64) -> Self {
+        Birthday { time, height: None }
+    }
+
+    /// Record the chain height at creation, e.g. from `getblockcount`.
+    pub fn with_height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Get the height a rescan or restore should start from.
+    ///
+    /// Uses the recorded height when known, otherwise a conservative
+    /// estimate from the time; either way [`SCAN_MARGIN_BLOCKS`] earlier.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    pub fn scan_start_height(&self, is_testnet: bool) -> u32 {
+        let height = self
+            .height
+            .unwrap_or_else(|| estimate_height(self.time, is_testnet));
+        height.saturating_sub(SCAN_MARGIN_BLOCKS)
+    }
+
+    /// Whether a block can be skipped because it predates the wallet.
+    pub fn is_before(&self, block_height: u32, is_testnet: bool) -> bool {
+        block_height < self.scan_start_height(is_testnet)
+    }
+}
+
+/// Estimate a lower bound for the chain height at `time`.
+pub fn estimate_height(time: u64, is_testnet: bool) -> u32 {
+    let genesis = if is_testnet {
+        GENESIS_TIME_TESTNET
+    } else {
+        GENESIS_TIME_MAINNET
+    };
+    let blocks = time.saturating_sub(genesis) / CONSERVATIVE_SPACING;
+    u32::try_from(blocks).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_is_below_real_height() {
+        // Block 5,000,000 was mined in December 2023.
+        assert!(estimate_height(1_701_388_800, false) < 5_000_000);
+        assert_eq!(estimate_height(GENESIS_TIME_MAINNET - 1, false), 0);
+        assert_eq!(Birthday::from_time(0).scan_start_height(true), 0);
+    }
+
+    #[test]
+    fn test_recorded_height_wins() {
+        let birthday = Birthday::from_time(1_704_067_200).with_height(5_030_000);
+        assert_eq!(
+            birthday.scan_start_height(false),
+            5_030_000 - SCAN_MARGIN_BLOCKS
+        );
+        assert!(birthday.is_before(5_000_000, false));
+        assert!(!birthday.is_before(5_030_000, false));
+        assert!(Birthday::now().time > GENESIS_TIME_MAINNET);
+    }
+}
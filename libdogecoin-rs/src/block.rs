@@ -0,0 +1,386 @@
+//! Block headers and blocks, including merge-mined (AuxPoW) blocks.
+//!
+//! Parsing works directly on byte slices; use [`BlockHeader::from_bytes`]
+//! when only the 80-byte header is needed, which avoids touching the
+//! transactions at all.
+//!
+//! All hashes are stored in the byte order RPC and explorers display.
+
+use crate::encode::{self, Reader};
+use crate::hash::sha256d;
+use crate::hex;
+use crate::transaction::{DecodeError, Transaction};
+
+/// Length of a serialized block header.
+pub const HEADER_LEN: usize = 80;
+
+/// Version bit marking a block that carries an AuxPoW proof.
+pub const VERSION_AUXPOW: i32 = 0x100;
+
+/// A block header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub version: i32,
+    pub prev_blockhash: [u8; 32],
+    pub merkle_root: [u8; 32],
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl BlockHeader {
+    /// Parse a header from the start of `bytes`.
+    ///
+    /// Anything after the first 80 bytes (an AuxPoW proof, transactions) is
+    /// ignored, so this works on full serialized blocks too.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::read(&mut Reader::new(bytes))
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut out = Vec::with_capacity(HEADER_LEN);
+        self.write(&mut out);
+        out.try_into().expect("header is 80 bytes")
+    }
+
+    /// Get the block hash in display byte order.
+    ///
+    /// This is the block's identity (double SHA-256), not its scrypt
+    /// proof-of-work hash.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hash = sha256d(&self.to_bytes());
+        hash.reverse();
+        hash
+    }
+
+    pub fn hash_hex(&self) -> String {
+        hex::encode(&self.hash())
+    }
+
+    /// Whether the version says an AuxPoW proof follows the header.
+    pub fn is_auxpow(&self) -> bool {
+        self.version & VERSION_AUXPOW != 0
+    }
+
+    /// Get the merge-mining chain ID from the version.
+    pub fn chain_id(&self) -> u32 {
+        (self.version as u32) >> 16
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(BlockHeader {
+            version: reader.i32()?,
+            prev_blockhash: reader.hash()?,
+            merkle_root: reader.hash()?,
+            time: reader.u32()?,
+            bits: reader.u32()?,
+            nonce: reader.u32()?,
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.version.to_le_bytes());
+        encode::write_hash(out, &self.prev_blockhash);
+        encode::write_hash(out, &self.merkle_root);
+        out.extend_from_slice(&self.time.to_le_bytes());
+        out.extend_from_slice(&self.bits.to_le_bytes());
+        out.extend_from_slice(&self.nonce.to_le_bytes());
+    }
+}
+
+/// A merkle branch and the side mask saying where each hash goes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MerkleBranch {
+    pub hashes: Vec<[u8; 32]>,
+    pub side_mask: u32,
+}
+
+impl MerkleBranch {
+    /// Hash `leaf` up the branch.
+    ///
+    /// Bit `i` of the side mask set means the `i`th branch hash goes on the
+    /// left, as in `CheckMerkleBranch`. Both `leaf` and the result are in
+    /// display byte order, so for a transaction branch the result is
+    /// compared with [`BlockHeader::merkle_root`].
+    pub fn root(&self, leaf: &[u8; 32]) -> [u8; 32] {
+        let mut hash = *leaf;
+        hash.reverse();
+        for (i, sibling) in self.hashes.iter().enumerate() {
+            let mut sibling = *sibling;
+            sibling.reverse();
+            hash = match self.side_mask >> i & 1 {
+                1 => merkle_parent(&sibling, &hash),
+                _ => merkle_parent(&hash, &sibling),
+            };
+        }
+        hash.reverse();
+        hash
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let count = reader.count()?;
+        let mut hashes = Vec::with_capacity(count);
+        for _ in 0..count {
+            hashes.push(reader.hash()?);
+        }
+        Ok(MerkleBranch {
+            hashes,
+            side_mask: reader.u32()?,
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        encode::write_compact_size(out, self.hashes.len() as u64);
+        for hash in &self.hashes {
+            encode::write_hash(out, hash);
+        }
+        out.extend_from_slice(&self.side_mask.to_le_bytes());
+    }
+}
+
+/// Proof that a parent chain (usually Litecoin) block mined this block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuxPow {
+    /// Parent block coinbase, which commits to this block's hash.
+    pub coinbase_tx: Transaction,
+    pub parent_hash: [u8; 32],
+    /// Links the coinbase to the parent block's merkle root.
+    pub coinbase_branch: MerkleBranch,
+    /// Links this block to the merge-mining root in the coinbase.
+    pub chain_branch: MerkleBranch,
+    pub parent_header: BlockHeader,
+}
+
+impl AuxPow {
+    fn read(reader: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(AuxPow {
+            coinbase_tx: Transaction::read(reader)?,
+            parent_hash: reader.hash()?,
+            coinbase_branch: MerkleBranch::read(reader)?,
+            chain_branch: MerkleBranch::read(reader)?,
+            parent_header: BlockHeader::read(reader)?,
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        self.coinbase_tx.write(out);
+        encode::write_hash(out, &self.parent_hash);
+        self.coinbase_branch.write(out);
+        self.chain_branch.write(out);
+        self.parent_header.write(out);
+    }
+}
+
+/// A full block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub header: BlockHeader,
+    /// Present exactly when [`BlockHeader::is_auxpow`] is set.
+    pub auxpow: Option<AuxPow>,
+    pub transactions: Vec<Transaction>,
+}
+
+impl Block {
+    /// Parse a serialized block.
+    ///
+    /// # Returns
+    /// An error if the bytes are truncated or followed by extra data.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let header = BlockHeader::read(&mut reader)?;
+        let auxpow = match header.is_auxpow() {
+            true => Some(AuxPow::read(&mut reader)?),
+            false => None,
+        };
+        let count = reader.count()?;
+        let mut transactions = Vec::with_capacity(count);
+        for _ in 0..count {
+            transactions.push(Transaction::read(&mut reader)?);
+        }
+        reader.finish()?;
+        Ok(Block {
+            header,
+            auxpow,
+            transactions,
+        })
+    }
+
+    /// Parse a block from hex, as returned by `getblock <hash> 0`.
+    pub fn from_hex(raw: &str) -> Option<Self> {
+        Self::from_bytes(&hex::decode(raw)?).ok()
+    }
+
+    /// Serialize the block.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.header.write(&mut out);
+        if let Some(auxpow) = &self.auxpow {
+            auxpow.write(&mut out);
+        }
+        encode::write_compact_size(&mut out, self.transactions.len() as u64);
+        for tx in &self.transactions {
+            tx.write(&mut out);
+        }
+        out
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.to_bytes())
+    }
+
+    pub fn hash(&self) -> [u8; 32] {
+        self.header.hash()
+    }
+
+    /// Build the merkle branch proving the transaction at `index` is in
+    /// the block; `None` if there is no such transaction.
+    pub fn merkle_branch(&self, index: usize) -> Option<MerkleBranch> {
+        if index >= self.transactions.len() || index > u32::MAX as usize {
+            return None;
+        }
+        let mut level: Vec<[u8; 32]> = self
+            .transactions
+            .iter()
+            .map(|tx| {
+                let mut txid = tx.txid();
+                txid.reverse();
+                txid
+            })
+            .collect();
+        let mut branch = MerkleBranch {
+            hashes: Vec::new(),
+            side_mask: index as u32,
+        };
+        let mut position = index;
+        while level.len() > 1 {
+            if level.len() % 2 == 1 {
+                level.push(level[level.len() - 1]);
+            }
+            let mut sibling = level[position ^ 1];
+            sibling.reverse();
+            branch.hashes.push(sibling);
+            level = level
+                .chunks(2)
+                .map(|pair| merkle_parent(&pair[0], &pair[1]))
+                .collect();
+            position /= 2;
+        }
+        Some(branch)
+    }
+}
+
+/// Hash two merkle tree nodes, both in internal byte order.
+fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(left);
+    data[32..].copy_from_slice(right);
+    sha256d(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::GENESIS_COINBASE_HEX;
+
+    const GENESIS_HEADER_HEX: &str = "010000000000000000000000000000000000000000000000000000000000000000000000696ad20e2dd4365c7459b4a4a5af743d5e92c6da3229e6532cd605f6533f2a5b24a6a152f0ff0f1e67860100";
+
+    fn genesis_bytes() -> Vec<u8> {
+        let mut bytes = hex::decode(GENESIS_HEADER_HEX).unwrap();
+        bytes.push(1);
+        bytes.extend(hex::decode(GENESIS_COINBASE_HEX).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn test_parse_genesis_block() {
+        let bytes = genesis_bytes();
+        let block = Block::from_bytes(&bytes).unwrap();
+        assert_eq!(block.header.version, 1);
+        assert_eq!(block.header.prev_blockhash, [0u8; 32]);
+        assert_eq!(
+            hex::encode(&block.header.merkle_root),
+            "5b2a3f53f605d62c53e62932dac6925e3d74afa5a4b459745c36d42d0ed26a69"
+        );
+        assert_eq!(block.header.time, 1_386_325_540);
+        assert_eq!(block.header.bits, 0x1e0f_fff0);
+        assert_eq!(block.header.nonce, 99_943);
+        assert!(block.auxpow.is_none());
+        assert!(block.transactions[0].is_coinbase());
+        assert_eq!(block.to_bytes(), bytes);
+
+        assert_eq!(BlockHeader::from_bytes(&bytes), Ok(block.header));
+        assert_eq!(hex::encode(&block.header.to_bytes()), GENESIS_HEADER_HEX);
+        assert_eq!(
+            Block::from_bytes(&bytes[..HEADER_LEN]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_auxpow_roundtrip() {
+        let mut header = BlockHeader::from_bytes(&genesis_bytes()).unwrap();
+        header.version = 0x0062_0104;
+        assert!(header.is_auxpow());
+        assert_eq!(header.chain_id(), crate::consensus::AUXPOW_CHAIN_ID);
+
+        let coinbase = Transaction::from_hex(GENESIS_COINBASE_HEX).unwrap();
+        let block = Block {
+            header,
+            auxpow: Some(AuxPow {
+                coinbase_tx: coinbase.clone(),
+                parent_hash: [0x11; 32],
+                coinbase_branch: MerkleBranch {
+                    hashes: vec![[0x22; 32], [0x33; 32]],
+                    side_mask: 0,
+                },
+                chain_branch: MerkleBranch::default(),
+                parent_header: BlockHeader {
+                    version: 2,
+                    nonce: 7,
+                    ..header
+                },
+            }),
+            transactions: vec![coinbase],
+        };
+        let bytes = block.to_bytes();
+        assert_eq!(Block::from_bytes(&bytes), Ok(block));
+    }
+
+    #[test]
+    fn test_merkle_branch() {
+        let genesis = Block::from_bytes(&genesis_bytes()).unwrap();
+        let branch = genesis.merkle_branch(0).unwrap();
+        assert!(branch.hashes.is_empty());
+        assert_eq!(
+            branch.root(&genesis.transactions[0].txid()),
+            genesis.header.merkle_root
+        );
+
+        let mut block = genesis.clone();
+        for lock_time in 1..3 {
+            let mut tx = genesis.transactions[0].clone();
+            tx.lock_time = lock_time;
+            block.transactions.push(tx);
+        }
+        let root = block
+            .merkle_branch(0)
+            .unwrap()
+            .root(&block.transactions[0].txid());
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let branch = block.merkle_branch(index).unwrap();
+            assert_eq!(branch.hashes.len(), 2);
+            assert_eq!(branch.root(&tx.txid()), root);
+        }
+        assert_ne!(root, genesis.header.merkle_root);
+        assert_eq!(block.merkle_branch(3), None);
+    }
+
+    #[test]
+    fn test_genesis_hash() {
+        let header = BlockHeader::from_bytes(&genesis_bytes()).unwrap();
+        assert_eq!(
+            header.hash_hex(),
+            "1a91e3dace36e2be3bf030a65679fe821aa1d6ef92e7c9902eb318182c355691"
+        );
+    }
+}
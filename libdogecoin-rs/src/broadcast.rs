@@ -0,0 +1,637 @@
+//! A persistent broadcast queue that backs off when the network pushes back.
+//!
+//! During fee spikes nodes reject transactions with "mempool full" or
+//! "min relay fee not met", and long chains of unconfirmed transactions hit
+//! `too-long-mempool-chain`. A [`BroadcastQueue`] keeps signed transactions
+//! until they are accepted: [`process`](BroadcastQueue::process) sends what
+//! is due, delays rejected transactions with exponential backoff, asks a
+//! [`Refee`] hook for a higher-fee replacement when the fee was too low, and
+//! sends at most [`BroadcastPolicy::max_in_flight_per_wallet`] unconfirmed
+//! transactions per wallet. Each wallet's transactions go out in the order
+//! they were queued, so children never overtake their parents.
+//!
+//! The queue is saved to a [`BroadcastStore`] on every change; with a
+//! [`FileBroadcastStore`] pending transactions survive restarts.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::broadcast::{BroadcastPolicy, BroadcastQueue, FileBroadcastStore};
+//! use libdogecoin_rs::DogeRpcClient;
+//!
+//! # let signed_hex = String::new();
+//! let rpc = DogeRpcClient::new("http://127.0.0.1:22555");
+//! let store = FileBroadcastStore::open("broadcasts.txt")?;
+//! let mut queue = BroadcastQueue::new(BroadcastPolicy::default(), store)?;
+//! queue.push("hot", &signed_hex, 1_000)?;
+//! for outcome in queue.process(&rpc, BroadcastQueue::now()) {
+//!     println!("{outcome:?}");
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::pipeline::Broadcaster;
+use crate::transaction::Transaction;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Why a node refused a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// The mempool is full and the fee is below its eviction threshold.
+    MempoolFull,
+    /// The fee is below the relay or mempool minimum.
+    FeeTooLow,
+    /// Too many unconfirmed ancestors or descendants.
+    ChainTooLong,
+    /// The node already has the transaction.
+    AlreadyKnown,
+    /// Anything else; retrying will not help.
+    Other(String),
+}
+
+impl Rejection {
+    /// Classify a node's rejection message, e.g. from `sendrawtransaction`.
+    pub fn classify(reason: &str) -> Self {
+        let reason_lower = reason.to_ascii_lowercase();
+        let has = |needle: &str| reason_lower.contains(needle);
+        if has("mempool full") {
+            Rejection::MempoolFull
+        } else if has("min relay fee") || has("min fee not met") || has("insufficient fee") {
+            Rejection::FeeTooLow
+        } else if has("too-long-mempool-chain") {
+            Rejection::ChainTooLong
+        } else if has("already in block chain") || has("txn-already-in-mempool") {
+            Rejection::AlreadyKnown
+        } else {
+            Rejection::Other(reason.to_string())
+        }
+    }
+
+    /// Whether the same transaction may succeed later.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Rejection::MempoolFull | Rejection::FeeTooLow | Rejection::ChainTooLong
+        )
+    }
+}
+
+/// How the queue retries and re-fees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BroadcastPolicy {
+    /// Delay after the first rejection, in seconds; doubled on each retry.
+    pub retry_delay: u64,
+    /// Longest delay between retries, in seconds.
+    pub max_delay: u64,
+    /// Attempts before a transaction is dropped.
+    pub max_attempts: u32,
+    /// Percentage added to the fee rate when the fee is too low.
+    pub fee_bump_percent: u64,
+    /// Highest fee rate to re-fee to, in koinu per byte.
+    pub max_fee_rate: u64,
+    /// Unconfirmed transactions allowed per wallet; Dogecoin Core's default
+    /// ancestor limit is 25.
+    pub max_in_flight_per_wallet: usize,
+}
+
+impl Default for BroadcastPolicy {
+    fn default() -> Self {
+        BroadcastPolicy {
+            retry_delay: 60,
+            max_delay: 3_600,
+            max_attempts: 50,
+            fee_bump_percent: 25,
+            max_fee_rate: 10_000,
+            max_in_flight_per_wallet: 25,
+        }
+    }
+}
+
+/// Builds a replacement for a transaction at a higher fee rate.
+pub trait Refee: Send {
+    /// # Returns
+    /// The signed replacement hex, or `None` to keep the original.
+    fn refee(&self, item: &BroadcastItem, fee_rate: u64) -> Option<String>;
+}
+
+impl<F: Fn(&BroadcastItem, u64) -> Option<String> + Send> Refee for F {
+    fn refee(&self, item: &BroadcastItem, fee_rate: u64) -> Option<String> {
+        self(item, fee_rate)
+    }
+}
+
+/// A queued transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastItem {
+    pub wallet: String,
+    pub txid: String,
+    pub raw_hex: String,
+    /// Fee rate in koinu per byte.
+    pub fee_rate: u64,
+    pub attempts: u32,
+    /// Unix time of the next attempt.
+    pub next_attempt: u64,
+    /// Accepted by the node and waiting to confirm.
+    pub in_flight: bool,
+}
+
+/// What [`BroadcastQueue::process`] did with a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastOutcome {
+    Sent {
+        txid: String,
+    },
+    /// Rejected for now; retried at `retry_at`.
+    Deferred {
+        txid: String,
+        reason: Rejection,
+        retry_at: u64,
+    },
+    /// Replaced by a higher-fee transaction, sent on the next call.
+    Refeed {
+        old_txid: String,
+        new_txid: String,
+    },
+    /// Removed from the queue.
+    Dropped {
+        txid: String,
+        reason: Rejection,
+    },
+}
+
+/// Storage for queued transactions.
+pub trait BroadcastStore: Send {
+    fn load(&self) -> io::Result<Vec<BroadcastItem>>;
+
+    /// Replace the stored queue.
+    fn save(&mut self, items: &[BroadcastItem]) -> io::Result<()>;
+}
+
+/// Volatile store; the queue is lost when it is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBroadcastStore {
+    items: Vec<BroadcastItem>,
+}
+
+impl MemoryBroadcastStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BroadcastStore for MemoryBroadcastStore {
+    fn load(&self) -> io::Result<Vec<BroadcastItem>> {
+        Ok(self.items.clone())
+    }
+
+    fn save(&mut self, items: &[BroadcastItem]) -> io::Result<()> {
+        self.items = items.to_vec();
+        Ok(())
+    }
+}
+
+/// Store backed by a small text file.
+///
+/// Each line holds `<txid> <fee_rate> <attempts> <next_attempt>
+/// <queued|in-flight> <raw_hex> <wallet>`. The file is rewritten atomically
+/// (write to a temporary file, then rename) on every save.
+#[derive(Debug, Clone)]
+pub struct FileBroadcastStore {
+    path: PathBuf,
+}
+
+impl FileBroadcastStore {
+    /// Open a store; the file is created on the first save.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(FileBroadcastStore {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Get the backing file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl BroadcastStore for FileBroadcastStore {
+    fn load(&self) -> io::Result<Vec<BroadcastItem>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut items = Vec::new();
+        for (lineno, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            items.push(parse_line(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{}:{}: malformed broadcast entry",
+                        self.path.display(),
+                        lineno + 1
+                    ),
+                )
+            })?);
+        }
+        Ok(items)
+    }
+
+    fn save(&mut self, items: &[BroadcastItem]) -> io::Result<()> {
+        let mut contents = String::new();
+        for item in items {
+            contents.push_str(&format!(
+                "{} {} {} {} {} {} {}\n",
+                item.txid,
+                item.fee_rate,
+                item.attempts,
+                item.next_attempt,
+                if item.in_flight {
+                    "in-flight"
+                } else {
+                    "queued"
+                },
+                item.raw_hex,
+                item.wallet,
+            ));
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+fn parse_line(line: &str) -> Option<BroadcastItem> {
+    let mut fields = line.splitn(7, ' ');
+    Some(BroadcastItem {
+        txid: fields.next()?.to_string(),
+        fee_rate: fields.next()?.parse().ok()?,
+        attempts: fields.next()?.parse().ok()?,
+        next_attempt: fields.next()?.parse().ok()?,
+        in_flight: match fields.next()? {
+            "in-flight" => true,
+            "queued" => false,
+            _ => return None,
+        },
+        raw_hex: fields.next()?.to_string(),
+        wallet: fields.next()?.to_string(),
+    })
+}
+
+/// Signed transactions waiting to be accepted and confirmed.
+pub struct BroadcastQueue {
+    policy: BroadcastPolicy,
+    store: Box<dyn BroadcastStore>,
+    refee: Option<Box<dyn Refee>>,
+    items: Vec<BroadcastItem>,
+}
+
+impl BroadcastQueue {
+    /// Create a queue, loading the transactions left in `store`.
+    pub fn new(policy: BroadcastPolicy, store: impl BroadcastStore + 'static) -> io::Result<Self> {
+        Ok(BroadcastQueue {
+            policy,
+            items: store.load()?,
+            store: Box::new(store),
+            refee: None,
+        })
+    }
+
+    /// Replace transactions rejected for a low fee using `refee`.
+    pub fn with_refee(mut self, refee: impl Refee + 'static) -> Self {
+        self.refee = Some(Box::new(refee));
+        self
+    }
+
+    /// Get the current Unix time, for [`process`](Self::process).
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+
+    /// Queue a signed transaction, to be sent on the next
+    /// [`process`](Self::process).
+    ///
+    /// # Arguments
+    /// * `wallet` - Wallet the transaction spends from, for the in-flight cap.
+    /// * `raw_hex` - The signed transaction.
+    /// * `fee_rate` - Its fee rate in koinu per byte.
+    ///
+    /// # Returns
+    /// The txid; [`io::ErrorKind::InvalidData`] if the transaction does not
+    /// parse.
+    pub fn push(&mut self, wallet: &str, raw_hex: &str, fee_rate: u64) -> io::Result<String> {
+        let txid = txid(raw_hex)?;
+        self.items.push(BroadcastItem {
+            wallet: wallet.to_string(),
+            txid: txid.clone(),
+            raw_hex: raw_hex.to_string(),
+            fee_rate,
+            attempts: 0,
+            next_attempt: 0,
+            in_flight: false,
+        });
+        self.save()?;
+        Ok(txid)
+    }
+
+    /// Get the queued and in-flight transactions, oldest first.
+    pub fn items(&self) -> &[BroadcastItem] {
+        &self.items
+    }
+
+    /// Get the number of accepted, unconfirmed transactions of a wallet.
+    pub fn in_flight(&self, wallet: &str) -> usize {
+        self.items
+            .iter()
+            .filter(|i| i.in_flight && i.wallet == wallet)
+            .count()
+    }
+
+    /// Forget a transaction once it confirms, freeing its wallet's slot.
+    ///
+    /// # Returns
+    /// Whether the transaction was queued.
+    pub fn mark_confirmed(&mut self, txid: &str) -> io::Result<bool> {
+        let before = self.items.len();
+        self.items.retain(|i| i.txid != txid);
+        if self.items.len() == before {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Send every transaction that is due and within its wallet's cap.
+    ///
+    /// # Arguments
+    /// * `broadcaster` - Where to send, e.g. a [`DogeRpcClient`](crate::DogeRpcClient).
+    /// * `now` - Current Unix time; see [`now`](Self::now).
+    ///
+    /// # Returns
+    /// What happened to each transaction attempted. If saving the queue
+    /// fails, the outcomes are still returned and the queue is saved on the
+    /// next change.
+    pub fn process(&mut self, broadcaster: &dyn Broadcaster, now: u64) -> Vec<BroadcastOutcome> {
+        let mut outcomes = Vec::new();
+        let mut in_flight: HashMap<String, usize> = HashMap::new();
+        for item in self.items.iter().filter(|i| i.in_flight) {
+            *in_flight.entry(item.wallet.clone()).or_default() += 1;
+        }
+        // Wallets with an earlier transaction still waiting.
+        let mut blocked: Vec<String> = Vec::new();
+
+        let mut index = 0;
+        while index < self.items.len() {
+            let item = &self.items[index];
+            let wallet = item.wallet.clone();
+            let slots = in_flight.entry(wallet.clone()).or_default();
+            if item.in_flight {
+                index += 1;
+                continue;
+            }
+            if item.next_attempt > now
+                || *slots >= self.policy.max_in_flight_per_wallet
+                || blocked.contains(&wallet)
+            {
+                blocked.push(wallet);
+                index += 1;
+                continue;
+            }
+
+            let reason = match broadcaster.broadcast(&item.raw_hex) {
+                Ok(()) => None,
+                Err(reason) => Some(Rejection::classify(&reason)),
+            };
+            let item = &mut self.items[index];
+            match reason {
+                None | Some(Rejection::AlreadyKnown) => {
+                    item.in_flight = true;
+                    *slots += 1;
+                    outcomes.push(BroadcastOutcome::Sent {
+                        txid: item.txid.clone(),
+                    });
+                }
+                Some(reason) if reason.is_transient() => {
+                    item.attempts += 1;
+                    if item.attempts >= self.policy.max_attempts {
+                        let item = self.items.remove(index);
+                        outcomes.push(BroadcastOutcome::Dropped {
+                            txid: item.txid,
+                            reason,
+                        });
+                        continue;
+                    }
+                    blocked.push(wallet);
+                    let refeed = (reason == Rejection::FeeTooLow)
+                        .then(|| self.replacement(index))
+                        .flatten();
+                    let delay = self.backoff(self.items[index].attempts);
+                    let item = &mut self.items[index];
+                    outcomes.push(match refeed {
+                        Some((raw_hex, new_txid, fee_rate)) => {
+                            let old_txid = std::mem::replace(&mut item.txid, new_txid.clone());
+                            item.raw_hex = raw_hex;
+                            item.fee_rate = fee_rate;
+                            item.next_attempt = now;
+                            BroadcastOutcome::Refeed { old_txid, new_txid }
+                        }
+                        None => {
+                            item.next_attempt = now.saturating_add(delay);
+                            BroadcastOutcome::Deferred {
+                                txid: item.txid.clone(),
+                                reason,
+                                retry_at: item.next_attempt,
+                            }
+                        }
+                    });
+                }
+                Some(reason) => {
+                    let item = self.items.remove(index);
+                    outcomes.push(BroadcastOutcome::Dropped {
+                        txid: item.txid,
+                        reason,
+                    });
+                    continue;
+                }
+            }
+            index += 1;
+        }
+        if !outcomes.is_empty() {
+            let _ = self.save();
+        }
+        outcomes
+    }
+
+    /// Ask the [`Refee`] hook for a replacement at a bumped fee rate.
+    fn replacement(&self, index: usize) -> Option<(String, String, u64)> {
+        let item = &self.items[index];
+        let bump = (item.fee_rate * self.policy.fee_bump_percent / 100).max(1);
+        let fee_rate = item.fee_rate.saturating_add(bump);
+        if fee_rate > self.policy.max_fee_rate {
+            return None;
+        }
+        let raw_hex = self.refee.as_ref()?.refee(item, fee_rate)?;
+        let txid = txid(&raw_hex).ok()?;
+        Some((raw_hex, txid, fee_rate))
+    }
+
+    /// Delay before retry number `attempts`.
+    fn backoff(&self, attempts: u32) -> u64 {
+        let factor = 1u64
+            .checked_shl(attempts.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        self.policy
+            .retry_delay
+            .saturating_mul(factor)
+            .min(self.policy.max_delay)
+    }
+
+    fn save(&mut self) -> io::Result<()> {
+        self.store.save(&self.items)
+    }
+}
+
+fn txid(raw_hex: &str) -> io::Result<String> {
+    Transaction::from_hex(raw_hex)
+        .map(|tx| tx.txid_hex())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid raw transaction"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{TxIn, TxOut};
+    use crate::utxo::OutPoint;
+    use std::sync::Mutex;
+
+    fn raw(id: u8, value: u64) -> String {
+        Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: [id; 32],
+                    vout: 0,
+                },
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![TxOut {
+                value,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        }
+        .to_hex()
+    }
+
+    #[test]
+    fn test_classify_rejections() {
+        assert_eq!(
+            Rejection::classify("66: mempool full"),
+            Rejection::MempoolFull
+        );
+        assert_eq!(
+            Rejection::classify("66: min relay fee not met"),
+            Rejection::FeeTooLow
+        );
+        assert_eq!(
+            Rejection::classify("64: too-long-mempool-chain"),
+            Rejection::ChainTooLong
+        );
+        assert!(!Rejection::classify("16: bad-txns-inputs-spent").is_transient());
+    }
+
+    #[test]
+    fn test_backoff_refee_and_cap() {
+        let policy = BroadcastPolicy {
+            max_in_flight_per_wallet: 1,
+            ..BroadcastPolicy::default()
+        };
+        let refee = |item: &BroadcastItem, fee_rate: u64| {
+            assert_eq!((item.fee_rate, fee_rate), (1_000, 1_250));
+            Some(raw(1, 90))
+        };
+        let mut queue = BroadcastQueue::new(policy, MemoryBroadcastStore::new())
+            .unwrap()
+            .with_refee(refee);
+        let first = queue.push("hot", &raw(1, 100), 1_000).unwrap();
+        let second = queue.push("hot", &raw(2, 100), 1_000).unwrap();
+        queue.push("cold", &raw(3, 100), 1_000).unwrap();
+
+        let replies = Mutex::new(vec![
+            Err("mempool full".to_string()),
+            Err("66: min relay fee not met".to_string()),
+        ]);
+        let broadcaster = |_: &str| replies.lock().unwrap().pop().unwrap_or(Ok(()));
+        let outcomes = queue.process(&broadcaster, 100);
+        let refeed = txid(&raw(1, 90)).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![
+                BroadcastOutcome::Refeed {
+                    old_txid: first,
+                    new_txid: refeed.clone()
+                },
+                BroadcastOutcome::Deferred {
+                    txid: queue.items()[2].txid.clone(),
+                    reason: Rejection::MempoolFull,
+                    retry_at: 160
+                },
+            ]
+        );
+
+        // The replacement goes out; the second "hot" transaction waits for
+        // the first to confirm.
+        let outcomes = queue.process(&broadcaster, 100);
+        assert_eq!(
+            outcomes,
+            vec![BroadcastOutcome::Sent {
+                txid: refeed.clone()
+            }]
+        );
+        assert_eq!(queue.in_flight("hot"), 1);
+        assert!(queue.mark_confirmed(&refeed).unwrap());
+        assert_eq!(
+            queue.process(&broadcaster, 200),
+            vec![
+                BroadcastOutcome::Sent { txid: second },
+                BroadcastOutcome::Sent {
+                    txid: queue.items()[1].txid.clone()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_store_persists_across_reopen() {
+        let path = std::env::temp_dir().join(format!(
+            "libdogecoin-rs-broadcast-{}.txt",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let store = FileBroadcastStore::open(&path).unwrap();
+        let mut queue = BroadcastQueue::new(BroadcastPolicy::default(), store).unwrap();
+        queue.push("hot wallet", &raw(1, 100), 1_000).unwrap();
+        assert!(queue.push("hot wallet", "00", 1_000).is_err());
+
+        let reopened = BroadcastQueue::new(
+            BroadcastPolicy::default(),
+            FileBroadcastStore::open(&path).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reopened.items(), queue.items());
+        fs::remove_file(&path).unwrap();
+    }
+}
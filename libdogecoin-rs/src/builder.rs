@@ -0,0 +1,456 @@
+//! One-call transaction building and signing.
+//!
+//! [`TransactionBuilder`] sits on top of [`DogeTransaction`]: give it the
+//! wallet's [`Utxo`]s, the recipients with [`Koinu`] amounts, a fee rate and
+//! a change address, and [`build_and_sign`](TransactionBuilder::build_and_sign)
+//! selects inputs, adds change and signs every input. Each input is checked
+//! as it is added rather than when libdogecoin fails later.
+//!
+//! For "send max" withdrawals,
+//! [`with_subtract_fee_from`](TransactionBuilder::with_subtract_fee_from)
+//! takes the fee out of one recipient's amount instead of adding it on top,
+//! so the whole balance can be sent without working out the fee first.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::builder::TransactionBuilder;
+//! use libdogecoin_rs::{Koinu, Utxo};
+//! use std::collections::HashMap;
+//!
+//! # let (utxos, keys): (Vec<Utxo>, HashMap<String, String>) = unimplemented!();
+//! let mut builder = TransactionBuilder::new(false, 1_000);
+//! builder
+//!     .add_recipient("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", Koinu::from_doge(10).unwrap())?
+//!     .set_change_address("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas")?;
+//! for utxo in utxos {
+//!     builder.add_utxo(utxo)?;
+//! }
+//! let signed_hex = builder.build_and_sign(&keys)?;
+//! # Ok::<(), libdogecoin_rs::builder::BuildError>(())
+//! ```
+
+use crate::address::{AddressError, AddressUtils};
+use crate::coinselect::{
+    ChangePolicy, CoinSelector, SelectError, Selection, DUST_LIMIT, P2PKH_INPUT_SIZE,
+    P2PKH_OUTPUT_SIZE, TX_OVERHEAD_SIZE,
+};
+use crate::hex;
+use crate::koinu::Koinu;
+use crate::script::{P2pkhTemplate, ScriptTemplate};
+use crate::transaction::{DogeTransaction, TransactionError};
+use crate::utxo::{OutPoint, Utxo};
+use std::collections::HashMap;
+
+/// Errors from building a transaction.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    #[error("invalid address {address:?}: {source}")]
+    InvalidAddress {
+        address: String,
+        source: AddressError,
+    },
+
+    #[error("output of {0} DOGE is below the dust limit")]
+    DustOutput(Koinu),
+
+    #[error("input {0:?} is spent twice")]
+    DuplicateInput(OutPoint),
+
+    #[error("input {0:?} does not pay to a P2PKH address")]
+    UnsupportedInput(OutPoint),
+
+    #[error("no recipients")]
+    NoRecipients,
+
+    #[error("no inputs worth spending")]
+    NoInputs,
+
+    #[error("no recipient {0} to take the fee from")]
+    NoSuchRecipient(usize),
+
+    #[error("fee of {fee} DOGE exceeds the output of {amount} DOGE it is taken from")]
+    FeeExceedsOutput { amount: Koinu, fee: Koinu },
+
+    #[error("change is due but no change address is set")]
+    NoChangeAddress,
+
+    #[error("total amount overflows")]
+    Overflow,
+
+    #[error("no private key for {0}")]
+    MissingKey(String),
+
+    #[error(transparent)]
+    Select(#[from] SelectError),
+
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+
+    #[error("libdogecoin failed to {0}")]
+    Ffi(&'static str),
+}
+
+/// A wallet input with the address whose key signs it.
+#[derive(Debug, Clone)]
+struct Input {
+    utxo: Utxo,
+    address: String,
+}
+
+/// Inputs, recipient amounts and change chosen for a transaction.
+#[derive(Debug)]
+struct Plan {
+    selection: Selection,
+    amounts: Vec<Koinu>,
+    change_address: Option<String>,
+}
+
+/// Builds and signs a P2PKH transaction.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    is_testnet: bool,
+    fee_rate: u64,
+    inputs: Vec<Input>,
+    recipients: Vec<(String, Koinu)>,
+    change_address: Option<String>,
+    change_policy: ChangePolicy,
+    subtract_fee_from: Option<usize>,
+}
+
+impl TransactionBuilder {
+    /// Create a builder.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    /// * `fee_rate` - Fee rate in koinu per byte.
+    pub fn new(is_testnet: bool, fee_rate: u64) -> Self {
+        Self {
+            is_testnet,
+            fee_rate,
+            inputs: Vec::new(),
+            recipients: Vec::new(),
+            change_address: None,
+            change_policy: ChangePolicy::default(),
+            subtract_fee_from: None,
+        }
+    }
+
+    /// Set how change is returned.
+    pub fn with_change_policy(mut self, change_policy: ChangePolicy) -> Self {
+        self.change_policy = change_policy;
+        self
+    }
+
+    /// Take the fee out of the recipient at `index` (in the order they were
+    /// added) instead of adding it to the amount spent.
+    ///
+    /// Inputs are then chosen to cover the recipients' amounts alone, and
+    /// that recipient receives its amount less the fee.
+    pub fn with_subtract_fee_from(mut self, index: usize) -> Self {
+        self.subtract_fee_from = Some(index);
+        self
+    }
+
+    /// Offer an output for spending. Only the inputs needed are spent.
+    ///
+    /// # Returns
+    /// An error if the output was already added or is not P2PKH.
+    pub fn add_utxo(&mut self, utxo: Utxo) -> Result<&mut Self, BuildError> {
+        if self.inputs.iter().any(|i| i.utxo.outpoint == utxo.outpoint) {
+            return Err(BuildError::DuplicateInput(utxo.outpoint));
+        }
+        let address = P2pkhTemplate
+            .parse(&utxo.script_pubkey)
+            .and_then(|kind| kind.address(self.is_testnet))
+            .ok_or(BuildError::UnsupportedInput(utxo.outpoint))?;
+        self.inputs.push(Input { utxo, address });
+        Ok(self)
+    }
+
+    /// Pay `amount` to `address`.
+    ///
+    /// # Returns
+    /// An error if the address is invalid or on the wrong network, or the
+    /// amount is below the dust limit.
+    pub fn add_recipient(&mut self, address: &str, amount: Koinu) -> Result<&mut Self, BuildError> {
+        self.check_address(address)?;
+        if amount < DUST_LIMIT {
+            return Err(BuildError::DustOutput(amount));
+        }
+        self.recipients.push((address.to_string(), amount));
+        Ok(self)
+    }
+
+    /// Set the address that receives change.
+    pub fn set_change_address(&mut self, address: &str) -> Result<&mut Self, BuildError> {
+        self.check_address(address)?;
+        self.change_address = Some(address.to_string());
+        Ok(self)
+    }
+
+    /// Select inputs and add change, leaving the transaction unsigned.
+    ///
+    /// Inputs are added with their amount and scriptPubKey, so the result
+    /// knows its fee and checks each signature as it is made.
+    pub fn build(&self) -> Result<DogeTransaction, BuildError> {
+        let plan = self.plan()?;
+        self.assemble(&plan)
+    }
+
+    /// Select inputs, add change, and sign.
+    ///
+    /// # Arguments
+    /// * `keys` - WIF private keys by the P2PKH address they control. Only
+    ///   the keys for the selected inputs are used.
+    ///
+    /// # Returns
+    /// The signed transaction hex.
+    pub fn build_and_sign(&self, keys: &HashMap<String, String>) -> Result<String, BuildError> {
+        let plan = self.plan()?;
+
+        // Resolve every key before libdogecoin sees the transaction.
+        let mut signing = Vec::with_capacity(plan.selection.inputs.len());
+        for utxo in &plan.selection.inputs {
+            let input = self
+                .inputs
+                .iter()
+                .find(|i| i.utxo.outpoint == utxo.outpoint)
+                .expect("selected from our inputs");
+            let key = keys
+                .get(&input.address)
+                .ok_or_else(|| BuildError::MissingKey(input.address.clone()))?;
+            signing.push(key);
+        }
+
+        let mut tx = self.assemble(&plan)?;
+        for (index, key) in signing.iter().enumerate() {
+            if !tx.sign_with_privkey(index as i32, key) {
+                return Err(BuildError::Ffi("sign an input"));
+            }
+        }
+        tx.get_raw()
+            .ok_or(BuildError::Ffi("serialize the transaction"))
+    }
+
+    /// Choose the inputs, recipient amounts and change.
+    fn plan(&self) -> Result<Plan, BuildError> {
+        if self.recipients.is_empty() {
+            return Err(BuildError::NoRecipients);
+        }
+        if let Some(index) = self
+            .subtract_fee_from
+            .filter(|&i| i >= self.recipients.len())
+        {
+            return Err(BuildError::NoSuchRecipient(index));
+        }
+        let target = self
+            .recipients
+            .iter()
+            .try_fold(Koinu::ZERO, |total, (_, amount)| total.checked_add(*amount))
+            .ok_or(BuildError::Overflow)?;
+
+        let utxos: Vec<Utxo> = self.inputs.iter().map(|i| i.utxo.clone()).collect();
+        let mut amounts: Vec<Koinu> = self.recipients.iter().map(|(_, v)| *v).collect();
+        // When the fee comes out of a recipient, select as if fees were
+        // free, then charge the real fee for what was chosen.
+        let fee_rate = match self.subtract_fee_from {
+            Some(_) => 0,
+            None => self.fee_rate,
+        };
+        let mut selection = CoinSelector::new(fee_rate)
+            .with_outputs(self.recipients.len() as u64)
+            .with_change_policy(self.change_policy)
+            .select(&utxos, target)?;
+        if let Some(index) = self.subtract_fee_from {
+            let amount = &mut amounts[index];
+            let outputs = (self.recipients.len() + selection.change.len()) as u64;
+            let fee = Koinu(self.fee_rate.saturating_mul(
+                TX_OVERHEAD_SIZE
+                    + selection.inputs.len() as u64 * P2PKH_INPUT_SIZE
+                    + outputs * P2PKH_OUTPUT_SIZE,
+            ));
+            let reduced = amount
+                .checked_sub(fee)
+                .ok_or(BuildError::FeeExceedsOutput {
+                    amount: *amount,
+                    fee,
+                })?;
+            if reduced < DUST_LIMIT {
+                return Err(BuildError::DustOutput(reduced));
+            }
+            *amount = reduced;
+            selection.fee = selection.fee.checked_add(fee).ok_or(BuildError::Overflow)?;
+        }
+        let change_address = match (&self.change_address, selection.change.is_empty()) {
+            (_, true) => None,
+            (Some(address), false) => Some(address.clone()),
+            (None, false) => return Err(BuildError::NoChangeAddress),
+        };
+        Ok(Plan {
+            selection,
+            amounts,
+            change_address,
+        })
+    }
+
+    /// Create the unsigned transaction for `plan`.
+    fn assemble(&self, plan: &Plan) -> Result<DogeTransaction, BuildError> {
+        let mut tx = DogeTransaction::try_new()?;
+        for utxo in &plan.selection.inputs {
+            let vout = i32::try_from(utxo.outpoint.vout)
+                .map_err(|_| BuildError::UnsupportedInput(utxo.outpoint))?;
+            if !tx.add_utxo_with_details(
+                &utxo.outpoint.txid_hex(),
+                vout,
+                Koinu(utxo.amount),
+                &hex::encode(&utxo.script_pubkey),
+            ) {
+                return Err(BuildError::Ffi("add an input"));
+            }
+        }
+        let change = plan
+            .change_address
+            .iter()
+            .flat_map(|address| plan.selection.change.iter().map(move |&c| (address, c)));
+        let recipients = self
+            .recipients
+            .iter()
+            .map(|(a, _)| a)
+            .zip(plan.amounts.iter().copied());
+        for (address, amount) in recipients.chain(change) {
+            if !tx.add_output(address, amount) {
+                return Err(BuildError::Ffi("add an output"));
+            }
+        }
+        Ok(tx)
+    }
+
+    fn check_address(&self, address: &str) -> Result<(), BuildError> {
+        AddressUtils::diagnose_for(address, self.is_testnet)
+            .map(|_| ())
+            .map_err(|source| self.invalid(address, source))
+    }
+
+    fn invalid(&self, address: &str, source: AddressError) -> BuildError {
+        BuildError::InvalidAddress {
+            address: address.to_string(),
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::COIN;
+    use crate::script::OutputKind;
+
+    const RECIPIENT: &str = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+    const CHANGE: &str = "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas";
+
+    fn utxo(id: u8, address: &str, amount: u64) -> Utxo {
+        let kind = OutputKind::from_address(address).unwrap();
+        Utxo {
+            outpoint: OutPoint {
+                txid: [id; 32],
+                vout: 0,
+            },
+            script_pubkey: P2pkhTemplate.build(&kind).unwrap(),
+            amount,
+            height: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_validation_at_each_step() {
+        let mut builder = TransactionBuilder::new(false, 1_000);
+        assert!(matches!(
+            builder.add_recipient("DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViZ", Koinu::ONE_DOGE),
+            Err(BuildError::InvalidAddress { .. })
+        ));
+        assert_eq!(
+            builder.add_recipient(RECIPIENT, Koinu(1)).err(),
+            Some(BuildError::DustOutput(Koinu(1)))
+        );
+        assert!(builder.set_change_address(RECIPIENT).is_ok());
+        assert!(TransactionBuilder::new(true, 1_000)
+            .set_change_address(CHANGE)
+            .is_err());
+
+        builder.add_utxo(utxo(1, CHANGE, COIN)).unwrap();
+        assert_eq!(
+            builder.add_utxo(utxo(1, CHANGE, COIN)).err(),
+            Some(BuildError::DuplicateInput(OutPoint {
+                txid: [1; 32],
+                vout: 0
+            }))
+        );
+        let mut bare = utxo(2, CHANGE, COIN);
+        bare.script_pubkey = vec![0x51];
+        assert!(matches!(
+            builder.add_utxo(bare),
+            Err(BuildError::UnsupportedInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_build_fails_before_ffi() {
+        let keys = HashMap::new();
+        let mut builder = TransactionBuilder::new(false, 1_000);
+        builder.add_utxo(utxo(1, CHANGE, 5 * COIN)).unwrap();
+        assert_eq!(builder.build_and_sign(&keys), Err(BuildError::NoRecipients));
+
+        builder.add_recipient(RECIPIENT, Koinu(10 * COIN)).unwrap();
+        assert!(matches!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::Select(SelectError::InsufficientFunds { .. }))
+        ));
+
+        builder.add_utxo(utxo(2, CHANGE, 20 * COIN)).unwrap();
+        assert_eq!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::NoChangeAddress)
+        );
+        builder.set_change_address(CHANGE).unwrap();
+        assert_eq!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::MissingKey(CHANGE.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_subtract_fee_from_recipient() {
+        let keys = HashMap::new();
+        let mut builder = TransactionBuilder::new(false, 1_000).with_subtract_fee_from(1);
+        builder.add_utxo(utxo(1, CHANGE, 10 * COIN)).unwrap();
+        builder.add_recipient(RECIPIENT, Koinu(10 * COIN)).unwrap();
+        assert_eq!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::NoSuchRecipient(1))
+        );
+
+        // The whole balance is covered once the fee comes out of it.
+        let mut builder = builder.with_subtract_fee_from(0);
+        assert_eq!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::MissingKey(CHANGE.to_string()))
+        );
+
+        let mut tiny = TransactionBuilder::new(false, 100_000).with_subtract_fee_from(0);
+        tiny.add_utxo(utxo(1, CHANGE, COIN)).unwrap();
+        tiny.add_recipient(RECIPIENT, Koinu(COIN / 10)).unwrap();
+        tiny.set_change_address(CHANGE).unwrap();
+        assert_eq!(
+            tiny.build_and_sign(&keys),
+            Err(BuildError::FeeExceedsOutput {
+                amount: Koinu(COIN / 10),
+                fee: Koinu(100_000 * (10 + 148 + 2 * 34)),
+            })
+        );
+        builder.add_recipient(CHANGE, Koinu::ONE_DOGE).unwrap();
+        assert!(matches!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::Select(SelectError::InsufficientFunds { .. }))
+        ));
+    }
+}
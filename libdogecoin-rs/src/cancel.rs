@@ -0,0 +1,123 @@
+//! Cooperative cancellation and timeouts for long-running loops.
+//!
+//! Batch key generation and range derivation accept a [`CancellationToken`].
+//! They check it between items and, when stopped early, return the work done
+//! so far inside [`Stopped`] so callers can keep or resume from it.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::cancel::CancellationToken;
+//! use libdogecoin_rs::DogeWallet;
+//! use std::time::Duration;
+//!
+//! let token = CancellationToken::new().with_timeout(Duration::from_secs(5));
+//! let wallets = match DogeWallet::generate_batch_cancellable(1_000_000, false, &token, None) {
+//!     Ok(all) => all,
+//!     Err(stopped) => stopped.partial,
+//! };
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Why an operation stopped before finishing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// [`CancellationToken::cancel`] was called.
+    Cancelled,
+    /// The token's deadline passed.
+    TimedOut,
+    /// An item could not be produced (e.g. libdogecoin reported an error).
+    Failed,
+}
+
+/// An operation that ended early, with the results gathered before it stopped.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("operation stopped early ({reason:?})")]
+pub struct Stopped<T: std::fmt::Debug> {
+    pub reason: StopReason,
+    pub partial: T,
+}
+
+/// A cloneable cancellation flag with an optional deadline.
+///
+/// Clones share the flag, so one can be handed to a worker and another kept
+/// to call [`cancel`](Self::cancel) from a signal handler or UI thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled and has no deadline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wrap an existing flag; setting it to `true` cancels the token.
+    pub fn from_flag(flag: Arc<AtomicBool>) -> Self {
+        CancellationToken {
+            flag,
+            deadline: None,
+        }
+    }
+
+    /// Stop operations once `timeout` has elapsed from now.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Instant::now().checked_add(timeout);
+        self
+    }
+
+    /// Request cancellation.
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called (ignores the deadline).
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+
+    /// Check whether work should stop.
+    ///
+    /// # Returns
+    /// `Err` with the reason once the token is cancelled or past its deadline.
+    pub fn check(&self) -> Result<(), StopReason> {
+        if self.is_cancelled() {
+            return Err(StopReason::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(StopReason::TimedOut),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_is_shared_between_clones() {
+        let token = CancellationToken::new();
+        let worker = token.clone();
+        assert_eq!(worker.check(), Ok(()));
+        token.cancel();
+        assert!(worker.is_cancelled());
+        assert_eq!(worker.check(), Err(StopReason::Cancelled));
+    }
+
+    #[test]
+    fn test_timeout_and_external_flag() {
+        let token = CancellationToken::new().with_timeout(Duration::ZERO);
+        assert_eq!(token.check(), Err(StopReason::TimedOut));
+        assert!(!token.is_cancelled());
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let token = CancellationToken::from_flag(Arc::clone(&flag));
+        flag.store(true, Ordering::SeqCst);
+        assert_eq!(token.check(), Err(StopReason::Cancelled));
+    }
+}
@@ -0,0 +1,449 @@
+//! Coin selection: choosing which UTXOs fund a payment.
+//!
+//! [`CoinSelector`] picks inputs from a wallet's [`Utxo`]s (convert RPC
+//! results with [`Utxo::from_list_unspent`]) for a target amount at a fee
+//! rate, and works out the fee and change. Sizes assume P2PKH inputs and
+//! outputs; the chosen outpoints go straight to
+//! [`DogeTransaction::add_utxo`](crate::DogeTransaction::add_utxo).
+//!
+//! Two algorithms are available:
+//! - [`Algorithm::LargestFirst`] spends the biggest outputs first, which
+//!   keeps the input count low.
+//! - [`Algorithm::BranchAndBound`] searches for inputs that cover the
+//!   payment without leaving change, falling back to largest-first.
+//!
+//! A [`ChangePolicy`] decides how change is returned: as one output, split
+//! into several for spending in parallel later, or rounded to a
+//! denomination so it looks less like change.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::coinselect::CoinSelector;
+//! use libdogecoin_rs::{Koinu, OutPoint, Utxo};
+//!
+//! let utxos: Vec<Utxo> = [5, 20, 8]
+//!     .iter()
+//!     .enumerate()
+//!     .map(|(i, doge)| Utxo {
+//!         outpoint: OutPoint { txid: [i as u8; 32], vout: 0 },
+//!         script_pubkey: Vec::new(),
+//!         amount: doge * libdogecoin_rs::consensus::COIN,
+//!         height: Some(100),
+//!     })
+//!     .collect();
+//!
+//! let selection = CoinSelector::new(1_000)
+//!     .select(&utxos, Koinu::from_doge(12).unwrap())
+//!     .unwrap();
+//! assert_eq!(selection.inputs.len(), 1);
+//! assert_eq!(selection.change.len(), 1);
+//! ```
+
+use crate::consensus::COIN;
+use crate::koinu::Koinu;
+use crate::utxo::Utxo;
+
+/// Size of a signed P2PKH input, in bytes.
+pub const P2PKH_INPUT_SIZE: u64 = 148;
+
+/// Size of a P2PKH output, in bytes.
+pub const P2PKH_OUTPUT_SIZE: u64 = 34;
+
+/// Version, lock time and input/output counts, in bytes.
+pub const TX_OVERHEAD_SIZE: u64 = 10;
+
+/// Smallest change output worth creating; Dogecoin Core's default dust limit.
+pub const DUST_LIMIT: Koinu = Koinu(COIN / 100);
+
+/// Most branches the branch-and-bound search explores before giving up.
+const BNB_MAX_TRIES: u32 = 100_000;
+
+/// Errors from coin selection.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SelectError {
+    #[error("target amount is zero")]
+    ZeroTarget,
+
+    #[error("insufficient funds: {available} DOGE available, {needed} DOGE needed")]
+    InsufficientFunds { available: Koinu, needed: Koinu },
+}
+
+/// How inputs are chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Spend the largest outputs first.
+    LargestFirst,
+    /// Look for a changeless match, else fall back to largest-first.
+    #[default]
+    BranchAndBound,
+}
+
+/// How change is returned to the wallet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangePolicy {
+    /// A single change output.
+    #[default]
+    Single,
+    /// Split change into outputs of about `size` each, at most
+    /// `max_outputs` of them, so they can be spent in parallel later. Each
+    /// extra output's fee comes out of the change.
+    Split { size: Koinu, max_outputs: usize },
+    /// Round change down to a multiple of `denomination`; the remainder is
+    /// added to the fee. If less than the dust limit is left, no change is
+    /// made.
+    Round { denomination: Koinu },
+}
+
+impl ChangePolicy {
+    /// Shape `change` into outputs, each at least `dust`.
+    ///
+    /// # Arguments
+    /// * `change` - Change left after paying for one change output.
+    /// * `output_fee` - Fee for each further output.
+    fn outputs(self, change: u64, output_fee: u64, dust: u64) -> Vec<Koinu> {
+        match self {
+            ChangePolicy::Single => vec![Koinu(change)],
+            ChangePolicy::Split { size, max_outputs } => {
+                let mut n = (change / size.0.max(1)).clamp(1, max_outputs.max(1) as u64);
+                loop {
+                    let extra_fee = (n - 1).saturating_mul(output_fee);
+                    let left = change.saturating_sub(extra_fee);
+                    if n == 1 || left / n >= dust {
+                        let mut parts = vec![Koinu(left / n); n as usize];
+                        parts[0].0 += left % n;
+                        return parts;
+                    }
+                    n -= 1;
+                }
+            }
+            ChangePolicy::Round { denomination } => {
+                let rounded = change - change % denomination.0.max(1);
+                if rounded >= dust {
+                    vec![Koinu(rounded)]
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+}
+
+/// The inputs chosen for a payment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub inputs: Vec<Utxo>,
+    /// Fee paid, including any excess too small to return as change.
+    pub fee: Koinu,
+    /// Amounts of the change outputs to create; empty if change is not
+    /// worth creating.
+    pub change: Vec<Koinu>,
+}
+
+impl Selection {
+    /// Get the total value of the inputs.
+    pub fn input_value(&self) -> Koinu {
+        Koinu(self.inputs.iter().map(|u| u.amount).sum())
+    }
+
+    /// Get the total value of the change outputs.
+    pub fn change_value(&self) -> Koinu {
+        Koinu(self.change.iter().map(|c| c.0).sum())
+    }
+}
+
+/// Selects inputs at a fee rate.
+#[derive(Debug, Clone)]
+pub struct CoinSelector {
+    fee_rate: u64,
+    outputs: u64,
+    dust_limit: Koinu,
+    algorithm: Algorithm,
+    change_policy: ChangePolicy,
+}
+
+impl CoinSelector {
+    /// Create a selector paying `fee_rate` koinu per byte for one recipient.
+    pub fn new(fee_rate: u64) -> Self {
+        Self {
+            fee_rate,
+            outputs: 1,
+            dust_limit: DUST_LIMIT,
+            algorithm: Algorithm::default(),
+            change_policy: ChangePolicy::default(),
+        }
+    }
+
+    /// Set the number of recipient outputs, not counting change.
+    pub fn with_outputs(mut self, outputs: u64) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    /// Set the smallest change output to create; less is added to the fee.
+    pub fn with_dust_limit(mut self, dust_limit: Koinu) -> Self {
+        self.dust_limit = dust_limit;
+        self
+    }
+
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set how change is returned.
+    pub fn with_change_policy(mut self, change_policy: ChangePolicy) -> Self {
+        self.change_policy = change_policy;
+        self
+    }
+
+    /// Choose inputs paying `target` to the recipients.
+    ///
+    /// Outputs worth less than the fee to spend them are skipped.
+    ///
+    /// # Arguments
+    /// * `utxos` - The spendable outputs.
+    /// * `target` - The total paid to the recipients.
+    pub fn select(&self, utxos: &[Utxo], target: Koinu) -> Result<Selection, SelectError> {
+        if target == Koinu::ZERO {
+            return Err(SelectError::ZeroTarget);
+        }
+        let input_fee = self.fee_for(P2PKH_INPUT_SIZE);
+        let mut pool: Vec<&Utxo> = utxos.iter().filter(|u| u.amount > input_fee).collect();
+        pool.sort_by_key(|u| std::cmp::Reverse(u.amount));
+
+        if self.algorithm == Algorithm::BranchAndBound {
+            if let Some(selection) = self.branch_and_bound(&pool, target.0) {
+                return Ok(selection);
+            }
+        }
+        self.largest_first(&pool, target.0)
+    }
+
+    fn fee_for(&self, size: u64) -> u64 {
+        size.saturating_mul(self.fee_rate)
+    }
+
+    /// Fee of a transaction with `inputs` inputs and `outputs` outputs.
+    fn tx_fee(&self, inputs: u64, outputs: u64) -> u64 {
+        self.fee_for(
+            TX_OVERHEAD_SIZE
+                .saturating_add(inputs.saturating_mul(P2PKH_INPUT_SIZE))
+                .saturating_add(outputs.saturating_mul(P2PKH_OUTPUT_SIZE)),
+        )
+    }
+
+    fn largest_first(&self, pool: &[&Utxo], target: u64) -> Result<Selection, SelectError> {
+        let mut total = 0u64;
+        for (i, utxo) in pool.iter().enumerate() {
+            total = total.saturating_add(utxo.amount);
+            let inputs = i as u64 + 1;
+            if total < target.saturating_add(self.tx_fee(inputs, self.outputs)) {
+                continue;
+            }
+            let chosen: Vec<Utxo> = pool[..=i].iter().map(|&u| u.clone()).collect();
+            let fee_with_change = self.tx_fee(inputs, self.outputs + 1);
+            let change = match total
+                .checked_sub(target.saturating_add(fee_with_change))
+                .filter(|&c| c >= self.dust_limit.0)
+            {
+                Some(change) => self.change_policy.outputs(
+                    change,
+                    self.fee_for(P2PKH_OUTPUT_SIZE),
+                    self.dust_limit.0,
+                ),
+                None => Vec::new(),
+            };
+            let change_value: u64 = change.iter().map(|c| c.0).sum();
+            return Ok(Selection {
+                inputs: chosen,
+                fee: Koinu(total - target - change_value),
+                change,
+            });
+        }
+        Err(SelectError::InsufficientFunds {
+            available: Koinu(total),
+            needed: Koinu(target.saturating_add(self.tx_fee(pool.len() as u64, self.outputs))),
+        })
+    }
+
+    /// Find inputs whose value after their own fee lands between the target
+    /// and the cost of making change, so no change output is needed.
+    fn branch_and_bound(&self, pool: &[&Utxo], target: u64) -> Option<Selection> {
+        let input_fee = self.fee_for(P2PKH_INPUT_SIZE);
+        let values: Vec<u64> = pool.iter().map(|u| u.amount - input_fee).collect();
+        let goal = target.checked_add(self.tx_fee(0, self.outputs))?;
+        let cost_of_change = self
+            .fee_for(P2PKH_OUTPUT_SIZE)
+            .saturating_add(self.dust_limit.0);
+        let search = BnbSearch {
+            values: &values,
+            goal,
+            upper: goal.saturating_add(cost_of_change),
+        };
+
+        let mut chosen = Vec::new();
+        let mut tries = BNB_MAX_TRIES;
+        let remaining = values.iter().fold(0u64, |a, &v| a.saturating_add(v));
+        if !search.run(0, 0, remaining, &mut chosen, &mut tries) {
+            return None;
+        }
+        let inputs: Vec<Utxo> = chosen.iter().map(|&i| pool[i].clone()).collect();
+        let total: u64 = inputs.iter().map(|u| u.amount).sum();
+        Some(Selection {
+            inputs,
+            fee: Koinu(total - target),
+            change: Vec::new(),
+        })
+    }
+}
+
+struct BnbSearch<'a> {
+    /// Effective values, largest first.
+    values: &'a [u64],
+    goal: u64,
+    upper: u64,
+}
+
+impl BnbSearch<'_> {
+    /// Depth-first search including, then excluding, each value in turn.
+    fn run(
+        &self,
+        i: usize,
+        sum: u64,
+        remaining: u64,
+        chosen: &mut Vec<usize>,
+        tries: &mut u32,
+    ) -> bool {
+        if sum > self.upper || *tries == 0 {
+            return false;
+        }
+        *tries -= 1;
+        if sum >= self.goal {
+            return true;
+        }
+        if i == self.values.len() || sum.saturating_add(remaining) < self.goal {
+            return false;
+        }
+        let value = self.values[i];
+        chosen.push(i);
+        if self.run(i + 1, sum + value, remaining - value, chosen, tries) {
+            return true;
+        }
+        chosen.pop();
+        self.run(i + 1, sum, remaining - value, chosen, tries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utxo::OutPoint;
+
+    fn utxos(amounts: &[u64]) -> Vec<Utxo> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| Utxo {
+                outpoint: OutPoint {
+                    txid: [i as u8; 32],
+                    vout: 0,
+                },
+                script_pubkey: Vec::new(),
+                amount,
+                height: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_largest_first_with_change() {
+        let selector = CoinSelector::new(1_000).with_algorithm(Algorithm::LargestFirst);
+        let pool = utxos(&[3 * COIN, 10 * COIN, 100]);
+        let selection = selector.select(&pool, Koinu(5 * COIN)).unwrap();
+
+        assert_eq!(selection.inputs, vec![pool[1].clone()]);
+        assert_eq!(selection.fee, Koinu((10 + 148 + 2 * 34) * 1_000));
+        assert_eq!(
+            selection.change_value().0 + selection.fee.0 + 5 * COIN,
+            selection.input_value().0
+        );
+    }
+
+    #[test]
+    fn test_small_excess_goes_to_fee() {
+        let fee = (10 + 148 + 34) * 1_000;
+        let pool = utxos(&[COIN + fee + 500]);
+        let selection = CoinSelector::new(1_000)
+            .with_algorithm(Algorithm::LargestFirst)
+            .select(&pool, Koinu(COIN))
+            .unwrap();
+        assert_eq!(
+            (selection.fee, selection.change),
+            (Koinu(fee + 500), vec![])
+        );
+    }
+
+    #[test]
+    fn test_branch_and_bound_avoids_change() {
+        let input_fee = 148 * 1_000;
+        let base_fee = (10 + 34) * 1_000;
+        // 7 + 3 DOGE pays exactly; largest-first would take 20 and make change.
+        let pool = utxos(&[
+            20 * COIN,
+            7 * COIN + input_fee + base_fee,
+            3 * COIN + input_fee,
+            COIN,
+        ]);
+        let selection = CoinSelector::new(1_000)
+            .select(&pool, Koinu(10 * COIN))
+            .unwrap();
+        assert_eq!(selection.inputs, vec![pool[1].clone(), pool[2].clone()]);
+        assert!(selection.change.is_empty());
+        assert_eq!(selection.fee, Koinu(2 * input_fee + base_fee));
+
+        assert!(matches!(
+            CoinSelector::new(1_000).select(&pool, Koinu(100 * COIN)),
+            Err(SelectError::InsufficientFunds { .. })
+        ));
+        assert_eq!(
+            CoinSelector::new(1_000).select(&pool, Koinu::ZERO),
+            Err(SelectError::ZeroTarget)
+        );
+    }
+
+    #[test]
+    fn test_change_policies() {
+        let out_fee = 34 * 1_000;
+        let dust = DUST_LIMIT.0;
+        let split = ChangePolicy::Split {
+            size: Koinu(10 * COIN),
+            max_outputs: 3,
+        };
+        let parts = split.outputs(25 * COIN, out_fee, dust);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts.iter().map(|p| p.0).sum::<u64>(), 25 * COIN - out_fee);
+        assert_eq!(split.outputs(100 * COIN, out_fee, dust).len(), 3);
+        assert_eq!(split.outputs(COIN, out_fee, dust), vec![Koinu(COIN)]);
+
+        let round = ChangePolicy::Round {
+            denomination: Koinu(COIN),
+        };
+        assert_eq!(
+            round.outputs(7 * COIN + 123, out_fee, dust),
+            vec![Koinu(7 * COIN)]
+        );
+        assert!(round.outputs(COIN - 1, out_fee, dust).is_empty());
+
+        let pool = utxos(&[100 * COIN]);
+        let selection = CoinSelector::new(1_000)
+            .with_algorithm(Algorithm::LargestFirst)
+            .with_change_policy(round)
+            .select(&pool, Koinu(10 * COIN))
+            .unwrap();
+        assert_eq!(selection.change, vec![Koinu(89 * COIN)]);
+        assert_eq!(
+            selection.fee.0 + selection.change_value().0 + 10 * COIN,
+            100 * COIN
+        );
+    }
+}
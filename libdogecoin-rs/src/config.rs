@@ -0,0 +1,559 @@
+//! Crate-wide configuration.
+//!
+//! A [`Config`] gathers the settings a deployment usually wants in one place:
+//! network, node connection, fee policy, address gap limit, storage location
+//! and telemetry toggles. Build one in code with the `with_*` methods, or
+//! (with the `toml` feature) load it from a file:
+//!
+//! ```toml
+//! network = "testnet"
+//! gap_limit = 50
+//! storage_path = "/var/lib/doge-wallet"
+//!
+//! [rpc]
+//! url = "http://127.0.0.1:44555"
+//! username = "user"
+//! password = "pass"
+//!
+//! [fees]
+//! fee_per_kb = 1000000
+//! ```
+//!
+//! Missing keys take their [`Default`] values. With the `serde` feature every
+//! type here implements `Serialize`/`Deserialize`.
+
+use crate::consensus::COIN;
+use crate::hdwallet::Chain;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Default number of consecutive unused addresses scanned before stopping.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Errors from validating or loading a configuration.
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("invalid configuration: {0}")]
+    Invalid(&'static str),
+
+    #[error("failed to read configuration: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "toml")]
+    #[error("failed to parse configuration: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[cfg(feature = "toml")]
+    #[error("failed to serialize configuration: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// Dogecoin network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    pub fn is_testnet(self) -> bool {
+        self == Network::Testnet
+    }
+}
+
+/// JSON-RPC node connection settings.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RpcConfig {
+    pub url: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub username: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub password: Option<String>,
+}
+
+impl RpcConfig {
+    /// Create settings for the given endpoint URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        RpcConfig {
+            url: url.into(),
+            username: None,
+            password: None,
+        }
+    }
+
+    /// Set HTTP Basic auth credentials.
+    pub fn with_basic_auth(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+}
+
+impl fmt::Debug for RpcConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcConfig")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// How transaction fees are chosen, in koinu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct FeePolicy {
+    /// Fee rate per 1000 bytes.
+    pub fee_per_kb: u64,
+    /// Smallest fee paid regardless of size.
+    pub min_fee: u64,
+    /// Largest fee the wallet will ever pay.
+    pub max_fee: u64,
+}
+
+impl FeePolicy {
+    /// Get the fee for a transaction of `size` bytes.
+    pub fn fee_for_size(&self, size: usize) -> u64 {
+        let fee = (size as u64).saturating_mul(self.fee_per_kb).div_ceil(1000);
+        fee.clamp(self.min_fee, self.max_fee.max(self.min_fee))
+    }
+}
+
+impl Default for FeePolicy {
+    /// The recommended 0.01 DOGE per kB, capped at 100 DOGE.
+    fn default() -> Self {
+        FeePolicy {
+            fee_per_kb: COIN / 100,
+            min_fee: COIN / 100,
+            max_fee: 100 * COIN,
+        }
+    }
+}
+
+/// Telemetry switches; everything is off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Telemetry {
+    /// Collect operation metrics (counters, timings).
+    pub metrics: bool,
+    /// Emit diagnostic logs.
+    pub logging: bool,
+}
+
+/// Gap limits for one account, overriding [`GapLimit`]'s defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountGapLimit {
+    pub account: u32,
+    pub external: u32,
+    pub internal: u32,
+}
+
+/// Consecutive unused addresses scanned before a chain is considered
+/// exhausted, per chain and optionally per account.
+///
+/// Merchants handing out many invoice addresses need a long receiving gap,
+/// while change addresses are used in order and rarely need more than the
+/// default. In TOML either a single number or a table is accepted:
+///
+/// ```toml
+/// [gap_limit]
+/// external = 500
+/// internal = 20
+///
+/// [[gap_limit.accounts]]
+/// account = 1
+/// external = 5000
+/// internal = 20
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "GapLimitRepr", into = "GapLimitRepr")
+)]
+pub struct GapLimit {
+    /// Limit for receiving chains.
+    pub external: u32,
+    /// Limit for change chains.
+    pub internal: u32,
+    /// Accounts with their own limits.
+    pub accounts: Vec<AccountGapLimit>,
+}
+
+impl GapLimit {
+    /// Use `limit` for both chains of every account.
+    pub fn new(limit: u32) -> Self {
+        GapLimit {
+            external: limit,
+            internal: limit,
+            accounts: Vec::new(),
+        }
+    }
+
+    pub fn with_external(mut self, limit: u32) -> Self {
+        self.external = limit;
+        self
+    }
+
+    pub fn with_internal(mut self, limit: u32) -> Self {
+        self.internal = limit;
+        self
+    }
+
+    /// Give `account` its own limits, replacing any set before.
+    pub fn with_account(mut self, account: u32, external: u32, internal: u32) -> Self {
+        self.accounts.retain(|a| a.account != account);
+        self.accounts.push(AccountGapLimit {
+            account,
+            external,
+            internal,
+        });
+        self
+    }
+
+    /// Get the limit for one chain of an account.
+    pub fn get(&self, account: u32, chain: Chain) -> u32 {
+        let (external, internal) = self
+            .accounts
+            .iter()
+            .find(|a| a.account == account)
+            .map_or((self.external, self.internal), |a| (a.external, a.internal));
+        match chain {
+            Chain::External => external,
+            Chain::Internal => internal,
+        }
+    }
+
+    /// Whether some chain would stop scanning before its first address.
+    fn has_zero(&self) -> bool {
+        self.external == 0
+            || self.internal == 0
+            || self
+                .accounts
+                .iter()
+                .any(|a| a.external == 0 || a.internal == 0)
+    }
+}
+
+impl Default for GapLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_GAP_LIMIT)
+    }
+}
+
+impl From<u32> for GapLimit {
+    fn from(limit: u32) -> Self {
+        Self::new(limit)
+    }
+}
+
+/// A single number when every chain shares one limit, otherwise a table.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum GapLimitRepr {
+    Uniform(u32),
+    PerChain {
+        #[serde(default = "default_gap_limit")]
+        external: u32,
+        #[serde(default = "default_gap_limit")]
+        internal: u32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        accounts: Vec<AccountGapLimit>,
+    },
+}
+
+#[cfg(feature = "serde")]
+fn default_gap_limit() -> u32 {
+    DEFAULT_GAP_LIMIT
+}
+
+#[cfg(feature = "serde")]
+impl From<GapLimitRepr> for GapLimit {
+    fn from(repr: GapLimitRepr) -> Self {
+        match repr {
+            GapLimitRepr::Uniform(limit) => GapLimit::new(limit),
+            GapLimitRepr::PerChain {
+                external,
+                internal,
+                accounts,
+            } => GapLimit {
+                external,
+                internal,
+                accounts,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<GapLimit> for GapLimitRepr {
+    fn from(limit: GapLimit) -> Self {
+        if limit.external == limit.internal && limit.accounts.is_empty() {
+            return GapLimitRepr::Uniform(limit.external);
+        }
+        GapLimitRepr::PerChain {
+            external: limit.external,
+            internal: limit.internal,
+            accounts: limit.accounts,
+        }
+    }
+}
+
+/// Configuration for the whole crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Config {
+    pub network: Network,
+    pub rpc: Option<RpcConfig>,
+    pub fees: FeePolicy,
+    pub gap_limit: GapLimit,
+    pub storage_path: Option<PathBuf>,
+    pub telemetry: Telemetry,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            network: Network::Mainnet,
+            rpc: None,
+            fees: FeePolicy::default(),
+            gap_limit: GapLimit::default(),
+            storage_path: None,
+            telemetry: Telemetry::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Create a default configuration for `network`.
+    pub fn new(network: Network) -> Self {
+        Config {
+            network,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        self.network.is_testnet()
+    }
+
+    /// Connect to a node over JSON-RPC.
+    pub fn with_rpc(mut self, rpc: RpcConfig) -> Self {
+        self.rpc = Some(rpc);
+        self
+    }
+
+    pub fn with_fee_policy(mut self, fees: FeePolicy) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Use one gap limit for both chains of every account.
+    pub fn with_gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = GapLimit::new(gap_limit);
+        self
+    }
+
+    /// Use separate gap limits per chain or account.
+    pub fn with_gap_limits(mut self, gap_limit: GapLimit) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
+
+    /// Directory for persistent state (address indexes, snapshots).
+    pub fn with_storage_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.storage_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_telemetry(mut self, telemetry: Telemetry) -> Self {
+        self.telemetry = telemetry;
+        self
+    }
+
+    /// Check the settings for obvious mistakes.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.gap_limit.has_zero() {
+            return Err(ConfigError::Invalid("gap_limit must be at least 1"));
+        }
+        if self.fees.min_fee > self.fees.max_fee {
+            return Err(ConfigError::Invalid("fees.min_fee exceeds fees.max_fee"));
+        }
+        if let Some(rpc) = &self.rpc {
+            if rpc.url.is_empty() {
+                return Err(ConfigError::Invalid("rpc.url is empty"));
+            }
+            if rpc.username.is_some() != rpc.password.is_some() {
+                return Err(ConfigError::Invalid(
+                    "rpc.username and rpc.password must be set together",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the file an [`IndexStore`](crate::IndexStore) should persist to.
+    ///
+    /// # Returns
+    /// `storage_path/indexes`, or `None` without a storage path.
+    pub fn index_store_path(&self) -> Option<PathBuf> {
+        self.storage_path.as_ref().map(|dir| dir.join("indexes"))
+    }
+
+    /// Build an RPC client from the `rpc` settings.
+    #[cfg(feature = "rpc")]
+    pub fn rpc_client(&self) -> Option<crate::DogeRpcClient> {
+        let rpc = self.rpc.as_ref()?;
+        let client = crate::DogeRpcClient::new(rpc.url.clone());
+        Some(match (&rpc.username, &rpc.password) {
+            (Some(user), Some(pass)) => client.with_basic_auth(user, pass),
+            _ => client,
+        })
+    }
+
+    /// Parse and validate a TOML configuration.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(text: &str) -> Result<Self, ConfigError> {
+        let config: Config = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Serialize to TOML.
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Load and validate a TOML configuration file.
+    #[cfg(feature = "toml")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_toml_str(&std::fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_and_validation() {
+        let config = Config::new(Network::Testnet)
+            .with_rpc(RpcConfig::new("http://127.0.0.1:44555").with_basic_auth("u", "p"))
+            .with_gap_limit(50)
+            .with_storage_path("/tmp/doge");
+        assert!(config.is_testnet());
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.index_store_path(),
+            Some(PathBuf::from("/tmp/doge/indexes"))
+        );
+        assert!(!format!("{config:?}").contains("\"p\""));
+
+        assert!(Config::default().with_gap_limit(0).validate().is_err());
+        let mut half_auth = RpcConfig::new("http://node");
+        half_auth.username = Some("u".into());
+        assert!(Config::default().with_rpc(half_auth).validate().is_err());
+    }
+
+    #[test]
+    fn test_gap_limit_per_chain_and_account() {
+        let limits = GapLimit::new(500)
+            .with_internal(20)
+            .with_account(1, 5_000, 30)
+            .with_account(1, 5_000, 40);
+        assert_eq!(limits.get(0, Chain::External), 500);
+        assert_eq!(limits.get(0, Chain::Internal), 20);
+        assert_eq!(limits.get(1, Chain::External), 5_000);
+        assert_eq!(limits.get(1, Chain::Internal), 40);
+        assert_eq!(limits.accounts.len(), 1);
+
+        let config = Config::default().with_gap_limits(limits);
+        assert!(config.validate().is_ok());
+        let zero = GapLimit::default().with_account(2, 0, 20);
+        assert!(Config::default().with_gap_limits(zero).validate().is_err());
+    }
+
+    #[test]
+    fn test_fee_for_size() {
+        let fees = FeePolicy::default();
+        assert_eq!(fees.fee_for_size(0), COIN / 100);
+        assert_eq!(fees.fee_for_size(1000), COIN / 100);
+        assert_eq!(fees.fee_for_size(1001), 1_001_000);
+        assert_eq!(fees.fee_for_size(usize::MAX), 100 * COIN);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_roundtrip() {
+        let config = Config::from_toml_str(
+            r#"
+            network = "testnet"
+            gap_limit = 50
+
+            [rpc]
+            url = "http://127.0.0.1:44555"
+
+            [fees]
+            fee_per_kb = 2000000
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.network, Network::Testnet);
+        assert_eq!(config.gap_limit, GapLimit::new(50));
+        assert_eq!(config.fees.fee_per_kb, 2_000_000);
+        assert_eq!(config.fees.min_fee, FeePolicy::default().min_fee);
+        assert_eq!(config.rpc.as_ref().unwrap().username, None);
+
+        let text = config.to_toml_string().unwrap();
+        assert_eq!(Config::from_toml_str(&text).unwrap(), config);
+        assert!(Config::from_toml_str("gap_limit = 0").is_err());
+
+        let config = Config::from_toml_str(
+            r#"
+            [gap_limit]
+            external = 500
+
+            [[gap_limit.accounts]]
+            account = 1
+            external = 5000
+            internal = 30
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.gap_limit,
+            GapLimit::new(500)
+                .with_internal(DEFAULT_GAP_LIMIT)
+                .with_account(1, 5_000, 30)
+        );
+        let text = config.to_toml_string().unwrap();
+        assert_eq!(Config::from_toml_str(&text).unwrap(), config);
+        assert!(Config::from_toml_str("network = \"regtest\"").is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Dogecoin consensus history: activation heights, rewards, supply and maturity.
+//!
+//! Amounts are in koinu (1 DOGE = [`COIN`] koinu). Helpers whose activation
+//! height differs on testnet take the usual `is_testnet` flag.
+
+/// Number of koinu in one DOGE.
+pub const COIN: u64 = 100_000_000;
+
+/// Largest amount a single output or transaction may carry (`MAX_MONEY`).
+pub const MAX_MONEY: u64 = 10_000_000_000 * COIN;
+
+/// Target time between blocks, in seconds.
+pub const TARGET_SPACING: u32 = 60;
+
+/// Blocks between reward reductions.
+pub const SUBSIDY_HALVING_INTERVAL: u32 = 100_000;
+
+/// Height at which DigiShield difficulty, fixed rewards and the longer
+/// coinbase maturity activated (mainnet and testnet).
+pub const DIGISHIELD_HEIGHT: u32 = 145_000;
+
+/// Height at which merged mining (AuxPoW) activated on mainnet.
+pub const AUXPOW_HEIGHT_MAINNET: u32 = 371_337;
+
+/// Height at which merged mining (AuxPoW) activated on testnet.
+pub const AUXPOW_HEIGHT_TESTNET: u32 = 158_100;
+
+/// Chain ID carried in the version of merge-mined Dogecoin blocks.
+pub const AUXPOW_CHAIN_ID: u32 = 0x0062;
+
+/// Coinbase maturity before [`DIGISHIELD_HEIGHT`].
+pub const COINBASE_MATURITY_LEGACY: u32 = 30;
+
+/// Coinbase maturity from [`DIGISHIELD_HEIGHT`] onwards.
+pub const COINBASE_MATURITY: u32 = 240;
+
+/// Constant reward paid once the reduction schedule ends.
+pub const TAIL_REWARD: u64 = 10_000 * COIN;
+
+/// Height from which every block pays [`TAIL_REWARD`].
+pub const TAIL_EMISSION_HEIGHT: u32 = 6 * SUBSIDY_HALVING_INTERVAL;
+
+/// Get the AuxPoW activation height.
+///
+/// # Arguments
+/// * `is_testnet` - Set to true for testnet, false for mainnet.
+pub fn auxpow_height(is_testnet: bool) -> u32 {
+    if is_testnet {
+        AUXPOW_HEIGHT_TESTNET
+    } else {
+        AUXPOW_HEIGHT_MAINNET
+    }
+}
+
+/// Whether a block at `height` may be merge-mined.
+pub fn is_auxpow_active(height: u32, is_testnet: bool) -> bool {
+    height >= auxpow_height(is_testnet)
+}
+
+/// Whether a block at `height` uses DigiShield difficulty retargeting.
+pub fn is_digishield_active(height: u32) -> bool {
+    height >= DIGISHIELD_HEIGHT
+}
+
+/// Get the coinbase maturity in force for a spend in a block at `height`.
+///
+/// Dogecoin applies the rules of the spending block, so coinbases mined before
+/// DigiShield still needed 240 confirmations once it activated.
+pub fn coinbase_maturity(height: u32) -> u32 {
+    if is_digishield_active(height) {
+        COINBASE_MATURITY
+    } else {
+        COINBASE_MATURITY_LEGACY
+    }
+}
+
+/// Whether a coinbase mined at `coinbase_height` is spendable in a block at
+/// `spend_height`.
+pub fn is_coinbase_mature(coinbase_height: u32, spend_height: u32) -> bool {
+    spend_height.saturating_sub(coinbase_height) >= coinbase_maturity(spend_height)
+}
+
+/// Get the block reward (subsidy, excluding fees) at `height`, in koinu.
+///
+/// # Returns
+/// `None` before [`DIGISHIELD_HEIGHT`], where the reward was drawn at random
+/// from the previous block hash and is not a function of height alone; use
+/// [`random_era_reward`] or [`block_reward_range`] there.
+pub fn block_reward(height: u32) -> Option<u64> {
+    if height < DIGISHIELD_HEIGHT {
+        return None;
+    }
+    if height >= TAIL_EMISSION_HEIGHT {
+        return Some(TAIL_REWARD);
+    }
+    let reductions = height / SUBSIDY_HALVING_INTERVAL;
+    Some((500_000 * COIN) >> reductions)
+}
+
+/// Get the smallest and largest possible block reward at `height`, in koinu.
+///
+/// Both bounds are equal from [`DIGISHIELD_HEIGHT`] onwards.
+pub fn block_reward_range(height: u32) -> (u64, u64) {
+    match block_reward(height) {
+        Some(reward) => (reward, reward),
+        None => (2 * COIN, random_era_max_draw(height) * COIN + COIN),
+    }
+}
+
+/// Compute a random-era block reward exactly, as Dogecoin 1.x did.
+///
+/// The reward was `1 + rand` DOGE, with `rand` drawn uniformly from
+/// `1..=max` by an MT19937 generator seeded with seven hex digits of the
+/// previous block hash. The draw is only reproducible from that hash, which
+/// is why [`block_reward`] cannot answer for these heights.
+///
+/// # Arguments
+/// * `height` - Height of the block, below [`DIGISHIELD_HEIGHT`].
+/// * `prev_block_hash` - Hash of the block at `height - 1`, as displayed by
+///   RPC and explorers (64 hex characters).
+///
+/// # Returns
+/// The reward in koinu, or `None` if `height` is outside the random era or
+/// the hash is malformed.
+pub fn random_era_reward(height: u32, prev_block_hash: &str) -> Option<u64> {
+    if height == 0 || height >= DIGISHIELD_HEIGHT || prev_block_hash.len() != 64 {
+        return None;
+    }
+    let seed_hex = prev_block_hash.get(7..14)?;
+    let seed = u32::from_str_radix(seed_hex, 16).ok()?;
+    let draw = uniform_draw(&mut Mt19937::new(seed), 1, random_era_max_draw(height));
+    Some((1 + draw) * COIN)
+}
+
+/// Cumulative block rewards up to a height, in koinu.
+///
+/// Rewards before [`DIGISHIELD_HEIGHT`] were random, so the supply is only
+/// known to lie between `min` and `max`; `expected` uses the mean reward for
+/// those blocks. An exact figure needs every random-era block hash (sum
+/// [`random_era_reward`] over them).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Supply {
+    pub min: u64,
+    pub max: u64,
+    pub expected: u64,
+}
+
+/// Get the coins created by blocks `1..=height`.
+///
+/// The genesis block's 88 DOGE output is unspendable and not counted. Values
+/// saturate at `u64::MAX`, far beyond any height the chain will reach.
+pub fn supply_at_height(height: u32) -> Supply {
+    let coin = COIN as u128;
+    let (mut min, mut max, mut expected) = (0u128, 0u128, 0u128);
+
+    let mut start = 1u32;
+    while start <= height {
+        // Each segment ends at the next reward change or at `height`.
+        let end = if start >= TAIL_EMISSION_HEIGHT {
+            height
+        } else if (SUBSIDY_HALVING_INTERVAL..DIGISHIELD_HEIGHT).contains(&start) {
+            height.min(DIGISHIELD_HEIGHT - 1)
+        } else {
+            height.min((start / SUBSIDY_HALVING_INTERVAL + 1) * SUBSIDY_HALVING_INTERVAL - 1)
+        };
+        let blocks = (end - start + 1) as u128;
+
+        match block_reward(start) {
+            Some(reward) => {
+                let total = blocks * reward as u128;
+                min += total;
+                max += total;
+                expected += total;
+            }
+            None => {
+                let top = random_era_max_draw(start) as u128;
+                min += blocks * 2 * coin;
+                max += blocks * (top + 1) * coin;
+                expected += blocks * (top + 3) * coin / 2;
+            }
+        }
+
+        if end == height {
+            break;
+        }
+        start = end + 1;
+    }
+
+    let clamp = |v: u128| u64::try_from(v).unwrap_or(u64::MAX);
+    Supply {
+        min: clamp(min),
+        max: clamp(max),
+        expected: clamp(expected),
+    }
+}
+
+/// Largest `rand` of the random-era reward at `height`.
+fn random_era_max_draw(height: u32) -> u64 {
+    (1_000_000 >> (height / SUBSIDY_HALVING_INTERVAL)) - 1
+}
+
+/// The 32-bit Mersenne Twister, matching `boost::mt19937`.
+struct Mt19937 {
+    state: [u32; 624],
+    index: usize,
+}
+
+impl Mt19937 {
+    fn new(seed: u32) -> Self {
+        let mut state = [0u32; 624];
+        state[0] = seed;
+        for i in 1..624 {
+            let prev = state[i - 1];
+            state[i] = 1_812_433_253u32
+                .wrapping_mul(prev ^ (prev >> 30))
+                .wrapping_add(i as u32);
+        }
+        Mt19937 { state, index: 624 }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.index >= 624 {
+            for i in 0..624 {
+                let y = (self.state[i] & 0x8000_0000) | (self.state[(i + 1) % 624] & 0x7fff_ffff);
+                let mag = if y & 1 == 1 { 0x9908_b0df } else { 0 };
+                self.state[i] = self.state[(i + 397) % 624] ^ (y >> 1) ^ mag;
+            }
+            self.index = 0;
+        }
+        let mut y = self.state[self.index];
+        self.index += 1;
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c_5680;
+        y ^= (y << 15) & 0xefc6_0000;
+        y ^ (y >> 18)
+    }
+}
+
+/// Draw from `min..=max` the way `boost::uniform_int` does with a 32-bit engine.
+fn uniform_draw(rng: &mut Mt19937, min: u64, max: u64) -> u64 {
+    let range = (max - min) as u32;
+    let span = u32::MAX;
+    let mut bucket = span / (range + 1);
+    if span % (range + 1) == range {
+        bucket += 1;
+    }
+    loop {
+        let result = rng.next_u32() / bucket;
+        if result <= range {
+            return min + result as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_reward_schedule() {
+        assert_eq!(block_reward(0), None);
+        assert_eq!(block_reward(144_999), None);
+        assert_eq!(block_reward(145_000), Some(250_000 * COIN));
+        assert_eq!(block_reward(199_999), Some(250_000 * COIN));
+        assert_eq!(block_reward(200_000), Some(125_000 * COIN));
+        assert_eq!(block_reward(300_000), Some(62_500 * COIN));
+        assert_eq!(block_reward(400_000), Some(31_250 * COIN));
+        assert_eq!(block_reward(599_999), Some(15_625 * COIN));
+        assert_eq!(block_reward(600_000), Some(TAIL_REWARD));
+        assert_eq!(block_reward(5_000_000), Some(TAIL_REWARD));
+    }
+
+    #[test]
+    fn test_random_era_reward() {
+        assert_eq!(Mt19937::new(5489).next_u32(), 3_499_211_612);
+
+        let genesis = "1a91e3dace36e2be3bf030a65679fe821aa1d6ef92e7c9902eb318182c355691";
+        assert_eq!(random_era_reward(1, genesis), Some(68_416 * COIN));
+        assert_eq!(
+            random_era_reward(120_000, &"0".repeat(64)),
+            Some(274_438 * COIN)
+        );
+        assert_eq!(
+            random_era_reward(144_999, &"f".repeat(64)),
+            Some(269_807 * COIN)
+        );
+        assert_eq!(random_era_reward(145_000, genesis), None);
+        assert_eq!(random_era_reward(0, genesis), None);
+        assert_eq!(random_era_reward(1, "zz"), None);
+
+        assert_eq!(block_reward_range(1), (2 * COIN, 1_000_000 * COIN));
+        assert_eq!(block_reward_range(100_000), (2 * COIN, 500_000 * COIN));
+        assert_eq!(
+            block_reward_range(145_000),
+            (250_000 * COIN, 250_000 * COIN)
+        );
+    }
+
+    #[test]
+    fn test_supply_at_height() {
+        assert_eq!(supply_at_height(0).max, 0);
+
+        let one = supply_at_height(1);
+        assert_eq!((one.min, one.max), (2 * COIN, 1_000_000 * COIN));
+        assert_eq!(one.expected, 500_001 * COIN);
+
+        let random_era = supply_at_height(DIGISHIELD_HEIGHT - 1);
+        assert_eq!(
+            random_era.expected,
+            (99_999 * 500_001 + 45_000 * 250_001) * COIN
+        );
+
+        let after = supply_at_height(DIGISHIELD_HEIGHT + 9);
+        assert_eq!(after.expected - random_era.expected, 10 * 250_000 * COIN);
+        assert_eq!(after.max - random_era.max, 10 * 250_000 * COIN);
+
+        let tail = supply_at_height(TAIL_EMISSION_HEIGHT + 1);
+        let before_tail = supply_at_height(TAIL_EMISSION_HEIGHT - 1);
+        assert_eq!(tail.min - before_tail.min, 2 * TAIL_REWARD);
+
+        assert_eq!(supply_at_height(u32::MAX).max, u64::MAX);
+    }
+
+    #[test]
+    fn test_activation_and_maturity() {
+        assert!(!is_auxpow_active(371_336, false));
+        assert!(is_auxpow_active(371_337, false));
+        assert!(is_auxpow_active(158_100, true));
+        assert!(!is_digishield_active(144_999));
+
+        assert_eq!(coinbase_maturity(100_000), 30);
+        assert_eq!(coinbase_maturity(145_000), 240);
+        assert!(is_coinbase_mature(100_000, 100_030));
+        assert!(!is_coinbase_mature(145_000, 145_239));
+        assert!(is_coinbase_mature(145_000, 145_240));
+        assert!(!is_coinbase_mature(10, 5));
+    }
+}
@@ -0,0 +1,305 @@
+//! Consolidating many small UTXOs into one output.
+//!
+//! Wallets that receive lots of tips end up holding thousands of tiny
+//! outputs, which make every later payment large and expensive. A
+//! [`ConsolidationBuilder`] sweeps them into a single output at a fee rate
+//! while fees are low. When the inputs do not fit in one standard
+//! transaction they are split into evenly sized batches, each under
+//! [`MAX_STANDARD_TX_SIZE`]. Outputs worth less than the fee to spend them
+//! are left out.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::consolidate::ConsolidationBuilder;
+//! use libdogecoin_rs::Utxo;
+//! use std::collections::HashMap;
+//!
+//! # let (utxos, keys): (Vec<Utxo>, HashMap<String, String>) = unimplemented!();
+//! let mut builder = ConsolidationBuilder::new(false, 1_000);
+//! builder.set_destination("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas")?;
+//! for utxo in utxos {
+//!     builder.add_utxo(utxo)?;
+//! }
+//! for signed_hex in builder.build_and_sign(&keys)? {
+//!     println!("{signed_hex}");
+//! }
+//! # Ok::<(), libdogecoin_rs::builder::BuildError>(())
+//! ```
+
+use crate::address::AddressUtils;
+use crate::builder::BuildError;
+use crate::coinselect::{DUST_LIMIT, P2PKH_INPUT_SIZE, P2PKH_OUTPUT_SIZE, TX_OVERHEAD_SIZE};
+use crate::hex;
+use crate::koinu::Koinu;
+use crate::script::{P2pkhTemplate, ScriptTemplate};
+use crate::transaction::{DogeTransaction, MAX_STANDARD_TX_SIZE};
+use crate::utxo::Utxo;
+use std::collections::HashMap;
+
+/// Extra bytes for the input count once it no longer fits in one byte.
+const LARGE_COUNT_SIZE: u64 = 2;
+
+/// A wallet input with the address whose key signs it.
+#[derive(Debug, Clone)]
+struct Input {
+    utxo: Utxo,
+    address: String,
+}
+
+/// The inputs of one consolidation transaction and what it pays.
+#[derive(Debug)]
+struct Batch<'a> {
+    inputs: Vec<&'a Input>,
+    amount: Koinu,
+}
+
+/// Builds transactions that sweep P2PKH outputs into one address.
+#[derive(Debug, Clone)]
+pub struct ConsolidationBuilder {
+    is_testnet: bool,
+    fee_rate: u64,
+    max_size: usize,
+    inputs: Vec<Input>,
+    destination: Option<String>,
+}
+
+impl ConsolidationBuilder {
+    /// Create a builder.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    /// * `fee_rate` - Fee rate in koinu per byte.
+    pub fn new(is_testnet: bool, fee_rate: u64) -> Self {
+        Self {
+            is_testnet,
+            fee_rate,
+            max_size: MAX_STANDARD_TX_SIZE,
+            inputs: Vec::new(),
+            destination: None,
+        }
+    }
+
+    /// Set the largest signed transaction to build, in bytes; inputs beyond
+    /// it go into further transactions. At least one input is always
+    /// spent per transaction.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Add an output to consolidate.
+    ///
+    /// # Returns
+    /// An error if the output was already added or is not P2PKH.
+    pub fn add_utxo(&mut self, utxo: Utxo) -> Result<&mut Self, BuildError> {
+        if self.inputs.iter().any(|i| i.utxo.outpoint == utxo.outpoint) {
+            return Err(BuildError::DuplicateInput(utxo.outpoint));
+        }
+        let address = P2pkhTemplate
+            .parse(&utxo.script_pubkey)
+            .and_then(|kind| kind.address(self.is_testnet))
+            .ok_or(BuildError::UnsupportedInput(utxo.outpoint))?;
+        self.inputs.push(Input { utxo, address });
+        Ok(self)
+    }
+
+    /// Set the address that receives the consolidated funds.
+    pub fn set_destination(&mut self, address: &str) -> Result<&mut Self, BuildError> {
+        AddressUtils::diagnose_for(address, self.is_testnet).map_err(|source| {
+            BuildError::InvalidAddress {
+                address: address.to_string(),
+                source,
+            }
+        })?;
+        self.destination = Some(address.to_string());
+        Ok(self)
+    }
+
+    /// Build the consolidation transactions, leaving them unsigned.
+    ///
+    /// Inputs are added with their amount and scriptPubKey, so each result
+    /// knows its fee and checks each signature as it is made.
+    pub fn build(&self) -> Result<Vec<DogeTransaction>, BuildError> {
+        let destination = self.destination.as_ref().ok_or(BuildError::NoRecipients)?;
+        self.batches()?
+            .iter()
+            .map(|batch| Self::assemble(batch, destination))
+            .collect()
+    }
+
+    /// Build and sign the consolidation transactions.
+    ///
+    /// # Arguments
+    /// * `keys` - WIF private keys by the P2PKH address they control.
+    ///
+    /// # Returns
+    /// The signed transaction hex, one per batch.
+    pub fn build_and_sign(
+        &self,
+        keys: &HashMap<String, String>,
+    ) -> Result<Vec<String>, BuildError> {
+        let destination = self.destination.as_ref().ok_or(BuildError::NoRecipients)?;
+        let batches = self.batches()?;
+
+        // Resolve every key before libdogecoin sees a transaction.
+        for input in batches.iter().flat_map(|batch| &batch.inputs) {
+            if !keys.contains_key(&input.address) {
+                return Err(BuildError::MissingKey(input.address.clone()));
+            }
+        }
+
+        let mut signed = Vec::with_capacity(batches.len());
+        for batch in &batches {
+            let mut tx = Self::assemble(batch, destination)?;
+            for (index, input) in batch.inputs.iter().enumerate() {
+                if !tx.sign_with_privkey(index as i32, &keys[&input.address]) {
+                    return Err(BuildError::Ffi("sign an input"));
+                }
+            }
+            signed.push(
+                tx.get_raw()
+                    .ok_or(BuildError::Ffi("serialize the transaction"))?,
+            );
+        }
+        Ok(signed)
+    }
+
+    /// Split the inputs worth spending into evenly sized batches.
+    fn batches(&self) -> Result<Vec<Batch<'_>>, BuildError> {
+        let input_fee = self.fee_rate.saturating_mul(P2PKH_INPUT_SIZE);
+        let worth: Vec<&Input> = self
+            .inputs
+            .iter()
+            .filter(|i| i.utxo.amount > input_fee)
+            .collect();
+        if worth.is_empty() {
+            return Err(BuildError::NoInputs);
+        }
+
+        let fixed = TX_OVERHEAD_SIZE + LARGE_COUNT_SIZE + P2PKH_OUTPUT_SIZE;
+        let per_tx = ((self.max_size as u64).saturating_sub(fixed) / P2PKH_INPUT_SIZE).max(1);
+        let count = worth.len() as u64;
+        let per_batch = count.div_ceil(count.div_ceil(per_tx)) as usize;
+
+        worth
+            .chunks(per_batch)
+            .map(|inputs| {
+                let size = fixed + inputs.len() as u64 * P2PKH_INPUT_SIZE;
+                let fee = self.fee_rate.saturating_mul(size);
+                let total = inputs
+                    .iter()
+                    .try_fold(0u64, |sum, i| sum.checked_add(i.utxo.amount))
+                    .ok_or(BuildError::Overflow)?;
+                let amount = Koinu(total.saturating_sub(fee));
+                if amount < DUST_LIMIT {
+                    return Err(BuildError::DustOutput(amount));
+                }
+                Ok(Batch {
+                    inputs: inputs.to_vec(),
+                    amount,
+                })
+            })
+            .collect()
+    }
+
+    /// Create the unsigned transaction for `batch`.
+    fn assemble(batch: &Batch<'_>, destination: &str) -> Result<DogeTransaction, BuildError> {
+        let mut tx = DogeTransaction::try_new()?;
+        for input in &batch.inputs {
+            let outpoint = input.utxo.outpoint;
+            let vout =
+                i32::try_from(outpoint.vout).map_err(|_| BuildError::UnsupportedInput(outpoint))?;
+            if !tx.add_utxo_with_details(
+                &outpoint.txid_hex(),
+                vout,
+                Koinu(input.utxo.amount),
+                &hex::encode(&input.utxo.script_pubkey),
+            ) {
+                return Err(BuildError::Ffi("add an input"));
+            }
+        }
+        if !tx.add_output(destination, batch.amount) {
+            return Err(BuildError::Ffi("add an output"));
+        }
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::COIN;
+    use crate::script::OutputKind;
+    use crate::utxo::OutPoint;
+
+    const SOURCE: &str = "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas";
+
+    fn utxo(id: u8, amount: u64) -> Utxo {
+        let kind = OutputKind::from_address(SOURCE).unwrap();
+        Utxo {
+            outpoint: OutPoint {
+                txid: [id; 32],
+                vout: 0,
+            },
+            script_pubkey: P2pkhTemplate.build(&kind).unwrap(),
+            amount,
+            height: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_batches_evenly_under_max_size() {
+        let three_inputs = (10 + 2 + 34 + 3 * 148) as usize;
+        let mut builder = ConsolidationBuilder::new(false, 1_000).with_max_size(three_inputs);
+        for id in 0..8 {
+            builder.add_utxo(utxo(id, COIN)).unwrap();
+        }
+        // Costs more to spend than it holds.
+        builder.add_utxo(utxo(8, 1_000 * 148)).unwrap();
+
+        let batches = builder.batches().unwrap();
+        let sizes: Vec<usize> = batches.iter().map(|b| b.inputs.len()).collect();
+        assert_eq!(sizes, [3, 3, 2]);
+        assert_eq!(
+            batches[0].amount,
+            Koinu(3 * COIN - 1_000 * three_inputs as u64)
+        );
+        assert!(batches
+            .iter()
+            .flat_map(|b| &b.inputs)
+            .all(|i| i.utxo.amount == COIN));
+
+        // Everything fits in one standard transaction by default.
+        let mut one = ConsolidationBuilder::new(false, 1_000);
+        for id in 0..8 {
+            one.add_utxo(utxo(id, COIN)).unwrap();
+        }
+        assert_eq!(one.batches().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_fails_before_ffi() {
+        let keys = HashMap::new();
+        let mut builder = ConsolidationBuilder::new(false, 1_000);
+        assert!(builder
+            .set_destination("nmLFkuoGdg6R56p6d7BAPZciaLk4JEd9kd")
+            .is_err());
+        assert!(matches!(builder.build(), Err(BuildError::NoRecipients)));
+
+        builder.set_destination(SOURCE).unwrap();
+        assert_eq!(builder.build_and_sign(&keys), Err(BuildError::NoInputs));
+        builder.add_utxo(utxo(1, 200_000)).unwrap();
+        builder.add_utxo(utxo(2, 200_000)).unwrap();
+        assert_eq!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::DustOutput(Koinu(
+                400_000 - 1_000 * (10 + 2 + 34 + 2 * 148)
+            )))
+        );
+        builder.add_utxo(utxo(3, COIN)).unwrap();
+        assert_eq!(
+            builder.build_and_sign(&keys),
+            Err(BuildError::MissingKey(SOURCE.to_string()))
+        );
+    }
+}
@@ -1,43 +1,158 @@
 //! Dogecoin ECC context management.
+//!
+//! libdogecoin keeps one process-wide secp256k1 context, seeded from the RNG
+//! when it starts. By default the crate starts it on first use and it lives
+//! until the process exits. A [`Session`] scopes it instead: the context
+//! starts with the first live session and is stopped, freeing its memory,
+//! when the last one is dropped. Crate calls made while a session is live
+//! hold one of their own until they return, and keys or HD nodes they create
+//! hold one while they exist, so the context never stops under them. Sessions
+//! can be opened and closed any number of times, so each test, or each load
+//! of a plugin that embeds the crate, gets a freshly seeded context.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::{DogeWallet, Session};
+//!
+//! for _ in 0..3 {
+//!     let session = Session::new();
+//!     let wallet = DogeWallet::new(false).unwrap();
+//!     drop(session); // The context is stopped here.
+//! }
+//! ```
 
-use crate::sys;
+use crate::ffi::sys;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+struct EccState {
+    sessions: usize,
+    started: bool,
+}
+
+static ECC: Mutex<EccState> = Mutex::new(EccState {
+    sessions: 0,
+    started: false,
+});
+
+/// Set once the context was started outside any session; it then stays up.
+static PINNED: AtomicBool = AtomicBool::new(false);
+
+fn lock_ecc() -> MutexGuard<'static, EccState> {
+    ECC.lock().unwrap_or_else(|e| e.into_inner())
+}
 
 /// Ensure ECC context is initialized (thread-safe).
-pub(crate) fn ensure_ecc_started() {
-    static INIT: std::sync::Once = std::sync::Once::new();
-    INIT.call_once(|| unsafe {
-        sys::dogecoin_ecc_start();
-    });
+///
+/// Outside any [`Session`], the context is started if needed and kept for
+/// the rest of the process, since callers without a session have nothing
+/// that marks when they are done with it. Inside one, the caller joins the
+/// live sessions instead, so another thread dropping the last user-held
+/// session cannot stop the context while the caller still uses it.
+///
+/// # Returns
+/// The session to hold for as long as the context is in use, or `None` if
+/// the context is pinned.
+#[must_use = "the context may be stopped once the guard is dropped"]
+pub(crate) fn ensure_ecc_started() -> Option<Session> {
+    if PINNED.load(Ordering::Acquire) {
+        return None;
+    }
+    let mut ecc = lock_ecc();
+    if ecc.sessions > 0 {
+        ecc.sessions += 1;
+        return Some(Session { _private: () });
+    }
+    if !ecc.started {
+        unsafe { sys::dogecoin_ecc_start() };
+        ecc.started = true;
+    }
+    PINNED.store(true, Ordering::Release);
+    None
 }
 
-/// Dogecoin ECC context.
+/// A scoped hold on libdogecoin's ECC context and RNG seeding.
+///
+/// The first live session starts the context; dropping the last one stops
+/// it. Dropping a session while other threads still use the crate is safe:
+/// their calls hold the context until they return.
 ///
-/// This struct manages the ECC context lifecycle.
-/// The context is started on creation and stopped when dropped.
-pub struct DogecoinContext {
-    // Placeholder for context management
+/// If the crate was used before any session existed, the context was
+/// started for the lifetime of the process and sessions leave it running.
+#[derive(Debug)]
+pub struct Session {
+    _private: (),
 }
 
-impl DogecoinContext {
-    /// Create a new ECC context.
+impl Session {
+    /// Open a session, starting and seeding the ECC context if needed.
     pub fn new() -> Self {
-        unsafe {
-            sys::dogecoin_ecc_start();
+        let mut ecc = lock_ecc();
+        if !ecc.started {
+            unsafe { sys::dogecoin_ecc_start() };
+            ecc.started = true;
         }
-        DogecoinContext {}
+        ecc.sessions += 1;
+        Session { _private: () }
+    }
+
+    /// Get the number of live sessions in the process, including those
+    /// held by crate calls in progress.
+    pub fn live_count() -> usize {
+        lock_ecc().sessions
     }
 }
 
-impl Default for DogecoinContext {
+impl Default for Session {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl Drop for DogecoinContext {
+impl Drop for Session {
     fn drop(&mut self) {
-        unsafe {
-            sys::dogecoin_ecc_stop();
+        let mut ecc = lock_ecc();
+        ecc.sessions -= 1;
+        if ecc.sessions == 0 && ecc.started && !PINNED.load(Ordering::Acquire) {
+            unsafe { sys::dogecoin_ecc_stop() };
+            ecc.started = false;
+        }
+    }
+}
+
+/// Dogecoin ECC context.
+#[deprecated(note = "use `Session`, which can be nested")]
+pub type DogecoinContext = Session;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calls_inside_a_session_hold_it() {
+        let session = Session::new();
+        let before = Session::live_count();
+        let guard = ensure_ecc_started();
+        if !PINNED.load(Ordering::Acquire) {
+            assert!(guard.is_some());
+            assert_eq!(Session::live_count(), before + 1);
+        }
+        // Dropping the user's session leaves the context to the guard.
+        drop(session);
+        assert!(lock_ecc().started);
+        drop(guard);
+    }
+
+    #[test]
+    fn test_sessions_nest_and_restart() {
+        for _ in 0..2 {
+            let outer = Session::new();
+            let before = Session::live_count();
+            let inner = Session::new();
+            assert_eq!(Session::live_count(), before + 1);
+            assert!(lock_ecc().started);
+            drop(inner);
+            drop(outer);
         }
     }
 }
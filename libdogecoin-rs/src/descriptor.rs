@@ -0,0 +1,248 @@
+//! Output descriptor checksums and canonical form.
+//!
+//! Dogecoin Core 1.14 has no descriptor wallet, but descriptors are the
+//! common way to hand watch-only setups between tools. A [`Descriptor`]
+//! checks the BIP380 checksum, if any, and rewrites the text into one
+//! canonical spelling, so two systems storing the same descriptor compare
+//! equal:
+//! - hardened path steps use `'` rather than `h` or `H`;
+//! - key origin fingerprints, hex public keys and `raw()` scripts are
+//!   lower-case.
+//!
+//! Only the text is handled; keys and scripts are not parsed or derived.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::descriptor::{checksum, Descriptor};
+//!
+//! assert_eq!(checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+//!
+//! let stored: Descriptor = "raw(DEADBEEF)".parse().unwrap();
+//! assert_eq!(stored.to_string(), "raw(deadbeef)#89f8spxm");
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Characters a descriptor may contain, in checksum symbol order.
+const INPUT_CHARSET: &str =
+    "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+
+const CHECKSUM_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+const GENERATOR: [u64; 5] = [
+    0xf5dee51989,
+    0xa9fdca3312,
+    0x1bab10e32d,
+    0x3706b1677a,
+    0x644d626ffd,
+];
+
+/// Errors from reading a descriptor.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum DescriptorError {
+    #[error("invalid character {0:?} in descriptor")]
+    InvalidCharacter(char),
+
+    #[error("descriptor checksum is {found:?}, expected {expected:?}")]
+    ChecksumMismatch { expected: String, found: String },
+}
+
+/// Compute the 8-character checksum of a descriptor without one.
+pub fn checksum(descriptor: &str) -> Result<String, DescriptorError> {
+    let mut c = 1u64;
+    let mut cls = 0u64;
+    let mut cls_count = 0;
+    for ch in descriptor.chars() {
+        let pos = INPUT_CHARSET
+            .find(ch)
+            .ok_or(DescriptorError::InvalidCharacter(ch))? as u64;
+        // Each character contributes its low 5 bits, and every three
+        // characters their high bits are packed into one more symbol.
+        c = polymod(c, pos & 31);
+        cls = cls * 3 + (pos >> 5);
+        cls_count += 1;
+        if cls_count == 3 {
+            c = polymod(c, cls);
+            cls = 0;
+            cls_count = 0;
+        }
+    }
+    if cls_count > 0 {
+        c = polymod(c, cls);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+    Ok((0..8)
+        .map(|i| CHECKSUM_CHARSET[((c >> (5 * (7 - i))) & 31) as usize] as char)
+        .collect())
+}
+
+fn polymod(c: u64, value: u64) -> u64 {
+    let top = c >> 35;
+    let mut c = ((c & 0x7_ffff_ffff) << 5) ^ value;
+    for (i, generator) in GENERATOR.iter().enumerate() {
+        if (top >> i) & 1 == 1 {
+            c ^= generator;
+        }
+    }
+    c
+}
+
+/// A descriptor in canonical form, with its checksum.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Descriptor {
+    body: String,
+    checksum: String,
+}
+
+impl Descriptor {
+    /// Read a descriptor, with or without a `#checksum` suffix.
+    ///
+    /// # Returns
+    /// An error if a checksum is given and does not match the text as
+    /// written, or the text has characters descriptors cannot contain.
+    pub fn parse(descriptor: &str) -> Result<Self, DescriptorError> {
+        let (body, given) = match descriptor.split_once('#') {
+            Some((body, given)) => (body, Some(given)),
+            None => (descriptor, None),
+        };
+        let expected = checksum(body)?;
+        if let Some(found) = given.filter(|&found| found != expected) {
+            return Err(DescriptorError::ChecksumMismatch {
+                expected,
+                found: found.to_string(),
+            });
+        }
+        let body = canonicalize(body);
+        let checksum = checksum(&body)?;
+        Ok(Descriptor { body, checksum })
+    }
+
+    /// Get the canonical descriptor without its checksum.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn checksum(&self) -> &str {
+        &self.checksum
+    }
+}
+
+impl fmt::Display for Descriptor {
+    /// Formats as the canonical descriptor with its checksum.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.body, self.checksum)
+    }
+}
+
+impl FromStr for Descriptor {
+    type Err = DescriptorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<Descriptor> for String {
+    fn from(descriptor: Descriptor) -> Self {
+        descriptor.to_string()
+    }
+}
+
+impl TryFrom<String> for Descriptor {
+    type Error = DescriptorError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+/// Rewrite each token between delimiters into its canonical spelling.
+fn canonicalize(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut before = None;
+    let mut rest = body;
+    loop {
+        let end = rest
+            .find(['(', ')', '[', ']', ',', '/'])
+            .unwrap_or(rest.len());
+        let (token, tail) = rest.split_at(end);
+        let is_hex = !token.is_empty() && token.bytes().all(|b| b.is_ascii_hexdigit());
+        let lower = before == Some('[') || out.ends_with("raw(") || matches!(token.len(), 66 | 130);
+        match token.strip_suffix(['h', 'H', '\'']) {
+            Some(index)
+                if before == Some('/')
+                    && !index.is_empty()
+                    && index.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                out.push_str(index);
+                out.push('\'');
+            }
+            _ if is_hex && lower => out.push_str(&token.to_ascii_lowercase()),
+            _ => out.push_str(token),
+        }
+        let Some(delimiter) = tail.chars().next() else {
+            return out;
+        };
+        out.push(delimiter);
+        before = Some(delimiter);
+        rest = &tail[1..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Account 0 xpub of the BIP39 "abandon ... about" seed.
+    const XPUB: &str = "dgub8rUhDtD3YFGZTUphBfpBbzvFxSMKQXYLzg87Me2ta78r2SdVLmypBUkkxrrn9RTnchsyiJSkHZyLWxD13ibBiXtuFWktBoDaGaZjQUBLNLs";
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        assert_eq!(
+            checksum("pkh(02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5)")
+                .unwrap(),
+            "8fhd9pwu"
+        );
+        assert_eq!(
+            checksum("raw(é)"),
+            Err(DescriptorError::InvalidCharacter('é'))
+        );
+        assert_eq!(
+            Descriptor::parse("raw(deadbeef)#89f8spxl"),
+            Err(DescriptorError::ChecksumMismatch {
+                expected: "89f8spxm".to_string(),
+                found: "89f8spxl".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_canonical_form_compares_equal() {
+        let written = format!("pkh([D34DB33F/44h/3h/0H]{XPUB}/0/*)#eyg56xuv");
+        let canonical = format!("pkh([d34db33f/44'/3'/0']{XPUB}/0/*)");
+        let a = Descriptor::parse(&written).unwrap();
+        let b = Descriptor::parse(&canonical).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.body(), canonical);
+        assert_eq!(a.to_string(), format!("{canonical}#kmlvtjcz"));
+        assert_eq!(a.to_string().parse(), Ok(b));
+
+        // Hex keys are lower-cased; base58 keys keep their case.
+        let key = "02C6047F9441ED7D6D3045406E95C07CD85C778E4B8CEF3CA7ABAC09B95C709EE5";
+        let pk = Descriptor::parse(&format!("sh(multi(1,{key},{XPUB}/1h/2))")).unwrap();
+        assert_eq!(
+            pk.body(),
+            format!("sh(multi(1,{},{XPUB}/1'/2))", key.to_ascii_lowercase())
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Helpers for integration tests against a regtest or testnet node
+//! (enabled by the `devtools` feature).
+//!
+//! [`DevNode`] wraps a [`DogeRpcClient`] whose node has a wallet loaded. It
+//! mines blocks on regtest, waits out coinbase maturity, and funds test
+//! addresses from the node's wallet. **Refuses to mine or spend on mainnet.**
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::devtools::DevNode;
+//! use libdogecoin_rs::{DogeRpcClient, Koinu};
+//!
+//! let client = DogeRpcClient::new("http://127.0.0.1:18332").with_basic_auth("user", "pass");
+//! let node = DevNode::connect(client).unwrap();
+//!
+//! let miner = "nZVmfmUtKPmskB9Ds4P9GUJy9eYFqPKHqH";
+//! let first = node.height().unwrap() + 1;
+//! node.mine_to_address(1, miner).unwrap();
+//! node.mine_until_mature(first, miner).unwrap();
+//! node.top_up("noVoUoC3q7wgmb25McqSdLCHhUAhSREkT9", Koinu::from_doge(1_000).unwrap())
+//!     .unwrap();
+//! ```
+
+use crate::consensus;
+use crate::koinu::Koinu;
+use crate::rpc::{DogeRpcClient, RpcError};
+use serde::Deserialize;
+
+/// Coinbase maturity on regtest, where it never changes with height.
+pub const REGTEST_COINBASE_MATURITY: u32 = 60;
+
+/// Errors from developer helpers.
+#[derive(thiserror::Error, Debug)]
+pub enum DevError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+
+    #[error("{0} is only allowed on regtest")]
+    RegtestOnly(&'static str),
+
+    #[error("refusing to spend on mainnet")]
+    Mainnet,
+
+    #[error("unknown chain {0:?}")]
+    UnknownChain(String),
+}
+
+/// The chain a node runs, as reported by `getblockchaininfo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainKind {
+    Main,
+    Test,
+    Regtest,
+}
+
+impl ChainKind {
+    /// Parse the `chain` field of `getblockchaininfo`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "main" => Some(ChainKind::Main),
+            "test" => Some(ChainKind::Test),
+            "regtest" => Some(ChainKind::Regtest),
+            _ => None,
+        }
+    }
+
+    /// Get the coinbase maturity for a spend in a block at `spend_height`.
+    pub fn coinbase_maturity(self, spend_height: u32) -> u32 {
+        match self {
+            ChainKind::Regtest => REGTEST_COINBASE_MATURITY,
+            _ => consensus::coinbase_maturity(spend_height),
+        }
+    }
+
+    /// Get how many blocks must be mined on top of `tip_height` before a
+    /// coinbase from `coinbase_height` can be spent in the next block.
+    pub fn blocks_until_mature(self, coinbase_height: u32, tip_height: u32) -> u32 {
+        let mut spend_height = tip_height.saturating_add(1);
+        // Maturity can grow as the spend height crosses DigiShield, so step
+        // until the rule at the candidate height is met.
+        loop {
+            let maturity = self.coinbase_maturity(spend_height);
+            if spend_height.saturating_sub(coinbase_height) >= maturity || spend_height == u32::MAX
+            {
+                break;
+            }
+            spend_height = coinbase_height
+                .saturating_add(maturity)
+                .max(spend_height + 1);
+        }
+        spend_height - tip_height.saturating_add(1)
+    }
+}
+
+#[derive(Deserialize)]
+struct BlockchainInfo {
+    chain: String,
+}
+
+/// A test node reached over JSON-RPC.
+#[derive(Debug, Clone)]
+pub struct DevNode {
+    client: DogeRpcClient,
+    chain: ChainKind,
+}
+
+impl DevNode {
+    /// Connect to a node and detect which chain it runs.
+    pub fn connect(client: DogeRpcClient) -> Result<Self, DevError> {
+        let info: BlockchainInfo = client.call("getblockchaininfo", serde_json::json!([]))?;
+        let chain = ChainKind::from_name(&info.chain).ok_or(DevError::UnknownChain(info.chain))?;
+        Ok(DevNode { client, chain })
+    }
+
+    pub fn chain(&self) -> ChainKind {
+        self.chain
+    }
+
+    pub fn client(&self) -> &DogeRpcClient {
+        &self.client
+    }
+
+    /// Get the height of the node's chain tip.
+    pub fn height(&self) -> Result<u32, DevError> {
+        Ok(self.client.call("getblockcount", serde_json::json!([]))?)
+    }
+
+    /// Mine `blocks` blocks paying their coinbase to `address` (regtest only).
+    ///
+    /// # Returns
+    /// The hashes of the new blocks.
+    pub fn mine_to_address(&self, blocks: u32, address: &str) -> Result<Vec<String>, DevError> {
+        if self.chain != ChainKind::Regtest {
+            return Err(DevError::RegtestOnly("mining"));
+        }
+        Ok(self
+            .client
+            .call("generatetoaddress", serde_json::json!([blocks, address]))?)
+    }
+
+    /// Mine until a coinbase from `coinbase_height` is spendable (regtest only).
+    ///
+    /// # Returns
+    /// The number of blocks mined (0 if it was already mature).
+    pub fn mine_until_mature(&self, coinbase_height: u32, address: &str) -> Result<u32, DevError> {
+        let needed = self
+            .chain
+            .blocks_until_mature(coinbase_height, self.height()?);
+        if needed > 0 {
+            self.mine_to_address(needed, address)?;
+        }
+        Ok(needed)
+    }
+
+    /// Send from the node's wallet so `address` holds at least `target`.
+    ///
+    /// The balance is read with `listunspent`, so the node's wallet must
+    /// track `address` (its own address, or added with `importaddress`).
+    ///
+    /// # Returns
+    /// The txid of the funding transaction, or `None` if no top-up was needed.
+    pub fn top_up(&self, address: &str, target: Koinu) -> Result<Option<String>, DevError> {
+        if self.chain == ChainKind::Main {
+            return Err(DevError::Mainnet);
+        }
+        let balance = self.client.utxo_balance(address, 0, 9_999_999)?;
+        let Some(shortfall) = target.checked_sub(balance).filter(|s| *s > Koinu::ZERO) else {
+            return Ok(None);
+        };
+        // Send the amount as a decimal string so it reaches the node exactly.
+        let amount = shortfall.to_string();
+        let txid = self
+            .client
+            .call("sendtoaddress", serde_json::json!([address, amount]))?;
+        Ok(Some(txid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_from_name() {
+        assert_eq!(ChainKind::from_name("regtest"), Some(ChainKind::Regtest));
+        assert_eq!(ChainKind::from_name("test"), Some(ChainKind::Test));
+        assert_eq!(ChainKind::from_name("signet"), None);
+    }
+
+    #[test]
+    fn test_blocks_until_mature() {
+        let regtest = ChainKind::Regtest;
+        assert_eq!(regtest.blocks_until_mature(1, 1), 59);
+        assert_eq!(regtest.blocks_until_mature(1, 60), 0);
+        assert_eq!(regtest.blocks_until_mature(101, 100), 60);
+
+        // Before DigiShield, 30 confirmations sufficed.
+        assert_eq!(ChainKind::Test.blocks_until_mature(1_000, 1_000), 29);
+        // A coinbase just below the switch needs 240 once the spend is past it.
+        assert_eq!(
+            ChainKind::Test.blocks_until_mature(144_990, 144_990),
+            144_990 + 240 - 144_991
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Proof-of-work targets and DigiShield difficulty retargeting.
+//!
+//! Since [`DIGISHIELD_HEIGHT`](crate::consensus::DIGISHIELD_HEIGHT) Dogecoin
+//! retargets every block from the time taken by the previous one, damped by
+//! an amplitude filter and clamped to -25%/+50% of the target timespan. These
+//! helpers reproduce that rule so header validators and mining tools can
+//! check the `nBits` of a block or template.
+
+use crate::consensus::{is_digishield_active, TARGET_SPACING};
+
+/// Compact encoding of the proof-of-work limit (`~0 >> 20`), on both networks.
+pub const POW_LIMIT_BITS: u32 = 0x1e0f_ffff;
+
+/// Timespan DigiShield retargets towards, in seconds.
+pub const DIGISHIELD_TARGET_TIMESPAN: i64 = TARGET_SPACING as i64;
+
+/// Testnet height from which slow blocks may be mined at minimum difficulty.
+pub const MIN_DIFFICULTY_HEIGHT_TESTNET: u32 = 157_500;
+
+/// The header fields difficulty retargeting depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderSample {
+    pub height: u32,
+    pub time: u32,
+    pub bits: u32,
+}
+
+/// Compute the `nBits` required for the block after `recent`.
+///
+/// # Arguments
+/// * `recent` - The latest headers in ascending height order; only the last
+///   two are used.
+/// * `new_block_time` - Timestamp of the block being checked or built. It only
+///   matters for the testnet minimum-difficulty rule.
+/// * `is_testnet` - Set to true for testnet, false for mainnet.
+///
+/// # Returns
+/// `None` if fewer than two consecutive headers are given, the next block is
+/// before DigiShield activation, or the last `nBits` is not a valid target.
+pub fn next_work_required(
+    recent: &[HeaderSample],
+    new_block_time: u32,
+    is_testnet: bool,
+) -> Option<u32> {
+    let [.., prev, last] = recent else {
+        return None;
+    };
+    if last.height != prev.height.checked_add(1)? || !is_digishield_active(last.height + 1) {
+        return None;
+    }
+
+    if is_testnet
+        && last.height >= MIN_DIFFICULTY_HEIGHT_TESTNET
+        && new_block_time as i64 > last.time as i64 + 2 * DIGISHIELD_TARGET_TIMESPAN
+    {
+        return Some(POW_LIMIT_BITS);
+    }
+
+    digishield_retarget(last.bits, last.time as i64 - prev.time as i64)
+}
+
+/// Apply one DigiShield retarget step.
+///
+/// # Arguments
+/// * `last_bits` - `nBits` of the last block.
+/// * `actual_timespan` - Seconds between the last block and its parent (may
+///   be negative, since timestamps are not monotonic).
+///
+/// # Returns
+/// The new `nBits`, or `None` if `last_bits` is not a valid target.
+pub fn digishield_retarget(last_bits: u32, actual_timespan: i64) -> Option<u32> {
+    let timespan = DIGISHIELD_TARGET_TIMESPAN;
+    let modulated = timespan + (actual_timespan - timespan) / 8;
+    let min = timespan - timespan / 4;
+    let max = timespan + timespan / 2;
+    let modulated = modulated.clamp(min, max);
+
+    let mut target = Target::from_compact(last_bits)?;
+    target.mul_small(modulated as u32);
+    target.div_small(timespan as u32);
+
+    let limit = Target::from_compact(POW_LIMIT_BITS)?;
+    Some(target.min(limit).to_compact())
+}
+
+/// Expand compact `nBits` into a big-endian 256-bit target.
+///
+/// # Returns
+/// `None` for negative or overflowing encodings.
+pub fn compact_to_target(bits: u32) -> Option<[u8; 32]> {
+    Target::from_compact(bits).map(|t| t.to_be_bytes())
+}
+
+/// Compress a big-endian 256-bit target into `nBits`.
+pub fn target_to_compact(target: &[u8; 32]) -> u32 {
+    Target::from_be_bytes(target).to_compact()
+}
+
+/// Unsigned 256-bit integer as little-endian 32-bit limbs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Target([u32; 8]);
+
+impl Target {
+    fn from_compact(bits: u32) -> Option<Self> {
+        let size = (bits >> 24) as usize;
+        let mut word = bits & 0x007f_ffff;
+        if word != 0 && bits & 0x0080_0000 != 0 {
+            return None;
+        }
+        let mut bytes = [0u8; 32];
+        if size <= 3 {
+            word >>= 8 * (3 - size);
+            bytes[29..].copy_from_slice(&word.to_be_bytes()[1..]);
+        } else {
+            let word_bytes = &word.to_be_bytes()[1..];
+            let start = 32usize.checked_sub(size)?;
+            // Mantissa bytes shifted past the top must be zero.
+            for (i, b) in word_bytes.iter().enumerate() {
+                match bytes.get_mut(start + i) {
+                    Some(slot) => *slot = *b,
+                    None if *b == 0 => {}
+                    None => return None,
+                }
+            }
+        }
+        Some(Self::from_be_bytes(&bytes))
+    }
+
+    fn to_compact(self) -> u32 {
+        let bytes = self.to_be_bytes();
+        let size = 32 - bytes.iter().take_while(|b| **b == 0).count();
+        let mut word = if size <= 3 {
+            let mut low = [0u8; 4];
+            low[1..].copy_from_slice(&bytes[29..]);
+            u32::from_be_bytes(low) << (8 * (3 - size))
+        } else {
+            let top = &bytes[32 - size..32 - size + 3];
+            u32::from_be_bytes([0, top[0], top[1], top[2]])
+        };
+        let mut size = size as u32;
+        if word & 0x0080_0000 != 0 {
+            word >>= 8;
+            size += 1;
+        }
+        word | (size << 24)
+    }
+
+    fn from_be_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0u32; 8];
+        for (i, chunk) in bytes.rchunks_exact(4).enumerate() {
+            limbs[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        Target(limbs)
+    }
+
+    fn to_be_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, chunk) in bytes.rchunks_exact_mut(4).enumerate() {
+            chunk.copy_from_slice(&self.0[i].to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Multiply in place, saturating to all ones on overflow.
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0u64;
+        for limb in self.0.iter_mut() {
+            let product = *limb as u64 * factor as u64 + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry != 0 {
+            self.0 = [u32::MAX; 8];
+        }
+    }
+
+    fn div_small(&mut self, divisor: u32) {
+        let mut rem = 0u64;
+        for limb in self.0.iter_mut().rev() {
+            let cur = (rem << 32) | *limb as u64;
+            *limb = (cur / divisor as u64) as u32;
+            rem = cur % divisor as u64;
+        }
+    }
+}
+
+impl PartialOrd for Target {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Target {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(height: u32, time: u32, bits: u32) -> HeaderSample {
+        HeaderSample { height, time, bits }
+    }
+
+    #[test]
+    fn test_compact_roundtrip() {
+        let target = compact_to_target(POW_LIMIT_BITS).unwrap();
+        assert_eq!(&target[..5], &[0x00, 0x00, 0x0f, 0xff, 0xff]);
+        assert!(target[5..].iter().all(|b| *b == 0));
+        assert_eq!(target_to_compact(&target), POW_LIMIT_BITS);
+
+        for bits in [0x1b01a7c8, 0x1a0d2a7b, 0x0300ffff, 0x0200ff00] {
+            assert_eq!(target_to_compact(&compact_to_target(bits).unwrap()), bits);
+        }
+        assert!(compact_to_target(0x1d80ffff).is_none());
+        assert!(compact_to_target(0x23012345).is_none());
+    }
+
+    #[test]
+    fn test_digishield_retarget() {
+        assert_eq!(digishield_retarget(0x1b01a7c8, 60), Some(0x1b01a7c8));
+        assert_eq!(digishield_retarget(0x1b01a7c8, 600), Some(0x1b027bac));
+        assert_eq!(digishield_retarget(0x1b01a7c8, 0), Some(0x1b017657));
+        assert_eq!(digishield_retarget(0x1b01a7c8, -300), Some(0x1b013dd6));
+        assert_eq!(digishield_retarget(0x1a0d2a7b, 72), Some(0x1a0d62a7));
+        assert_eq!(digishield_retarget(0x1e0ffff0, 600), Some(POW_LIMIT_BITS));
+    }
+
+    #[test]
+    fn test_next_work_required() {
+        let headers = [
+            sample(200_000, 1_000, 0x1b01a7c8),
+            sample(200_001, 1_600, 0x1b01a7c8),
+        ];
+        assert_eq!(next_work_required(&headers, 1_700, false), Some(0x1b027bac));
+        assert_eq!(
+            next_work_required(&headers, 1_900, true),
+            Some(POW_LIMIT_BITS)
+        );
+        assert_eq!(next_work_required(&headers, 1_700, true), Some(0x1b027bac));
+
+        assert_eq!(next_work_required(&headers[..1], 1_700, false), None);
+        let gap = [headers[0], sample(200_005, 1_600, 0x1b01a7c8)];
+        assert_eq!(next_work_required(&gap, 1_700, false), None);
+        let legacy = [sample(100, 0, 0x1e0ffff0), sample(101, 60, 0x1e0ffff0)];
+        assert_eq!(next_work_required(&legacy, 120, false), None);
+    }
+}
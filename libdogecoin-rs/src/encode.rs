@@ -0,0 +1,157 @@
+//! Consensus wire encoding shared by transactions and blocks.
+
+#[cfg(feature = "full")]
+use crate::transaction::DecodeError;
+
+/// Cursor over a byte slice that hands out borrowed sub-slices.
+#[cfg(feature = "full")]
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+#[cfg(feature = "full")]
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// Read a hash, reversing it into display order.
+    pub(crate) fn hash(&mut self) -> Result<[u8; 32], DecodeError> {
+        let mut hash: [u8; 32] = self.array()?;
+        hash.reverse();
+        Ok(hash)
+    }
+
+    /// Read a CompactSize integer, rejecting non-canonical encodings.
+    pub(crate) fn compact_size(&mut self) -> Result<u64, DecodeError> {
+        let (value, min) = match self.u8()? {
+            0xfd => (u16::from_le_bytes(self.array()?) as u64, 0xfd),
+            0xfe => (u32::from_le_bytes(self.array()?) as u64, 0x1_0000),
+            0xff => (self.u64()?, 0x1_0000_0000),
+            n => return Ok(n as u64),
+        };
+        if value < min {
+            return Err(DecodeError::NonCanonical);
+        }
+        Ok(value)
+    }
+
+    /// Read an item count, bounded by the bytes left so a corrupt count
+    /// cannot trigger a huge allocation.
+    pub(crate) fn count(&mut self) -> Result<usize, DecodeError> {
+        let count = self.compact_size()?;
+        if count > self.bytes.len() as u64 {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        Ok(count as usize)
+    }
+
+    /// Read length-prefixed bytes.
+    pub(crate) fn var_bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.count()?;
+        self.take(len)
+    }
+
+    /// Fail unless every byte was consumed.
+    pub(crate) fn finish(self) -> Result<(), DecodeError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::TrailingData(self.bytes.len()))
+        }
+    }
+}
+
+pub(crate) fn write_compact_size(out: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0xfc => out.push(value as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(value as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+pub(crate) fn write_var_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Write a display-order hash in wire order.
+#[cfg(feature = "full")]
+pub(crate) fn write_hash(out: &mut Vec<u8>, hash: &[u8; 32]) {
+    out.extend(hash.iter().rev());
+}
+
+#[cfg(all(test, feature = "full"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_size_roundtrip() {
+        for value in [0, 0xfc, 0xfd, 0xffff, 0x1_0000, 0xffff_ffff, u64::MAX] {
+            let mut out = Vec::new();
+            write_compact_size(&mut out, value);
+            let mut reader = Reader::new(&out);
+            assert_eq!(reader.compact_size(), Ok(value));
+            assert!(reader.finish().is_ok());
+        }
+        assert_eq!(
+            Reader::new(&[0xfd, 0x10, 0x00]).compact_size(),
+            Err(DecodeError::NonCanonical)
+        );
+        assert_eq!(
+            Reader::new(&[0xfe, 0x00]).compact_size(),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_count_is_bounded_by_input() {
+        assert_eq!(
+            Reader::new(&[0xfe, 0xff, 0xff, 0xff, 0x7f]).count(),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Reader::new(&[0x02, 0xaa]).var_bytes(),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Crate-wide error type.
+
+/// Errors reported by the safe wrappers.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("input contains an interior NUL byte")]
+    NulInInput,
+
+    #[error("libdogecoin returned non-UTF-8 data")]
+    NonUtf8Output,
+
+    #[error("libdogecoin output is not NUL-terminated within its {capacity}-byte buffer")]
+    UnterminatedOutput { capacity: usize },
+
+    #[error("libdogecoin returned a null pointer")]
+    NullOutput,
+
+    #[error("libdogecoin {function} failed with code {code}")]
+    Ffi { function: &'static str, code: i32 },
+
+    #[error("malformed {0} rejected before reaching libdogecoin")]
+    MalformedInput(&'static str),
+
+    // Wallets.
+    #[error("a key generation worker thread panicked")]
+    WorkerPanicked,
+
+    #[error("invalid extended key")]
+    InvalidExtendedKey,
+
+    #[error("invalid derivation path {0:?}")]
+    InvalidPath(String),
+
+    #[error("{preset} has no account {account}")]
+    NoSuchAccount { preset: &'static str, account: u32 },
+
+    #[error("index {0} is out of range")]
+    IndexOutOfRange(u32),
+
+    #[error("index store: {0}")]
+    IndexStore(String),
+
+    #[error("invalid amount {0:?}")]
+    InvalidAmount(String),
+
+    // Mnemonics.
+    #[error("unsupported entropy size {0:?}")]
+    InvalidEntropySize(String),
+
+    #[error("PBKDF2 iteration count must be non-zero")]
+    ZeroIterations,
+
+    // QR codes.
+    #[error("payload does not fit in a QR code")]
+    QrCapacity,
+
+    #[error("no readable QR code found")]
+    QrNotFound,
+
+    #[error("chunk length {0} cannot hold a character")]
+    ChunkTooSmall(usize),
+
+    #[error("payload needs {0} QR parts, more than a sequence may have")]
+    TooManyParts(usize),
+
+    #[error("malformed QR sequence part")]
+    MalformedPart,
+
+    #[error("QR part belongs to a different sequence")]
+    ForeignPart,
+
+    #[error("QR sequence incomplete: {received} of {total} parts")]
+    IncompleteSequence { received: usize, total: usize },
+
+    #[error("reassembled payload fails its checksum")]
+    ChecksumMismatch,
+
+    #[error("I/O: {0}")]
+    Io(String),
+
+    #[error("image: {0}")]
+    Image(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.to_string())
+    }
+}
+
+/// Result type using the crate [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,241 @@
+//! Crate-wide event stream.
+//!
+//! Components that observe the chain or the wallet publish [`Event`]s on an
+//! [`EventBus`]; applications subscribe once, through a channel or a
+//! callback, and receive every event from every component attached to that
+//! bus. Currently [`DogeRpcClient`](crate::DogeRpcClient) publishes broadcast
+//! results when built with `with_event_bus`.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::events::{Event, EventBus};
+//!
+//! let bus = EventBus::new();
+//! let events = bus.subscribe();
+//! bus.emit(Event::NewBlock { height: 5_000_000, hash: "ab".repeat(32) });
+//! assert!(matches!(events.try_recv(), Ok(Event::NewBlock { height: 5_000_000, .. })));
+//! ```
+
+use crate::ffi::guard_callback;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// Something that happened on the chain or in the wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A block became the new chain tip.
+    NewBlock { height: u32, hash: String },
+
+    /// A transaction was first seen (in the mempool or as just broadcast).
+    TxSeen { txid: String },
+
+    /// A transaction was included in a block.
+    TxConfirmed { txid: String, height: u32 },
+
+    /// The chain reorganized; blocks above `fork_height` were replaced.
+    Reorg {
+        fork_height: u32,
+        old_tip: String,
+        new_tip: String,
+    },
+
+    /// The wallet balance changed, in koinu.
+    BalanceChanged { old: u64, new: u64 },
+
+    /// A transaction could not be broadcast.
+    BroadcastFailed { reason: String },
+}
+
+/// Handle returned by [`EventBus::on`], used to unsubscribe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Callback = Arc<dyn Fn(&Event) + Send + Sync>;
+
+enum Subscriber {
+    Channel(Sender<Event>),
+    Callback(Callback),
+}
+
+/// A cloneable publish/subscribe hub; clones share their subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<(SubscriptionId, Subscriber)>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl EventBus {
+    /// Create a bus with no subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe through a channel.
+    ///
+    /// The subscription ends when the receiver is dropped.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (tx, rx) = mpsc::channel();
+        self.add(Subscriber::Channel(tx));
+        rx
+    }
+
+    /// Subscribe with a callback, run on the emitting thread.
+    ///
+    /// Callbacks should return quickly; hand heavy work to another thread.
+    /// A callback that panics is contained: the emitter and the remaining
+    /// subscribers carry on, and the callback stays subscribed.
+    pub fn on<F>(&self, callback: F) -> SubscriptionId
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        self.add(Subscriber::Callback(Arc::new(callback)))
+    }
+
+    /// Remove a callback subscription.
+    ///
+    /// # Returns
+    /// `true` if the subscription existed.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut subscribers = self.lock();
+        let before = subscribers.len();
+        subscribers.retain(|(sid, _)| *sid != id);
+        subscribers.len() != before
+    }
+
+    /// Deliver `event` to every subscriber.
+    pub fn emit(&self, event: Event) {
+        let callbacks: Vec<Callback> = {
+            let mut subscribers = self.lock();
+            // Sending also prunes channels whose receiver is gone.
+            subscribers.retain(|(_, sub)| match sub {
+                Subscriber::Channel(tx) => tx.send(event.clone()).is_ok(),
+                Subscriber::Callback(_) => true,
+            });
+            subscribers
+                .iter()
+                .filter_map(|(_, sub)| match sub {
+                    Subscriber::Callback(cb) => Some(Arc::clone(cb)),
+                    Subscriber::Channel(_) => None,
+                })
+                .collect()
+        };
+        // Run callbacks without the lock so they may subscribe or emit.
+        for callback in callbacks {
+            guard_callback((), || callback(&event));
+        }
+    }
+
+    /// Get the number of live subscriptions.
+    pub fn subscriber_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn add(&self, subscriber: Subscriber) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.lock().push((id, subscriber));
+        id
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(SubscriptionId, Subscriber)>> {
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.subscriber_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_channel_and_callback_subscribers() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        let id = bus.on(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let event = Event::TxSeen {
+            txid: "00".repeat(32),
+        };
+        bus.clone().emit(event.clone());
+        assert_eq!(rx.try_recv(), Ok(event));
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        bus.emit(Event::BalanceChanged { old: 0, new: 1 });
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_dropped_receivers_are_pruned() {
+        let bus = EventBus::new();
+        drop(bus.subscribe());
+        assert_eq!(bus.subscriber_count(), 1);
+        bus.emit(Event::BroadcastFailed {
+            reason: "rejected".into(),
+        });
+        assert_eq!(bus.subscriber_count(), 0);
+    }
+
+    #[test]
+    fn test_panicking_callback_is_contained() {
+        let bus = EventBus::new();
+        bus.on(|_| panic!("subscriber bug"));
+        let rx = bus.subscribe();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        bus.on(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        for height in [1, 2] {
+            bus.emit(Event::NewBlock {
+                height,
+                hash: "h".into(),
+            });
+        }
+        assert_eq!(seen.load(Ordering::SeqCst), 2);
+        assert_eq!(rx.try_iter().count(), 2);
+        assert_eq!(bus.subscriber_count(), 3);
+    }
+
+    #[test]
+    fn test_callback_may_reenter_bus() {
+        let bus = EventBus::new();
+        let inner = bus.clone();
+        bus.on(move |event| {
+            if let Event::NewBlock { height, .. } = event {
+                inner.emit(Event::TxConfirmed {
+                    txid: "tx".into(),
+                    height: *height,
+                });
+            }
+        });
+        let rx = bus.subscribe();
+        bus.emit(Event::NewBlock {
+            height: 7,
+            hash: "h".into(),
+        });
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(Event::NewBlock { height: 7, .. })
+        ));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(Event::TxConfirmed { height: 7, .. })
+        ));
+    }
+}
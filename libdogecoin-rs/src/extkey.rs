@@ -0,0 +1,352 @@
+//! Extended (BIP32) keys.
+//!
+//! Typed wrappers around serialized extended private/public keys as produced
+//! by libdogecoin (`dgpv`/`dgub` on mainnet, `tprv`/`tpub` on testnet), and
+//! conversion from and to the Bitcoin `xprv`/`xpub` version bytes some
+//! tooling emits for Dogecoin keys.
+
+use crate::address::{base58check_decode, base58check_encode};
+use crate::error::{Error, Result};
+use crate::ffi::{checked_cstring, is_base58_byte, MAX_EXTKEY_LEN};
+use crate::fingerprint::KeyFingerprint;
+use crate::hdnode::HdNode;
+use crate::hdwallet::Chain;
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// A serialized BIP32 extended key.
+///
+/// Private keys are zeroized on drop and redacted from `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedKey {
+    key: Zeroizing<String>,
+    path: Option<String>,
+}
+
+impl ExtendedKey {
+    /// Wrap a serialized extended key.
+    ///
+    /// # Returns
+    /// `None` if the string does not carry a known Dogecoin extended key prefix.
+    pub fn parse(key: &str) -> Option<Self> {
+        key_kind(key)?;
+        Some(ExtendedKey {
+            key: Zeroizing::new(key.to_string()),
+            path: None,
+        })
+    }
+
+    pub(crate) fn with_path(key: String, path: &str) -> Self {
+        ExtendedKey {
+            key: Zeroizing::new(key),
+            path: Some(path.to_string()),
+        }
+    }
+
+    /// Get the serialized key.
+    pub fn as_str(&self) -> &str {
+        self.key.as_str()
+    }
+
+    /// Get the derivation path this key was derived at, if known.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Whether this is an extended private key.
+    pub fn is_private(&self) -> bool {
+        key_kind(&self.key).is_some_and(|(private, _)| private)
+    }
+
+    /// Whether this key is for testnet.
+    pub fn is_testnet(&self) -> bool {
+        key_kind(&self.key).is_some_and(|(_, testnet)| testnet)
+    }
+
+    /// Derive the P2PKH address at `chain/index` below this key, using
+    /// public derivation so an account xpub is enough.
+    ///
+    /// # Arguments
+    /// * `chain` - External (receiving) or internal (change) chain.
+    /// * `index` - Address index; must not be hardened.
+    pub fn derive_address(&self, chain: Chain, index: u32) -> Result<String> {
+        let key = checked_cstring(&self.key, MAX_EXTKEY_LEN, is_base58_byte, "extended key")
+            .map_err(|_| Error::InvalidExtendedKey)
+            .map(Zeroizing::new)?;
+        HdNode::deserialize(&key)?
+            .public_child(chain.is_change() as u32)?
+            .public_child(index)?
+            .p2pkh_address()
+    }
+
+    /// Get a [`KeyFingerprint`] of the public key, safe to log.
+    ///
+    /// # Returns
+    /// `None` for private keys, or if the key fails to decode.
+    pub fn fingerprint(&self) -> Option<KeyFingerprint> {
+        if self.is_private() {
+            return None;
+        }
+        let payload = base58check_decode(&self.key)?;
+        let pubkey = payload
+            .get(PAYLOAD_LEN - 33..)
+            .filter(|_| payload.len() == PAYLOAD_LEN)?;
+        Some(KeyFingerprint::of_public_key(pubkey))
+    }
+}
+
+impl fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = if self.is_private() {
+            "<redacted>"
+        } else {
+            self.key.as_str()
+        };
+        f.debug_struct("ExtendedKey")
+            .field("key", &key)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Length of a decoded extended key: version, depth, parent fingerprint,
+/// child number, chain code and key.
+const PAYLOAD_LEN: usize = 78;
+
+/// Version-byte scheme of a serialized extended key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScheme {
+    /// `dgpv`/`dgub`.
+    Dogecoin,
+    /// `tprv`/`tpub`.
+    DogecoinTestnet,
+    /// `xprv`/`xpub`, as used by Bitcoin tooling.
+    Bitcoin,
+}
+
+impl KeyScheme {
+    /// Detect the scheme of a serialized key by its prefix.
+    ///
+    /// # Returns
+    /// The scheme and whether the key is private.
+    pub fn of(key: &str) -> Option<(Self, bool)> {
+        match key.get(..4)? {
+            "dgpv" => Some((KeyScheme::Dogecoin, true)),
+            "dgub" => Some((KeyScheme::Dogecoin, false)),
+            "tprv" => Some((KeyScheme::DogecoinTestnet, true)),
+            "tpub" => Some((KeyScheme::DogecoinTestnet, false)),
+            "xprv" => Some((KeyScheme::Bitcoin, true)),
+            "xpub" => Some((KeyScheme::Bitcoin, false)),
+            _ => None,
+        }
+    }
+
+    /// Get the version bytes for a private or public key.
+    pub fn version(self, private: bool) -> [u8; 4] {
+        let version: u32 = match (self, private) {
+            (KeyScheme::Dogecoin, true) => 0x02fa_c398,
+            (KeyScheme::Dogecoin, false) => 0x02fa_cafd,
+            (KeyScheme::DogecoinTestnet, true) => 0x0435_8394,
+            (KeyScheme::DogecoinTestnet, false) => 0x0435_87cf,
+            (KeyScheme::Bitcoin, true) => 0x0488_ade4,
+            (KeyScheme::Bitcoin, false) => 0x0488_b21e,
+        };
+        version.to_be_bytes()
+    }
+}
+
+/// Something a caller must know about a converted key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionWarning {
+    /// The key came from Bitcoin tooling.
+    FromBitcoin,
+    /// The key is being handed to Bitcoin tooling.
+    ToBitcoin,
+    /// The key moves between mainnet and testnet.
+    NetworkChange,
+}
+
+impl fmt::Display for ConversionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConversionWarning::FromBitcoin => {
+                "WARNING: key came from Bitcoin tooling; check it was derived along a \
+                 Dogecoin path (coin type 3'), not Bitcoin's (coin type 0')"
+            }
+            ConversionWarning::ToBitcoin => {
+                "WARNING: Bitcoin software will show Bitcoin addresses for this key; \
+                 never send DOGE to them"
+            }
+            ConversionWarning::NetworkChange => {
+                "WARNING: key moved between mainnet and testnet; its addresses change"
+            }
+        })
+    }
+}
+
+/// The result of [`convert_key`]. Always check [`warnings`](Self::warnings).
+#[must_use = "conversions carry warnings that should be shown to the user"]
+pub struct Conversion {
+    /// The re-encoded key.
+    pub key: Zeroizing<String>,
+    pub from: KeyScheme,
+    pub to: KeyScheme,
+    pub is_private: bool,
+    pub warnings: Vec<ConversionWarning>,
+}
+
+impl Conversion {
+    /// Wrap the converted key, if it is a Dogecoin key.
+    pub fn into_extended_key(self) -> Option<ExtendedKey> {
+        ExtendedKey::parse(&self.key)
+    }
+}
+
+impl fmt::Debug for Conversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = if self.is_private {
+            "<redacted>"
+        } else {
+            self.key.as_str()
+        };
+        f.debug_struct("Conversion")
+            .field("key", &key)
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("warnings", &self.warnings)
+            .finish()
+    }
+}
+
+/// Re-encode an extended key under another version-byte scheme.
+///
+/// The checksum of `key` is verified and a new one computed; nothing but the
+/// four version bytes changes. Converting between Dogecoin and Bitcoin
+/// schemes, or between networks, adds [`ConversionWarning`]s.
+///
+/// # Example
+/// ```no_run
+/// use libdogecoin_rs::extkey::{convert_key, KeyScheme};
+///
+/// let conversion = convert_key("xprv9s21ZrQH143K...", KeyScheme::Dogecoin).unwrap();
+/// for warning in &conversion.warnings {
+///     eprintln!("{warning}");
+/// }
+/// let key = conversion.into_extended_key().unwrap();
+/// ```
+///
+/// # Returns
+/// `None` if the key has an unknown prefix, a bad checksum or the wrong
+/// length.
+pub fn convert_key(key: &str, to: KeyScheme) -> Option<Conversion> {
+    let (from, is_private) = KeyScheme::of(key)?;
+    let mut payload = Zeroizing::new(base58check_decode(key)?);
+    if payload.len() != PAYLOAD_LEN || payload[..4] != from.version(is_private) {
+        return None;
+    }
+    payload[..4].copy_from_slice(&to.version(is_private));
+
+    let mut warnings = Vec::new();
+    if from == KeyScheme::Bitcoin && to != KeyScheme::Bitcoin {
+        warnings.push(ConversionWarning::FromBitcoin);
+    }
+    if to == KeyScheme::Bitcoin && from != KeyScheme::Bitcoin {
+        warnings.push(ConversionWarning::ToBitcoin);
+    }
+    let testnet = |scheme| scheme == KeyScheme::DogecoinTestnet;
+    if testnet(from) != testnet(to) {
+        warnings.push(ConversionWarning::NetworkChange);
+    }
+
+    Some(Conversion {
+        key: Zeroizing::new(base58check_encode(&payload)?),
+        from,
+        to,
+        is_private,
+        warnings,
+    })
+}
+
+/// Classify a serialized key as `(is_private, is_testnet)` by its prefix.
+pub(crate) fn key_kind(key: &str) -> Option<(bool, bool)> {
+    match key.get(..4)? {
+        "dgpv" => Some((true, false)),
+        "dgub" => Some((false, false)),
+        "tprv" => Some((true, true)),
+        "tpub" => Some((false, true)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prefixes() {
+        let xpub =
+            ExtendedKey::parse("dgub8kXBZ7ymNWy2S8Q3jNgVjFUm5ZJ3QLLaSTdAA89ukSv7Q6MSXwE").unwrap();
+        assert!(!xpub.is_private());
+        assert!(!xpub.is_testnet());
+        assert_eq!(xpub.path(), None);
+
+        let tprv = ExtendedKey::parse("tprv8ZgxMBicQKsPd").unwrap();
+        assert!(tprv.is_private());
+        assert!(tprv.is_testnet());
+
+        assert!(ExtendedKey::parse("xprv9s21ZrQH143K").is_none());
+        assert!(ExtendedKey::parse("dg").is_none());
+    }
+
+    #[test]
+    fn test_key_schemes() {
+        assert_eq!(
+            KeyScheme::of("xpub6Bxse8AT19u9"),
+            Some((KeyScheme::Bitcoin, false))
+        );
+        assert_eq!(
+            KeyScheme::of("tprv8ZgxMBicQKsPd"),
+            Some((KeyScheme::DogecoinTestnet, true))
+        );
+        assert_eq!(KeyScheme::of("ypub"), None);
+        assert_eq!(KeyScheme::Dogecoin.version(true), [0x02, 0xfa, 0xc3, 0x98]);
+        assert!(ConversionWarning::ToBitcoin
+            .to_string()
+            .starts_with("WARNING"));
+    }
+
+    #[test]
+    fn test_convert_key() {
+        let dgpv = "dgpv51eADS3spNJh98bWAfYnAW8K1gMy86HKmH1dpyT8kLsUKBqssT3jsLLFWyK4zbruL51UjejFDzrFzBcwjjA57rSv6D2978QigKG4xbCfJV6";
+        let xprv = "xprv9s21ZrQH143K3GJpoapnV8SFfukcVBSfeCficPSGfubmSFDxo1kuHnLisriDvSnRRuL2Qrg5ggqHKNVpxR86QEC8w35uxmGoggxtQTPvfUu";
+
+        let to_bitcoin = convert_key(dgpv, KeyScheme::Bitcoin).unwrap();
+        assert_eq!(to_bitcoin.key.as_str(), xprv);
+        assert_eq!(to_bitcoin.warnings, [ConversionWarning::ToBitcoin]);
+        assert!(!format!("{to_bitcoin:?}").contains("xprv9"));
+
+        let back = convert_key(xprv, KeyScheme::Dogecoin).unwrap();
+        assert_eq!(back.warnings, [ConversionWarning::FromBitcoin]);
+        assert_eq!(back.into_extended_key().unwrap().as_str(), dgpv);
+
+        let xpub = "xpub6Bxse8AT19u9HExKtP1EAudLi9CpLxPpxDvanL2fFtM7UFE2Q7TTWRg4bnMnmT4KcyN6GQkSgZmPWDtyUywSii3MDpMNfXSTuzH7gvZywLU";
+        let dgub = convert_key(xpub, KeyScheme::Dogecoin).unwrap();
+        assert_eq!(
+            dgub.key.as_str(),
+            "dgub8rUhDtD3YFGZTUphBfpBbzvFxSMKQXYLzg87Me2ta78r2SdVLmypBUkkxrrn9RTnchsyiJSkHZyLWxD13ibBiXtuFWktBoDaGaZjQUBLNLs"
+        );
+
+        let mut corrupted = xprv.to_string();
+        corrupted.pop();
+        corrupted.push('v');
+        assert!(convert_key(&corrupted, KeyScheme::Dogecoin).is_none());
+    }
+
+    #[test]
+    fn test_debug_redacts_private_keys() {
+        let key = ExtendedKey::with_path("dgpvSECRET".to_string(), "m/44'/3'/0'");
+        let debug = format!("{key:?}");
+        assert!(!debug.contains("SECRET"));
+        assert!(debug.contains("m/44'/3'/0'"));
+    }
+}
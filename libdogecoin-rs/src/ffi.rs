@@ -0,0 +1,540 @@
+//! Strict conversions at the FFI boundary and libdogecoin buffer sizes.
+//!
+//! Output buffers are sized from the constants in `libdogecoin.h`; where the
+//! header's value is known to be too tight the buffer is padded, as noted.
+//!
+//! # Panics and unwinding
+//! Calls only go from Rust into C; libdogecoin is never handed a Rust
+//! function pointer. Any `extern "C"` trampoline added for C-driven hooks
+//! must run its body through [`guard_callback`] so a panic is caught on the
+//! Rust side instead of unwinding through C frames. Callbacks the crate runs
+//! itself, such as [`EventBus`](crate::EventBus) subscribers, are guarded the
+//! same way.
+//!
+//! libdogecoin does not `longjmp`; on allocation failure it exits the
+//! process, and secp256k1 aborts on violated preconditions. Neither can be
+//! contained from Rust, which is why untrusted input is checked here before
+//! it reaches C.
+//!
+//! # Upgrading libdogecoin
+//! Modules call libdogecoin through [`sys`], never `libdogecoin_sys`
+//! directly. Functions whose C signatures have shifted between releases
+//! (`char*` parameters gaining `const`, flags moving between `dogecoin_bool`
+//! and `bool`) are wrapped there with one Rust signature, so a vendored
+//! update that changes them is fixed in that module alone.
+//!
+//! `libdogecoin-sys` reports the vendored release's major.minor version and
+//! `build.rs` turns it into a `libdogecoin = "x.y"` cfg. The wrappers are
+//! gated on that cfg; a release without its own set fails to build with a
+//! `compile_error!` instead of compiling against mismatched bindings.
+
+use crate::error::{Error, Result};
+use std::ffi::{c_char, CStr, CString};
+#[cfg(feature = "full")]
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// WIF private key output of `generatePrivPubKeypair`: 52 chars + NUL
+/// (`PRIVKEYWIFLEN`).
+pub(crate) const WIF_BUF_LEN: usize = 53;
+
+/// P2PKH address outputs (`generatePrivPubKeypair`, `generateHDMasterPubKeypair`,
+/// `generateDerivedHDPubkey`, `getDerivedHDAddressFromMnemonic`,
+/// `dogecoin_hdnode_get_p2pkh_address`). Addresses are 34 chars + NUL
+/// (`P2PKHLEN` is 35); padded for safety.
+pub(crate) const P2PKH_BUF_LEN: usize = 64;
+
+/// Serialized extended key outputs (`generateHDMasterPubKeypair`,
+/// `getDerivedHDAddress*`, `dogecoin_hdnode_serialize_*`). libdogecoin documents
+/// `HDKEYLEN` as 128 although keys are 111 chars + NUL.
+#[cfg(feature = "full")]
+pub(crate) const HD_KEY_BUF_LEN: usize = 128;
+
+/// Mnemonic output of `generateRandomEnglishMnemonic` (`MAX_MNEMONIC_SIZE`).
+#[cfg(feature = "full")]
+pub(crate) const MNEMONIC_BUF_LEN: usize = 1024;
+
+/// Amount output of `koinu_to_coins_str`: up to 20 integer digits, a point
+/// and 8 decimals + NUL; padded for safety.
+#[cfg(feature = "full")]
+pub(crate) const KOINU_STR_BUF_LEN: usize = 32;
+
+/// Longest address passed to libdogecoin: addresses are 34 chars, one spare.
+pub(crate) const MAX_ADDRESS_LEN: usize = 35;
+
+/// Longest WIF private key passed to libdogecoin (`PRIVKEYWIFLEN` - 1).
+pub(crate) const MAX_WIF_LEN: usize = WIF_BUF_LEN - 1;
+
+/// Longest serialized extended key passed to libdogecoin: 111 chars.
+#[cfg(feature = "full")]
+pub(crate) const MAX_EXTKEY_LEN: usize = 111;
+
+/// Longest hex scriptPubKey passed to libdogecoin (`MAX_SCRIPT_SIZE` bytes).
+#[cfg(feature = "full")]
+pub(crate) const MAX_SCRIPT_HEX_LEN: usize = 2 * 10_000;
+
+/// Longest Base64 message signature: a 65-byte compact signature.
+pub(crate) const MAX_SIGNATURE_B64_LEN: usize = 88;
+
+/// Run a user callback, returning `fallback` if it panics.
+///
+/// The panic is still reported by the panic hook, but stops here: it never
+/// unwinds into the caller, which may be C code or a loop over other
+/// callbacks.
+#[cfg(feature = "full")]
+pub(crate) fn guard_callback<R>(fallback: R, callback: impl FnOnce() -> R) -> R {
+    catch_unwind(AssertUnwindSafe(callback)).unwrap_or(fallback)
+}
+
+/// Convert a Rust string for an FFI call, rejecting interior NUL bytes.
+pub(crate) fn to_cstring(s: &str) -> Result<CString> {
+    CString::new(s).map_err(|_| Error::NulInInput)
+}
+
+/// Convert untrusted text for an FFI call, rejecting it if it is empty,
+/// longer than `max_len` bytes, or has a byte outside `allowed`.
+///
+/// libdogecoin decodes many inputs into fixed-size or length-sized stack
+/// buffers without checking, so oversized or malformed input stops here.
+pub(crate) fn checked_cstring(
+    s: &str,
+    max_len: usize,
+    allowed: fn(u8) -> bool,
+    what: &'static str,
+) -> Result<CString> {
+    if s.is_empty() || s.len() > max_len || !s.bytes().all(allowed) {
+        return Err(Error::MalformedInput(what));
+    }
+    to_cstring(s)
+}
+
+/// Like [`checked_cstring`] for an even-length hex string.
+#[cfg(feature = "full")]
+pub(crate) fn hex_cstring(s: &str, max_len: usize, what: &'static str) -> Result<CString> {
+    if !s.len().is_multiple_of(2) {
+        return Err(Error::MalformedInput(what));
+    }
+    checked_cstring(s, max_len, |b| b.is_ascii_hexdigit(), what)
+}
+
+/// Whether `b` is in the Base58 alphabet.
+pub(crate) fn is_base58_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() && !matches!(b, b'0' | b'O' | b'I' | b'l')
+}
+
+/// Whether `b` is in the standard Base64 alphabet or padding.
+pub(crate) fn is_base64_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'=')
+}
+
+/// Read a NUL-terminated UTF-8 string written into `buf` by libdogecoin.
+pub(crate) fn string_from_buf(buf: &[u8]) -> Result<String> {
+    let cstr = CStr::from_bytes_until_nul(buf).map_err(|_| Error::UnterminatedOutput {
+        capacity: buf.len(),
+    })?;
+    cstr_to_string(cstr)
+}
+
+/// Read a NUL-terminated UTF-8 string returned by libdogecoin.
+///
+/// # Safety
+/// `ptr` must be null or point to a valid NUL-terminated string.
+pub(crate) unsafe fn string_from_ptr(ptr: *const c_char) -> Result<String> {
+    if ptr.is_null() {
+        return Err(Error::NullOutput);
+    }
+    cstr_to_string(unsafe { CStr::from_ptr(ptr) })
+}
+
+fn cstr_to_string(cstr: &CStr) -> Result<String> {
+    cstr.to_str()
+        .map(str::to_owned)
+        .map_err(|_| Error::NonUtf8Output)
+}
+
+/// libdogecoin as the safe modules call it.
+///
+/// Every symbol the crate uses has an explicit wrapper here; nothing from
+/// `libdogecoin_sys` is re-exported wholesale, so a vendored update that
+/// changes a signature fails to compile in this module rather than at a call
+/// site. Input strings are `*const c_char` and flags `bool` whatever the
+/// headers declare. The wrappers are selected by the `libdogecoin` cfg that
+/// `build.rs` sets from the vendored release.
+#[cfg(libdogecoin = "0.1")]
+#[allow(non_snake_case)]
+pub(crate) mod sys {
+    #[cfg(feature = "full")]
+    pub(crate) use libdogecoin_sys::{dogecoin_chainparams, dogecoin_hdnode};
+
+    use libdogecoin_sys as raw;
+    use std::ffi::{c_char, c_int, c_void};
+
+    /// Forward to the binding of the same name, whose signature already
+    /// matches.
+    macro_rules! forward {
+        ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)?;)*) => {
+            $(
+                $(#[$attr])*
+                pub(crate) unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                    unsafe { raw::$name($($arg),*) }
+                }
+            )*
+        };
+    }
+
+    forward! {
+        fn dogecoin_ecc_start();
+        fn dogecoin_ecc_stop();
+        fn dogecoin_free(ptr: *mut c_void);
+        fn isMainnetFromB58Prefix(address: *const c_char) -> u8;
+        fn isTestnetFromB58Prefix(address: *const c_char) -> u8;
+        fn dogecoin_base58_encode_check(
+            data: *const u8,
+            len: usize,
+            out: *mut c_char,
+            out_len: usize
+        ) -> usize;
+        fn dogecoin_base58_decode_check(encoded: *const c_char, out: *mut u8, out_len: usize) -> c_int;
+        fn dogecoin_ecc_sign_compact_recoverable(
+            private_key: *const u8,
+            hash: *const u8,
+            sigrec: *mut u8,
+            outlen: *mut usize,
+            recid: *mut c_int
+        ) -> u8;
+        fn dogecoin_ecc_recover_pubkey(
+            sigrec: *const u8,
+            hash: *const u8,
+            recid: c_int,
+            public_key: *mut u8,
+            outlen: *mut usize
+        ) -> u8;
+        #[cfg(feature = "full")]
+        fn dogecoin_ecc_sign(
+            private_key: *const u8,
+            hash: *const u8,
+            sigder: *mut u8,
+            outlen: *mut usize
+        ) -> u8;
+        #[cfg(feature = "full")]
+        fn koinu_to_coins_str(koinu: u64, out: *mut c_char) -> c_int;
+        #[cfg(feature = "full")]
+        fn pbkdf2_hmac_sha512(
+            pass: *const u8,
+            pass_len: i32,
+            salt: *const u8,
+            salt_len: i32,
+            iterations: u32,
+            key: *mut u8,
+            key_len: i32
+        );
+        #[cfg(feature = "full")]
+        fn get_words(lang: *const c_char) -> *const *const c_char;
+        #[cfg(feature = "shamir")]
+        fn dogecoin_random_bytes(buf: *mut u8, len: u32, update_seed: u8) -> u8;
+        #[cfg(feature = "full")]
+        fn qrgen_p2pkh_to_qrbits(address: *const c_char, out: *mut u8) -> c_int;
+        #[cfg(feature = "full")]
+        fn qrgen_p2pkh_to_qr_string(address: *const c_char, out: *mut c_char) -> c_int;
+        #[cfg(feature = "full")]
+        fn qrgen_string_to_qr_pngfile(
+            filename: *const c_char,
+            data: *const c_char,
+            size_multiplier: u8
+        ) -> c_int;
+        #[cfg(feature = "full")]
+        fn qrgen_string_to_qr_jpgfile(
+            filename: *const c_char,
+            data: *const c_char,
+            size_multiplier: u8
+        ) -> c_int;
+        #[cfg(feature = "full")]
+        fn start_transaction() -> c_int;
+        #[cfg(feature = "full")]
+        fn get_raw_transaction(tx_index: c_int) -> *mut c_char;
+        #[cfg(feature = "full")]
+        fn clear_transaction(tx_index: c_int);
+        #[cfg(feature = "full")]
+        fn remove_all();
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_new() -> *mut dogecoin_hdnode;
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_copy(node: *const dogecoin_hdnode) -> *mut dogecoin_hdnode;
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_free(node: *mut dogecoin_hdnode);
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_deserialize(
+            key: *const c_char,
+            chain: *const dogecoin_chainparams,
+            node: *mut dogecoin_hdnode
+        ) -> u8;
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_private_ckd(node: *mut dogecoin_hdnode, index: u32) -> u8;
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_public_ckd(node: *mut dogecoin_hdnode, index: u32) -> u8;
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_get_p2pkh_address(
+            node: *const dogecoin_hdnode,
+            chain: *const dogecoin_chainparams,
+            out: *mut c_char,
+            out_len: c_int
+        );
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_serialize_public(
+            node: *const dogecoin_hdnode,
+            chain: *const dogecoin_chainparams,
+            out: *mut c_char,
+            out_len: usize
+        );
+        #[cfg(feature = "full")]
+        fn dogecoin_hdnode_serialize_private(
+            node: *const dogecoin_hdnode,
+            chain: *const dogecoin_chainparams,
+            out: *mut c_char,
+            out_len: usize
+        );
+    }
+
+    /// Mainnet chain parameters.
+    #[cfg(feature = "full")]
+    pub(crate) fn dogecoin_chainparams_main() -> *const dogecoin_chainparams {
+        std::ptr::addr_of!(raw::dogecoin_chainparams_main)
+    }
+
+    /// Testnet chain parameters.
+    #[cfg(feature = "full")]
+    pub(crate) fn dogecoin_chainparams_test() -> *const dogecoin_chainparams {
+        std::ptr::addr_of!(raw::dogecoin_chainparams_test)
+    }
+
+    pub(crate) unsafe fn generatePrivPubKeypair(
+        wif_privkey: *mut c_char,
+        p2pkh_pubkey: *mut c_char,
+        is_testnet: bool,
+    ) -> c_int {
+        unsafe { raw::generatePrivPubKeypair(wif_privkey, p2pkh_pubkey, is_testnet as u8) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn generateHDMasterPubKeypair(
+        hd_privkey: *mut c_char,
+        p2pkh_pubkey: *mut c_char,
+        is_testnet: bool,
+    ) -> c_int {
+        unsafe { raw::generateHDMasterPubKeypair(hd_privkey, p2pkh_pubkey, is_testnet as u8) }
+    }
+
+    pub(crate) unsafe fn verifyP2pkhAddress(address: *const c_char, len: usize) -> c_int {
+        unsafe { raw::verifyP2pkhAddress(address.cast_mut(), len) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn generateRandomEnglishMnemonic(
+        entropy_size: *const c_char,
+        mnemonic: *mut c_char,
+    ) -> c_int {
+        unsafe { raw::generateRandomEnglishMnemonic(entropy_size.cast_mut(), mnemonic) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn dogecoin_seed_from_mnemonic(
+        mnemonic: *const c_char,
+        passphrase: *const c_char,
+        seed: *mut u8,
+    ) -> c_int {
+        unsafe {
+            raw::dogecoin_seed_from_mnemonic(mnemonic.cast_mut(), passphrase.cast_mut(), seed)
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn getDerivedHDAddressFromMnemonic(
+        account: u32,
+        index: u32,
+        change_level: *const c_char,
+        mnemonic: *const c_char,
+        passphrase: *const c_char,
+        p2pkh_pubkey: *mut c_char,
+        is_testnet: bool,
+    ) -> c_int {
+        unsafe {
+            raw::getDerivedHDAddressFromMnemonic(
+                account,
+                index,
+                change_level.cast_mut(),
+                mnemonic.cast_mut(),
+                passphrase.cast_mut(),
+                p2pkh_pubkey,
+                is_testnet,
+            )
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn coins_to_koinu_str(coins: *const c_char) -> u64 {
+        unsafe { raw::coins_to_koinu_str(coins.cast_mut()) }
+    }
+
+    pub(crate) unsafe fn sign_message(
+        privkey: *const c_char,
+        message: *const c_char,
+    ) -> *mut c_char {
+        unsafe { raw::sign_message(privkey.cast_mut(), message.cast_mut()) }
+    }
+
+    pub(crate) unsafe fn verify_message(
+        signature: *const c_char,
+        message: *const c_char,
+        address: *const c_char,
+    ) -> c_int {
+        unsafe { raw::verify_message(signature.cast_mut(), message.cast_mut(), address.cast_mut()) }
+    }
+
+    pub(crate) unsafe fn dogecoin_ecc_get_pubkey(
+        private_key: *const u8,
+        public_key: *mut u8,
+        public_key_len: *mut usize,
+        compressed: bool,
+    ) {
+        unsafe {
+            raw::dogecoin_ecc_get_pubkey(private_key, public_key, public_key_len, compressed as u8)
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn dogecoin_ecc_verify_sig(
+        public_key: *const u8,
+        compressed: bool,
+        hash: *const u8,
+        sigder: *const u8,
+        siglen: usize,
+    ) -> u8 {
+        unsafe {
+            raw::dogecoin_ecc_verify_sig(
+                public_key,
+                compressed as u8,
+                hash,
+                sigder.cast_mut(),
+                siglen,
+            )
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn qrgen_p2pkh_consoleprint_to_qr(address: *const c_char) {
+        unsafe { raw::qrgen_p2pkh_consoleprint_to_qr(address.cast_mut()) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn add_utxo(tx_index: c_int, txid: *const c_char, vout: c_int) -> c_int {
+        unsafe { raw::add_utxo(tx_index, txid.cast_mut(), vout) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn add_output(
+        tx_index: c_int,
+        address: *const c_char,
+        amount: *const c_char,
+    ) -> c_int {
+        unsafe { raw::add_output(tx_index, address.cast_mut(), amount.cast_mut()) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn finalize_transaction(
+        tx_index: c_int,
+        destination: *const c_char,
+        fee: *const c_char,
+        amount: *const c_char,
+        change_address: *const c_char,
+    ) -> *mut c_char {
+        unsafe {
+            raw::finalize_transaction(
+                tx_index,
+                destination.cast_mut(),
+                fee.cast_mut(),
+                amount.cast_mut(),
+                change_address.cast_mut(),
+            )
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn sign_transaction(
+        tx_index: c_int,
+        script_pubkey: *const c_char,
+        privkey: *const c_char,
+    ) -> c_int {
+        unsafe { raw::sign_transaction(tx_index, script_pubkey.cast_mut(), privkey.cast_mut()) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn sign_transaction_w_privkey(
+        tx_index: c_int,
+        vout_index: c_int,
+        privkey: *const c_char,
+    ) -> c_int {
+        unsafe { raw::sign_transaction_w_privkey(tx_index, vout_index, privkey.cast_mut()) }
+    }
+
+    #[cfg(feature = "full")]
+    pub(crate) unsafe fn store_raw_transaction(raw_hex: *const c_char) -> c_int {
+        unsafe { raw::store_raw_transaction(raw_hex.cast_mut()) }
+    }
+}
+
+#[cfg(not(libdogecoin = "0.1"))]
+compile_error!("ffi::sys has no wrappers for the vendored libdogecoin release");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_from_buf() {
+        assert_eq!(string_from_buf(b"DAddr\0junk"), Ok("DAddr".to_string()));
+        assert_eq!(string_from_buf(b"\0"), Ok(String::new()));
+        assert_eq!(
+            string_from_buf(b"no terminator"),
+            Err(Error::UnterminatedOutput { capacity: 13 })
+        );
+        assert_eq!(string_from_buf(b"\xff\xfe\0"), Err(Error::NonUtf8Output));
+    }
+
+    #[test]
+    fn test_to_cstring_rejects_nul() {
+        assert!(to_cstring("ok").is_ok());
+        assert_eq!(to_cstring("a\0b"), Err(Error::NulInInput));
+    }
+
+    #[test]
+    fn test_checked_cstring() {
+        let address = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+        assert!(checked_cstring(address, MAX_ADDRESS_LEN, is_base58_byte, "address").is_ok());
+        for bad in [
+            "",
+            "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC00",
+            "DBus0",
+            "DBus\0",
+        ] {
+            assert_eq!(
+                checked_cstring(bad, MAX_ADDRESS_LEN, is_base58_byte, "address"),
+                Err(Error::MalformedInput("address"))
+            );
+        }
+        #[cfg(feature = "full")]
+        {
+            assert!(hex_cstring("00ff", 4, "script").is_ok());
+            assert!(hex_cstring("0ff", 4, "script").is_err());
+            assert!(hex_cstring("00ff00", 4, "script").is_err());
+        }
+    }
+
+    #[test]
+    fn test_string_from_ptr() {
+        let owned = CString::new("raw").unwrap();
+        assert_eq!(unsafe { string_from_ptr(owned.as_ptr()) }, Ok("raw".into()));
+        assert_eq!(
+            unsafe { string_from_ptr(std::ptr::null()) },
+            Err(Error::NullOutput)
+        );
+    }
+}
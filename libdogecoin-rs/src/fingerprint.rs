@@ -0,0 +1,137 @@
+//! Identifiers that are safe to log.
+//!
+//! A [`WalletId`] names a wallet by its first account's extended public key
+//! and a [`KeyFingerprint`] names a single public key. Both are truncated
+//! SHA-256 hashes, so multi-wallet deployments can attribute operations in
+//! logs and audit trails without recording key material. A watch-only
+//! service holding only the account xpub computes the same [`WalletId`] as
+//! the signing wallet.
+//!
+//! These are not BIP32 key fingerprints, which hash with HASH160.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::HdWallet;
+//!
+//! let wallet = HdWallet::new(false).unwrap();
+//! println!("wallet {} derived an address", wallet.wallet_id().unwrap());
+//! ```
+
+use crate::extkey::ExtendedKey;
+use crate::hash::sha256;
+use crate::hex;
+use std::fmt;
+use std::str::FromStr;
+
+/// Stable identifier of an HD wallet: the first 8 bytes of SHA-256 of its
+/// account 0 extended public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct WalletId([u8; 8]);
+
+impl WalletId {
+    /// Compute the identifier from an account-level extended public key.
+    ///
+    /// # Returns
+    /// `None` if `xpub` is a private key, which must never be hashed into
+    /// something that is logged.
+    pub fn from_xpub(xpub: &ExtendedKey) -> Option<Self> {
+        if xpub.is_private() {
+            return None;
+        }
+        let digest = sha256(xpub.as_str().as_bytes());
+        digest[..8].try_into().ok().map(WalletId)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 8] {
+        &self.0
+    }
+}
+
+/// Short identifier of a public key: the first 4 bytes of its SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct KeyFingerprint([u8; 4]);
+
+impl KeyFingerprint {
+    /// Fingerprint a serialized public key.
+    pub fn of_public_key(pubkey: &[u8]) -> Self {
+        let digest = sha256(pubkey);
+        KeyFingerprint([digest[0], digest[1], digest[2], digest[3]])
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+}
+
+macro_rules! hex_id {
+    ($name:ident, $what:literal) => {
+        impl fmt::Display for $name {
+            /// Formats as lower-case hex.
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&hex::encode(&self.0))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                hex::decode_array(s)
+                    .map($name)
+                    .ok_or_else(|| format!("invalid {} {s:?}", $what))
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.to_string()
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = String;
+
+            fn try_from(s: String) -> Result<Self, Self::Error> {
+                s.parse()
+            }
+        }
+    };
+}
+
+hex_id!(WalletId, "wallet id");
+hex_id!(KeyFingerprint, "key fingerprint");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Account 0 xpub of the BIP39 "abandon ... about" seed.
+    const ABANDON_ACCOUNT_XPUB: &str = "dgub8rUhDtD3YFGZTUphBfpBbzvFxSMKQXYLzg87Me2ta78r2SdVLmypBUkkxrrn9RTnchsyiJSkHZyLWxD13ibBiXtuFWktBoDaGaZjQUBLNLs";
+
+    #[test]
+    fn test_wallet_id_from_xpub() {
+        let xpub = ExtendedKey::parse(ABANDON_ACCOUNT_XPUB).unwrap();
+        let id = WalletId::from_xpub(&xpub).unwrap();
+        assert_eq!(id.to_string().len(), 16);
+        assert_eq!(id.to_string().parse::<WalletId>(), Ok(id));
+        assert_eq!(WalletId::from_xpub(&xpub), Some(id));
+
+        let xprv = ExtendedKey::parse(&ABANDON_ACCOUNT_XPUB.replacen("dgub", "dgpv", 1)).unwrap();
+        assert_eq!(WalletId::from_xpub(&xprv), None);
+        assert!("00".parse::<KeyFingerprint>().is_err());
+
+        let fingerprint = xpub.fingerprint().unwrap();
+        assert_eq!(fingerprint.to_string().parse(), Ok(fingerprint));
+        assert_eq!(xprv.fingerprint(), None);
+    }
+}
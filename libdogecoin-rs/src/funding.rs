@@ -0,0 +1,272 @@
+//! Funding transactions from a node's view of an address or account.
+//!
+//! [`fund_transaction`] asks a Dogecoin Core node for the unspent outputs of
+//! a [`FundingSource`], runs coin selection for the recipients at a fee
+//! rate, adds change and returns the unsigned [`DogeTransaction`]. Inputs
+//! carry their amount and scriptPubKey, so the result knows its fee and
+//! each input's address for signing.
+//!
+//! [`sweep_wif`] redeems a paper wallet: it sends everything the key's
+//! address holds, less the fee, to one destination in a signed transaction.
+//!
+//! The node must track the source addresses, e.g. after importing them
+//! with [`DogeRpcClient::import_multi`].
+//!
+//! This module is enabled by the `rpc` feature.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::funding::{fund_transaction, FundingSource};
+//! use libdogecoin_rs::{DogeRpcClient, Koinu};
+//!
+//! let client = DogeRpcClient::new("http://127.0.0.1:22555").with_basic_auth("user", "pass");
+//! let source = FundingSource::Address("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas");
+//! let recipients = [("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", Koinu::from_doge(10).unwrap())];
+//! let mut tx = fund_transaction(&client, &source, &recipients, 1_000, None)?;
+//! tx.sign_with_privkey(0, "private_key_wif");
+//! # Ok::<(), libdogecoin_rs::funding::FundError>(())
+//! ```
+
+use crate::address::{AddressNetwork, AddressUtils};
+use crate::builder::{BuildError, TransactionBuilder};
+use crate::extkey::ExtendedKey;
+use crate::hdwallet::Chain;
+use crate::hex;
+use crate::koinu::Koinu;
+use crate::rpc::{DogeRpcClient, RpcError};
+use crate::transaction::DogeTransaction;
+use crate::utxo::{OutPoint, Utxo};
+use std::collections::HashMap;
+
+/// Confirmations an output needs before it is spent.
+pub const MIN_CONFIRMATIONS: u32 = 1;
+
+/// `listunspent`'s default upper bound on confirmations.
+const MAX_CONFIRMATIONS: u32 = 9_999_999;
+
+/// Errors from funding a transaction.
+#[derive(thiserror::Error, Debug)]
+pub enum FundError {
+    #[error("invalid source address {0:?}")]
+    InvalidSource(String),
+
+    #[error("invalid private key")]
+    InvalidKey,
+
+    #[error("no spendable outputs at {0}")]
+    NoFunds(String),
+
+    #[error("failed to derive a source address: {0}")]
+    Derive(#[from] crate::error::Error),
+
+    #[error("node listed an unreadable output {txid}:{vout}")]
+    InvalidUnspent { txid: String, vout: u32 },
+
+    #[error(transparent)]
+    Build(#[from] BuildError),
+
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+}
+
+/// Where the inputs come from.
+#[derive(Debug, Clone, Copy)]
+pub enum FundingSource<'a> {
+    /// One P2PKH address. Change returns to it unless another address is
+    /// given.
+    Address(&'a str),
+    /// The first `lookahead` receiving and change addresses of an account
+    /// extended key, e.g. from [`HdWallet::account_xpub`]. A change address
+    /// must be given.
+    ///
+    /// [`HdWallet::account_xpub`]: crate::HdWallet::account_xpub
+    Xpub {
+        key: &'a ExtendedKey,
+        lookahead: u32,
+    },
+}
+
+impl FundingSource<'_> {
+    /// Get the network, the addresses to fund from, and where change goes
+    /// by default.
+    fn resolve(&self) -> Result<(bool, Vec<String>, Option<String>), FundError> {
+        match *self {
+            FundingSource::Address(address) => {
+                let is_testnet = match AddressUtils::network(address) {
+                    AddressNetwork::Mainnet => false,
+                    AddressNetwork::Testnet => true,
+                    AddressNetwork::Unknown => {
+                        return Err(FundError::InvalidSource(address.to_string()))
+                    }
+                };
+                let address = address.to_string();
+                Ok((is_testnet, vec![address.clone()], Some(address)))
+            }
+            FundingSource::Xpub { key, lookahead } => {
+                let mut addresses = Vec::with_capacity(2 * lookahead as usize);
+                for chain in [Chain::External, Chain::Internal] {
+                    for index in 0..lookahead {
+                        addresses.push(key.derive_address(chain, index)?);
+                    }
+                }
+                Ok((key.is_testnet(), addresses, None))
+            }
+        }
+    }
+}
+
+/// Build an unsigned transaction paying `recipients` from `source`.
+///
+/// # Arguments
+/// * `client` - The node to list unspent outputs from.
+/// * `source` - The address or account to spend from.
+/// * `recipients` - Addresses and amounts to pay.
+/// * `fee_rate` - Fee rate in koinu per byte.
+/// * `change_address` - Where change goes; defaults to the source address.
+///
+/// # Returns
+/// The unsigned transaction. Recipients are checked before the node is
+/// called.
+pub fn fund_transaction(
+    client: &DogeRpcClient,
+    source: &FundingSource<'_>,
+    recipients: &[(&str, Koinu)],
+    fee_rate: u64,
+    change_address: Option<&str>,
+) -> Result<DogeTransaction, FundError> {
+    let (is_testnet, addresses, default_change) = source.resolve()?;
+    if recipients.is_empty() {
+        return Err(BuildError::NoRecipients.into());
+    }
+    let mut builder = TransactionBuilder::new(is_testnet, fee_rate);
+    for &(address, amount) in recipients {
+        builder.add_recipient(address, amount)?;
+    }
+    if let Some(address) = change_address.or(default_change.as_deref()) {
+        builder.set_change_address(address)?;
+    }
+
+    for utxo in list_utxos(client, &addresses)? {
+        builder.add_utxo(utxo)?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Send the whole balance of a private key's address to `destination`.
+///
+/// # Arguments
+/// * `privkey_wif` - The key to sweep, e.g. from a paper wallet.
+/// * `destination` - Where the funds go, on the key's network.
+/// * `client` - The node to list unspent outputs from.
+/// * `fee_rate` - Fee rate in koinu per byte.
+///
+/// # Returns
+/// The signed transaction hex, paying the balance less the fee. The key
+/// and destination are checked before the node is called.
+pub fn sweep_wif(
+    privkey_wif: &str,
+    destination: &str,
+    client: &DogeRpcClient,
+    fee_rate: u64,
+) -> Result<String, FundError> {
+    let address = AddressUtils::address_from_wif(privkey_wif).ok_or(FundError::InvalidKey)?;
+    let is_testnet = AddressUtils::diagnose(&address)
+        .map_err(|_| FundError::InvalidKey)?
+        .network
+        == AddressNetwork::Testnet;
+    let mut builder = TransactionBuilder::new(is_testnet, fee_rate).with_subtract_fee_from(0);
+    builder.set_change_address(destination)?;
+
+    let utxos = list_utxos(client, std::slice::from_ref(&address))?;
+    if utxos.is_empty() {
+        return Err(FundError::NoFunds(address));
+    }
+    let balance = utxos
+        .iter()
+        .try_fold(Koinu::ZERO, |total, utxo| {
+            total.checked_add(Koinu(utxo.amount))
+        })
+        .ok_or(BuildError::Overflow)?;
+    builder.add_recipient(destination, balance)?;
+    for utxo in utxos {
+        builder.add_utxo(utxo)?;
+    }
+    let keys = HashMap::from([(address, privkey_wif.to_string())]);
+    Ok(builder.build_and_sign(&keys)?)
+}
+
+/// List the confirmed unspent outputs of `addresses`.
+fn list_utxos(client: &DogeRpcClient, addresses: &[String]) -> Result<Vec<Utxo>, FundError> {
+    let entries = client.list_unspent(MIN_CONFIRMATIONS, MAX_CONFIRMATIONS, addresses)?;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let invalid = || FundError::InvalidUnspent {
+                txid: entry.txid.clone(),
+                vout: entry.vout,
+            };
+            Ok(Utxo {
+                outpoint: OutPoint::from_hex(&entry.txid, entry.vout).ok_or_else(invalid)?,
+                script_pubkey: hex::decode(&entry.script_pub_key).ok_or_else(invalid)?,
+                amount: entry.amount.0,
+                height: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPIENT: &str = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+
+    #[test]
+    fn test_checks_before_calling_node() {
+        // Nothing listens here; every case fails before a request is sent.
+        let client = DogeRpcClient::new("http://127.0.0.1:1");
+        let amount = Koinu::ONE_DOGE;
+
+        let source = FundingSource::Address("not-an-address");
+        assert!(matches!(
+            fund_transaction(&client, &source, &[(RECIPIENT, amount)], 1_000, None),
+            Err(FundError::InvalidSource(_))
+        ));
+
+        let source = FundingSource::Address("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas");
+        assert!(matches!(
+            fund_transaction(&client, &source, &[], 1_000, None),
+            Err(FundError::Build(BuildError::NoRecipients))
+        ));
+        assert!(matches!(
+            fund_transaction(&client, &source, &[(RECIPIENT, Koinu(1))], 1_000, None),
+            Err(FundError::Build(BuildError::DustOutput(_)))
+        ));
+        let testnet_change = Some("nmLFkuoGdg6R56p6d7BAPZciaLk4JEd9kd");
+        assert!(matches!(
+            fund_transaction(
+                &client,
+                &source,
+                &[(RECIPIENT, amount)],
+                1_000,
+                testnet_change
+            ),
+            Err(FundError::Build(BuildError::InvalidAddress { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_sweep_checks_before_calling_node() {
+        let client = DogeRpcClient::new("http://127.0.0.1:1");
+        assert!(matches!(
+            sweep_wif("not-a-key", RECIPIENT, &client, 1_000),
+            Err(FundError::InvalidKey)
+        ));
+        // A testnet key cannot sweep to a mainnet address.
+        let testnet_key = "cfY6uYff8D1EDQ3doALA15crcrJdg67x5aKhUuWeoeoHUEPA5NiY";
+        assert!(matches!(
+            sweep_wif(testnet_key, RECIPIENT, &client, 1_000),
+            Err(FundError::Build(BuildError::InvalidAddress { .. }))
+        ));
+    }
+}
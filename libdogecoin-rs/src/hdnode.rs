@@ -0,0 +1,205 @@
+//! Owned wrapper around libdogecoin's native BIP32 node.
+
+use crate::context::Session;
+use crate::error::{Error, Result};
+use crate::extkey::key_kind;
+use crate::ffi::{string_from_buf, sys, HD_KEY_BUF_LEN, P2PKH_BUF_LEN};
+use crate::key::SigningKey;
+use std::ffi::CStr;
+use zeroize::Zeroizing;
+
+/// Flag marking a hardened child index.
+pub(crate) const HARDENED: u32 = 0x8000_0000;
+
+/// A `dogecoin_hdnode` owned by Rust and freed on drop.
+pub(crate) struct HdNode {
+    node: *mut sys::dogecoin_hdnode,
+    chain: *const sys::dogecoin_chainparams,
+    /// Keeps the ECC context up while the node derives children.
+    _ecc: Option<Session>,
+}
+
+impl HdNode {
+    /// Parse a serialized extended key.
+    ///
+    /// The network is taken from the key's prefix, as libdogecoin's own
+    /// derivation functions do.
+    pub(crate) fn deserialize(key: &CStr) -> Result<Self> {
+        let (_, is_testnet) = key
+            .to_str()
+            .ok()
+            .and_then(key_kind)
+            .ok_or(Error::InvalidExtendedKey)?;
+        let ecc = crate::context::ensure_ecc_started();
+
+        let chain = chain_params(is_testnet);
+        let node = unsafe { sys::dogecoin_hdnode_new() };
+        if node.is_null() {
+            return Err(Error::NullOutput);
+        }
+        let node = HdNode {
+            node,
+            chain,
+            _ecc: ecc,
+        };
+
+        let ok = unsafe { sys::dogecoin_hdnode_deserialize(key.as_ptr(), chain, node.node) };
+        if ok != 1 {
+            return Err(Error::InvalidExtendedKey);
+        }
+        Ok(node)
+    }
+
+    /// Derive the child at `index` (hardened if `index >= HARDENED`).
+    pub(crate) fn child(&self, index: u32) -> Result<Self> {
+        let child = self.duplicate()?;
+        let ok = unsafe { sys::dogecoin_hdnode_private_ckd(child.node, index) };
+        if ok != 1 {
+            return Err(Error::Ffi {
+                function: "dogecoin_hdnode_private_ckd",
+                code: ok.into(),
+            });
+        }
+        Ok(child)
+    }
+
+    /// Derive the non-hardened child at `index` from the public key alone,
+    /// so it works on nodes read from an extended public key.
+    pub(crate) fn public_child(&self, index: u32) -> Result<Self> {
+        if index >= HARDENED {
+            return Err(Error::IndexOutOfRange(index));
+        }
+        let child = self.duplicate()?;
+        let ok = unsafe { sys::dogecoin_hdnode_public_ckd(child.node, index) };
+        if ok != 1 {
+            return Err(Error::Ffi {
+                function: "dogecoin_hdnode_public_ckd",
+                code: ok.into(),
+            });
+        }
+        Ok(child)
+    }
+
+    /// Derive a descendant along a path like `m/44'/3'/0'/0/5`.
+    pub(crate) fn derive_path(&self, path: &str) -> Result<Self> {
+        let invalid = || Error::InvalidPath(path.to_string());
+        let mut parts = path.split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid());
+        }
+
+        let mut node = self.duplicate()?;
+        for part in parts {
+            let (digits, hardened) = match part.strip_suffix(['\'', 'h']) {
+                Some(digits) => (digits, true),
+                None => (part, false),
+            };
+            let index: u32 = digits
+                .parse()
+                .ok()
+                .filter(|i| *i < HARDENED)
+                .ok_or_else(invalid)?;
+            node = node.child(if hardened { index | HARDENED } else { index })?;
+        }
+        Ok(node)
+    }
+
+    /// P2PKH address of this node's public key.
+    pub(crate) fn p2pkh_address(&self) -> Result<String> {
+        let mut out = [0u8; P2PKH_BUF_LEN];
+        unsafe {
+            sys::dogecoin_hdnode_get_p2pkh_address(
+                self.node,
+                self.chain,
+                out.as_mut_ptr().cast(),
+                P2PKH_BUF_LEN as i32,
+            );
+        }
+        non_empty(string_from_buf(&out)?, "dogecoin_hdnode_get_p2pkh_address")
+    }
+
+    /// Serialized extended private key.
+    pub(crate) fn serialize_private(&self) -> Result<Zeroizing<String>> {
+        let mut out = Zeroizing::new([0u8; HD_KEY_BUF_LEN]);
+        unsafe {
+            sys::dogecoin_hdnode_serialize_private(
+                self.node,
+                self.chain,
+                out.as_mut_ptr().cast(),
+                HD_KEY_BUF_LEN,
+            );
+        }
+        let key = Zeroizing::new(string_from_buf(&*out)?);
+        if key.is_empty() {
+            return Err(Error::Ffi {
+                function: "dogecoin_hdnode_serialize_private",
+                code: 0,
+            });
+        }
+        Ok(key)
+    }
+
+    /// This node's private key with its compressed public key, for signing.
+    pub(crate) fn signing_key(&self) -> Result<SigningKey> {
+        let mut secret = Zeroizing::new([0u8; 32]);
+        secret.copy_from_slice(unsafe { &(*self.node).private_key });
+        // Nodes parsed from an extended public key hold a zero secret.
+        SigningKey::from_secret(secret, true).ok_or(Error::InvalidExtendedKey)
+    }
+
+    /// Serialized extended public key.
+    pub(crate) fn serialize_public(&self) -> Result<String> {
+        let mut out = [0u8; HD_KEY_BUF_LEN];
+        unsafe {
+            sys::dogecoin_hdnode_serialize_public(
+                self.node,
+                self.chain,
+                out.as_mut_ptr().cast(),
+                HD_KEY_BUF_LEN,
+            );
+        }
+        non_empty(string_from_buf(&out)?, "dogecoin_hdnode_serialize_public")
+    }
+
+    /// Copy the node, e.g. to hand to a [`DerivationSession`](crate::DerivationSession).
+    pub(crate) fn duplicate(&self) -> Result<Self> {
+        let node = unsafe { sys::dogecoin_hdnode_copy(self.node) };
+        if node.is_null() {
+            return Err(Error::NullOutput);
+        }
+        Ok(HdNode {
+            node,
+            chain: self.chain,
+            _ecc: crate::context::ensure_ecc_started(),
+        })
+    }
+}
+
+// SAFETY: the node is owned exclusively and only read through `&self`
+// (derivations work on a copy); the chain params are immutable statics.
+unsafe impl Send for HdNode {}
+unsafe impl Sync for HdNode {}
+
+impl Drop for HdNode {
+    fn drop(&mut self) {
+        // dogecoin_hdnode_free wipes the key material before freeing.
+        unsafe { sys::dogecoin_hdnode_free(self.node) }
+    }
+}
+
+/// The void serializers signal failure by leaving the buffer empty.
+fn non_empty(s: String, function: &'static str) -> Result<String> {
+    if s.is_empty() {
+        return Err(Error::Ffi { function, code: 0 });
+    }
+    Ok(s)
+}
+
+/// Chain parameters for mainnet or testnet.
+fn chain_params(is_testnet: bool) -> *const sys::dogecoin_chainparams {
+    if is_testnet {
+        sys::dogecoin_chainparams_test()
+    } else {
+        sys::dogecoin_chainparams_main()
+    }
+}
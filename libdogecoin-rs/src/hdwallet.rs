@@ -2,19 +2,113 @@
 //!
 //! This module provides HD wallet functionality following BIP32 and BIP44 standards.
 
-use crate::sys;
-use std::ffi::{CStr, CString};
+use crate::address::{AddressNetwork, AddressUtils};
+use crate::birthday::Birthday;
+use crate::cancel::{CancellationToken, StopReason, Stopped};
+use crate::config::GapLimit;
+use crate::consensus::MAX_MONEY;
+use crate::error::{Error, Result};
+use crate::extkey::ExtendedKey;
+use crate::ffi::{
+    checked_cstring, is_base58_byte, string_from_buf, sys, HD_KEY_BUF_LEN, MAX_EXTKEY_LEN,
+    P2PKH_BUF_LEN,
+};
+use crate::fingerprint::WalletId;
+use crate::hdnode::{HdNode, HARDENED};
+use crate::history::HistoryEntry;
+use crate::indexstore::{IndexStore, MemoryIndexStore};
+use crate::key::SigningKey;
+use crate::preset::{bip44_account_path, WalletPreset};
+use crate::progress::{ProgressSink, ProgressTracker};
+use crate::script::{P2pkhTemplate, ScriptTemplate};
+use crate::transaction::Transaction;
+use crate::utxo::OutPoint;
+use crate::{Koinu, PaymentUri, QrCode};
+use std::collections::HashMap;
+use std::ops::Range;
 use zeroize::Zeroizing;
 
-/// HD Wallet key length constant from libdogecoin.
-/// Note: libdogecoin docs say function expects 128 but returns 111.
-const HDKEYLEN: usize = 128;
-/// P2PKH address length constant from libdogecoin.
-/// Note: function expects 35 but actual addresses are 34 chars.
-const P2PKHLEN: usize = 64;
 /// Key path maximum length.
 #[allow(dead_code)]
 const KEYPATHMAXLEN: usize = 256;
+/// Size multiplier used for receive QR codes.
+const RECEIVE_QR_MULTIPLIER: u8 = 8;
+
+/// BIP44 chain within an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Chain {
+    /// Receiving addresses (`change = 0`).
+    External,
+    /// Change addresses (`change = 1`).
+    Internal,
+}
+
+impl Chain {
+    /// Whether this is the internal (change) chain.
+    pub fn is_change(self) -> bool {
+        self == Chain::Internal
+    }
+}
+
+/// Who an output pays, from [`DerivationSession::classify_outputs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputRole {
+    /// Someone else.
+    External,
+    /// One of our receiving addresses, at this index.
+    Receive(u32),
+    /// One of our change addresses, at this index.
+    Change(u32),
+}
+
+/// Output roles of a transaction with per-role totals in koinu.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputClassification {
+    /// Role of each output, in output order.
+    pub roles: Vec<OutputRole>,
+    /// Paid to others.
+    pub external: u64,
+    /// Paid to our receiving addresses.
+    pub received: u64,
+    /// Returned to our change addresses.
+    pub change: u64,
+}
+
+/// A used address found by [`DerivationSession::discover`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredAddress {
+    pub preset: WalletPreset,
+    pub account: u32,
+    pub chain: Chain,
+    pub index: u32,
+    pub address: String,
+}
+
+/// A chain whose stored next index is behind its used addresses, from
+/// [`HdWallet::check_indexes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexRepair {
+    pub account: u32,
+    pub chain: Chain,
+    /// The next index in the store, if any was stored.
+    pub stored: Option<u32>,
+    /// One past the highest used index: the next index that is safe to hand
+    /// out.
+    pub next_index: u32,
+}
+
+/// Everything needed to display a request for payment to a fresh address.
+#[derive(Debug, Clone)]
+pub struct Receive {
+    /// The receiving P2PKH address.
+    pub address: String,
+    /// The `dogecoin:` payment URI.
+    pub uri: String,
+    /// PNG image of the payment URI as a QR code.
+    pub qr_png: Vec<u8>,
+    /// BIP44 address index of the receiving address (account 0, external chain).
+    pub index: u32,
+}
 
 /// A Hierarchical Deterministic (HD) Wallet.
 ///
@@ -34,52 +128,91 @@ const KEYPATHMAXLEN: usize = 256;
 /// ```
 pub struct HdWallet {
     master_key: Zeroizing<String>,
+    /// The master key parsed into a native HD node once, or why it failed.
+    root: Result<HdNode>,
     is_testnet: bool,
+    index_store: Box<dyn IndexStore>,
+    birthday: Option<Birthday>,
 }
 
 impl HdWallet {
     /// Generate a new HD wallet with a random master key.
     ///
+    /// The wallet's [`birthday`](Self::birthday) is set to now.
+    ///
     /// # Arguments
     /// * `is_testnet` - Set to true for testnet, false for mainnet.
-    pub fn new(is_testnet: bool) -> Option<Self> {
-        crate::context::ensure_ecc_started();
+    pub fn new(is_testnet: bool) -> Result<Self> {
+        let _ecc = crate::context::ensure_ecc_started();
 
-        let mut hd_privkey = [0u8; HDKEYLEN];
-        let mut p2pkh_pubkey = [0u8; P2PKHLEN];
+        let mut hd_privkey = Zeroizing::new([0u8; HD_KEY_BUF_LEN]);
+        let mut p2pkh_pubkey = [0u8; P2PKH_BUF_LEN];
 
         let result = unsafe {
             sys::generateHDMasterPubKeypair(
-                hd_privkey.as_mut_ptr() as *mut i8,
-                p2pkh_pubkey.as_mut_ptr() as *mut i8,
-                is_testnet as u8,
+                hd_privkey.as_mut_ptr().cast(),
+                p2pkh_pubkey.as_mut_ptr().cast(),
+                is_testnet,
             )
         };
 
         if result != 1 {
-            return None;
+            return Err(Error::Ffi {
+                function: "generateHDMasterPubKeypair",
+                code: result,
+            });
         }
 
-        let master_key_cstr = unsafe { CStr::from_ptr(hd_privkey.as_ptr() as *const i8) };
+        let master_key = Zeroizing::new(string_from_buf(&*hd_privkey)?);
 
-        Some(HdWallet {
-            master_key: Zeroizing::new(master_key_cstr.to_string_lossy().into_owned()),
-            is_testnet,
-        })
+        Ok(Self::from_master_key(&master_key, is_testnet).with_birthday(Birthday::now()))
     }
 
     /// Create an HD wallet from an existing master key.
     ///
+    /// The key is parsed once here; if it is invalid, derivations return
+    /// the error.
+    ///
     /// # Arguments
     /// * `master_key` - The master private key in extended key format.
-    /// * `is_testnet` - Whether this is a testnet key.
+    /// * `is_testnet` - Whether this is a testnet wallet. Addresses are
+    ///   encoded for the network of the key's own prefix.
     pub fn from_master_key(master_key: &str, is_testnet: bool) -> Self {
         HdWallet {
             master_key: Zeroizing::new(master_key.to_string()),
+            root: checked_cstring(master_key, MAX_EXTKEY_LEN, is_base58_byte, "extended key")
+                .map_err(|_| Error::InvalidExtendedKey)
+                .map(Zeroizing::new)
+                .and_then(|key| HdNode::deserialize(&key)),
             is_testnet,
+            index_store: Box::new(MemoryIndexStore::new()),
+            birthday: None,
         }
     }
 
+    /// Record when the wallet was created, so scans can skip older blocks.
+    pub fn with_birthday(mut self, birthday: Birthday) -> Self {
+        self.birthday = Some(birthday);
+        self
+    }
+
+    /// Get the wallet's birthday, if known.
+    ///
+    /// Persist it alongside the master key; wallets restored without one
+    /// must scan from genesis.
+    pub fn birthday(&self) -> Option<Birthday> {
+        self.birthday
+    }
+
+    /// Get the height a rescan of this wallet should start from.
+    ///
+    /// # Returns
+    /// `0` if the birthday is unknown.
+    pub fn scan_start_height(&self) -> u32 {
+        self.birthday
+            .map_or(0, |birthday| birthday.scan_start_height(self.is_testnet))
+    }
+
     /// Get the master private key.
     pub fn master_key(&self) -> &str {
         self.master_key.as_str()
@@ -90,8 +223,18 @@ impl HdWallet {
         self.is_testnet
     }
 
+    /// The parsed master node.
+    fn root(&self) -> Result<&HdNode> {
+        self.root.as_ref().map_err(Clone::clone)
+    }
+
     /// Derive a child address following BIP44 path.
     ///
+    /// The path is `m/44'/3'/<account>'/<change>/<index>` on both networks,
+    /// as libdogecoin's `getDerivedHDAddress` derives it. For coin type `1'`
+    /// on testnet use [`HdWallet::derive_preset_address`] with
+    /// [`WalletPreset::Bip44`].
+    ///
     /// # Arguments
     /// * `account` - Account index (BIP44 account level).
     /// * `index` - Address index.
@@ -99,79 +242,722 @@ impl HdWallet {
     ///
     /// # Returns
     /// The derived P2PKH address.
-    pub fn derive_address(&self, account: u32, index: u32, is_change: bool) -> Option<String> {
-        crate::context::ensure_ecc_started();
+    pub fn derive_address(&self, account: u32, index: u32, is_change: bool) -> Result<String> {
+        let chain = if is_change {
+            Chain::Internal
+        } else {
+            Chain::External
+        };
+        self.derive_by_path(&legacy_address_path(account, chain, index)?)
+    }
 
-        let mut out_address = [0u8; P2PKHLEN];
-        let master_cstr = CString::new(self.master_key.as_str()).ok()?;
+    /// Derive an address by a custom BIP32 path.
+    ///
+    /// # Arguments
+    /// * `path` - The derivation path (e.g., "m/44'/3'/0'/0/0").
+    ///
+    /// # Returns
+    /// The derived P2PKH address.
+    pub fn derive_by_path(&self, path: &str) -> Result<String> {
+        self.root()?.derive_path(path)?.p2pkh_address()
+    }
 
-        let result = unsafe {
-            sys::getDerivedHDAddress(
-                master_cstr.as_ptr(),
+    /// Derive an address using another wallet's path layout.
+    ///
+    /// # Arguments
+    /// * `preset` - The wallet whose layout to follow.
+    /// * `account` - Account index.
+    /// * `chain` - External (receiving) or internal (change) chain.
+    /// * `index` - Address index.
+    ///
+    /// # Returns
+    /// The derived P2PKH address, or an error if the preset has no such path.
+    pub fn derive_preset_address(
+        &self,
+        preset: WalletPreset,
+        account: u32,
+        chain: Chain,
+        index: u32,
+    ) -> Result<String> {
+        let path = preset
+            .address_path(account, chain, index, self.is_testnet)
+            .ok_or_else(|| no_preset_path(preset, account, index))?;
+        self.derive_by_path(&path)
+    }
+
+    /// Derive the extended key at a custom BIP32 path.
+    ///
+    /// # Arguments
+    /// * `path` - The derivation path (e.g., "m/44'/3'/0'").
+    /// * `private` - Return the extended private key instead of the public one.
+    ///
+    /// # Returns
+    /// The serialized extended key, tagged with its path.
+    pub fn derive_extended_key(&self, path: &str, private: bool) -> Result<ExtendedKey> {
+        let node = self.root()?.derive_path(path)?;
+        let key = if private {
+            node.serialize_private()?.to_string()
+        } else {
+            node.serialize_public()?
+        };
+        Ok(ExtendedKey::with_path(key, path))
+    }
+
+    /// Derive the account-level extended private key (`m/44'/<coin>'/<account>'`).
+    ///
+    /// The result can derive every address of the account, so treat it like
+    /// the master key; it does not expose keys of other accounts.
+    pub fn account_xpriv(&self, account: u32) -> Result<ExtendedKey> {
+        self.derive_extended_key(&self.account_path(account), true)
+    }
+
+    /// Derive the account-level extended public key (`m/44'/<coin>'/<account>'`).
+    ///
+    /// Suitable for watch-only exports of a single account.
+    pub fn account_xpub(&self, account: u32) -> Result<ExtendedKey> {
+        self.derive_extended_key(&self.account_path(account), false)
+    }
+
+    /// Derive the private key at `key`, for signing inside the crate.
+    pub(crate) fn signing_key(&self, key: KeyPath<'_>) -> Result<SigningKey> {
+        let path = match key {
+            KeyPath::Path(path) => path.to_string(),
+            KeyPath::Bip44 {
                 account,
-                is_change as u8,
+                chain,
                 index,
-                out_address.as_mut_ptr() as *mut i8,
-                false as u8,
-            )
+            } => legacy_address_path(account, chain, index)?,
         };
+        self.root()?.derive_path(&path)?.signing_key()
+    }
 
-        if result != 1 {
-            return None;
+    /// Get the wallet's [`WalletId`], derived from the account 0 xpub.
+    ///
+    /// Safe to log; the same for every copy of the wallet, including
+    /// watch-only ones built from that xpub.
+    pub fn wallet_id(&self) -> Result<WalletId> {
+        WalletId::from_xpub(&self.account_xpub(0)?).ok_or(Error::InvalidExtendedKey)
+    }
+
+    /// Derive the change-level extended private key (`m/44'/<coin>'/<account>'/<0|1>`).
+    pub fn chain_xpriv(&self, account: u32, chain: Chain) -> Result<ExtendedKey> {
+        self.derive_extended_key(&self.chain_path(account, chain), true)
+    }
+
+    /// Derive the change-level extended public key (`m/44'/<coin>'/<account>'/<0|1>`).
+    ///
+    /// Useful for delegating address generation for one chain (e.g. receiving
+    /// addresses) to another system.
+    pub fn chain_xpub(&self, account: u32, chain: Chain) -> Result<ExtendedKey> {
+        self.derive_extended_key(&self.chain_path(account, chain), false)
+    }
+
+    /// BIP44 account path for this wallet's network.
+    fn account_path(&self, account: u32) -> String {
+        bip44_account_path(self.is_testnet, account)
+    }
+
+    /// BIP44 change-level path for this wallet's network.
+    fn chain_path(&self, account: u32, chain: Chain) -> String {
+        format!("{}/{}", self.account_path(account), chain.is_change() as u8)
+    }
+
+    /// Whether `address` is one of this wallet's account 0 addresses.
+    ///
+    /// See [`HdWallet::find_index`].
+    pub fn owns_address(&self, address: &str, search_depth: u32) -> Result<bool> {
+        Ok(self.find_index(address, search_depth)?.is_some())
+    }
+
+    /// Find where an address sits in account 0.
+    ///
+    /// Both chains are scanned up to `search_depth` addresses, or further if
+    /// the index store shows more were handed out. Use
+    /// [`DerivationSession::find_index`] to search other accounts.
+    ///
+    /// # Returns
+    /// The account, chain and index of the address, or `None` if it was
+    /// not found.
+    pub fn find_index(
+        &self,
+        address: &str,
+        search_depth: u32,
+    ) -> Result<Option<(u32, Chain, u32)>> {
+        let issued = self
+            .next_index(0, Chain::External)?
+            .max(self.next_index(0, Chain::Internal)?);
+        self.derivation_session()?
+            .find_index(address, 0..1, search_depth.max(issued))
+    }
+
+    /// Start a derivation session for fast repeated derivations.
+    ///
+    /// The session keeps the account/chain-level nodes it has visited, so
+    /// deriving another address costs a single child derivation instead of a
+    /// walk down the whole path from the master node.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use libdogecoin_rs::HdWallet;
+    ///
+    /// let wallet = HdWallet::new(false).unwrap();
+    /// let mut session = wallet.derivation_session().unwrap();
+    /// let addresses: Vec<String> = (0..100)
+    ///     .map(|i| session.derive_address(0, i, false).unwrap())
+    ///     .collect();
+    /// ```
+    pub fn derivation_session(&self) -> Result<DerivationSession> {
+        let root = self.root()?.duplicate()?;
+        Ok(DerivationSession {
+            root,
+            is_testnet: self.is_testnet,
+            chain_nodes: HashMap::new(),
+            owned: HashMap::new(),
+            owned_depth: HashMap::new(),
+            wallet_id: None,
+        })
+    }
+
+    /// Use a custom store for the next-unused address indexes.
+    ///
+    /// Wallets start with a [`MemoryIndexStore`]; use a persistent store such as
+    /// [`FileIndexStore`](crate::indexstore::FileIndexStore) so that
+    /// [`HdWallet::next_receive_address`] stays monotonic across restarts.
+    pub fn with_index_store(mut self, store: impl IndexStore + 'static) -> Self {
+        self.index_store = Box::new(store);
+        self
+    }
+
+    /// Get the next unused index for an account/chain without reserving it.
+    pub fn next_index(&self, account: u32, chain: Chain) -> Result<u32> {
+        self.index_store
+            .load(account, chain)
+            .map(|next| next.unwrap_or(0))
+            .map_err(|e| Error::IndexStore(e.to_string()))
+    }
+
+    /// Find chains whose stored next index would hand out an address that is
+    /// already used, e.g. after another wallet spent from the same seed or
+    /// the store was lost.
+    ///
+    /// Each BIP44 chain is scanned from its stored index until `gap_limit`
+    /// consecutive addresses are unused, so used addresses past a stretch of
+    /// reserved but unpaid ones are still found. Accounts are scanned in
+    /// order until one has no stored state and no used addresses.
+    ///
+    /// # Arguments
+    /// * `gap_limit` - Unused addresses in a row that end a chain, per
+    ///   account and chain.
+    /// * `is_used` - Whether an address has any history.
+    ///
+    /// # Returns
+    /// The chains that need repair; the store is not changed.
+    pub fn check_indexes(
+        &self,
+        gap_limit: &GapLimit,
+        mut is_used: impl FnMut(&str) -> bool,
+    ) -> Result<Vec<IndexRepair>> {
+        let mut session = self.derivation_session()?;
+        let mut repairs = Vec::new();
+        for account in 0.. {
+            let mut account_seen = false;
+            for chain in [Chain::External, Chain::Internal] {
+                let stored = self
+                    .index_store
+                    .load(account, chain)
+                    .map_err(|e| Error::IndexStore(e.to_string()))?;
+                let mut index = stored.unwrap_or(0);
+                let (mut next_index, mut gap) = (index, 0);
+                while gap < gap_limit.get(account, chain) {
+                    let address = session.derive_address(account, index, chain.is_change())?;
+                    let after = index.checked_add(1).ok_or(Error::IndexOutOfRange(index))?;
+                    if is_used(&address) {
+                        next_index = after;
+                        gap = 0;
+                    } else {
+                        gap += 1;
+                    }
+                    index = after;
+                }
+                account_seen |= stored.is_some() || next_index > 0;
+                if next_index > stored.unwrap_or(0) {
+                    repairs.push(IndexRepair {
+                        account,
+                        chain,
+                        stored,
+                        next_index,
+                    });
+                }
+            }
+            if !account_seen {
+                break;
+            }
+        }
+        Ok(repairs)
+    }
+
+    /// Move every stored next index found by
+    /// [`check_indexes`](Self::check_indexes) past the used addresses.
+    ///
+    /// Indexes are only ever raised, so addresses already handed out are
+    /// never handed out again.
+    ///
+    /// # Returns
+    /// The chains that were repaired.
+    pub fn repair_indexes(
+        &mut self,
+        gap_limit: &GapLimit,
+        is_used: impl FnMut(&str) -> bool,
+    ) -> Result<Vec<IndexRepair>> {
+        let repairs = self.check_indexes(gap_limit, is_used)?;
+        for repair in &repairs {
+            self.index_store
+                .save(repair.account, repair.chain, repair.next_index)
+                .map_err(|e| Error::IndexStore(e.to_string()))?;
         }
+        Ok(repairs)
+    }
 
-        let addr_cstr = unsafe { CStr::from_ptr(out_address.as_ptr() as *const i8) };
-        Some(addr_cstr.to_string_lossy().into_owned())
+    /// Derive the next unused receiving address and reserve its index.
+    ///
+    /// # Arguments
+    /// * `account` - Account index (BIP44 account level).
+    ///
+    /// # Returns
+    /// The address index and the derived P2PKH address.
+    pub fn next_receive_address(&mut self, account: u32) -> Result<(u32, String)> {
+        self.next_address(account, Chain::External)
     }
 
-    /// Derive an address by a custom BIP32 path.
+    /// Derive the next unused change address and reserve its index.
     ///
     /// # Arguments
-    /// * `path` - The derivation path (e.g., "m/44'/3'/0'/0/0").
+    /// * `account` - Account index (BIP44 account level).
     ///
     /// # Returns
-    /// The derived P2PKH address.
-    pub fn derive_by_path(&self, path: &str) -> Option<String> {
-        crate::context::ensure_ecc_started();
+    /// The address index and the derived P2PKH address.
+    pub fn next_change_address(&mut self, account: u32) -> Result<(u32, String)> {
+        self.next_address(account, Chain::Internal)
+    }
 
-        let mut out_address = [0u8; P2PKHLEN];
-        let master_cstr = CString::new(self.master_key.as_str()).ok()?;
-        let path_cstr = CString::new(path).ok()?;
+    /// Reserve the next index in the store, then derive its address.
+    ///
+    /// The index is persisted before the address is returned, so a crash can
+    /// at worst skip an index but never reuse one.
+    fn next_address(&mut self, account: u32, chain: Chain) -> Result<(u32, String)> {
+        let index = self.next_index(account, chain)?;
+        let address = self.derive_address(account, index, chain.is_change())?;
+        let next = index.checked_add(1).ok_or(Error::IndexOutOfRange(index))?;
+        self.index_store
+            .save(account, chain, next)
+            .map_err(|e| Error::IndexStore(e.to_string()))?;
+        Ok((index, address))
+    }
 
-        let result = unsafe {
-            sys::getDerivedHDAddressByPath(
-                master_cstr.as_ptr(),
-                path_cstr.as_ptr(),
-                out_address.as_mut_ptr() as *mut i8,
-                false as u8,
-            )
-        };
+    /// Derive the next receiving address and render its payment URI and QR code.
+    ///
+    /// # Arguments
+    /// * `amount` - Optional requested amount.
+    /// * `label` - Optional label to embed in the URI.
+    ///
+    /// # Returns
+    /// The address, URI, QR PNG and index of the next unused receiving
+    /// address of account 0.
+    pub fn receive(&mut self, amount: Option<Koinu>, label: Option<&str>) -> Result<Receive> {
+        // Validate before reserving so a bad amount doesn't burn an index.
+        if let Some(amount) = amount.filter(|a| a.0 > MAX_MONEY) {
+            return Err(Error::InvalidAmount(amount.0.to_string()));
+        }
 
-        if result != 1 {
-            return None;
+        let (index, address) = self.next_receive_address(0)?;
+        let mut uri = PaymentUri::new(&address);
+        if let Some(amount) = amount {
+            uri = uri.with_amount(amount);
         }
+        if let Some(label) = label {
+            uri = uri.with_label(label);
+        }
+        let uri = uri.to_string();
+        let qr_png = QrCode::to_png_bytes(&uri, RECEIVE_QR_MULTIPLIER)?;
 
-        let addr_cstr = unsafe { CStr::from_ptr(out_address.as_ptr() as *const i8) };
-        Some(addr_cstr.to_string_lossy().into_owned())
+        Ok(Receive {
+            address,
+            uri,
+            qr_png,
+            index,
+        })
     }
 
     /// Derive a new address from the master key (simple wrapper).
-    pub fn derive_new_address(&self) -> Option<String> {
-        crate::context::ensure_ecc_started();
+    pub fn derive_new_address(&self) -> Result<String> {
+        self.root()?.p2pkh_address()
+    }
+}
 
-        let mut p2pkh_pubkey = [0u8; P2PKHLEN];
-        let master_cstr = CString::new(self.master_key.as_str()).ok()?;
+/// Path of [`HdWallet::derive_address`]: BIP44 with mainnet's coin type on
+/// every network, as libdogecoin's `getDerivedHDAddress` has always used.
+fn legacy_address_path(account: u32, chain: Chain, index: u32) -> Result<String> {
+    WalletPreset::Bip44
+        .address_path(account, chain, index, false)
+        .ok_or_else(|| no_preset_path(WalletPreset::Bip44, account, index))
+}
 
-        let result = unsafe {
-            sys::generateDerivedHDPubkey(master_cstr.as_ptr(), p2pkh_pubkey.as_mut_ptr() as *mut i8)
+/// Why a preset has no path for an account and index.
+fn no_preset_path(preset: WalletPreset, account: u32, index: u32) -> Error {
+    if account >= HARDENED {
+        Error::IndexOutOfRange(account)
+    } else if index >= HARDENED {
+        Error::IndexOutOfRange(index)
+    } else {
+        Error::NoSuchAccount {
+            preset: preset.name(),
+            account,
+        }
+    }
+}
+
+/// Where a key sits in an [`HdWallet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPath<'a> {
+    /// A BIP32 path such as `m/44'/3'/0'/0/5`.
+    Path(&'a str),
+    /// The key of [`HdWallet::derive_address`] for this account, chain
+    /// and index.
+    Bip44 {
+        account: u32,
+        chain: Chain,
+        index: u32,
+    },
+}
+
+impl<'a> From<&'a str> for KeyPath<'a> {
+    fn from(path: &'a str) -> Self {
+        KeyPath::Path(path)
+    }
+}
+
+/// Native HD node cache returned by [`HdWallet::derivation_session`].
+pub struct DerivationSession {
+    root: HdNode,
+    is_testnet: bool,
+    /// Chain-level nodes by path.
+    chain_nodes: HashMap<String, HdNode>,
+    /// BIP44 addresses derived for ownership lookups.
+    owned: HashMap<String, (u32, Chain, u32)>,
+    /// How many addresses of each account/chain are in `owned`.
+    owned_depth: HashMap<(u32, Chain), u32>,
+    wallet_id: Option<WalletId>,
+}
+
+impl DerivationSession {
+    /// Derive a child address following BIP44 path.
+    ///
+    /// # Arguments
+    /// * `account` - Account index (BIP44 account level).
+    /// * `index` - Address index.
+    /// * `is_change` - Whether this is a change address (internal) or receiving (external).
+    ///
+    /// # Returns
+    /// The derived P2PKH address.
+    pub fn derive_address(&mut self, account: u32, index: u32, is_change: bool) -> Result<String> {
+        let chain = if is_change {
+            Chain::Internal
+        } else {
+            Chain::External
         };
+        // Mainnet's BIP44 coin type on both networks, as HdWallet::derive_address.
+        self.derive_at(WalletPreset::Bip44, account, chain, index, false)
+    }
 
-        if result != 1 {
-            return None;
+    /// Derive an address using another wallet's path layout.
+    ///
+    /// # Arguments
+    /// * `preset` - The wallet whose layout to follow.
+    /// * `account` - Account index.
+    /// * `chain` - External (receiving) or internal (change) chain.
+    /// * `index` - Address index.
+    ///
+    /// # Returns
+    /// The derived P2PKH address, or an error if the preset has no such path.
+    pub fn derive_preset_address(
+        &mut self,
+        preset: WalletPreset,
+        account: u32,
+        chain: Chain,
+        index: u32,
+    ) -> Result<String> {
+        self.derive_at(preset, account, chain, index, self.is_testnet)
+    }
+
+    /// Derive an address below a cached chain node, with the preset's
+    /// coin type for `is_testnet`.
+    fn derive_at(
+        &mut self,
+        preset: WalletPreset,
+        account: u32,
+        chain: Chain,
+        index: u32,
+        is_testnet: bool,
+    ) -> Result<String> {
+        let path = preset
+            .chain_path(account, chain, is_testnet)
+            .ok_or_else(|| no_preset_path(preset, account, index))?;
+        let child = preset
+            .child_index(index)
+            .ok_or(Error::IndexOutOfRange(index))?;
+        if !self.chain_nodes.contains_key(&path) {
+            let node = self.root.derive_path(&path)?;
+            self.chain_nodes.insert(path.clone(), node);
         }
+        self.chain_nodes[&path].child(child)?.p2pkh_address()
+    }
+
+    /// Find the used addresses of a restored seed.
+    ///
+    /// Each preset's chains are scanned until `gap_limit` consecutive
+    /// addresses are unused; accounts are scanned in order until one has no
+    /// used addresses. Presets sharing a layout (e.g. Ledger and BIP44) are
+    /// scanned once, under the first of them.
+    ///
+    /// # Arguments
+    /// * `presets` - Layouts to try, e.g. [`WalletPreset::ALL`].
+    /// * `gap_limit` - Unused addresses in a row that end a chain, per
+    ///   account and chain.
+    /// * `is_used` - Whether an address has any history.
+    ///
+    /// # Returns
+    /// The used addresses found, or the first derivation error.
+    pub fn discover(
+        &mut self,
+        presets: &[WalletPreset],
+        gap_limit: &GapLimit,
+        mut is_used: impl FnMut(&str) -> bool,
+    ) -> Result<Vec<DiscoveredAddress>> {
+        let mut found = Vec::new();
+        let mut scanned = Vec::new();
+        for &preset in presets {
+            let layout = preset
+                .chain_path(0, Chain::External, self.is_testnet)
+                .ok_or_else(|| no_preset_path(preset, 0, 0))?;
+            if scanned.contains(&layout) {
+                continue;
+            }
+            scanned.push(layout);
 
-        let addr_cstr = unsafe { CStr::from_ptr(p2pkh_pubkey.as_ptr() as *const i8) };
-        Some(addr_cstr.to_string_lossy().into_owned())
+            for account in 0.. {
+                let mut account_used = false;
+                for chain in [Chain::External, Chain::Internal] {
+                    let (mut index, mut gap) = (0u32, 0);
+                    while gap < gap_limit.get(account, chain) {
+                        let address = self.derive_preset_address(preset, account, chain, index)?;
+                        if is_used(&address) {
+                            account_used = true;
+                            gap = 0;
+                            found.push(DiscoveredAddress {
+                                preset,
+                                account,
+                                chain,
+                                index,
+                                address,
+                            });
+                        } else {
+                            gap += 1;
+                        }
+                        index = index.checked_add(1).ok_or(Error::IndexOutOfRange(index))?;
+                    }
+                }
+                if !account_used || !preset.has_accounts() {
+                    break;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Derive the addresses for a range of indexes, e.g. for account discovery.
+    ///
+    /// # Arguments
+    /// * `account` - Account index (BIP44 account level).
+    /// * `is_change` - Whether to derive change (internal) or receiving (external) addresses.
+    /// * `indexes` - Address indexes to derive, in order.
+    /// * `token` - Checked before each address.
+    /// * `progress` - Notified after each address.
+    ///
+    /// # Returns
+    /// The addresses in index order, or [`Stopped`] holding those derived
+    /// before the token fired or a derivation failed.
+    pub fn derive_range(
+        &mut self,
+        account: u32,
+        is_change: bool,
+        indexes: Range<u32>,
+        token: &CancellationToken,
+        progress: Option<&dyn ProgressSink>,
+    ) -> std::result::Result<Vec<String>, Stopped<Vec<String>>> {
+        let tracker = ProgressTracker::new(progress, Some(indexes.len() as u64));
+        let mut addresses = Vec::with_capacity(indexes.len());
+        for index in indexes {
+            if let Err(reason) = token.check() {
+                return Err(Stopped {
+                    reason,
+                    partial: addresses,
+                });
+            }
+            match self.derive_address(account, index, is_change) {
+                Ok(address) => addresses.push(address),
+                Err(_) => {
+                    return Err(Stopped {
+                        reason: StopReason::Failed,
+                        partial: addresses,
+                    })
+                }
+            }
+            tracker.advance(1);
+        }
+        Ok(addresses)
+    }
+
+    /// Find where an address sits among the given accounts.
+    ///
+    /// # Arguments
+    /// * `address` - The address to look for.
+    /// * `accounts` - Accounts to search, in order.
+    /// * `search_depth` - Addresses to check on each chain of each account.
+    ///
+    /// # Returns
+    /// The account, chain and index of the address, or `None` if it was
+    /// not found.
+    pub fn find_index(
+        &mut self,
+        address: &str,
+        accounts: Range<u32>,
+        search_depth: u32,
+    ) -> Result<Option<(u32, Chain, u32)>> {
+        let expected = if self.is_testnet {
+            AddressNetwork::Testnet
+        } else {
+            AddressNetwork::Mainnet
+        };
+        if AddressUtils::network(address) != expected {
+            return Ok(None);
+        }
+        for account in accounts {
+            self.index_owned(account, search_depth)?;
+            match self.owned.get(address) {
+                Some(&(a, chain, index)) if a == account && index < search_depth => {
+                    return Ok(Some((account, chain, index)))
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Work out which outputs of a transaction pay this wallet.
+    ///
+    /// Outputs to the account's internal chain are change, so history can
+    /// show the net amount that left the wallet rather than a self-transfer.
+    ///
+    /// # Arguments
+    /// * `tx` - The transaction.
+    /// * `account` - Account to match against.
+    /// * `search_depth` - Addresses to check on each chain.
+    ///
+    /// # Returns
+    /// The role of each output, or the first derivation error.
+    pub fn classify_outputs(
+        &mut self,
+        tx: &Transaction,
+        account: u32,
+        search_depth: u32,
+    ) -> Result<OutputClassification> {
+        self.index_owned(account, search_depth)?;
+        let mut classification = OutputClassification::default();
+        for output in &tx.outputs {
+            let owner = P2pkhTemplate
+                .parse(&output.script_pubkey)
+                .and_then(|kind| kind.address(self.is_testnet))
+                .and_then(|address| self.owned.get(&address).copied())
+                .filter(|&(a, _, index)| a == account && index < search_depth);
+            let role = match owner {
+                Some((_, Chain::External, index)) => OutputRole::Receive(index),
+                Some((_, Chain::Internal, index)) => OutputRole::Change(index),
+                None => OutputRole::External,
+            };
+            let total = match role {
+                OutputRole::External => &mut classification.external,
+                OutputRole::Receive(_) => &mut classification.received,
+                OutputRole::Change(_) => &mut classification.change,
+            };
+            *total = total.saturating_add(output.value);
+            classification.roles.push(role);
+        }
+        Ok(classification)
+    }
+
+    /// Summarise a transaction for the account's history, tagged with the
+    /// [`wallet_id`](Self::wallet_id).
+    ///
+    /// See [`HistoryEntry::new`] for how `our_value` values the inputs.
+    ///
+    /// # Returns
+    /// `None` if the transaction does not touch the account.
+    pub fn history_entry(
+        &mut self,
+        tx: &Transaction,
+        account: u32,
+        search_depth: u32,
+        our_value: impl FnMut(&OutPoint) -> Option<u64>,
+    ) -> Result<Option<HistoryEntry>> {
+        let outputs = self.classify_outputs(tx, account, search_depth)?;
+        let Some(mut entry) = HistoryEntry::new(tx, &outputs, our_value) else {
+            return Ok(None);
+        };
+        entry.wallet = Some(self.wallet_id()?);
+        Ok(Some(entry))
+    }
+
+    /// Get the wallet's [`WalletId`]; see [`HdWallet::wallet_id`].
+    pub fn wallet_id(&mut self) -> Result<WalletId> {
+        if let Some(id) = self.wallet_id {
+            return Ok(id);
+        }
+        let id = WalletId::from_xpub(&self.account_xpub(0)?).ok_or(Error::InvalidExtendedKey)?;
+        self.wallet_id = Some(id);
+        Ok(id)
+    }
+
+    /// Make sure the first `depth` addresses of both chains are in `owned`.
+    fn index_owned(&mut self, account: u32, depth: u32) -> Result<()> {
+        for chain in [Chain::External, Chain::Internal] {
+            let start = self
+                .owned_depth
+                .get(&(account, chain))
+                .copied()
+                .unwrap_or(0);
+            for index in start..depth {
+                let address =
+                    self.derive_preset_address(WalletPreset::Bip44, account, chain, index)?;
+                self.owned.insert(address, (account, chain, index));
+                self.owned_depth.insert((account, chain), index + 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Derive the P2PKH address at a custom BIP32 path.
+    ///
+    /// # Arguments
+    /// * `path` - The derivation path (e.g., "m/44'/3'/0'/0/0").
+    pub fn derive_by_path(&self, path: &str) -> Result<String> {
+        self.root.derive_path(path)?.p2pkh_address()
+    }
+
+    /// Derive the account-level extended public key.
+    pub fn account_xpub(&self, account: u32) -> Result<ExtendedKey> {
+        let path = bip44_account_path(self.is_testnet, account);
+        let key = self.root.derive_path(&path)?.serialize_public()?;
+        Ok(ExtendedKey::with_path(key, &path))
     }
 }
 
@@ -179,6 +965,9 @@ impl HdWallet {
 mod tests {
     use super::*;
 
+    /// Master key of the BIP39 "abandon ... about" seed.
+    const ABANDON_MASTER_KEY: &str = "dgpv51eADS3spNJh98bWAfYnAW8K1gMy86HKmH1dpyT8kLsUKBqssT3jsLLFWyK4zbruL51UjejFDzrFzBcwjjA57rSv6D2978QigKG4xbCfJV6";
+
     #[test]
     fn test_create_hd_wallet_mainnet() {
         let wallet = HdWallet::new(false).unwrap();
@@ -192,24 +981,386 @@ mod tests {
         let wallet = HdWallet::new(true).unwrap();
         assert!(!wallet.master_key().is_empty());
         assert!(wallet.is_testnet());
+        assert!(wallet.scan_start_height() > 0);
+    }
+
+    #[test]
+    fn test_birthday() {
+        let restored = HdWallet::from_master_key(ABANDON_MASTER_KEY, false);
+        assert_eq!(restored.birthday(), None);
+        assert_eq!(restored.scan_start_height(), 0);
+
+        let birthday = Birthday::from_time(0).with_height(5_000_000);
+        let restored = restored.with_birthday(birthday);
+        assert_eq!(restored.birthday(), Some(birthday));
+        assert_eq!(
+            restored.scan_start_height(),
+            5_000_000 - crate::birthday::SCAN_MARGIN_BLOCKS
+        );
     }
 
     #[test]
     fn test_derive_address() {
         let wallet = HdWallet::new(false).unwrap();
         // Use the simple derive method which works reliably
-        let addr = wallet.derive_new_address();
-        assert!(addr.is_some());
-        let addr = addr.unwrap();
+        let addr = wallet.derive_new_address().unwrap();
         assert!(addr.starts_with("D"), "Mainnet address should start with D");
         println!("Derived address: {}", addr);
     }
 
+    #[test]
+    fn test_receive_advances_index() {
+        let mut wallet = HdWallet::new(false).unwrap();
+        let first = wallet
+            .receive(Koinu::from_doge(10), Some("Invoice 1"))
+            .unwrap();
+        let second = wallet.receive(None, None).unwrap();
+
+        assert_eq!(first.index, 0);
+        assert_eq!(second.index, 1);
+        assert_eq!(wallet.next_index(0, Chain::External), Ok(2));
+        assert_eq!(
+            wallet
+                .receive(Some(Koinu(MAX_MONEY + 1)), None)
+                .unwrap_err(),
+            Error::InvalidAmount((MAX_MONEY + 1).to_string())
+        );
+        assert_eq!(wallet.next_index(0, Chain::External), Ok(2));
+        assert!(first
+            .uri
+            .starts_with(&format!("dogecoin:{}?amount=10", first.address)));
+        assert!(first.qr_png.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_next_addresses_persist_across_restarts() {
+        use crate::indexstore::FileIndexStore;
+
+        let path = std::env::temp_dir().join(format!(
+            "libdogecoin-rs-hd-index-{}.txt",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let master = HdWallet::new(false).unwrap().master_key().to_string();
+
+        let mut wallet = HdWallet::from_master_key(&master, false)
+            .with_index_store(FileIndexStore::open(&path).unwrap());
+        let (i0, a0) = wallet.next_receive_address(0).unwrap();
+        let (c0, _) = wallet.next_change_address(0).unwrap();
+        drop(wallet);
+
+        let mut restarted = HdWallet::from_master_key(&master, false)
+            .with_index_store(FileIndexStore::open(&path).unwrap());
+        let (i1, a1) = restarted.next_receive_address(0).unwrap();
+        let (c1, _) = restarted.next_change_address(0).unwrap();
+
+        assert_eq!((i0, i1), (0, 1));
+        assert_eq!((c0, c1), (0, 1));
+        assert_ne!(a0, a1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bip44_paths() {
+        let mainnet = HdWallet::from_master_key("dgpv", false);
+        assert_eq!(mainnet.account_path(2), "m/44'/3'/2'");
+        assert_eq!(mainnet.chain_path(0, Chain::Internal), "m/44'/3'/0'/1");
+
+        let testnet = HdWallet::from_master_key("tprv", true);
+        assert_eq!(testnet.chain_path(1, Chain::External), "m/44'/1'/1'/0");
+    }
+
+    #[test]
+    fn test_account_extended_keys() {
+        let wallet = HdWallet::new(false).unwrap();
+        let xpriv = wallet.account_xpriv(0).unwrap();
+        let xpub = wallet.account_xpub(0).unwrap();
+        assert!(xpriv.is_private());
+        assert!(!xpub.is_private());
+        assert_eq!(xpub.path(), Some("m/44'/3'/0'"));
+        assert_ne!(xpriv.as_str(), wallet.master_key());
+
+        let receive = wallet.chain_xpub(0, Chain::External).unwrap();
+        let change = wallet.chain_xpub(0, Chain::Internal).unwrap();
+        assert_ne!(receive, change);
+        assert_eq!(change.path(), Some("m/44'/3'/0'/1"));
+    }
+
+    #[test]
+    fn test_derive_address_matches_libdogecoin() {
+        use crate::testing::{MAINNET_MASTER_KEY, TESTNET_MASTER_KEY, TESTNET_RECEIVE_ADDRESSES};
+
+        // getDerivedHDAddress and generateDerivedHDPubkey output for the
+        // fixture keys: coin type 3 on both networks, and the network of
+        // the key's prefix whatever the wallet's flag says.
+        let vectors = [
+            (
+                MAINNET_MASTER_KEY,
+                "DFhFGJBm4UBHxQAExQ5Gohz1v9AUPG1WjV",
+                [
+                    "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+                    "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas",
+                ],
+                "D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e",
+            ),
+            (
+                TESTNET_MASTER_KEY,
+                "nekJzJvfzSe1qNjRzDij47aKA1YmTH7UeK",
+                [
+                    "naxvmcKgLi92MJkVvNBGVPooeJKY4wHDxY",
+                    "nZfGtu3LvsQuAwjHEvLatJbavnMkT6nw4j",
+                ],
+                "nWUhuMbLqyVVb8PjitwLuevm7smjk4GRjQ",
+            ),
+        ];
+        for (key, master, receive, change) in vectors {
+            for is_testnet in [false, true] {
+                let wallet = HdWallet::from_master_key(key, is_testnet);
+                let mut session = wallet.derivation_session().unwrap();
+                assert_eq!(wallet.derive_new_address().as_deref(), Ok(master));
+                for (index, expected) in (0..).zip(receive) {
+                    assert_eq!(
+                        wallet.derive_address(0, index, false).as_deref(),
+                        Ok(expected)
+                    );
+                    assert_eq!(
+                        session.derive_address(0, index, false).as_deref(),
+                        Ok(expected)
+                    );
+                }
+                assert_eq!(wallet.derive_address(0, 0, true).as_deref(), Ok(change));
+                assert_eq!(session.derive_address(0, 0, true).as_deref(), Ok(change));
+            }
+        }
+
+        // The BIP44 preset keeps coin type 1 on testnet.
+        let testnet = HdWallet::from_master_key(TESTNET_MASTER_KEY, true);
+        assert_eq!(
+            testnet
+                .derive_preset_address(WalletPreset::Bip44, 0, Chain::External, 0)
+                .as_deref(),
+            Ok(TESTNET_RECEIVE_ADDRESSES[0])
+        );
+    }
+
+    #[test]
+    fn test_derivation_session() {
+        let wallet = HdWallet::from_master_key(ABANDON_MASTER_KEY, false);
+        let mut session = wallet.derivation_session().unwrap();
+
+        assert_eq!(
+            session.derive_address(0, 0, false).as_deref(),
+            Ok("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC")
+        );
+        assert_eq!(
+            session.derive_address(0, 1, false).as_deref(),
+            Ok("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas")
+        );
+        assert_eq!(
+            session.derive_address(0, 0, true).as_deref(),
+            Ok("D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e")
+        );
+        assert_eq!(
+            session.derive_by_path("m/44'/3'/0'/0/1"),
+            session.derive_address(0, 1, false)
+        );
+        assert_eq!(
+            wallet.derive_address(0, 1, false),
+            session.derive_address(0, 1, false)
+        );
+        assert_eq!(
+            HdWallet::from_master_key("dgpv-bogus", false).derive_new_address(),
+            Err(Error::InvalidExtendedKey)
+        );
+        assert_eq!(
+            session.account_xpub(0).unwrap(),
+            wallet.account_xpub(0).unwrap()
+        );
+        assert_eq!(
+            session.derive_by_path("44'/3'"),
+            Err(Error::InvalidPath("44'/3'".into()))
+        );
+
+        let token = CancellationToken::new();
+        let reports = std::sync::Mutex::new(Vec::new());
+        let progress = |p: &crate::progress::Progress| reports.lock().unwrap().push(p.done);
+        let range = session
+            .derive_range(0, false, 0..2, &token, Some(&progress))
+            .unwrap();
+        assert_eq!(
+            range,
+            [
+                "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+                "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas"
+            ]
+        );
+        assert_eq!(*reports.lock().unwrap(), [1, 2]);
+        token.cancel();
+        let stopped = session
+            .derive_range(0, false, 0..2, &token, None)
+            .unwrap_err();
+        assert_eq!(stopped.reason, StopReason::Cancelled);
+        assert!(stopped.partial.is_empty());
+    }
+
+    #[test]
+    fn test_presets_and_discovery() {
+        let wallet = HdWallet::from_master_key(ABANDON_MASTER_KEY, false);
+        let mut session = wallet.derivation_session().unwrap();
+
+        assert_eq!(
+            session
+                .derive_preset_address(WalletPreset::DogecoinCore, 0, Chain::External, 0)
+                .as_deref(),
+            Ok("DAoc3tHT4BiuRAeWTM8PdD5mn8ziXggTwJ")
+        );
+        assert_eq!(
+            session
+                .derive_preset_address(WalletPreset::DogecoinWalletAndroid, 0, Chain::External, 1)
+                .as_deref(),
+            Ok("DFD5KWFdWokDSu5bQpzfHNn7A6evdPbQYj")
+        );
+        assert_eq!(
+            session.derive_preset_address(WalletPreset::Exodus, 1, Chain::External, 0),
+            Err(Error::NoSuchAccount {
+                preset: "Exodus",
+                account: 1
+            })
+        );
+        assert_eq!(
+            wallet.derive_preset_address(WalletPreset::DogecoinCore, 0, Chain::Internal, 0),
+            session.derive_preset_address(WalletPreset::DogecoinCore, 0, Chain::Internal, 0)
+        );
+
+        let used = [
+            "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas",
+            "DAoc3tHT4BiuRAeWTM8PdD5mn8ziXggTwJ",
+        ];
+        let found = session
+            .discover(&WalletPreset::ALL, &GapLimit::new(3), |a| used.contains(&a))
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].preset, WalletPreset::Bip44);
+        assert_eq!(found[0].index, 1);
+        assert_eq!(found[1].preset, WalletPreset::DogecoinCore);
+        assert_eq!(found[1].chain, Chain::External);
+    }
+
+    #[test]
+    fn test_repair_indexes() {
+        let mut store = MemoryIndexStore::new();
+        store.save(0, Chain::External, 1).unwrap();
+        let mut wallet =
+            HdWallet::from_master_key(ABANDON_MASTER_KEY, false).with_index_store(store);
+        let drifted_change = wallet.derive_address(0, 4, true).unwrap();
+        let used = ["DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas", &drifted_change];
+
+        // A change gap of 4 stops just short of the drifted address.
+        let short = GapLimit::new(3).with_internal(4);
+        let found = wallet.check_indexes(&short, |a| used.contains(&a)).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].chain, Chain::External);
+
+        let repairs = wallet
+            .repair_indexes(&GapLimit::new(5), |a| used.contains(&a))
+            .unwrap();
+        assert_eq!(
+            repairs,
+            [
+                IndexRepair {
+                    account: 0,
+                    chain: Chain::External,
+                    stored: Some(1),
+                    next_index: 2,
+                },
+                IndexRepair {
+                    account: 0,
+                    chain: Chain::Internal,
+                    stored: None,
+                    next_index: 5,
+                },
+            ]
+        );
+        assert_eq!(wallet.next_index(0, Chain::Internal), Ok(5));
+        assert_eq!(
+            wallet.check_indexes(&GapLimit::new(5), |a| used.contains(&a)),
+            Ok(Vec::new())
+        );
+        assert_eq!(wallet.next_change_address(0).unwrap().0, 5);
+    }
+
+    #[test]
+    fn test_find_index() {
+        let wallet = HdWallet::from_master_key(ABANDON_MASTER_KEY, false);
+        assert_eq!(
+            wallet.find_index("D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e", 5),
+            Ok(Some((0, Chain::Internal, 0)))
+        );
+        assert_eq!(
+            wallet.owns_address("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas", 5),
+            Ok(true)
+        );
+        assert_eq!(
+            wallet.owns_address("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas", 1),
+            Ok(false)
+        );
+        assert_eq!(
+            wallet.owns_address("nZVmfmUtKPmskB9Ds4P9GUJy9eYFqPKHqH", 5),
+            Ok(false)
+        );
+
+        let mut session = wallet.derivation_session().unwrap();
+        let account1 = session.derive_address(1, 2, false).unwrap();
+        assert_eq!(
+            session.find_index(&account1, 0..3, 5),
+            Ok(Some((1, Chain::External, 2)))
+        );
+    }
+
+    #[test]
+    fn test_classify_outputs() {
+        use crate::script::OutputKind;
+        use crate::transaction::TxOut;
+
+        let wallet = HdWallet::from_master_key(ABANDON_MASTER_KEY, false);
+        let mut session = wallet.derivation_session().unwrap();
+        let pay = |address: &str, value| TxOut {
+            value,
+            script_pubkey: P2pkhTemplate
+                .build(&OutputKind::from_address(address).unwrap())
+                .unwrap(),
+        };
+        let tx = Transaction {
+            version: 1,
+            inputs: Vec::new(),
+            outputs: vec![
+                pay("DAoc3tHT4BiuRAeWTM8PdD5mn8ziXggTwJ", 500),
+                pay("D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e", 300),
+                pay("DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas", 200),
+            ],
+            lock_time: 0,
+        };
+
+        let classified = session.classify_outputs(&tx, 0, 5).unwrap();
+        assert_eq!(
+            classified.roles,
+            [
+                OutputRole::External,
+                OutputRole::Change(0),
+                OutputRole::Receive(1)
+            ]
+        );
+        assert_eq!(
+            (classified.external, classified.change, classified.received),
+            (500, 300, 200)
+        );
+        assert_eq!(session.classify_outputs(&tx, 1, 5).unwrap().external, 1_000);
+    }
+
     #[test]
     fn test_derive_new_address() {
         let wallet = HdWallet::new(false).unwrap();
-        let addr = wallet.derive_new_address();
-        assert!(addr.is_some());
-        println!("New derived address: {}", addr.unwrap());
+        let addr = wallet.derive_new_address().unwrap();
+        println!("New derived address: {}", addr);
     }
 }
@@ -0,0 +1,150 @@
+//! Hex encoding and decoding, and constant-time comparison.
+//!
+//! The `*_to_slice` variants work in caller-provided buffers, so hot loops
+//! (hashing many transactions, matching scripts) can reuse one buffer instead
+//! of allocating a `String` or `Vec` per item.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::hex;
+//!
+//! let mut buf = [0u8; 64];
+//! let txid = [0xab; 32];
+//! assert_eq!(hex::encode_to_slice(&txid, &mut buf), Some("ab".repeat(32).as_str()));
+//!
+//! let parsed: [u8; 32] = hex::decode_array(&"ab".repeat(32)).unwrap();
+//! assert!(hex::ct_eq(&parsed, &txid));
+//! ```
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Encode bytes as lowercase hex.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    encode_into(bytes, &mut out);
+    out
+}
+
+/// Append lowercase hex of `bytes` to `out`.
+pub fn encode_into(bytes: &[u8], out: &mut String) {
+    out.reserve(bytes.len() * 2);
+    for &b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0x0f) as usize] as char);
+    }
+}
+
+/// Encode bytes as lowercase hex into `out`.
+///
+/// # Returns
+/// The hex, borrowed from the start of `out`, or `None` if `out` is shorter
+/// than twice `bytes`.
+pub fn encode_to_slice<'a>(bytes: &[u8], out: &'a mut [u8]) -> Option<&'a str> {
+    let out = out.get_mut(..bytes.len() * 2)?;
+    for (pair, &b) in out.chunks_exact_mut(2).zip(bytes) {
+        pair[0] = DIGITS[(b >> 4) as usize];
+        pair[1] = DIGITS[(b & 0x0f) as usize];
+    }
+    // Only ASCII hex digits were written.
+    std::str::from_utf8(out).ok()
+}
+
+/// Decode hex (either case) into bytes.
+///
+/// # Returns
+/// `None` on odd length or a non-hex character.
+pub fn decode(hex: &str) -> Option<Vec<u8>> {
+    let mut out = vec![0u8; hex.len() / 2];
+    decode_to_slice(hex, &mut out)?;
+    Some(out)
+}
+
+/// Decode hex (either case) into the start of `out`.
+///
+/// # Returns
+/// The number of bytes written, or `None` on odd length, a non-hex
+/// character, or if `out` is too short.
+pub fn decode_to_slice(hex: &str, out: &mut [u8]) -> Option<usize> {
+    let hex = hex.as_bytes();
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    let len = hex.len() / 2;
+    for (byte, pair) in out.get_mut(..len)?.iter_mut().zip(hex.chunks_exact(2)) {
+        *byte = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Some(len)
+}
+
+/// Decode hex of exactly `N` bytes, e.g. a txid or hash.
+pub fn decode_array<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 {
+        return None;
+    }
+    let mut out = [0u8; N];
+    decode_to_slice(hex, &mut out)?;
+    Some(out)
+}
+
+/// Compare two byte strings in time independent of their contents.
+///
+/// Use for checksums and digests derived from secrets. The length is not
+/// hidden.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+fn nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let bytes = [0x00, 0x1e, 0xab, 0xff];
+        assert_eq!(encode(&bytes), "001eabff");
+        assert_eq!(decode("001EabFF").as_deref(), Some(&bytes[..]));
+        assert_eq!(decode("").as_deref(), Some(&[][..]));
+        assert!(decode("abc").is_none());
+        assert!(decode("zz").is_none());
+    }
+
+    #[test]
+    fn test_buffer_variants() {
+        let mut buf = [0u8; 6];
+        assert_eq!(encode_to_slice(&[0x01, 0xfe], &mut buf), Some("01fe"));
+        assert_eq!(encode_to_slice(&[0; 4], &mut buf), None);
+
+        let mut out = [0u8; 3];
+        assert_eq!(decode_to_slice("0a0B", &mut out), Some(2));
+        assert_eq!(out[..2], [0x0a, 0x0b]);
+        assert_eq!(decode_to_slice("00112233", &mut out), None);
+
+        assert_eq!(decode_array::<2>("beef"), Some([0xbe, 0xef]));
+        assert_eq!(decode_array::<2>("beefbeef"), None);
+
+        let mut s = String::from("0x");
+        encode_into(&[0xc0, 0xde], &mut s);
+        assert_eq!(s, "0xc0de");
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        assert!(ct_eq(b"digest", b"digest"));
+        assert!(!ct_eq(b"digest", b"digesT"));
+        assert!(!ct_eq(b"digest", b"diges"));
+        assert!(ct_eq(&[], &[]));
+    }
+}
@@ -0,0 +1,210 @@
+//! Wallet history entries with net amounts and fees.
+//!
+//! A [`HistoryEntry`] summarises what a transaction did to the wallet's
+//! balance: which way value moved, the net change, and the fee when the
+//! wallet paid it. Outputs are attributed with
+//! [`DerivationSession::classify_outputs`](crate::DerivationSession::classify_outputs);
+//! inputs are valued by looking up the wallet's own outputs they spend.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::history::HistoryEntry;
+//! use libdogecoin_rs::{HdWallet, Transaction, UtxoSnapshot};
+//!
+//! # let (wallet, tx, utxos): (HdWallet, Transaction, UtxoSnapshot) = unimplemented!();
+//! let mut session = wallet.derivation_session().unwrap();
+//! let outputs = session.classify_outputs(&tx, 0, 100).unwrap();
+//! let entry = HistoryEntry::new(&tx, &outputs, |outpoint| {
+//!     utxos.get(outpoint).map(|utxo| utxo.amount)
+//! });
+//! if let Some(entry) = entry {
+//!     println!("{:?} {} koinu, fee {:?}", entry.direction, entry.net, entry.fee);
+//! }
+//! ```
+
+use crate::fingerprint::WalletId;
+use crate::hdwallet::OutputClassification;
+use crate::transaction::Transaction;
+use crate::utxo::OutPoint;
+
+/// Which way a transaction moved value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    /// Paid to the wallet; none of its inputs are ours.
+    Incoming,
+    /// Spent our outputs and paid someone else.
+    Outgoing,
+    /// Spent our outputs and paid only ourselves.
+    SelfTransfer,
+}
+
+/// The wallet's view of one transaction. Amounts are in koinu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryEntry {
+    /// Transaction ID in display order.
+    pub txid: String,
+    pub direction: Direction,
+    /// Change in balance: what came back to us minus what we spent.
+    pub net: i64,
+    /// Fee paid, known only when every input is ours.
+    pub fee: Option<u64>,
+    /// Value of our outputs spent by the transaction.
+    pub spent: u64,
+    /// Paid to our receiving addresses.
+    pub received: u64,
+    /// Returned to our change addresses.
+    pub change: u64,
+    /// Paid to others; `0` for incoming transactions, whose other outputs
+    /// belong to the sender.
+    pub sent: u64,
+    /// The wallet the entry belongs to, when built by
+    /// [`DerivationSession::history_entry`](crate::DerivationSession::history_entry).
+    pub wallet: Option<WalletId>,
+}
+
+impl HistoryEntry {
+    /// Summarise a transaction for the wallet.
+    ///
+    /// # Arguments
+    /// * `tx` - The transaction.
+    /// * `outputs` - Its output roles for the wallet's account.
+    /// * `our_value` - Value of the spent output if it is ours, e.g. from the
+    ///   wallet's [`UtxoSnapshot`](crate::UtxoSnapshot) before this
+    ///   transaction.
+    ///
+    /// # Returns
+    /// `None` if the transaction neither spends from nor pays the wallet, or
+    /// if the amounts overflow.
+    pub fn new(
+        tx: &Transaction,
+        outputs: &OutputClassification,
+        mut our_value: impl FnMut(&OutPoint) -> Option<u64>,
+    ) -> Option<Self> {
+        let mut spent = 0u64;
+        let mut all_ours = !tx.inputs.is_empty() && !tx.is_coinbase();
+        for input in &tx.inputs {
+            match our_value(&input.previous_output) {
+                Some(value) => spent = spent.checked_add(value)?,
+                None => all_ours = false,
+            }
+        }
+        let credit = outputs.received.checked_add(outputs.change)?;
+        if spent == 0 && credit == 0 {
+            return None;
+        }
+
+        let direction = if spent == 0 {
+            Direction::Incoming
+        } else if outputs.external == 0 {
+            Direction::SelfTransfer
+        } else {
+            Direction::Outgoing
+        };
+        let fee = if all_ours {
+            spent.checked_sub(tx.output_value()?)
+        } else {
+            None
+        };
+
+        Some(HistoryEntry {
+            txid: tx.txid_hex(),
+            direction,
+            net: i64::try_from(credit).ok()? - i64::try_from(spent).ok()?,
+            fee,
+            spent,
+            received: outputs.received,
+            change: outputs.change,
+            sent: if spent == 0 { 0 } else { outputs.external },
+            wallet: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdwallet::OutputRole;
+    use crate::transaction::{TxIn, TxOut};
+
+    fn tx(inputs: &[[u8; 32]], outputs: &[u64]) -> Transaction {
+        Transaction {
+            version: 1,
+            inputs: inputs
+                .iter()
+                .map(|&txid| TxIn {
+                    previous_output: OutPoint { txid, vout: 0 },
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                })
+                .collect(),
+            outputs: outputs
+                .iter()
+                .map(|&value| TxOut {
+                    value,
+                    script_pubkey: Vec::new(),
+                })
+                .collect(),
+            lock_time: 0,
+        }
+    }
+
+    fn classified(roles: &[OutputRole], values: &[u64]) -> OutputClassification {
+        let mut c = OutputClassification::default();
+        for (&role, &value) in roles.iter().zip(values) {
+            match role {
+                OutputRole::External => c.external += value,
+                OutputRole::Receive(_) => c.received += value,
+                OutputRole::Change(_) => c.change += value,
+            }
+            c.roles.push(role);
+        }
+        c
+    }
+
+    #[test]
+    fn test_outgoing_with_fee() {
+        let ours = [1u8; 32];
+        let values = [700, 250];
+        let tx = tx(&[ours], &values);
+        let outputs = classified(&[OutputRole::External, OutputRole::Change(0)], &values);
+        let entry =
+            HistoryEntry::new(&tx, &outputs, |o| (o.txid == ours).then_some(1_000)).unwrap();
+
+        assert_eq!(entry.direction, Direction::Outgoing);
+        assert_eq!(entry.fee, Some(50));
+        assert_eq!(entry.net, -750);
+        assert_eq!(entry.sent, 700);
+        assert_eq!(entry.net, -(entry.sent as i64) - entry.fee.unwrap() as i64);
+    }
+
+    #[test]
+    fn test_incoming_and_unrelated() {
+        let values = [400, 9_000];
+        let tx = tx(&[[2u8; 32]], &values);
+        let outputs = classified(&[OutputRole::Receive(3), OutputRole::External], &values);
+        let entry = HistoryEntry::new(&tx, &outputs, |_| None).unwrap();
+        assert_eq!(entry.direction, Direction::Incoming);
+        assert_eq!((entry.net, entry.fee, entry.sent), (400, None, 0));
+
+        let outputs = classified(&[OutputRole::External, OutputRole::External], &values);
+        assert_eq!(HistoryEntry::new(&tx, &outputs, |_| None), None);
+    }
+
+    #[test]
+    fn test_self_transfer_and_mixed_inputs() {
+        let (a, b) = ([3u8; 32], [4u8; 32]);
+        let tx = tx(&[a, b], &[1_900]);
+        let outputs = classified(&[OutputRole::Receive(0)], &[1_900]);
+
+        let entry = HistoryEntry::new(&tx, &outputs, |_| Some(1_000)).unwrap();
+        assert_eq!(entry.direction, Direction::SelfTransfer);
+        assert_eq!((entry.net, entry.fee), (-100, Some(100)));
+
+        // A co-signed input from someone else hides the fee.
+        let entry = HistoryEntry::new(&tx, &outputs, |o| (o.txid == a).then_some(1_000)).unwrap();
+        assert_eq!(entry.fee, None);
+        assert_eq!(entry.net, 900);
+    }
+}
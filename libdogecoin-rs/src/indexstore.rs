@@ -0,0 +1,174 @@
+//! Persistent address-index state for HD wallets.
+//!
+//! An [`IndexStore`] remembers the next unused address index for each
+//! account/chain pair so that [`HdWallet::next_receive_address`] and
+//! [`HdWallet::next_change_address`] never hand out the same address twice,
+//! even across restarts.
+//!
+//! [`HdWallet::next_receive_address`]: crate::HdWallet::next_receive_address
+//! [`HdWallet::next_change_address`]: crate::HdWallet::next_change_address
+
+use crate::hdwallet::Chain;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Storage for the next unused address index per account and chain.
+pub trait IndexStore: Send {
+    /// Load the next unused index, or `None` if nothing was stored yet.
+    fn load(&self, account: u32, chain: Chain) -> io::Result<Option<u32>>;
+
+    /// Persist the next unused index.
+    fn save(&mut self, account: u32, chain: Chain, next_index: u32) -> io::Result<()>;
+}
+
+/// Volatile index store; state is lost when it is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryIndexStore {
+    indexes: HashMap<(u32, Chain), u32>,
+}
+
+impl MemoryIndexStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IndexStore for MemoryIndexStore {
+    fn load(&self, account: u32, chain: Chain) -> io::Result<Option<u32>> {
+        Ok(self.indexes.get(&(account, chain)).copied())
+    }
+
+    fn save(&mut self, account: u32, chain: Chain, next_index: u32) -> io::Result<()> {
+        self.indexes.insert((account, chain), next_index);
+        Ok(())
+    }
+}
+
+/// Index store backed by a small text file.
+///
+/// Each line holds `<account> <external|internal> <next_index>`. The file is
+/// rewritten atomically (write to a temporary file, then rename) on every save.
+#[derive(Debug, Clone)]
+pub struct FileIndexStore {
+    path: PathBuf,
+    indexes: MemoryIndexStore,
+}
+
+impl FileIndexStore {
+    /// Open a store, loading existing state if the file exists.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut indexes = MemoryIndexStore::new();
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for (lineno, line) in contents.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let (account, chain, next) = parse_line(line).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("{}:{}: malformed index entry", path.display(), lineno + 1),
+                        )
+                    })?;
+                    indexes.save(account, chain, next)?;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(FileIndexStore { path, indexes })
+    }
+
+    /// Get the backing file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let mut entries: Vec<_> = self.indexes.indexes.iter().collect();
+        entries.sort();
+
+        let mut contents = String::new();
+        for ((account, chain), next) in entries {
+            contents.push_str(&format!("{account} {} {next}\n", chain_name(*chain)));
+        }
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+impl IndexStore for FileIndexStore {
+    fn load(&self, account: u32, chain: Chain) -> io::Result<Option<u32>> {
+        self.indexes.load(account, chain)
+    }
+
+    fn save(&mut self, account: u32, chain: Chain, next_index: u32) -> io::Result<()> {
+        self.indexes.save(account, chain, next_index)?;
+        self.flush()
+    }
+}
+
+fn chain_name(chain: Chain) -> &'static str {
+    match chain {
+        Chain::External => "external",
+        Chain::Internal => "internal",
+    }
+}
+
+fn parse_line(line: &str) -> Option<(u32, Chain, u32)> {
+    let mut fields = line.split_whitespace();
+    let account = fields.next()?.parse().ok()?;
+    let chain = match fields.next()? {
+        "external" => Chain::External,
+        "internal" => Chain::Internal,
+        _ => return None,
+    };
+    let next = fields.next()?.parse().ok()?;
+    fields.next().is_none().then_some((account, chain, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store() {
+        let mut store = MemoryIndexStore::new();
+        assert_eq!(store.load(0, Chain::External).unwrap(), None);
+        store.save(0, Chain::External, 5).unwrap();
+        assert_eq!(store.load(0, Chain::External).unwrap(), Some(5));
+        assert_eq!(store.load(0, Chain::Internal).unwrap(), None);
+    }
+
+    #[test]
+    fn test_file_store_persists_across_reopen() {
+        let path = std::env::temp_dir().join(format!(
+            "libdogecoin-rs-indexstore-{}.txt",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let mut store = FileIndexStore::open(&path).unwrap();
+        store.save(0, Chain::External, 3).unwrap();
+        store.save(2, Chain::Internal, 7).unwrap();
+
+        let reopened = FileIndexStore::open(&path).unwrap();
+        assert_eq!(reopened.load(0, Chain::External).unwrap(), Some(3));
+        assert_eq!(reopened.load(2, Chain::Internal).unwrap(), Some(7));
+        assert_eq!(reopened.load(1, Chain::External).unwrap(), None);
+
+        fs::write(&path, "0 sideways 1\n").unwrap();
+        assert!(FileIndexStore::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
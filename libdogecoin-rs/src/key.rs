@@ -0,0 +1,83 @@
+//! Private keys held as raw secrets for signing in Rust.
+
+use crate::address::{base58check_decode, WIF_VERSION_MAINNET, WIF_VERSION_TESTNET};
+use crate::context::Session;
+use crate::ffi::sys;
+use crate::transaction::SIGHASH_ALL;
+use zeroize::Zeroizing;
+
+/// Longest DER-encoded ECDSA signature.
+const MAX_DER_SIGNATURE_LEN: usize = 72;
+
+/// A private key and its public key.
+pub(crate) struct SigningKey {
+    secret: Zeroizing<[u8; 32]>,
+    pub(crate) pubkey: Vec<u8>,
+    /// Keeps the ECC context up for [`SigningKey::sign`].
+    _ecc: Option<Session>,
+}
+
+impl SigningKey {
+    /// Decode a WIF key for the given network.
+    pub(crate) fn from_wif(privkey_wif: &str, is_testnet: bool) -> Option<Self> {
+        // Version byte, key, and a 0x01 suffix for compressed keys.
+        let payload = Zeroizing::new(base58check_decode(privkey_wif)?);
+        let compressed = match payload.len() {
+            34 if payload[33] == 0x01 => true,
+            33 => false,
+            _ => return None,
+        };
+        let version = match is_testnet {
+            true => WIF_VERSION_TESTNET,
+            false => WIF_VERSION_MAINNET,
+        };
+        if payload[0] != version {
+            return None;
+        }
+        let mut secret = Zeroizing::new([0u8; 32]);
+        secret.copy_from_slice(&payload[1..33]);
+        Self::from_secret(secret, compressed)
+    }
+
+    /// Wrap a raw secret, computing its public key.
+    ///
+    /// # Returns
+    /// `None` if the secret is zero or not below the curve order.
+    pub(crate) fn from_secret(secret: Zeroizing<[u8; 32]>, compressed: bool) -> Option<Self> {
+        let ecc = crate::context::ensure_ecc_started();
+        let mut pubkey = [0u8; 65];
+        let mut len = if compressed { 33 } else { 65 };
+        unsafe {
+            sys::dogecoin_ecc_get_pubkey(secret.as_ptr(), pubkey.as_mut_ptr(), &mut len, compressed)
+        };
+        // The key is left zeroed if the secret is out of range.
+        if pubkey[0] == 0 {
+            return None;
+        }
+        Some(SigningKey {
+            secret,
+            pubkey: pubkey[..len].to_vec(),
+            _ecc: ecc,
+        })
+    }
+
+    /// Sign `hash`, returning the DER signature with `SIGHASH_ALL` appended.
+    pub(crate) fn sign(&self, hash: &[u8; 32]) -> Option<Vec<u8>> {
+        let mut signature = [0u8; MAX_DER_SIGNATURE_LEN];
+        let mut len = signature.len();
+        let signed = unsafe {
+            sys::dogecoin_ecc_sign(
+                self.secret.as_ptr(),
+                hash.as_ptr(),
+                signature.as_mut_ptr(),
+                &mut len,
+            )
+        };
+        if signed != 1 || len > MAX_DER_SIGNATURE_LEN {
+            return None;
+        }
+        let mut signature = signature[..len].to_vec();
+        signature.push(SIGHASH_ALL);
+        Some(signature)
+    }
+}
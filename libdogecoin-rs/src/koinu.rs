@@ -0,0 +1,151 @@
+//! Integer DOGE amounts.
+//!
+//! [`Koinu`] counts the smallest unit (1 DOGE = [`COIN`] koinu), so amounts
+//! add up exactly. Decimal DOGE strings are parsed and formatted with
+//! libdogecoin's `koinu.c` conversions, as the transaction builder expects.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::Koinu;
+//!
+//! let amount: Koinu = "10.5".parse().unwrap();
+//! assert_eq!(amount, Koinu(1_050_000_000));
+//! assert_eq!(amount.checked_add(Koinu::from_doge(2).unwrap()), Some(Koinu(1_250_000_000)));
+//! assert!("0.000000001".parse::<Koinu>().is_err());
+//! ```
+
+use crate::consensus::{COIN, MAX_MONEY};
+use crate::error::{Error, Result};
+use crate::ffi::{string_from_buf, sys, to_cstring, KOINU_STR_BUF_LEN};
+use crate::uri::is_valid_amount;
+use std::fmt;
+use std::str::FromStr;
+
+/// An amount in koinu.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Koinu(pub u64);
+
+impl Koinu {
+    pub const ZERO: Koinu = Koinu(0);
+
+    /// One DOGE.
+    pub const ONE_DOGE: Koinu = Koinu(COIN);
+
+    /// Get an amount of whole DOGE, or `None` on overflow.
+    pub fn from_doge(doge: u64) -> Option<Self> {
+        doge.checked_mul(COIN).map(Koinu)
+    }
+
+    /// Convert a floating-point DOGE amount, as returned by JSON-RPC.
+    ///
+    /// # Returns
+    /// The amount rounded to the nearest koinu, or `None` if it is negative,
+    /// not finite, or above [`MAX_MONEY`].
+    pub fn from_doge_f64(doge: f64) -> Option<Self> {
+        let koinu = (doge * COIN as f64).round();
+        (koinu.is_finite() && koinu >= 0.0 && koinu <= MAX_MONEY as f64)
+            .then_some(Koinu(koinu as u64))
+    }
+
+    pub fn to_u64(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Koinu) -> Option<Koinu> {
+        self.0.checked_add(other.0).map(Koinu)
+    }
+
+    pub fn checked_sub(self, other: Koinu) -> Option<Koinu> {
+        self.0.checked_sub(other.0).map(Koinu)
+    }
+
+    /// Format as a decimal DOGE string with eight places, e.g. `"10.50000000"`.
+    pub fn to_doge_string(self) -> Result<String> {
+        let mut buf = [0u8; KOINU_STR_BUF_LEN];
+        let ok = unsafe { sys::koinu_to_coins_str(self.0, buf.as_mut_ptr().cast()) };
+        if ok != 1 {
+            return Err(Error::Ffi {
+                function: "koinu_to_coins_str",
+                code: ok,
+            });
+        }
+        string_from_buf(&buf)
+    }
+}
+
+impl FromStr for Koinu {
+    type Err = Error;
+
+    /// Parse a decimal DOGE amount with at most eight places, e.g. `"10.5"`.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidAmount(s.to_owned());
+        if !is_valid_amount(s) {
+            return Err(invalid());
+        }
+        // Range-check before handing the string to libdogecoin, which
+        // converts through floating point.
+        let whole = s.split('.').next().unwrap_or(s);
+        if whole.len() > 11 || whole.parse::<u64>().map_err(|_| invalid())? > MAX_MONEY / COIN {
+            return Err(invalid());
+        }
+        let cstr = to_cstring(s)?;
+        let koinu = unsafe { sys::coins_to_koinu_str(cstr.as_ptr()) };
+        if koinu > MAX_MONEY {
+            return Err(invalid());
+        }
+        Ok(Koinu(koinu))
+    }
+}
+
+impl fmt::Display for Koinu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_doge_string() {
+            Ok(s) => f.write_str(&s),
+            Err(_) => write!(f, "{}.{:08}", self.0 / COIN, self.0 % COIN),
+        }
+    }
+}
+
+impl From<u64> for Koinu {
+    fn from(koinu: u64) -> Self {
+        Koinu(koinu)
+    }
+}
+
+impl From<Koinu> for u64 {
+    fn from(amount: Koinu) -> Self {
+        amount.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        assert_eq!("1".parse::<Koinu>(), Ok(Koinu::ONE_DOGE));
+        assert_eq!("0.00000001".parse::<Koinu>(), Ok(Koinu(1)));
+        assert_eq!("10000000000".parse::<Koinu>(), Ok(Koinu(MAX_MONEY)));
+        for bad in ["", "-1", "1.", "1e3", "0.123456789", "10000000000.1"] {
+            assert!(bad.parse::<Koinu>().is_err(), "{bad}");
+        }
+
+        let amount = Koinu(1_234_567_891);
+        assert_eq!(amount.to_string().parse::<Koinu>(), Ok(amount));
+    }
+
+    #[test]
+    fn test_arithmetic_and_f64() {
+        assert_eq!(Koinu(5).checked_sub(Koinu(6)), None);
+        assert_eq!(Koinu(u64::MAX).checked_add(Koinu(1)), None);
+        assert_eq!(Koinu::from_doge_f64(0.1 + 0.2), Some(Koinu(30_000_000)));
+        assert_eq!(Koinu::from_doge_f64(-1.0), None);
+        assert_eq!(Koinu::from_doge_f64(f64::NAN), None);
+    }
+}
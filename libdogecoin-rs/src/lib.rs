@@ -4,27 +4,181 @@
 //! - Wallet creation and key generation
 //! - Transaction creation and signing
 //! - HD Wallet support (BIP32/BIP44)
-//! - Mnemonic phrase generation (BIP39)
+//! - Mnemonic phrase generation (BIP39) and wordlist lookup
 //! - QR Code generation for addresses
+//! - Payment URIs and one-call receive requests
+//!
+//! ## Panic safety
+//! Panics never unwind across the FFI boundary: libdogecoin is only called,
+//! never calls back into Rust, and callbacks the crate runs for you (event
+//! subscribers) have their panics caught. Internal locks recover from
+//! poisoning, so a panic in your code does not wedge later calls.
+//! libdogecoin itself exits the process on allocation failure; that cannot
+//! be caught, and inputs are validated before they reach it.
+//!
+//! ## Minimal build
+//! Everything beyond key generation ([`DogeWallet`]), address derivation and
+//! validation ([`AddressUtils`]) and message signing ([`Message`]) is behind
+//! the default `full` feature. With `default-features = false` only the
+//! libdogecoin sources those need are compiled, for embedded and serverless
+//! targets that want a small binary and a fast build.
 
 pub mod address;
+#[cfg(feature = "full")]
+pub mod anchor;
+#[cfg(feature = "full")]
+pub mod birthday;
+#[cfg(feature = "full")]
+pub mod block;
+#[cfg(feature = "full")]
+pub mod broadcast;
+#[cfg(feature = "full")]
+pub mod builder;
+pub mod cancel;
+#[cfg(feature = "full")]
+pub mod coinselect;
+#[cfg(feature = "full")]
+pub mod config;
+#[cfg(feature = "full")]
+pub mod consensus;
+#[cfg(feature = "full")]
+pub mod consolidate;
 pub mod context;
+#[cfg(feature = "full")]
+pub mod descriptor;
+#[cfg(feature = "devtools")]
+pub mod devtools;
+#[cfg(feature = "full")]
+pub mod difficulty;
+mod encode;
+pub mod error;
+#[cfg(feature = "full")]
+pub mod events;
+#[cfg(feature = "full")]
+pub mod extkey;
+mod ffi;
+#[cfg(feature = "full")]
+pub mod fingerprint;
+#[cfg(feature = "rpc")]
+pub mod funding;
+mod hash;
+#[cfg(feature = "full")]
+mod hdnode;
+#[cfg(feature = "full")]
 pub mod hdwallet;
+pub mod hex;
+#[cfg(feature = "full")]
+pub mod history;
+#[cfg(feature = "full")]
+pub mod indexstore;
+#[cfg(feature = "full")]
+mod key;
+#[cfg(feature = "full")]
+pub mod koinu;
 pub mod message;
+#[cfg(feature = "full")]
 pub mod mnemonic;
+#[cfg(feature = "full")]
+pub mod offline;
+#[cfg(feature = "full")]
+pub mod pipeline;
+#[cfg(feature = "full")]
+pub mod preset;
+#[cfg(feature = "full")]
+pub mod price;
+#[cfg(feature = "full")]
+pub mod privacy;
+pub mod progress;
+#[cfg(feature = "full")]
+pub mod psdt;
+#[cfg(feature = "full")]
 pub mod qrcode;
+#[cfg(feature = "full")]
+mod qrdecode;
+#[cfg(feature = "full")]
+pub mod replace;
 #[cfg(feature = "rpc")]
 pub mod rpc;
+#[cfg(feature = "full")]
+pub mod script;
+#[cfg(feature = "shamir")]
+pub mod shamir;
+#[cfg(all(feature = "full", any(test, feature = "testing")))]
+pub mod testing;
+#[cfg(feature = "full")]
 pub mod transaction;
+#[cfg(feature = "full")]
+pub mod uri;
+#[cfg(feature = "full")]
+pub mod utxo;
+#[cfg(feature = "full")]
+pub mod verify;
 pub mod wallet;
+#[cfg(feature = "full")]
+pub mod watch;
+#[cfg(feature = "full")]
+pub mod wordlist;
 
-pub use address::{AddressNetwork, AddressUtils};
-pub use hdwallet::HdWallet;
+pub use address::{AddressError, AddressNetwork, AddressUtils};
+#[cfg(feature = "full")]
+pub use block::{Block, BlockHeader};
+#[cfg(feature = "full")]
+pub use builder::{BuildError, TransactionBuilder};
+#[cfg(feature = "full")]
+pub use coinselect::{ChangePolicy, CoinSelector, Selection};
+#[cfg(feature = "full")]
+pub use config::{Config, GapLimit, Network};
+#[cfg(feature = "full")]
+pub use consolidate::ConsolidationBuilder;
+pub use context::Session;
+pub use error::{Error, Result};
+#[cfg(feature = "full")]
+pub use events::{Event, EventBus};
+#[cfg(feature = "full")]
+pub use extkey::ExtendedKey;
+#[cfg(feature = "full")]
+pub use fingerprint::{KeyFingerprint, WalletId};
+#[cfg(feature = "full")]
+pub use hdwallet::{
+    Chain, DerivationSession, DiscoveredAddress, HdWallet, IndexRepair, KeyPath,
+    OutputClassification, OutputRole, Receive,
+};
+#[cfg(feature = "full")]
+pub use history::{Direction, HistoryEntry};
+#[cfg(feature = "full")]
+pub use indexstore::{FileIndexStore, IndexStore, MemoryIndexStore};
+#[cfg(feature = "full")]
+pub use koinu::Koinu;
+/// The raw libdogecoin bindings. Unstable: they change with the vendored C
+/// library, outside this crate's semver guarantees.
+#[cfg(feature = "unstable-sys")]
 pub use libdogecoin_sys as sys;
 pub use message::Message;
+#[cfg(feature = "full")]
 pub use mnemonic::Mnemonic;
-pub use qrcode::QrCode;
+#[cfg(feature = "full")]
+pub use offline::UnsignedTransaction;
+#[cfg(feature = "full")]
+pub use pipeline::{Pipeline, PipelineConfig};
+#[cfg(feature = "full")]
+pub use preset::WalletPreset;
+#[cfg(feature = "full")]
+pub use psdt::Psdt;
+#[cfg(feature = "full")]
+pub use qrcode::{QrAssembler, QrCode};
 #[cfg(feature = "rpc")]
 pub use rpc::DogeRpcClient;
-pub use transaction::DogeTransaction;
+#[cfg(feature = "full")]
+pub use script::{OutputKind, ScriptTemplate, TemplateRegistry};
+#[cfg(feature = "full")]
+pub use transaction::{
+    DecodeError, DecodedTransaction, DogeTransaction, StandardError, StandardPolicy, Transaction,
+    TransactionError, TxSize, Txid,
+};
+#[cfg(feature = "full")]
+pub use uri::PaymentUri;
+#[cfg(feature = "full")]
+pub use utxo::{OutPoint, Utxo, UtxoSnapshot};
 pub use wallet::DogeWallet;
+#[cfg(feature = "full")]
+pub use watch::WatchSet;
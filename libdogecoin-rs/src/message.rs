@@ -1,8 +1,98 @@
 //! Message signing and verification.
+//!
+//! [`Message::sign`] and [`Message::verify`] use Dogecoin Core's format. For
+//! proofs from other tools, [`Message::sign_with`] and
+//! [`Message::verify_with`] take [`MessageOptions`]: a different magic
+//! prefix, and lenient header bytes as written by Electrum-derived wallets
+//! (segwit header values, or a compression flag that does not match the
+//! signing key).
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::message::{Message, MessageOptions};
+//!
+//! # let (signature, address) = ("", "");
+//! let legacy = MessageOptions::new()
+//!     .with_magic("Bitcoin Signed Message:\n")
+//!     .with_lenient_header();
+//! let valid = Message::verify_with(signature, "I own this address", address, &legacy);
+//! ```
 
-use crate::sys;
-use std::ffi::{CStr, CString};
-use std::os::raw::c_void;
+use crate::address::{base58check_decode, AddressKind, AddressUtils};
+use crate::encode;
+use crate::ffi::{
+    checked_cstring, is_base58_byte, is_base64_byte, string_from_ptr, sys, MAX_ADDRESS_LEN,
+    MAX_SIGNATURE_B64_LEN, MAX_WIF_LEN,
+};
+use crate::hash::{hash160, sha256d};
+use std::ffi::CString;
+use std::os::raw::{c_int, c_void};
+use zeroize::Zeroizing;
+
+/// Prefix Dogecoin Core hashes before a signed message.
+pub const DOGECOIN_MESSAGE_MAGIC: &str = "Dogecoin Signed Message:\n";
+
+/// Length of a compact signature with its header byte.
+const SIGNATURE_LEN: usize = 65;
+
+/// Header byte of a signature by an uncompressed key with recovery id 0.
+const HEADER_BASE: u8 = 27;
+
+/// How [`Message::sign_with`] and [`Message::verify_with`] hash and read
+/// signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageOptions {
+    magic: String,
+    lenient_header: bool,
+}
+
+impl Default for MessageOptions {
+    fn default() -> Self {
+        MessageOptions {
+            magic: DOGECOIN_MESSAGE_MAGIC.to_string(),
+            lenient_header: false,
+        }
+    }
+}
+
+impl MessageOptions {
+    /// Dogecoin Core's format.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hash messages with another prefix, e.g. `"Bitcoin Signed Message:\n"`
+    /// for proofs from tools that never changed it.
+    pub fn with_magic(mut self, magic: &str) -> Self {
+        self.magic = magic.to_string();
+        self
+    }
+
+    /// Accept any header byte from 27 to 42 and try both key encodings,
+    /// ignoring the compression flag, as Electrum-derived wallets need.
+    pub fn with_lenient_header(mut self) -> Self {
+        self.lenient_header = true;
+        self
+    }
+
+    pub fn magic(&self) -> &str {
+        &self.magic
+    }
+}
+
+/// What a signature's header byte says about how it was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureInfo {
+    /// The raw header byte.
+    pub header: u8,
+    /// Which of the candidate public keys the signature recovers to, 0-3.
+    pub recovery_id: u8,
+    /// Whether the signing key's address uses the compressed public key.
+    pub compressed: bool,
+    /// Whether the header is one of BIP137's segwit values (35-42), which
+    /// Dogecoin Core never writes.
+    pub segwit_header: bool,
+}
 
 /// Message signing helpers.
 pub struct Message;
@@ -12,32 +102,70 @@ impl Message {
     ///
     /// Returns a Base64 encoded signature.
     pub fn sign(privkey_wif: &str, message: &str) -> Option<String> {
-        crate::context::ensure_ecc_started();
+        let _ecc = crate::context::ensure_ecc_started();
 
-        let c_priv = CString::new(privkey_wif).ok()?;
+        let c_priv =
+            checked_cstring(privkey_wif, MAX_WIF_LEN, is_base58_byte, "private key").ok()?;
         let c_msg = CString::new(message).ok()?;
 
-        let sig_ptr =
-            unsafe { sys::sign_message(c_priv.as_ptr() as *mut i8, c_msg.as_ptr() as *mut i8) };
+        let sig_ptr = unsafe { sys::sign_message(c_priv.as_ptr(), c_msg.as_ptr()) };
         if sig_ptr.is_null() {
             return None;
         }
 
-        let sig = unsafe { CStr::from_ptr(sig_ptr) }
-            .to_string_lossy()
-            .into_owned();
+        let sig = unsafe { string_from_ptr(sig_ptr) };
         unsafe {
             sys::dogecoin_free(sig_ptr as *mut c_void);
         }
 
-        Some(sig)
+        sig.ok()
     }
 
     /// Verify a Base64 signature against a message and address.
+    ///
+    /// Accepts `signmessage` output from Dogecoin Core on either network,
+    /// for compressed and uncompressed keys. Whitespace from wrapped or
+    /// pasted signatures is ignored. If libdogecoin rejects the signature,
+    /// the key is recovered here and checked in both encodings the header
+    /// allows, so an uncompressed key's signature is not refused for its
+    /// encoding alone.
     pub fn verify(signature_base64: &str, message: &str, address: &str) -> bool {
-        crate::context::ensure_ecc_started();
+        let _ecc = crate::context::ensure_ecc_started();
+
+        let signature_base64 = strip_whitespace(signature_base64);
+        if Self::verify_ffi(&signature_base64, message, address) {
+            return true;
+        }
+        Self::verify_with(&signature_base64, message, address, &MessageOptions::new())
+    }
 
-        let c_sig = match CString::new(signature_base64) {
+    /// Describe a signature from its header byte, without checking it.
+    ///
+    /// # Returns
+    /// `None` if the signature is not 65 bytes of Base64 or the header is
+    /// outside 27-42.
+    pub fn inspect(signature_base64: &str) -> Option<SignatureInfo> {
+        let signature = base64_decode(&strip_whitespace(signature_base64))?;
+        if signature.len() != SIGNATURE_LEN || !(HEADER_BASE..=42).contains(&signature[0]) {
+            return None;
+        }
+        let header = signature[0];
+        let offset = header - HEADER_BASE;
+        Some(SignatureInfo {
+            header,
+            recovery_id: offset % 4,
+            compressed: offset >= 4,
+            segwit_header: offset >= 8,
+        })
+    }
+
+    fn verify_ffi(signature_base64: &str, message: &str, address: &str) -> bool {
+        let c_sig = match checked_cstring(
+            signature_base64,
+            MAX_SIGNATURE_B64_LEN,
+            is_base64_byte,
+            "signature",
+        ) {
             Ok(s) => s,
             Err(_) => return false,
         };
@@ -45,21 +173,173 @@ impl Message {
             Ok(s) => s,
             Err(_) => return false,
         };
-        let c_addr = match CString::new(address) {
+        let c_addr = match checked_cstring(address, MAX_ADDRESS_LEN, is_base58_byte, "address") {
             Ok(s) => s,
             Err(_) => return false,
         };
 
-        let result = unsafe {
-            sys::verify_message(
-                c_sig.as_ptr() as *mut i8,
-                c_msg.as_ptr() as *mut i8,
-                c_addr.as_ptr() as *mut i8,
+        let result =
+            unsafe { sys::verify_message(c_sig.as_ptr(), c_msg.as_ptr(), c_addr.as_ptr()) };
+
+        result == 1
+    }
+
+    /// Get the digest signed for `message`: the double SHA-256 of the
+    /// length-prefixed magic and message.
+    pub fn hash(message: &str, magic: &str) -> [u8; 32] {
+        let mut data = Vec::with_capacity(magic.len() + message.len() + 10);
+        encode::write_var_bytes(&mut data, magic.as_bytes());
+        encode::write_var_bytes(&mut data, message.as_bytes());
+        sha256d(&data)
+    }
+
+    /// Sign a message with a WIF private key in the given format.
+    ///
+    /// # Returns
+    /// A Base64 encoded signature, or `None` if the key is invalid.
+    pub fn sign_with(privkey_wif: &str, message: &str, options: &MessageOptions) -> Option<String> {
+        // Version byte, key, and a 0x01 suffix for compressed keys.
+        let payload = Zeroizing::new(base58check_decode(privkey_wif)?);
+        let compressed = match payload.len() {
+            34 if payload[33] == 0x01 => true,
+            33 => false,
+            _ => return None,
+        };
+        let hash = Self::hash(message, &options.magic);
+
+        let _ecc = crate::context::ensure_ecc_started();
+        let mut signature = [0u8; SIGNATURE_LEN];
+        let mut len = SIGNATURE_LEN - 1;
+        let mut recid: c_int = 0;
+        let signed = unsafe {
+            sys::dogecoin_ecc_sign_compact_recoverable(
+                payload[1..33].as_ptr(),
+                hash.as_ptr(),
+                signature[1..].as_mut_ptr(),
+                &mut len,
+                &mut recid,
             )
         };
+        if signed != 1 || len != SIGNATURE_LEN - 1 || !(0..4).contains(&recid) {
+            return None;
+        }
+        signature[0] = HEADER_BASE + recid as u8 + if compressed { 4 } else { 0 };
+        Some(base64_encode(&signature))
+    }
 
-        result == 1
+    /// Verify a Base64 signature against a message and address in the
+    /// given format.
+    pub fn verify_with(
+        signature_base64: &str,
+        message: &str,
+        address: &str,
+        options: &MessageOptions,
+    ) -> bool {
+        // A signature proves a key, and only a P2PKH address is one key's.
+        let Ok(info) = AddressUtils::diagnose(address) else {
+            return false;
+        };
+        if info.kind != AddressKind::P2pkh {
+            return false;
+        }
+        let signature_base64 = strip_whitespace(signature_base64);
+        let Some(header) = Self::inspect(&signature_base64) else {
+            return false;
+        };
+        if header.segwit_header && !options.lenient_header {
+            return false;
+        }
+        let Some(signature) = base64_decode(&signature_base64) else {
+            return false;
+        };
+        let recid = header.recovery_id as c_int;
+        let flagged_compressed = header.compressed;
+
+        let hash = Self::hash(message, &options.magic);
+        let _ecc = crate::context::ensure_ecc_started();
+        let mut pubkey = [0u8; 65];
+        let mut len = pubkey.len();
+        let recovered = unsafe {
+            sys::dogecoin_ecc_recover_pubkey(
+                signature[1..].as_ptr(),
+                hash.as_ptr(),
+                recid,
+                pubkey.as_mut_ptr(),
+                &mut len,
+            )
+        };
+        if recovered != 1 {
+            return false;
+        }
+        let (compressed, uncompressed) = match len {
+            65 => (compress(&pubkey), Some(&pubkey[..])),
+            33 => (pubkey[..33].try_into().expect("33 bytes"), None),
+            _ => return false,
+        };
+        let matches = |key: &[u8]| hash160(key) == info.hash;
+        match (options.lenient_header, flagged_compressed) {
+            (true, _) => matches(&compressed) || uncompressed.is_some_and(matches),
+            (false, true) => matches(&compressed),
+            (false, false) => uncompressed.is_some_and(matches),
+        }
+    }
+}
+
+/// Drop the whitespace a signature picks up from line wrapping or pasting.
+fn strip_whitespace(signature_base64: &str) -> String {
+    signature_base64
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect()
+}
+
+/// Compress an uncompressed (`04 || x || y`) public key.
+fn compress(pubkey: &[u8; 65]) -> [u8; 33] {
+    let mut out = [0u8; 33];
+    out[0] = 0x02 | (pubkey[64] & 1);
+    out[1..].copy_from_slice(&pubkey[1..33]);
+    out
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let bytes = encoded.as_bytes();
+    if !bytes.len().is_multiple_of(4) || bytes.len() > MAX_SIGNATURE_B64_LEN {
+        return None;
+    }
+    let padding = bytes.iter().rev().take_while(|&&b| b == b'=').count();
+    if padding > 2 {
+        return None;
     }
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+    for chunk in bytes[..bytes.len() - padding].chunks(4) {
+        let mut n = 0u32;
+        for (i, &b) in chunk.iter().enumerate() {
+            let digit = BASE64_ALPHABET.iter().position(|&c| c == b)? as u32;
+            n |= digit << (18 - 6 * i);
+        }
+        out.extend_from_slice(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(out)
 }
 
 #[cfg(test)]
@@ -80,4 +360,137 @@ mod tests {
             wallet.address()
         ));
     }
+
+    #[test]
+    fn test_verify_core_signmessage_output() {
+        // `signmessage` output from Dogecoin Core for one key, as
+        // compressed and uncompressed WIF on mainnet and testnet.
+        let msg = "Much wow, very signed";
+        let compressed = "H+mYEPjfCGswRa4PMjJWYvSDEuLDGYtTNrUaKYG78ughADKlK8EQ8O6g8SGnb6Z0ADJNPds2iYb+Wz2hACaPSWM=";
+        let uncompressed = "G+mYEPjfCGswRa4PMjJWYvSDEuLDGYtTNrUaKYG78ughADKlK8EQ8O6g8SGnb6Z0ADJNPds2iYb+Wz2hACaPSWM=";
+        let vectors = [
+            ("DGCpYmJ5YxarfGajqQnfmyrz8CW9NAe6hS", compressed),
+            ("D7VnXMvzjQwJFVZTXDNhDKnv1gDhjgmHF9", uncompressed),
+            ("nfFtGn2zUw3aYF9vsES82PTHN4tSPYApQJ", compressed),
+            ("nWYrFNfufPQ28U8eZ329TjPDFYbzkgpdYi", uncompressed),
+        ];
+        for (address, sig) in vectors {
+            assert!(Message::verify(sig, msg, address), "{address}");
+            assert!(!Message::verify(sig, "different message", address));
+        }
+        // The header decides which of the key's addresses it proves.
+        assert!(!Message::verify(uncompressed, msg, vectors[0].0));
+        assert!(!Message::verify(compressed, msg, vectors[1].0));
+
+        let wrapped = format!("{}\n{}", &compressed[..44], &compressed[44..]);
+        assert!(Message::verify(&wrapped, msg, vectors[0].0));
+
+        let wifs = [
+            "QPQmT9kzbSxpLSwstsa2vdmRxndVPMccZuct3Cko3wFD6koynPPQ",
+            "6JK5BTTHDnkusAxUquh7Kx16f1pgxpGKUr5WEe6qx3KdGv7NcNo",
+        ];
+        for (wif, (_, sig)) in wifs.iter().zip(vectors) {
+            let ours = Message::sign_with(wif, msg, &MessageOptions::new()).unwrap();
+            assert_eq!(ours, sig);
+        }
+    }
+
+    #[test]
+    fn test_magic_is_signed() {
+        let msg = "Much wow, very signed";
+        let wif = "QPQmT9kzbSxpLSwstsa2vdmRxndVPMccZuct3Cko3wFD6koynPPQ";
+        let address = "DGCpYmJ5YxarfGajqQnfmyrz8CW9NAe6hS";
+        let bitcoin = MessageOptions::new().with_magic("Bitcoin Signed Message:\n");
+
+        let sig = Message::sign_with(wif, msg, &bitcoin).unwrap();
+        assert!(Message::verify_with(&sig, msg, address, &bitcoin));
+        assert!(!Message::verify_with(
+            &sig,
+            msg,
+            address,
+            &MessageOptions::new()
+        ));
+        assert!(!Message::verify(&sig, msg, address));
+    }
+
+    #[test]
+    fn test_p2sh_address_is_not_the_signer() {
+        let msg = "Much wow, very signed";
+        let sig = "H+mYEPjfCGswRa4PMjJWYvSDEuLDGYtTNrUaKYG78ughADKlK8EQ8O6g8SGnb6Z0ADJNPds2iYb+Wz2hACaPSWM=";
+        let p2pkh = "DGCpYmJ5YxarfGajqQnfmyrz8CW9NAe6hS";
+        let hash = AddressUtils::diagnose(p2pkh).unwrap().hash;
+        let mut payload = vec![crate::address::P2SH_VERSION_MAINNET];
+        payload.extend_from_slice(&hash);
+        let p2sh = crate::address::base58check_encode(&payload).unwrap();
+
+        let options = MessageOptions::new();
+        assert!(Message::verify_with(sig, msg, p2pkh, &options));
+        assert!(!Message::verify_with(sig, msg, &p2sh, &options));
+        assert!(!Message::verify_with(
+            sig,
+            msg,
+            &p2sh,
+            &options.with_lenient_header()
+        ));
+        assert!(!Message::verify(sig, msg, &p2sh));
+    }
+
+    #[test]
+    fn test_inspect_signature_header() {
+        let sig = "G+mYEPjfCGswRa4PMjJWYvSDEuLDGYtTNrUaKYG78ughADKlK8EQ8O6g8SGnb6Z0ADJNPds2iYb+Wz2hACaPSWM=";
+        assert_eq!(
+            Message::inspect(sig),
+            Some(SignatureInfo {
+                header: 27,
+                recovery_id: 0,
+                compressed: false,
+                segwit_header: false,
+            })
+        );
+        let mut bytes = base64_decode(sig).unwrap();
+        bytes[0] = 27 + 4 + 3;
+        let info = Message::inspect(&base64_encode(&bytes)).unwrap();
+        assert_eq!((info.recovery_id, info.compressed), (3, true));
+        bytes[0] = 40;
+        assert!(
+            Message::inspect(&base64_encode(&bytes))
+                .unwrap()
+                .segwit_header
+        );
+        bytes[0] = 43;
+        assert_eq!(Message::inspect(&base64_encode(&bytes)), None);
+        assert_eq!(Message::inspect(&sig[..40]), None);
+    }
+
+    #[test]
+    fn test_base64_and_header_checks() {
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foob", &[0xff; 65]] {
+            assert_eq!(base64_decode(&base64_encode(data)).as_deref(), Some(data));
+        }
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_decode("Zm8"), None);
+        assert_eq!(base64_decode("Z!8="), None);
+
+        let address = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+        let strict = MessageOptions::new();
+        // A segwit-style header only passes the lenient check.
+        let mut signature = [0u8; SIGNATURE_LEN];
+        signature[0] = 39;
+        assert!(!Message::verify_with(
+            &base64_encode(&signature),
+            "msg",
+            address,
+            &strict
+        ));
+        assert!(!Message::verify_with(
+            &base64_encode(&signature[..64]),
+            "msg",
+            address,
+            &strict.clone().with_lenient_header()
+        ));
+        assert_ne!(
+            Message::hash("msg", DOGECOIN_MESSAGE_MAGIC),
+            Message::hash("msg", "Bitcoin Signed Message:\n")
+        );
+    }
 }
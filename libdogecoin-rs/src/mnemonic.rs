@@ -3,19 +3,39 @@
 //! This module provides mnemonic generation, seed derivation, and address generation
 //! from mnemonic phrases following the BIP39 standard.
 
-use crate::sys;
-use std::ffi::{CStr, CString};
+use crate::error::{Error, Result};
+use crate::ffi::{
+    checked_cstring, string_from_buf, sys, to_cstring, MNEMONIC_BUF_LEN, P2PKH_BUF_LEN,
+};
+use crate::hash::sha256;
+use crate::hex;
+use std::ffi::CString;
+use std::sync::Mutex;
+use unicode_normalization::UnicodeNormalization;
 use zeroize::Zeroizing;
 
-/// Maximum mnemonic size from libdogecoin.
-const MAX_MNEMONIC_SIZE: usize = 1024;
 /// Maximum passphrase size.
 #[allow(dead_code)]
 const MAX_PASS_SIZE: usize = 256;
 /// Maximum seed size.
 const MAX_SEED_SIZE: usize = 64;
-/// P2PKH address length - using larger buffer for safety.
-const P2PKHLEN: usize = 64;
+/// PBKDF2 rounds mandated by BIP39.
+pub const BIP39_PBKDF2_ROUNDS: u32 = 2048;
+/// Salt prefix mandated by BIP39.
+const BIP39_SALT_PREFIX: &str = "mnemonic";
+/// Entropy sizes accepted by [`Mnemonic::generate`], in bits.
+const ENTROPY_SIZES: [&str; 5] = ["128", "160", "192", "224", "256"];
+/// Bytes of the seed hash shown as a passphrase fingerprint.
+const FINGERPRINT_LEN: usize = 4;
+
+/// Normalize text to Unicode NFKD, as BIP39 requires for phrases and passphrases.
+///
+/// Visually identical passphrases typed on different platforms may use
+/// different code point sequences (e.g. precomposed "é" vs "e" + combining
+/// accent); after normalization they are byte-identical.
+pub fn normalize(text: &str) -> Zeroizing<String> {
+    Zeroizing::new(text.nfkd().collect())
+}
 
 /// A BIP39 mnemonic phrase.
 ///
@@ -36,6 +56,15 @@ const P2PKHLEN: usize = 64;
 /// ```
 pub struct Mnemonic {
     phrase: Zeroizing<String>,
+    /// Most recently derived seed, keyed by its normalized passphrase.
+    /// PBKDF2 dominates seed derivation, so repeat calls reuse it.
+    seed_cache: Mutex<Option<CachedSeed>>,
+}
+
+/// A derived seed together with the normalized passphrase it belongs to.
+struct CachedSeed {
+    passphrase: Zeroizing<String>,
+    seed: Zeroizing<[u8; MAX_SEED_SIZE]>,
 }
 
 impl Mnemonic {
@@ -46,27 +75,29 @@ impl Mnemonic {
     ///
     /// # Returns
     /// A new Mnemonic with a random phrase.
-    pub fn generate(entropy_size: &str) -> Option<Self> {
-        crate::context::ensure_ecc_started();
+    pub fn generate(entropy_size: &str) -> Result<Self> {
+        if !ENTROPY_SIZES.contains(&entropy_size) {
+            return Err(Error::InvalidEntropySize(entropy_size.to_string()));
+        }
+        let _ecc = crate::context::ensure_ecc_started();
 
-        let mut mnemonic = [0u8; MAX_MNEMONIC_SIZE];
-        let size_cstr = CString::new(entropy_size).ok()?;
+        let mut mnemonic = Zeroizing::new([0u8; MNEMONIC_BUF_LEN]);
+        let size_cstr = to_cstring(entropy_size)?;
 
         let result = unsafe {
-            sys::generateRandomEnglishMnemonic(
-                size_cstr.as_ptr() as *mut i8,
-                mnemonic.as_mut_ptr() as *mut i8,
-            )
+            sys::generateRandomEnglishMnemonic(size_cstr.as_ptr(), mnemonic.as_mut_ptr().cast())
         };
 
         if result != 0 {
-            return None;
+            return Err(Error::Ffi {
+                function: "generateRandomEnglishMnemonic",
+                code: result,
+            });
         }
 
-        let phrase_cstr = unsafe { CStr::from_ptr(mnemonic.as_ptr() as *const i8) };
-        Some(Mnemonic {
-            phrase: Zeroizing::new(phrase_cstr.to_string_lossy().into_owned()),
-        })
+        let phrase = Zeroizing::new(string_from_buf(&*mnemonic)?);
+
+        Ok(Self::from_phrase(&phrase))
     }
 
     /// Create a Mnemonic from an existing phrase.
@@ -76,6 +107,7 @@ impl Mnemonic {
     pub fn from_phrase(phrase: &str) -> Self {
         Mnemonic {
             phrase: Zeroizing::new(phrase.to_string()),
+            seed_cache: Mutex::new(None),
         }
     }
 
@@ -84,33 +116,119 @@ impl Mnemonic {
         self.phrase.as_str()
     }
 
+    /// The normalized phrase for FFI calls, bounded by libdogecoin's
+    /// `MAX_MNEMONIC_SIZE`.
+    fn phrase_cstring(&self) -> Result<CString> {
+        checked_cstring(
+            &normalize(&self.phrase),
+            MNEMONIC_BUF_LEN - 1,
+            |_| true,
+            "mnemonic",
+        )
+    }
+
     /// Derive a seed from the mnemonic phrase.
     ///
+    /// The phrase and passphrase are NFKD-normalized first (see [`normalize`]).
+    /// The last derived seed is cached, so repeated calls with the same
+    /// passphrase skip the PBKDF2 work.
+    ///
     /// # Arguments
     /// * `passphrase` - Optional passphrase (use empty string for no passphrase).
     ///
     /// # Returns
     /// A 64-byte seed.
-    pub fn to_seed(&self, passphrase: &str) -> Option<[u8; MAX_SEED_SIZE]> {
-        crate::context::ensure_ecc_started();
+    pub fn to_seed(&self, passphrase: &str) -> Result<[u8; MAX_SEED_SIZE]> {
+        let passphrase = normalize(passphrase);
+        let mut cache = self.seed_cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.as_ref().filter(|c| c.passphrase == passphrase) {
+            return Ok(*cached.seed);
+        }
+
+        let _ecc = crate::context::ensure_ecc_started();
 
         let mut seed = [0u8; MAX_SEED_SIZE];
-        let mnemonic_cstr = CString::new(self.phrase.as_str()).ok()?;
-        let pass_cstr = CString::new(passphrase).ok()?;
+        let mnemonic_cstr = self.phrase_cstring()?;
+        let pass_cstr = to_cstring(&passphrase)?;
 
         let result = unsafe {
             sys::dogecoin_seed_from_mnemonic(
-                mnemonic_cstr.as_ptr() as *mut i8,
-                pass_cstr.as_ptr() as *mut i8,
+                mnemonic_cstr.as_ptr(),
+                pass_cstr.as_ptr(),
                 seed.as_mut_ptr(),
             )
         };
 
         if result != 0 {
-            return None;
+            return Err(Error::Ffi {
+                function: "dogecoin_seed_from_mnemonic",
+                code: result,
+            });
         }
 
-        Some(seed)
+        *cache = Some(CachedSeed {
+            passphrase,
+            seed: Zeroizing::new(seed),
+        });
+        Ok(seed)
+    }
+
+    /// Derive a seed with a custom number of PBKDF2-HMAC-SHA512 rounds.
+    ///
+    /// **Not BIP39-standard** unless `iterations` is [`BIP39_PBKDF2_ROUNDS`]:
+    /// other wallets will derive a different seed from the same phrase. Use it
+    /// only for secrets that never leave this application, e.g. to make
+    /// brute-forcing a stored phrase more expensive. Inputs are NFKD-normalized
+    /// as in [`Mnemonic::to_seed`].
+    ///
+    /// # Arguments
+    /// * `passphrase` - Optional passphrase (use empty string for no passphrase).
+    /// * `iterations` - PBKDF2 rounds (must be non-zero).
+    ///
+    /// # Returns
+    /// A 64-byte seed.
+    pub fn to_seed_with_params(
+        &self,
+        passphrase: &str,
+        iterations: u32,
+    ) -> Result<[u8; MAX_SEED_SIZE]> {
+        if iterations == 0 {
+            return Err(Error::ZeroIterations);
+        }
+
+        let mut seed = [0u8; MAX_SEED_SIZE];
+        let salt = Zeroizing::new(format!("{BIP39_SALT_PREFIX}{}", *normalize(passphrase)));
+        let phrase = normalize(&self.phrase);
+        let phrase = phrase.as_bytes();
+
+        unsafe {
+            sys::pbkdf2_hmac_sha512(
+                phrase.as_ptr(),
+                phrase.len() as i32,
+                salt.as_ptr(),
+                salt.len() as i32,
+                iterations,
+                seed.as_mut_ptr(),
+                MAX_SEED_SIZE as i32,
+            );
+        }
+
+        Ok(seed)
+    }
+
+    /// Get a short fingerprint identifying this phrase/passphrase combination.
+    ///
+    /// Lets a UI confirm that the user typed the same passphrase twice (or the
+    /// same one as at wallet creation) without displaying it. The fingerprint
+    /// is the first 4 bytes of SHA-256 of the seed, in hex, so it reveals
+    /// nothing useful about the passphrase or seed.
+    ///
+    /// # Arguments
+    /// * `passphrase` - The passphrase to fingerprint (NFKD-normalized first).
+    pub fn passphrase_fingerprint(&self, passphrase: &str) -> Result<String> {
+        let seed = Zeroizing::new(self.to_seed(passphrase)?);
+        let digest = sha256(&*seed);
+        Ok(hex::encode(&digest[..FINGERPRINT_LEN]))
     }
 
     /// Derive a P2PKH address from the mnemonic using BIP44 derivation.
@@ -129,34 +247,8 @@ impl Mnemonic {
         index: u32,
         passphrase: &str,
         is_testnet: bool,
-    ) -> Option<String> {
-        crate::context::ensure_ecc_started();
-
-        let mut p2pkh_pubkey = [0u8; P2PKHLEN];
-        let mnemonic_cstr = CString::new(self.phrase.as_str()).ok()?;
-        let pass_cstr = CString::new(passphrase).ok()?;
-
-        // Change level: "0" for external (receiving), "1" for internal (change)
-        let change_level_cstr = CString::new("0").ok()?;
-
-        let result = unsafe {
-            sys::getDerivedHDAddressFromMnemonic(
-                account,
-                index,
-                change_level_cstr.as_ptr() as *mut i8,
-                mnemonic_cstr.as_ptr() as *mut i8,
-                pass_cstr.as_ptr() as *mut i8,
-                p2pkh_pubkey.as_mut_ptr() as *mut i8,
-                is_testnet,
-            )
-        };
-
-        if result != 0 {
-            return None;
-        }
-
-        let addr_cstr = unsafe { CStr::from_ptr(p2pkh_pubkey.as_ptr() as *const i8) };
-        Some(addr_cstr.to_string_lossy().into_owned())
+    ) -> Result<String> {
+        self.derive_chain_address(account, "0", index, passphrase, is_testnet)
     }
 
     /// Derive a change address from the mnemonic.
@@ -172,32 +264,46 @@ impl Mnemonic {
         index: u32,
         passphrase: &str,
         is_testnet: bool,
-    ) -> Option<String> {
-        crate::context::ensure_ecc_started();
+    ) -> Result<String> {
+        self.derive_chain_address(account, "1", index, passphrase, is_testnet)
+    }
 
-        let mut p2pkh_pubkey = [0u8; P2PKHLEN];
-        let mnemonic_cstr = CString::new(self.phrase.as_str()).ok()?;
-        let pass_cstr = CString::new(passphrase).ok()?;
-        let change_level_cstr = CString::new("1").ok()?;
+    /// Change level: "0" for external (receiving), "1" for internal (change).
+    fn derive_chain_address(
+        &self,
+        account: u32,
+        change_level: &str,
+        index: u32,
+        passphrase: &str,
+        is_testnet: bool,
+    ) -> Result<String> {
+        let _ecc = crate::context::ensure_ecc_started();
+
+        let mut p2pkh_pubkey = [0u8; P2PKH_BUF_LEN];
+        let mnemonic_cstr = self.phrase_cstring()?;
+        let pass_cstr = to_cstring(&normalize(passphrase))?;
+        let change_level_cstr = to_cstring(change_level)?;
 
         let result = unsafe {
             sys::getDerivedHDAddressFromMnemonic(
                 account,
                 index,
-                change_level_cstr.as_ptr() as *mut i8,
-                mnemonic_cstr.as_ptr() as *mut i8,
-                pass_cstr.as_ptr() as *mut i8,
-                p2pkh_pubkey.as_mut_ptr() as *mut i8,
+                change_level_cstr.as_ptr(),
+                mnemonic_cstr.as_ptr(),
+                pass_cstr.as_ptr(),
+                p2pkh_pubkey.as_mut_ptr().cast(),
                 is_testnet,
             )
         };
 
         if result != 0 {
-            return None;
+            return Err(Error::Ffi {
+                function: "getDerivedHDAddressFromMnemonic",
+                code: result,
+            });
         }
 
-        let addr_cstr = unsafe { CStr::from_ptr(p2pkh_pubkey.as_ptr() as *const i8) };
-        Some(addr_cstr.to_string_lossy().into_owned())
+        string_from_buf(&p2pkh_pubkey)
     }
 }
 
@@ -221,21 +327,89 @@ mod tests {
         println!("24-word mnemonic: {}", mnemonic.phrase());
     }
 
+    #[test]
+    fn test_generate_rejects_bad_entropy_size() {
+        assert_eq!(
+            Mnemonic::generate("100").err(),
+            Some(Error::InvalidEntropySize("100".into()))
+        );
+    }
+
     #[test]
     fn test_mnemonic_to_seed() {
         let mnemonic = Mnemonic::generate("128").unwrap();
-        let seed = mnemonic.to_seed("");
-        assert!(seed.is_some());
-        let seed = seed.unwrap();
+        let seed = mnemonic.to_seed("").unwrap();
         assert_eq!(seed.len(), 64);
     }
 
+    #[test]
+    fn test_seed_cache_is_keyed_by_passphrase() {
+        let mnemonic = Mnemonic::from_phrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        );
+        let plain = mnemonic.to_seed("").unwrap();
+        let with_pass = mnemonic.to_seed("TREZOR").unwrap();
+        assert_ne!(plain, with_pass);
+        assert_eq!(mnemonic.to_seed("TREZOR"), Ok(with_pass));
+        assert_eq!(mnemonic.to_seed(""), Ok(plain));
+    }
+
+    #[test]
+    fn test_seed_with_params() {
+        let mnemonic = Mnemonic::from_phrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        );
+        let standard = mnemonic
+            .to_seed_with_params("TREZOR", BIP39_PBKDF2_ROUNDS)
+            .unwrap();
+        assert_eq!(Ok(standard), mnemonic.to_seed("TREZOR"));
+        // BIP39 test vector.
+        assert_eq!(standard[..4], [0xc5, 0x52, 0x57, 0xc3]);
+
+        let stretched = mnemonic.to_seed_with_params("TREZOR", 100_000).unwrap();
+        assert_ne!(standard, stretched);
+        assert_eq!(
+            mnemonic.to_seed_with_params("TREZOR", 0),
+            Err(Error::ZeroIterations)
+        );
+    }
+
+    #[test]
+    fn test_normalize_unifies_unicode_forms() {
+        let composed = "caf\u{e9} \u{212b}ngstr\u{f6}m";
+        let decomposed = "cafe\u{301} A\u{30a}ngstro\u{308}m";
+        assert_ne!(composed, decomposed);
+        assert_eq!(*normalize(composed), *normalize(decomposed));
+        assert_eq!(*normalize("ascii only"), "ascii only");
+    }
+
+    #[test]
+    fn test_non_ascii_passphrase_seed() {
+        let mnemonic = Mnemonic::from_phrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        );
+        let composed = mnemonic.to_seed("\u{c5}ngstr\u{f6}m \u{2615}").unwrap();
+        let decomposed = mnemonic.to_seed("A\u{30a}ngstro\u{308}m \u{2615}").unwrap();
+        assert_eq!(composed, decomposed);
+        // PBKDF2-HMAC-SHA512 over the NFKD form, computed independently.
+        assert_eq!(composed[..4], [0x1f, 0x81, 0xb6, 0x5b]);
+
+        let fingerprint = mnemonic.passphrase_fingerprint("\u{c5}ngstr\u{f6}m \u{2615}");
+        assert_eq!(fingerprint.as_deref(), Ok("1eccc139"));
+        assert_eq!(
+            fingerprint,
+            mnemonic.passphrase_fingerprint("A\u{30a}ngstro\u{308}m \u{2615}")
+        );
+        assert_ne!(
+            fingerprint,
+            mnemonic.passphrase_fingerprint("Angstrom \u{2615}")
+        );
+    }
+
     #[test]
     fn test_derive_address_from_mnemonic() {
         let mnemonic = Mnemonic::generate("128").unwrap();
-        let addr = mnemonic.derive_address(0, 0, "", false);
-        assert!(addr.is_some());
-        let addr = addr.unwrap();
+        let addr = mnemonic.derive_address(0, 0, "", false).unwrap();
         assert!(addr.starts_with("D"), "Mainnet address should start with D");
         println!("Address from mnemonic: {}", addr);
     }
@@ -0,0 +1,333 @@
+//! Offline signing: build watch-only, sign on an air-gapped machine.
+//!
+//! The online machine builds the unsigned transaction and wraps it in an
+//! [`UnsignedTransaction`] together with the outputs its inputs spend, so
+//! the offline machine can show the amounts and fee and pick the signing
+//! keys without a node. The blob moves between machines as bytes or hex;
+//! the offline side signs it and hands back the signed transaction hex for
+//! broadcast.
+//!
+//! # Format
+//! `"DUSG"`, a version byte, a flags byte (bit 0: testnet), the unsigned
+//! transaction with a CompactSize length prefix, then for each input the
+//! spent output's value (8 bytes, little-endian) and length-prefixed
+//! scriptPubKey, and finally the first 4 bytes of the double SHA-256 of
+//! everything before it.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::offline::UnsignedTransaction;
+//! use libdogecoin_rs::{DogeTransaction, Utxo};
+//! use std::collections::HashMap;
+//!
+//! # let (tx, utxos, keys): (DogeTransaction, Vec<Utxo>, HashMap<String, String>) = unimplemented!();
+//! // Online, watch-only:
+//! let blob = UnsignedTransaction::new(false, &tx.get_raw().unwrap(), &utxos)?.to_hex();
+//!
+//! // Offline:
+//! let unsigned = UnsignedTransaction::from_hex(&blob)?;
+//! println!("fee: {:?} koinu", unsigned.fee());
+//! let signed_hex = unsigned.sign(&keys)?;
+//! # Ok::<(), libdogecoin_rs::offline::OfflineError>(())
+//! ```
+
+use crate::encode::{self, Reader};
+use crate::hash::sha256d;
+use crate::hex;
+use crate::script::{P2pkhTemplate, ScriptTemplate};
+use crate::transaction::{DecodeError, DogeTransaction, Transaction, TransactionError, TxOut};
+use crate::utxo::{OutPoint, Utxo};
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"DUSG";
+const VERSION: u8 = 1;
+const FLAG_TESTNET: u8 = 0x01;
+const CHECKSUM_LEN: usize = 4;
+
+/// Errors from preparing, loading or signing an unsigned transaction.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum OfflineError {
+    #[error("not an unsigned transaction blob")]
+    BadMagic,
+
+    #[error("unsupported blob version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("blob checksum mismatch")]
+    ChecksumMismatch,
+
+    #[error("malformed blob: {0}")]
+    Malformed(#[from] DecodeError),
+
+    #[error("invalid raw transaction")]
+    InvalidTransaction,
+
+    #[error("no spent output given for input {0:?}")]
+    MissingPrevout(OutPoint),
+
+    #[error("input {0} does not spend a P2PKH output")]
+    UnsupportedInput(usize),
+
+    #[error("no private key for {0}")]
+    MissingKey(String),
+
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+
+    #[error("libdogecoin failed to {0}")]
+    Ffi(&'static str),
+}
+
+/// An unsigned transaction with the outputs its inputs spend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedTransaction {
+    is_testnet: bool,
+    tx: Transaction,
+    prevouts: Vec<TxOut>,
+}
+
+impl UnsignedTransaction {
+    /// Prepare a transaction for offline signing.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    /// * `raw` - The unsigned transaction hex, e.g. from
+    ///   [`DogeTransaction::get_raw`].
+    /// * `utxos` - The outputs spent by the inputs, in any order; others
+    ///   are ignored.
+    pub fn new(is_testnet: bool, raw: &str, utxos: &[Utxo]) -> Result<Self, OfflineError> {
+        let tx = Transaction::from_hex(raw).ok_or(OfflineError::InvalidTransaction)?;
+        let prevouts = tx
+            .inputs
+            .iter()
+            .map(|input| {
+                utxos
+                    .iter()
+                    .find(|u| u.outpoint == input.previous_output)
+                    .map(|u| TxOut {
+                        value: u.amount,
+                        script_pubkey: u.script_pubkey.clone(),
+                    })
+                    .ok_or(OfflineError::MissingPrevout(input.previous_output))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(UnsignedTransaction {
+            is_testnet,
+            tx,
+            prevouts,
+        })
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        self.is_testnet
+    }
+
+    pub fn transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    /// Get the outputs spent by each input, in input order.
+    pub fn prevouts(&self) -> &[TxOut] {
+        &self.prevouts
+    }
+
+    /// Get the fee in koinu; `None` if the outputs exceed the inputs.
+    pub fn fee(&self) -> Option<u64> {
+        let inputs = self
+            .prevouts
+            .iter()
+            .try_fold(0u64, |sum, out| sum.checked_add(out.value))?;
+        inputs.checked_sub(self.tx.output_value()?)
+    }
+
+    /// Get the address that must sign each input; `None` for inputs that
+    /// are not P2PKH.
+    pub fn signing_addresses(&self) -> Vec<Option<String>> {
+        self.prevouts
+            .iter()
+            .map(|out| {
+                P2pkhTemplate
+                    .parse(&out.script_pubkey)
+                    .and_then(|kind| kind.address(self.is_testnet))
+            })
+            .collect()
+    }
+
+    /// Load the transaction into libdogecoin for signing by hand.
+    pub fn to_doge_transaction(&self) -> Result<DogeTransaction, TransactionError> {
+        DogeTransaction::from_raw(&self.tx.to_hex())
+    }
+
+    /// Sign every input.
+    ///
+    /// # Arguments
+    /// * `keys` - WIF private keys by the P2PKH address they control.
+    ///
+    /// # Returns
+    /// The signed transaction hex, ready to broadcast.
+    pub fn sign(&self, keys: &HashMap<String, String>) -> Result<String, OfflineError> {
+        let mut signing = Vec::with_capacity(self.prevouts.len());
+        for (index, address) in self.signing_addresses().into_iter().enumerate() {
+            let address = address.ok_or(OfflineError::UnsupportedInput(index))?;
+            let key = keys
+                .get(&address)
+                .ok_or(OfflineError::MissingKey(address))?;
+            signing.push(key);
+        }
+
+        let mut tx = self.to_doge_transaction()?;
+        for (index, key) in signing.into_iter().enumerate() {
+            let index = i32::try_from(index).map_err(|_| OfflineError::UnsupportedInput(index))?;
+            if !tx.sign_with_privkey(index, key) {
+                return Err(OfflineError::Ffi("sign an input"));
+            }
+        }
+        tx.get_raw()
+            .ok_or(OfflineError::Ffi("serialize the transaction"))
+    }
+
+    /// Serialize to the blob format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(if self.is_testnet { FLAG_TESTNET } else { 0 });
+        encode::write_var_bytes(&mut out, &self.tx.to_bytes());
+        for prevout in &self.prevouts {
+            out.extend_from_slice(&prevout.value.to_le_bytes());
+            encode::write_var_bytes(&mut out, &prevout.script_pubkey);
+        }
+        let checksum = sha256d(&out);
+        out.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+        out
+    }
+
+    /// Parse a blob produced by [`UnsignedTransaction::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OfflineError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(OfflineError::BadMagic);
+        }
+        if bytes.len() < MAGIC.len() + 2 + CHECKSUM_LEN {
+            return Err(DecodeError::UnexpectedEnd.into());
+        }
+        let (body, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if sha256d(body)[..CHECKSUM_LEN] != *sum {
+            return Err(OfflineError::ChecksumMismatch);
+        }
+        let version = body[MAGIC.len()];
+        if version != VERSION {
+            return Err(OfflineError::UnsupportedVersion(version));
+        }
+
+        let mut reader = Reader::new(&body[MAGIC.len() + 1..]);
+        let flags = reader.u8()?;
+        let tx = Transaction::from_bytes(reader.var_bytes()?)?;
+        let mut prevouts = Vec::with_capacity(tx.inputs.len());
+        for _ in &tx.inputs {
+            prevouts.push(TxOut {
+                value: reader.u64()?,
+                script_pubkey: reader.var_bytes()?.to_vec(),
+            });
+        }
+        reader.finish()?;
+        Ok(UnsignedTransaction {
+            is_testnet: flags & FLAG_TESTNET != 0,
+            tx,
+            prevouts,
+        })
+    }
+
+    /// Serialize to hex, for copying or a QR code.
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.to_bytes())
+    }
+
+    pub fn from_hex(blob: &str) -> Result<Self, OfflineError> {
+        Self::from_bytes(&hex::decode(blob).ok_or(DecodeError::InvalidHex)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::OutputKind;
+    use crate::transaction::TxIn;
+
+    const ADDRESS: &str = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+
+    fn utxo(id: u8, amount: u64) -> Utxo {
+        let kind = OutputKind::from_address(ADDRESS).unwrap();
+        Utxo {
+            outpoint: OutPoint {
+                txid: [id; 32],
+                vout: 1,
+            },
+            script_pubkey: P2pkhTemplate.build(&kind).unwrap(),
+            amount,
+            height: Some(1),
+        }
+    }
+
+    fn unsigned_hex() -> String {
+        Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: utxo(1, 0).outpoint,
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![TxOut {
+                value: 90_000,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        }
+        .to_hex()
+    }
+
+    #[test]
+    fn test_blob_roundtrip() {
+        let unsigned =
+            UnsignedTransaction::new(false, &unsigned_hex(), &[utxo(2, 5), utxo(1, 100_000)])
+                .unwrap();
+        assert_eq!(unsigned.fee(), Some(10_000));
+        assert_eq!(
+            unsigned.signing_addresses(),
+            vec![Some(ADDRESS.to_string())]
+        );
+
+        let blob = unsigned.to_hex();
+        assert_eq!(UnsignedTransaction::from_hex(&blob), Ok(unsigned.clone()));
+
+        let mut bytes = unsigned.to_bytes();
+        bytes[10] ^= 1;
+        assert_eq!(
+            UnsignedTransaction::from_bytes(&bytes),
+            Err(OfflineError::ChecksumMismatch)
+        );
+        assert_eq!(
+            UnsignedTransaction::from_bytes(b"DUTX..."),
+            Err(OfflineError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn test_prepare_and_sign_fail_before_ffi() {
+        assert_eq!(
+            UnsignedTransaction::new(false, &unsigned_hex(), &[utxo(2, 5)]),
+            Err(OfflineError::MissingPrevout(utxo(1, 0).outpoint))
+        );
+
+        let mut unsigned =
+            UnsignedTransaction::new(false, &unsigned_hex(), &[utxo(1, 100_000)]).unwrap();
+        assert_eq!(
+            unsigned.sign(&HashMap::new()),
+            Err(OfflineError::MissingKey(ADDRESS.to_string()))
+        );
+        unsigned.prevouts[0].script_pubkey = vec![0x51];
+        assert_eq!(
+            unsigned.sign(&HashMap::new()),
+            Err(OfflineError::UnsupportedInput(0))
+        );
+    }
+}
@@ -0,0 +1,779 @@
+//! A deposit and withdrawal pipeline for custodial services.
+//!
+//! [`Pipeline`] wires the crate's primitives into the loop an exchange runs:
+//!
+//! 1. **Deposits** - transactions fed to [`scan_transaction`] that pay a
+//!    watched deposit address become pending deposits and hot-wallet coins.
+//!    Any scriptPubKey can be watched too, e.g. a P2SH multisig treasury;
+//!    payments to it are credited but not spent by the hot wallet.
+//! 2. **Accounting** - [`on_block`] credits deposits to their account in a
+//!    [`Ledger`] once they have enough confirmations.
+//! 3. **Withdrawal queue** - [`request_withdrawal`] validates the address,
+//!    runs the [`WithdrawalPolicy`] and holds the amount in the ledger.
+//! 4. **Coin selection** - [`process_withdrawals`] selects confirmed coins
+//!    that no in-flight withdrawal has reserved, and reserves them.
+//! 5. **Signing** - a [`Signer`] signs the [`UnsignedTransaction`], so keys
+//!    can live in this process, an HSM or on an offline machine.
+//! 6. **Broadcast** - a [`Broadcaster`] sends it, e.g. a
+//!    [`DogeRpcClient`](crate::DogeRpcClient).
+//! 7. **Confirmations** - [`on_block`] marks withdrawals confirmed and
+//!    forgets the coins they spent.
+//!
+//! Every stage is a trait, with in-memory defaults for the ledger and
+//! policy. State other than the ledger is kept in memory; reorgs are handled
+//! only in that rescanning a transaction at a new height updates it.
+//!
+//! [`scan_transaction`]: Pipeline::scan_transaction
+//! [`on_block`]: Pipeline::on_block
+//! [`request_withdrawal`]: Pipeline::request_withdrawal
+//! [`process_withdrawals`]: Pipeline::process_withdrawals
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::pipeline::{Pipeline, PipelineConfig};
+//! use libdogecoin_rs::{DogeRpcClient, Koinu};
+//! use std::collections::HashMap;
+//!
+//! # let keys: HashMap<String, String> = unimplemented!();
+//! let config = PipelineConfig::new(false, "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas");
+//! let rpc = DogeRpcClient::new("http://127.0.0.1:22555");
+//! let mut pipeline = Pipeline::new(config, keys, rpc)?;
+//! pipeline.watch_deposit_address("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", "alice")?;
+//! // For each new block: scan its transactions, then
+//! let report = pipeline.on_block(5_000_000);
+//! pipeline.request_withdrawal("alice", "D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e", Koinu::ONE_DOGE)?;
+//! for (id, result) in pipeline.process_withdrawals() {
+//!     println!("withdrawal {id:?}: {result:?}");
+//! }
+//! # Ok::<(), libdogecoin_rs::pipeline::PipelineError>(())
+//! ```
+
+use crate::address::{AddressError, AddressUtils};
+use crate::coinselect::{ChangePolicy, CoinSelector, SelectError};
+use crate::events::{Event, EventBus};
+use crate::koinu::Koinu;
+use crate::offline::{OfflineError, UnsignedTransaction};
+use crate::script::{OutputKind, P2pkhTemplate, ScriptTemplate, TemplateRegistry};
+use crate::transaction::{Transaction, TxIn, TxOut};
+use crate::utxo::{OutPoint, Utxo, UtxoSnapshot};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// Confirmations required by default before deposits are credited and
+/// withdrawals count as final.
+pub const DEFAULT_CONFIRMATIONS: u32 = 6;
+
+/// Errors from the pipeline.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PipelineError {
+    #[error("invalid address {address:?}: {source}")]
+    InvalidAddress {
+        address: String,
+        source: AddressError,
+    },
+
+    #[error("withdrawal rejected by policy: {0}")]
+    Rejected(String),
+
+    #[error("account {0} has insufficient balance")]
+    InsufficientBalance(String),
+
+    #[error(transparent)]
+    Select(#[from] SelectError),
+
+    #[error("signing failed: {0}")]
+    Sign(#[from] OfflineError),
+
+    #[error("broadcast failed: {0}")]
+    Broadcast(String),
+}
+
+/// Account balances.
+pub trait Ledger: Send {
+    fn balance(&self, account: &str) -> Koinu;
+
+    fn credit(&mut self, account: &str, amount: Koinu);
+
+    /// Take `amount` from the account.
+    ///
+    /// # Returns
+    /// `false`, changing nothing, if the balance is too low.
+    fn debit(&mut self, account: &str, amount: Koinu) -> bool;
+}
+
+/// Volatile ledger; balances are lost when it is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryLedger {
+    balances: HashMap<String, Koinu>,
+}
+
+impl MemoryLedger {
+    /// Create an empty ledger.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Ledger for MemoryLedger {
+    fn balance(&self, account: &str) -> Koinu {
+        self.balances.get(account).copied().unwrap_or_default()
+    }
+
+    fn credit(&mut self, account: &str, amount: Koinu) {
+        let balance = self.balances.entry(account.to_string()).or_default();
+        *balance = Koinu(balance.0.saturating_add(amount.0));
+    }
+
+    fn debit(&mut self, account: &str, amount: Koinu) -> bool {
+        match self.balances.get_mut(account) {
+            Some(balance) if *balance >= amount => {
+                *balance = Koinu(balance.0 - amount.0);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Checks a withdrawal before it is queued, e.g. limits or address lists.
+pub trait WithdrawalPolicy: Send {
+    /// # Returns
+    /// The reason, if the withdrawal is rejected.
+    fn check(&self, withdrawal: &Withdrawal) -> Result<(), String>;
+}
+
+impl<F: Fn(&Withdrawal) -> Result<(), String> + Send> WithdrawalPolicy for F {
+    fn check(&self, withdrawal: &Withdrawal) -> Result<(), String> {
+        self(withdrawal)
+    }
+}
+
+/// Signs withdrawal transactions.
+pub trait Signer: Send {
+    /// # Returns
+    /// The signed transaction hex.
+    fn sign(&self, tx: &UnsignedTransaction) -> Result<String, OfflineError>;
+}
+
+/// Signs with WIF keys by the address they control.
+impl Signer for HashMap<String, String> {
+    fn sign(&self, tx: &UnsignedTransaction) -> Result<String, OfflineError> {
+        tx.sign(self)
+    }
+}
+
+impl<F: Fn(&UnsignedTransaction) -> Result<String, OfflineError> + Send> Signer for F {
+    fn sign(&self, tx: &UnsignedTransaction) -> Result<String, OfflineError> {
+        self(tx)
+    }
+}
+
+/// Sends signed transactions to the network.
+pub trait Broadcaster: Send {
+    /// # Returns
+    /// The node's rejection reason on failure.
+    fn broadcast(&self, raw_hex: &str) -> Result<(), String>;
+}
+
+impl<F: Fn(&str) -> Result<(), String> + Send> Broadcaster for F {
+    fn broadcast(&self, raw_hex: &str) -> Result<(), String> {
+        self(raw_hex)
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl Broadcaster for crate::rpc::DogeRpcClient {
+    fn broadcast(&self, raw_hex: &str) -> Result<(), String> {
+        self.send_raw_transaction(raw_hex)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Settings for a [`Pipeline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineConfig {
+    pub is_testnet: bool,
+    /// Fee rate in koinu per byte.
+    pub fee_rate: u64,
+    /// Address of the hot wallet that receives change.
+    pub change_address: String,
+    pub change_policy: ChangePolicy,
+    /// Confirmations before deposits are credited and withdrawals are final.
+    pub confirmations: u32,
+}
+
+impl PipelineConfig {
+    /// Create a config with a fee rate of 1,000 koinu per byte and
+    /// [`DEFAULT_CONFIRMATIONS`].
+    pub fn new(is_testnet: bool, change_address: impl Into<String>) -> Self {
+        PipelineConfig {
+            is_testnet,
+            fee_rate: 1_000,
+            change_address: change_address.into(),
+            change_policy: ChangePolicy::default(),
+            confirmations: DEFAULT_CONFIRMATIONS,
+        }
+    }
+}
+
+/// Identifier of a queued withdrawal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WithdrawalId(pub u64);
+
+/// A withdrawal request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub id: WithdrawalId,
+    pub account: String,
+    pub address: String,
+    pub amount: Koinu,
+}
+
+/// Where a withdrawal is in the pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WithdrawalStatus {
+    /// Waiting for [`Pipeline::process_withdrawals`].
+    Queued,
+    /// Sent; waiting for confirmations.
+    Broadcast {
+        txid: String,
+    },
+    Confirmed {
+        txid: String,
+        height: u32,
+    },
+    /// Not sent; the amount was returned to the account.
+    Failed {
+        reason: String,
+    },
+}
+
+/// What a new block settled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockReport {
+    /// Deposits credited, by account.
+    pub credited: Vec<(String, Koinu)>,
+    pub confirmed: Vec<WithdrawalId>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingDeposit {
+    account: String,
+    amount: Koinu,
+    height: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+struct InFlight {
+    id: WithdrawalId,
+    spent: Vec<OutPoint>,
+    height: Option<u32>,
+}
+
+/// Deposit detection, accounting and withdrawals for one hot wallet.
+pub struct Pipeline {
+    config: PipelineConfig,
+    ledger: Box<dyn Ledger>,
+    policy: Box<dyn WithdrawalPolicy>,
+    signer: Box<dyn Signer>,
+    broadcaster: Box<dyn Broadcaster>,
+    events: Option<EventBus>,
+    change_script: Vec<u8>,
+    /// Account of each deposit scriptPubKey.
+    deposit_scripts: HashMap<Vec<u8>, String>,
+    deposits: BTreeMap<OutPoint, PendingDeposit>,
+    /// Deposits already credited, so rescans do not credit them twice.
+    credited: HashSet<OutPoint>,
+    utxos: UtxoSnapshot,
+    reserved: HashSet<OutPoint>,
+    queue: VecDeque<Withdrawal>,
+    statuses: BTreeMap<WithdrawalId, WithdrawalStatus>,
+    in_flight: HashMap<String, InFlight>,
+    next_id: u64,
+}
+
+impl Pipeline {
+    /// Create a pipeline with a [`MemoryLedger`] and no withdrawal policy.
+    ///
+    /// # Returns
+    /// An error if the change address is invalid.
+    pub fn new(
+        config: PipelineConfig,
+        signer: impl Signer + 'static,
+        broadcaster: impl Broadcaster + 'static,
+    ) -> Result<Self, PipelineError> {
+        let change_script = address_script(&config.change_address, config.is_testnet)?;
+        Ok(Pipeline {
+            utxos: UtxoSnapshot::new(config.is_testnet, 0),
+            config,
+            ledger: Box::new(MemoryLedger::new()),
+            policy: Box::new(|_: &Withdrawal| Ok(())),
+            signer: Box::new(signer),
+            broadcaster: Box::new(broadcaster),
+            events: None,
+            change_script,
+            deposit_scripts: HashMap::new(),
+            deposits: BTreeMap::new(),
+            credited: HashSet::new(),
+            reserved: HashSet::new(),
+            queue: VecDeque::new(),
+            statuses: BTreeMap::new(),
+            in_flight: HashMap::new(),
+            next_id: 0,
+        })
+    }
+
+    /// Keep balances in `ledger`, e.g. the exchange's database.
+    pub fn with_ledger(mut self, ledger: impl Ledger + 'static) -> Self {
+        self.ledger = Box::new(ledger);
+        self
+    }
+
+    pub fn with_policy(mut self, policy: impl WithdrawalPolicy + 'static) -> Self {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    /// Publish [`Event::TxSeen`] for broadcasts, [`Event::BroadcastFailed`]
+    /// and [`Event::TxConfirmed`] for final withdrawals on `bus`.
+    pub fn with_event_bus(mut self, bus: EventBus) -> Self {
+        self.events = Some(bus);
+        self
+    }
+
+    /// Seed the hot wallet with coins it already holds.
+    pub fn with_utxos(mut self, utxos: impl IntoIterator<Item = Utxo>) -> Self {
+        self.utxos.extend(utxos);
+        self
+    }
+
+    pub fn ledger(&self) -> &dyn Ledger {
+        self.ledger.as_ref()
+    }
+
+    /// Credit payments to `address` to `account`.
+    pub fn watch_deposit_address(
+        &mut self,
+        address: &str,
+        account: &str,
+    ) -> Result<&mut Self, PipelineError> {
+        let script = address_script(address, self.config.is_testnet)?;
+        self.deposit_scripts.insert(script, account.to_string());
+        Ok(self)
+    }
+
+    /// Credit payments to a raw scriptPubKey to `account`, e.g. a P2SH
+    /// multisig treasury or bare multisig with no address form.
+    ///
+    /// Only P2PKH outputs become hot-wallet coins; payments to other scripts
+    /// are credited but never selected for withdrawals.
+    pub fn watch_deposit_script(&mut self, script_pubkey: &[u8], account: &str) -> &mut Self {
+        self.deposit_scripts
+            .insert(script_pubkey.to_vec(), account.to_string());
+        self
+    }
+
+    /// Record a transaction seen in the mempool (`height` `None`) or a block.
+    ///
+    /// Outputs paying deposit addresses or scripts become pending deposits,
+    /// and the pipeline's own withdrawals are tracked towards confirmation.
+    pub fn scan_transaction(&mut self, tx: &Transaction, height: Option<u32>) {
+        let txid = tx.txid();
+        for (vout, output) in tx.outputs.iter().enumerate() {
+            let Some(account) = self.deposit_scripts.get(&output.script_pubkey) else {
+                continue;
+            };
+            let outpoint = OutPoint {
+                txid,
+                vout: vout as u32,
+            };
+            if self.credited.contains(&outpoint) {
+                continue;
+            }
+            self.deposits.insert(
+                outpoint,
+                PendingDeposit {
+                    account: account.clone(),
+                    amount: Koinu(output.value),
+                    height,
+                },
+            );
+            if P2pkhTemplate.parse(&output.script_pubkey).is_some() {
+                self.utxos.insert(Utxo {
+                    outpoint,
+                    script_pubkey: output.script_pubkey.clone(),
+                    amount: output.value,
+                    height,
+                });
+            }
+        }
+
+        if let Some(in_flight) = self.in_flight.get_mut(&tx.txid_hex()) {
+            in_flight.height = height;
+            // Change from our withdrawals becomes spendable once mined.
+            for vout in 0..tx.outputs.len() as u32 {
+                if let Some(mut utxo) = self.utxos.remove(&OutPoint { txid, vout }) {
+                    utxo.height = height;
+                    self.utxos.insert(utxo);
+                }
+            }
+        }
+    }
+
+    /// Advance to a new chain tip, crediting deposits and finalizing
+    /// withdrawals that reached the required confirmations.
+    pub fn on_block(&mut self, height: u32) -> BlockReport {
+        self.utxos.set_tip_height(height);
+        let confirmations = self.config.confirmations.max(1);
+        let is_final = |mined: Option<u32>| {
+            mined.is_some_and(|h| height >= h && height - h + 1 >= confirmations)
+        };
+
+        let mut report = BlockReport::default();
+        let credited: Vec<OutPoint> = self
+            .deposits
+            .iter()
+            .filter(|(_, d)| is_final(d.height))
+            .map(|(outpoint, _)| *outpoint)
+            .collect();
+        for outpoint in credited {
+            let deposit = self.deposits.remove(&outpoint).expect("listed above");
+            self.credited.insert(outpoint);
+            self.ledger.credit(&deposit.account, deposit.amount);
+            report.credited.push((deposit.account, deposit.amount));
+        }
+
+        let confirmed: Vec<String> = self
+            .in_flight
+            .iter()
+            .filter(|(_, tx)| is_final(tx.height))
+            .map(|(txid, _)| txid.clone())
+            .collect();
+        for txid in confirmed {
+            let tx = self.in_flight.remove(&txid).expect("listed above");
+            for outpoint in &tx.spent {
+                self.reserved.remove(outpoint);
+                self.utxos.remove(outpoint);
+            }
+            let mined = tx.height.expect("final transactions are mined");
+            self.emit(Event::TxConfirmed {
+                txid: txid.clone(),
+                height: mined,
+            });
+            self.statuses.insert(
+                tx.id,
+                WithdrawalStatus::Confirmed {
+                    txid,
+                    height: mined,
+                },
+            );
+            report.confirmed.push(tx.id);
+        }
+        report
+    }
+
+    /// Queue a withdrawal, holding its amount in the ledger.
+    ///
+    /// # Returns
+    /// An error if the address is invalid, the policy rejects it or the
+    /// account cannot cover it.
+    pub fn request_withdrawal(
+        &mut self,
+        account: &str,
+        address: &str,
+        amount: Koinu,
+    ) -> Result<WithdrawalId, PipelineError> {
+        address_script(address, self.config.is_testnet)?;
+        let withdrawal = Withdrawal {
+            id: WithdrawalId(self.next_id),
+            account: account.to_string(),
+            address: address.to_string(),
+            amount,
+        };
+        self.policy
+            .check(&withdrawal)
+            .map_err(PipelineError::Rejected)?;
+        if !self.ledger.debit(account, amount) {
+            return Err(PipelineError::InsufficientBalance(account.to_string()));
+        }
+        self.next_id += 1;
+        self.statuses
+            .insert(withdrawal.id, WithdrawalStatus::Queued);
+        self.queue.push_back(withdrawal);
+        Ok(WithdrawalId(self.next_id - 1))
+    }
+
+    pub fn status(&self, id: WithdrawalId) -> Option<&WithdrawalStatus> {
+        self.statuses.get(&id)
+    }
+
+    /// Get the queued withdrawals, oldest first.
+    pub fn queued(&self) -> impl Iterator<Item = &Withdrawal> {
+        self.queue.iter()
+    }
+
+    /// Sign and broadcast queued withdrawals, one transaction each.
+    ///
+    /// Withdrawals the hot wallet cannot fund yet stay queued; any other
+    /// failure returns the amount to the account.
+    ///
+    /// # Returns
+    /// The txid or error of each withdrawal attempted.
+    pub fn process_withdrawals(&mut self) -> Vec<(WithdrawalId, Result<String, PipelineError>)> {
+        let mut results = Vec::new();
+        for withdrawal in std::mem::take(&mut self.queue) {
+            let result = self.send(&withdrawal);
+            let status = match &result {
+                Ok(txid) => WithdrawalStatus::Broadcast { txid: txid.clone() },
+                Err(PipelineError::Select(SelectError::InsufficientFunds { .. })) => {
+                    self.queue.push_back(withdrawal.clone());
+                    WithdrawalStatus::Queued
+                }
+                Err(e) => {
+                    self.ledger.credit(&withdrawal.account, withdrawal.amount);
+                    WithdrawalStatus::Failed {
+                        reason: e.to_string(),
+                    }
+                }
+            };
+            self.statuses.insert(withdrawal.id, status);
+            results.push((withdrawal.id, result));
+        }
+        results
+    }
+
+    /// Build, sign and broadcast one withdrawal, reserving its inputs.
+    fn send(&mut self, withdrawal: &Withdrawal) -> Result<String, PipelineError> {
+        let available: Vec<Utxo> = self
+            .utxos
+            .iter()
+            .filter(|u| u.height.is_some() && !self.reserved.contains(&u.outpoint))
+            .cloned()
+            .collect();
+        let selection = CoinSelector::new(self.config.fee_rate)
+            .with_change_policy(self.config.change_policy)
+            .select(&available, withdrawal.amount)?;
+
+        let recipient = TxOut {
+            value: withdrawal.amount.0,
+            script_pubkey: address_script(&withdrawal.address, self.config.is_testnet)?,
+        };
+        let change = selection.change.iter().map(|amount| TxOut {
+            value: amount.0,
+            script_pubkey: self.change_script.clone(),
+        });
+        let tx = Transaction {
+            version: 1,
+            inputs: selection
+                .inputs
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: Vec::new(),
+                    sequence: u32::MAX,
+                })
+                .collect(),
+            outputs: std::iter::once(recipient).chain(change).collect(),
+            lock_time: 0,
+        };
+        let unsigned =
+            UnsignedTransaction::new(self.config.is_testnet, &tx.to_hex(), &selection.inputs)?;
+        let signed = self.signer.sign(&unsigned)?;
+        let signed_tx = Transaction::from_hex(&signed).ok_or(OfflineError::InvalidTransaction)?;
+
+        if let Err(reason) = self.broadcaster.broadcast(&signed) {
+            self.emit(Event::BroadcastFailed {
+                reason: reason.clone(),
+            });
+            return Err(PipelineError::Broadcast(reason));
+        }
+        let txid = signed_tx.txid_hex();
+        self.emit(Event::TxSeen { txid: txid.clone() });
+
+        let spent: Vec<OutPoint> = selection.inputs.iter().map(|u| u.outpoint).collect();
+        self.reserved.extend(spent.iter().copied());
+        for (vout, output) in signed_tx.outputs.iter().enumerate().skip(1) {
+            self.utxos.insert(Utxo {
+                outpoint: OutPoint {
+                    txid: signed_tx.txid(),
+                    vout: vout as u32,
+                },
+                script_pubkey: output.script_pubkey.clone(),
+                amount: output.value,
+                height: None,
+            });
+        }
+        self.in_flight.insert(
+            txid.clone(),
+            InFlight {
+                id: withdrawal.id,
+                spent,
+                height: None,
+            },
+        );
+        Ok(txid)
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(bus) = &self.events {
+            bus.emit(event);
+        }
+    }
+}
+
+/// The scriptPubKey paying `address`, checked for the network.
+fn address_script(address: &str, is_testnet: bool) -> Result<Vec<u8>, PipelineError> {
+    let invalid = |source| PipelineError::InvalidAddress {
+        address: address.to_string(),
+        source,
+    };
+    AddressUtils::diagnose_for(address, is_testnet).map_err(invalid)?;
+    OutputKind::from_address(address)
+        .and_then(|kind| TemplateRegistry::new().build(&kind))
+        .ok_or_else(|| invalid(AddressError::BadChecksum))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::COIN;
+    use std::sync::{Arc, Mutex};
+
+    const HOT: &str = "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas";
+    const DEPOSIT: &str = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+    const USER: &str = "D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e";
+
+    /// Pays `amount` to `address` from a made-up input.
+    fn payment(id: u8, address: &str, amount: u64) -> Transaction {
+        Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: [id; 32],
+                    vout: 0,
+                },
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![TxOut {
+                value: amount,
+                script_pubkey: address_script(address, false).unwrap(),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    /// A pipeline whose signer returns the unsigned transaction and whose
+    /// broadcaster records what it was given.
+    fn pipeline() -> (Pipeline, Arc<Mutex<Vec<String>>>) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&sent);
+        let signer = |tx: &UnsignedTransaction| Ok(tx.transaction().to_hex());
+        let broadcaster = move |raw: &str| {
+            log.lock().unwrap().push(raw.to_string());
+            Ok(())
+        };
+        let mut config = PipelineConfig::new(false, HOT);
+        config.confirmations = 2;
+        let mut pipeline = Pipeline::new(config, signer, broadcaster)
+            .unwrap()
+            .with_policy(|w: &Withdrawal| match w.amount.0 > 100 * COIN {
+                true => Err("over limit".to_string()),
+                false => Ok(()),
+            });
+        pipeline.watch_deposit_address(DEPOSIT, "alice").unwrap();
+        (pipeline, sent)
+    }
+
+    #[test]
+    fn test_deposit_credited_after_confirmations() {
+        let (mut pipeline, _) = pipeline();
+        let deposit = payment(1, DEPOSIT, 50 * COIN);
+        pipeline.scan_transaction(&deposit, None);
+        pipeline.scan_transaction(&payment(2, USER, COIN), Some(10));
+        assert_eq!(pipeline.on_block(10), BlockReport::default());
+
+        pipeline.scan_transaction(&deposit, Some(11));
+        assert!(pipeline.on_block(11).credited.is_empty());
+        assert_eq!(
+            pipeline.on_block(12).credited,
+            vec![("alice".to_string(), Koinu(50 * COIN))]
+        );
+        assert_eq!(pipeline.ledger().balance("alice"), Koinu(50 * COIN));
+        pipeline.scan_transaction(&deposit, Some(11));
+        assert!(pipeline.on_block(13).credited.is_empty());
+    }
+
+    #[test]
+    fn test_script_deposit_credited_but_not_spent() {
+        let (mut pipeline, sent) = pipeline();
+        let treasury: Vec<u8> = [&[0xa9, 0x14][..], &[7; 20], &[0x87]].concat();
+        pipeline.watch_deposit_script(&treasury, "treasury");
+        let mut deposit = payment(1, DEPOSIT, 50 * COIN);
+        deposit.outputs[0].script_pubkey = treasury;
+        pipeline.scan_transaction(&deposit, Some(1));
+        assert_eq!(
+            pipeline.on_block(2).credited,
+            vec![("treasury".to_string(), Koinu(50 * COIN))]
+        );
+
+        // The hot wallet holds no coins it can sign for.
+        let id = pipeline
+            .request_withdrawal("treasury", USER, Koinu(COIN))
+            .unwrap();
+        assert!(matches!(
+            pipeline.process_withdrawals()[0].1,
+            Err(PipelineError::Select(SelectError::InsufficientFunds { .. }))
+        ));
+        assert_eq!(pipeline.status(id), Some(&WithdrawalStatus::Queued));
+        assert!(sent.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_withdrawal_lifecycle() {
+        let (mut pipeline, sent) = pipeline();
+        pipeline.scan_transaction(&payment(1, DEPOSIT, 50 * COIN), Some(1));
+        pipeline.on_block(2);
+
+        assert!(matches!(
+            pipeline.request_withdrawal("alice", "DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViZ", COIN.into()),
+            Err(PipelineError::InvalidAddress { .. })
+        ));
+        assert_eq!(
+            pipeline.request_withdrawal("alice", USER, Koinu(200 * COIN)),
+            Err(PipelineError::Rejected("over limit".to_string()))
+        );
+        assert_eq!(
+            pipeline.request_withdrawal("bob", USER, Koinu(COIN)),
+            Err(PipelineError::InsufficientBalance("bob".to_string()))
+        );
+
+        let first = pipeline
+            .request_withdrawal("alice", USER, Koinu(10 * COIN))
+            .unwrap();
+        let second = pipeline
+            .request_withdrawal("alice", USER, Koinu(10 * COIN))
+            .unwrap();
+        assert_eq!(pipeline.ledger().balance("alice"), Koinu(30 * COIN));
+
+        // The only confirmed coin is reserved by the first withdrawal, so
+        // the second waits for its change to confirm.
+        let results = pipeline.process_withdrawals();
+        let txid = results[0].1.clone().unwrap();
+        assert!(matches!(results[1].1, Err(PipelineError::Select(_))));
+        assert_eq!(pipeline.status(second), Some(&WithdrawalStatus::Queued));
+
+        let broadcast = Transaction::from_hex(&sent.lock().unwrap()[0]).unwrap();
+        assert_eq!(broadcast.txid_hex(), txid);
+        pipeline.scan_transaction(&broadcast, Some(3));
+        assert!(pipeline.on_block(3).confirmed.is_empty());
+        assert_eq!(pipeline.on_block(4).confirmed, vec![first]);
+        assert_eq!(
+            pipeline.status(first),
+            Some(&WithdrawalStatus::Confirmed { txid, height: 3 })
+        );
+
+        assert!(pipeline.process_withdrawals()[0].1.is_ok());
+        assert_eq!(pipeline.queued().count(), 0);
+    }
+}
@@ -0,0 +1,181 @@
+//! Derivation path presets for other Dogecoin wallets.
+//!
+//! A seed restored from another wallet only finds its funds if addresses are
+//! derived the way that wallet did. [`WalletPreset`] captures the known
+//! layouts so callers can pick one by name, or try them all with
+//! [`DerivationSession::discover`](crate::DerivationSession::discover).
+//!
+//! | Preset | Address path |
+//! |---|---|
+//! | [`Bip44`](WalletPreset::Bip44), [`Ledger`](WalletPreset::Ledger), [`Trezor`](WalletPreset::Trezor), [`Exodus`](WalletPreset::Exodus) | `m/44'/3'/<account>'/<change>/<index>` |
+//! | [`DogecoinCore`](WalletPreset::DogecoinCore) | `m/0'/<change>'/<index>'` |
+//! | [`DogecoinWalletAndroid`](WalletPreset::DogecoinWalletAndroid) | `m/<account>'/<change>/<index>` |
+//!
+//! On testnet the BIP44 coin type is `1'` instead of `3'`.
+
+use crate::hdnode::HARDENED;
+use crate::hdwallet::Chain;
+
+/// BIP44 coin type for Dogecoin mainnet.
+pub(crate) const BIP44_COIN_TYPE: u32 = 3;
+/// BIP44 coin type for testnets.
+pub(crate) const BIP44_COIN_TYPE_TEST: u32 = 1;
+
+/// Address layout used by a known wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WalletPreset {
+    /// Standard BIP44, used by this crate.
+    Bip44,
+    /// Ledger hardware wallets (BIP44).
+    Ledger,
+    /// Trezor hardware wallets (BIP44).
+    Trezor,
+    /// Exodus (BIP44, account 0 only).
+    Exodus,
+    /// Dogecoin Core 1.14+ HD wallets, with hardened address indexes.
+    DogecoinCore,
+    /// Dogecoin Wallet for Android (bitcoinj layout).
+    DogecoinWalletAndroid,
+}
+
+impl WalletPreset {
+    /// Every preset, BIP44 first.
+    pub const ALL: [WalletPreset; 6] = [
+        WalletPreset::Bip44,
+        WalletPreset::Ledger,
+        WalletPreset::Trezor,
+        WalletPreset::Exodus,
+        WalletPreset::DogecoinCore,
+        WalletPreset::DogecoinWalletAndroid,
+    ];
+
+    /// Human-readable wallet name.
+    pub fn name(self) -> &'static str {
+        match self {
+            WalletPreset::Bip44 => "BIP44",
+            WalletPreset::Ledger => "Ledger",
+            WalletPreset::Trezor => "Trezor",
+            WalletPreset::Exodus => "Exodus",
+            WalletPreset::DogecoinCore => "Dogecoin Core",
+            WalletPreset::DogecoinWalletAndroid => "Dogecoin Wallet (Android)",
+        }
+    }
+
+    /// Whether the wallet uses accounts other than 0.
+    pub fn has_accounts(self) -> bool {
+        !matches!(self, WalletPreset::Exodus | WalletPreset::DogecoinCore)
+    }
+
+    /// Whether address indexes are hardened (`/<index>'`).
+    pub fn hardened_index(self) -> bool {
+        self == WalletPreset::DogecoinCore
+    }
+
+    /// Get the path of the chain node that addresses are derived from.
+    ///
+    /// # Arguments
+    /// * `account` - Account index.
+    /// * `chain` - External (receiving) or internal (change) chain.
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    ///
+    /// # Returns
+    /// `None` for a non-zero account on a wallet without accounts.
+    pub fn chain_path(self, account: u32, chain: Chain, is_testnet: bool) -> Option<String> {
+        if account >= HARDENED || (account != 0 && !self.has_accounts()) {
+            return None;
+        }
+        let change = chain.is_change() as u8;
+        Some(match self {
+            WalletPreset::Bip44
+            | WalletPreset::Ledger
+            | WalletPreset::Trezor
+            | WalletPreset::Exodus => {
+                format!("{}/{change}", bip44_account_path(is_testnet, account))
+            }
+            WalletPreset::DogecoinCore => format!("m/0'/{change}'"),
+            WalletPreset::DogecoinWalletAndroid => format!("m/{account}'/{change}"),
+        })
+    }
+
+    /// Get the full path of an address.
+    pub fn address_path(
+        self,
+        account: u32,
+        chain: Chain,
+        index: u32,
+        is_testnet: bool,
+    ) -> Option<String> {
+        if index >= HARDENED {
+            return None;
+        }
+        let suffix = if self.hardened_index() { "'" } else { "" };
+        Some(format!(
+            "{}/{index}{suffix}",
+            self.chain_path(account, chain, is_testnet)?
+        ))
+    }
+
+    /// Get the child number of `index` below the chain node.
+    pub(crate) fn child_index(self, index: u32) -> Option<u32> {
+        if index >= HARDENED {
+            return None;
+        }
+        Some(if self.hardened_index() {
+            index | HARDENED
+        } else {
+            index
+        })
+    }
+}
+
+/// BIP44 account path (`m/44'/<coin>'/<account>'`) for a network.
+pub(crate) fn bip44_account_path(is_testnet: bool, account: u32) -> String {
+    let coin_type = if is_testnet {
+        BIP44_COIN_TYPE_TEST
+    } else {
+        BIP44_COIN_TYPE
+    };
+    format!("m/44'/{coin_type}'/{account}'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_paths() {
+        assert_eq!(
+            WalletPreset::Ledger.address_path(1, Chain::Internal, 7, false),
+            Some("m/44'/3'/1'/1/7".into())
+        );
+        assert_eq!(
+            WalletPreset::Bip44.address_path(0, Chain::External, 0, true),
+            Some("m/44'/1'/0'/0/0".into())
+        );
+        assert_eq!(
+            WalletPreset::DogecoinCore.address_path(0, Chain::Internal, 2, false),
+            Some("m/0'/1'/2'".into())
+        );
+        assert_eq!(
+            WalletPreset::DogecoinWalletAndroid.address_path(1, Chain::External, 3, false),
+            Some("m/1'/0/3".into())
+        );
+    }
+
+    #[test]
+    fn test_preset_limits() {
+        assert_eq!(
+            WalletPreset::Exodus.chain_path(1, Chain::External, false),
+            None
+        );
+        assert_eq!(
+            WalletPreset::Bip44.address_path(0, Chain::External, HARDENED, false),
+            None
+        );
+        assert_eq!(
+            WalletPreset::DogecoinCore.child_index(5),
+            Some(5 | HARDENED)
+        );
+        assert_eq!(WalletPreset::ALL.len(), 6);
+    }
+}
@@ -0,0 +1,223 @@
+//! Fiat equivalents for display.
+//!
+//! Prices come from a [`PriceSource`], so wallets choose where quotes come
+//! from; this crate hardcodes no exchange. [`FixedPrices`] serves quotes the
+//! caller already has, and [`HttpPriceSource`] (enabled by the `price-http`
+//! feature) reads one number out of any JSON price API.
+//!
+//! Fiat values are for display only; never build transactions from them.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::price::FixedPrices;
+//! use libdogecoin_rs::Koinu;
+//!
+//! let prices = FixedPrices::new().with_price("USD", 0.125);
+//! let fiat = Koinu::from_doge(10).unwrap().to_fiat(&prices, "usd").unwrap();
+//! assert_eq!(fiat.to_string(), "1.25 USD");
+//! ```
+
+use crate::consensus::COIN;
+use crate::koinu::Koinu;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Errors from price lookups.
+#[derive(thiserror::Error, Debug)]
+pub enum PriceError {
+    #[error("no DOGE price available in {0}")]
+    Unavailable(String),
+
+    #[error("invalid price {0}")]
+    InvalidPrice(String),
+
+    #[error("price request failed: {0}")]
+    Http(String),
+}
+
+/// A source of DOGE prices.
+pub trait PriceSource {
+    /// Get the price of one DOGE.
+    ///
+    /// # Arguments
+    /// * `currency` - Upper-case ISO 4217 code, e.g. "USD".
+    fn doge_price(&self, currency: &str) -> Result<f64, PriceError>;
+}
+
+/// An amount in a fiat currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatAmount {
+    pub value: f64,
+    /// Upper-case ISO 4217 code.
+    pub currency: String,
+}
+
+impl fmt::Display for FiatAmount {
+    /// Formats with two decimals, e.g. `1.25 USD`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} {}", self.value, self.currency)
+    }
+}
+
+impl Koinu {
+    /// Convert to a fiat amount for display.
+    ///
+    /// # Arguments
+    /// * `source` - Where to get the DOGE price.
+    /// * `currency` - ISO 4217 code, in any case.
+    pub fn to_fiat(
+        self,
+        source: &(impl PriceSource + ?Sized),
+        currency: &str,
+    ) -> Result<FiatAmount, PriceError> {
+        let currency = currency.to_ascii_uppercase();
+        let price = source.doge_price(&currency)?;
+        if !price.is_finite() || price < 0.0 {
+            return Err(PriceError::InvalidPrice(price.to_string()));
+        }
+        Ok(FiatAmount {
+            value: self.0 as f64 / COIN as f64 * price,
+            currency,
+        })
+    }
+}
+
+/// Prices supplied by the caller, e.g. cached quotes.
+#[derive(Debug, Clone, Default)]
+pub struct FixedPrices {
+    prices: HashMap<String, f64>,
+}
+
+impl FixedPrices {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the price of one DOGE in `currency`.
+    pub fn with_price(mut self, currency: &str, price: f64) -> Self {
+        self.prices.insert(currency.to_ascii_uppercase(), price);
+        self
+    }
+}
+
+impl PriceSource for FixedPrices {
+    fn doge_price(&self, currency: &str) -> Result<f64, PriceError> {
+        self.prices
+            .get(currency)
+            .copied()
+            .ok_or_else(|| PriceError::Unavailable(currency.to_string()))
+    }
+}
+
+/// Reads the price from a JSON HTTP API (enabled by the `price-http` feature).
+///
+/// The URL and a JSON pointer to the price are templates: `{currency}` is
+/// replaced with the lower-case currency code and `{CURRENCY}` with the
+/// upper-case one. The price may be a JSON number or a numeric string.
+///
+/// # Example
+/// ```no_run
+/// use libdogecoin_rs::price::HttpPriceSource;
+/// use libdogecoin_rs::Koinu;
+///
+/// let source = HttpPriceSource::new(
+///     "https://prices.example.com/v1/doge?convert={CURRENCY}",
+///     "/data/quote/{CURRENCY}/price",
+/// );
+/// let fiat = Koinu::ONE_DOGE.to_fiat(&source, "EUR").unwrap();
+/// ```
+#[cfg(feature = "price-http")]
+#[derive(Debug, Clone)]
+pub struct HttpPriceSource {
+    url: String,
+    pointer: String,
+    user_agent: String,
+}
+
+#[cfg(feature = "price-http")]
+impl HttpPriceSource {
+    /// Create a source from a URL template and a JSON pointer template.
+    pub fn new(url: impl Into<String>, pointer: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            pointer: pointer.into(),
+            user_agent: "libdogecoin-rs".to_string(),
+        }
+    }
+
+    /// Override User-Agent header.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+}
+
+#[cfg(feature = "price-http")]
+impl PriceSource for HttpPriceSource {
+    fn doge_price(&self, currency: &str) -> Result<f64, PriceError> {
+        let url = fill_template(&self.url, currency);
+        let body: serde_json::Value = ureq::get(&url)
+            .set("Accept", "application/json")
+            .set("User-Agent", &self.user_agent)
+            .call()
+            .map_err(|e| PriceError::Http(e.to_string()))?
+            .into_json()
+            .map_err(|e| PriceError::Http(e.to_string()))?;
+        let value = body
+            .pointer(&fill_template(&self.pointer, currency))
+            .ok_or_else(|| PriceError::Unavailable(currency.to_string()))?;
+        price_from_json(value)
+    }
+}
+
+#[cfg(feature = "price-http")]
+fn fill_template(template: &str, currency: &str) -> String {
+    template
+        .replace("{currency}", &currency.to_ascii_lowercase())
+        .replace("{CURRENCY}", &currency.to_ascii_uppercase())
+}
+
+#[cfg(feature = "price-http")]
+fn price_from_json(value: &serde_json::Value) -> Result<f64, PriceError> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| PriceError::InvalidPrice(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_fiat() {
+        let prices = FixedPrices::new()
+            .with_price("usd", 0.2)
+            .with_price("BAD", -1.0);
+        let fiat = Koinu(250_000_000).to_fiat(&prices, "USD").unwrap();
+        assert_eq!(fiat.currency, "USD");
+        assert!((fiat.value - 0.5).abs() < 1e-9);
+
+        assert!(matches!(
+            Koinu::ONE_DOGE.to_fiat(&prices, "EUR"),
+            Err(PriceError::Unavailable(c)) if c == "EUR"
+        ));
+        assert!(Koinu::ONE_DOGE.to_fiat(&prices, "bad").is_err());
+    }
+
+    #[cfg(feature = "price-http")]
+    #[test]
+    fn test_http_templates() {
+        assert_eq!(
+            fill_template("/p?vs={currency}&c={CURRENCY}", "Usd"),
+            "/p?vs=usd&c=USD"
+        );
+        let body = serde_json::json!({"dogecoin": {"usd": 0.1, "eur": "0.09"}});
+        let price = |p| price_from_json(body.pointer(p).unwrap()).ok();
+        assert_eq!(price("/dogecoin/usd"), Some(0.1));
+        assert_eq!(price("/dogecoin/eur"), Some(0.09));
+        assert_eq!(price("/dogecoin"), None);
+    }
+}
@@ -0,0 +1,258 @@
+//! Privacy checks for transactions before broadcast.
+//!
+//! [`DecodedTransaction::privacy_report`] looks for the patterns chain
+//! analysis uses to link addresses and tell payments from change, so wallet
+//! UIs can warn before a transaction is sent. The checks are heuristics:
+//! a clean report does not mean a transaction is private.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::transaction::{DecodedInput, DecodedTransaction};
+//!
+//! # let (raw, input_address): (&str, fn(&DecodedInput) -> Option<String>) = unimplemented!();
+//! let tx = DecodedTransaction::from_hex(raw, false).unwrap();
+//! for warning in &tx.privacy_report(input_address).warnings {
+//!     eprintln!("privacy: {warning}");
+//! }
+//! ```
+
+use crate::consensus::COIN;
+use crate::script::OutputKind;
+use crate::transaction::{DecodedInput, DecodedTransaction};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Values that are a multiple of this (0.01 DOGE) count as round.
+pub const ROUND_AMOUNT_UNIT: u64 = COIN / 100;
+
+/// Why an output looks like change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeHint {
+    /// The other outputs are round amounts and this one is not.
+    NotRound,
+    /// It pays back to an address the inputs spend from.
+    PaysInputAddress,
+    /// It is the only output whose script type matches the inputs.
+    ScriptType,
+}
+
+/// A pattern that leaks information about the wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivacyWarning {
+    /// An address is paid more than once, or paid by coins it already held.
+    AddressReuse { address: String },
+    /// An output value is a round number, marking it as the payment.
+    RoundAmount { output: usize },
+    /// An output is identifiable as change.
+    ObviousChange { output: usize, hint: ChangeHint },
+    /// Inputs from several addresses are spent together, linking them.
+    InputMerging { addresses: Vec<String> },
+}
+
+impl fmt::Display for PrivacyWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrivacyWarning::AddressReuse { address } => write!(f, "address {address} is reused"),
+            PrivacyWarning::RoundAmount { output } => {
+                write!(f, "output {output} is a round amount")
+            }
+            PrivacyWarning::ObviousChange { output, hint } => {
+                write!(f, "output {output} is identifiable as change ({hint:?})")
+            }
+            PrivacyWarning::InputMerging { addresses } => {
+                write!(f, "inputs link {} addresses", addresses.len())
+            }
+        }
+    }
+}
+
+/// The warnings found in a transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrivacyReport {
+    pub warnings: Vec<PrivacyWarning>,
+}
+
+impl PrivacyReport {
+    /// Whether no warnings were found.
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+impl DecodedTransaction {
+    /// Check the transaction for address reuse, round amounts, obvious
+    /// change and merged inputs.
+    ///
+    /// # Arguments
+    /// * `input_address` - Address of the output an input spends, e.g. from
+    ///   the wallet's UTXOs; `None` if unknown. Checks involving inputs only
+    ///   use the inputs it knows.
+    pub fn privacy_report(
+        &self,
+        mut input_address: impl FnMut(&DecodedInput) -> Option<String>,
+    ) -> PrivacyReport {
+        let mut warnings = Vec::new();
+        let spent_from: BTreeSet<String> =
+            self.inputs.iter().filter_map(&mut input_address).collect();
+
+        let mut paid = BTreeSet::new();
+        for address in self.outputs.iter().filter_map(|o| o.address.as_ref()) {
+            if (!paid.insert(address) || spent_from.contains(address))
+                && !warnings.contains(&reuse(address))
+            {
+                warnings.push(reuse(address));
+            }
+        }
+
+        let round: Vec<bool> = self
+            .outputs
+            .iter()
+            .map(|o| o.value > 0 && o.value % ROUND_AMOUNT_UNIT == 0)
+            .collect();
+        let round_count = round.iter().filter(|&&r| r).count();
+        if round_count < self.outputs.len() {
+            for (output, _) in round.iter().enumerate().filter(|(_, &r)| r) {
+                warnings.push(PrivacyWarning::RoundAmount { output });
+            }
+        }
+
+        if let Some((output, hint)) = self.obvious_change(&spent_from, &round) {
+            warnings.push(PrivacyWarning::ObviousChange { output, hint });
+        }
+
+        if spent_from.len() > 1 {
+            warnings.push(PrivacyWarning::InputMerging {
+                addresses: spent_from.into_iter().collect(),
+            });
+        }
+        PrivacyReport { warnings }
+    }
+
+    /// Find the one output that stands out as change.
+    fn obvious_change(
+        &self,
+        spent_from: &BTreeSet<String>,
+        round: &[bool],
+    ) -> Option<(usize, ChangeHint)> {
+        let outputs = 0..self.outputs.len();
+        let to_inputs = outputs.clone().filter(|&i| {
+            self.outputs[i]
+                .address
+                .as_ref()
+                .is_some_and(|a| spent_from.contains(a))
+        });
+        if let Some(i) = only(to_inputs) {
+            return Some((i, ChangeHint::PaysInputAddress));
+        }
+        if self.outputs.len() < 2 {
+            return None;
+        }
+        if let Some(i) = only(outputs.clone().filter(|&i| !round[i])) {
+            return Some((i, ChangeHint::NotRound));
+        }
+
+        // Wallets spend their own script type; a lone output of that type
+        // among outputs of another is likely change.
+        let input_kinds: BTreeSet<u8> = spent_from
+            .iter()
+            .filter_map(|a| OutputKind::from_address(a).map(|k| kind_tag(&k)))
+            .collect();
+        let [kind] = input_kinds.into_iter().collect::<Vec<_>>()[..] else {
+            return None;
+        };
+        let same = outputs.filter(|&i| self.outputs[i].kind.as_ref().map(kind_tag) == Some(kind));
+        only(same).map(|i| (i, ChangeHint::ScriptType))
+    }
+}
+
+/// The sole item of `matches`, if there is exactly one.
+fn only(mut matches: impl Iterator<Item = usize>) -> Option<usize> {
+    let first = matches.next()?;
+    matches.next().is_none().then_some(first)
+}
+
+fn reuse(address: &str) -> PrivacyWarning {
+    PrivacyWarning::AddressReuse {
+        address: address.to_string(),
+    }
+}
+
+fn kind_tag(kind: &OutputKind) -> u8 {
+    match kind {
+        OutputKind::P2pkh(_) => 0,
+        OutputKind::P2sh(_) => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::DecodedOutput;
+
+    const A: &str = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+    const B: &str = "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas";
+    const C: &str = "D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e";
+
+    fn tx(inputs: usize, outputs: &[(&str, u64)]) -> DecodedTransaction {
+        DecodedTransaction {
+            txid: String::new(),
+            version: 1,
+            inputs: (0..inputs)
+                .map(|i| DecodedInput {
+                    txid: format!("{i:064x}"),
+                    vout: 0,
+                    script_sig: String::new(),
+                    sequence: u32::MAX,
+                })
+                .collect(),
+            outputs: outputs
+                .iter()
+                .map(|&(address, value)| DecodedOutput {
+                    value,
+                    script_pubkey: String::new(),
+                    kind: OutputKind::from_address(address),
+                    address: Some(address.to_string()),
+                })
+                .collect(),
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_flags_reuse_round_and_change() {
+        let tx = tx(2, &[(A, 10 * COIN), (B, 123_456_789)]);
+        let report = tx.privacy_report(|input| {
+            Some(if input.txid.ends_with('0') { B } else { C }.to_string())
+        });
+        assert_eq!(
+            report.warnings,
+            vec![
+                reuse(B),
+                PrivacyWarning::RoundAmount { output: 0 },
+                PrivacyWarning::ObviousChange {
+                    output: 1,
+                    hint: ChangeHint::PaysInputAddress
+                },
+                PrivacyWarning::InputMerging {
+                    addresses: vec![C.to_string(), B.to_string()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clean_and_not_round_change() {
+        let report = tx(1, &[(A, 123_456_789), (B, 987_654_321)]).privacy_report(|_| None);
+        assert!(report.is_clean());
+
+        let report = tx(1, &[(A, 5 * COIN), (B, 987_654_321)]).privacy_report(|_| None);
+        assert_eq!(
+            report.warnings[1],
+            PrivacyWarning::ObviousChange {
+                output: 1,
+                hint: ChangeHint::NotRound
+            }
+        );
+    }
+}
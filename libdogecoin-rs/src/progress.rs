@@ -0,0 +1,153 @@
+//! Progress reporting for long-running operations.
+//!
+//! Operations that accept a [`ProgressSink`] report a [`Progress`] after each
+//! item, from whichever thread did the work. Any `Fn(&Progress)` closure that
+//! is `Send + Sync` is a sink.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::cancel::CancellationToken;
+//! use libdogecoin_rs::progress::Progress;
+//! use libdogecoin_rs::DogeWallet;
+//!
+//! let report = |p: &Progress| {
+//!     if let Some(fraction) = p.fraction() {
+//!         eprint!("\r{:>3.0}% (eta {:?})", fraction * 100.0, p.eta);
+//!     }
+//! };
+//! let token = CancellationToken::new();
+//! let wallets = DogeWallet::generate_batch_cancellable(10_000, false, &token, Some(&report));
+//! ```
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A snapshot of an operation's progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Items finished so far.
+    pub done: u64,
+    /// Total items, if known up front.
+    pub total: Option<u64>,
+    /// Chain height reached, for operations that walk the chain.
+    pub height: Option<u32>,
+    /// Estimated time remaining, once there is enough data to estimate it.
+    pub eta: Option<Duration>,
+}
+
+impl Progress {
+    /// Fraction complete in `0.0..=1.0`, if the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.done as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+/// Receiver of progress updates.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Send + Sync> ProgressSink for F {
+    fn report(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// Shared counter that turns item completions into [`Progress`] reports.
+///
+/// Safe to use from several worker threads at once; also usable by
+/// applications that drive their own scanning loops.
+pub struct ProgressTracker<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    total: Option<u64>,
+    done: AtomicU64,
+    /// Height + 1, or 0 if no height was recorded.
+    height: AtomicU32,
+    started: Instant,
+}
+
+impl<'a> ProgressTracker<'a> {
+    /// Start tracking an operation of `total` items (if known).
+    pub fn new(sink: Option<&'a dyn ProgressSink>, total: Option<u64>) -> Self {
+        ProgressTracker {
+            sink,
+            total,
+            done: AtomicU64::new(0),
+            height: AtomicU32::new(0),
+            started: Instant::now(),
+        }
+    }
+
+    /// Record the chain height reached (reported with the next update).
+    pub fn set_height(&self, height: u32) {
+        self.height
+            .store(height.saturating_add(1), Ordering::Relaxed);
+    }
+
+    /// Record `n` finished items and report.
+    pub fn advance(&self, n: u64) {
+        let done = self.done.fetch_add(n, Ordering::Relaxed) + n;
+        let Some(sink) = self.sink else {
+            return;
+        };
+        let eta = match self.total {
+            Some(total) if done > 0 => {
+                let remaining = total.saturating_sub(done) as f64;
+                let per_item = self.started.elapsed().as_secs_f64() / done as f64;
+                Some(Duration::from_secs_f64(remaining * per_item))
+            }
+            _ => None,
+        };
+        sink.report(&Progress {
+            done,
+            total: self.total,
+            height: self.height.load(Ordering::Relaxed).checked_sub(1),
+            eta,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_tracker_reports() {
+        let reports = Mutex::new(Vec::new());
+        let sink = |p: &Progress| reports.lock().unwrap().push(*p);
+        let tracker = ProgressTracker::new(Some(&sink), Some(4));
+
+        tracker.advance(1);
+        tracker.set_height(100);
+        tracker.advance(3);
+
+        let reports = reports.into_inner().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].done, 1);
+        assert_eq!(reports[0].height, None);
+        assert_eq!(reports[1].height, Some(100));
+        assert_eq!(reports[1].fraction(), Some(1.0));
+        assert_eq!(reports[1].eta, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_fraction_without_total() {
+        let progress = Progress {
+            done: 5,
+            total: None,
+            height: None,
+            eta: None,
+        };
+        assert_eq!(progress.fraction(), None);
+        let empty = Progress {
+            total: Some(0),
+            ..progress
+        };
+        assert_eq!(empty.fraction(), Some(1.0));
+    }
+}
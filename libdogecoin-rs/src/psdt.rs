@@ -0,0 +1,655 @@
+//! Partially signed Dogecoin transactions (PSDT).
+//!
+//! A [`Psdt`] carries an unsigned transaction between the parties that sign
+//! it: the outputs its inputs spend, P2SH redeem scripts, and the signatures
+//! collected so far. Each signer computes [`Psdt::signature_hash`] for its
+//! inputs, signs it with its own key (e.g. on a hardware device) and records
+//! the result with [`Psdt::add_signature`]; copies signed in parallel are
+//! merged with [`Psdt::combine`]. Signers holding WIF keys in this process
+//! use [`Psdt::sign_with_privkey`] or [`Psdt::sign_all`] instead. Once
+//! [`Psdt::is_complete`], [`Psdt::finalize`] builds the scriptSigs and
+//! returns the transaction.
+//!
+//! Supported inputs spend P2PKH, P2PK and bare multisig outputs, and P2SH
+//! outputs whose redeem script is P2PK or multisig. Signatures must use
+//! `SIGHASH_ALL`; they are checked for shape and signing key but not
+//! verified.
+//!
+//! # Format
+//! `"DPST"`, a version byte, a flags byte (bit 0: testnet), the unsigned
+//! transaction with a CompactSize length prefix, then for each input the
+//! spent output's value (8 bytes, little-endian) and length-prefixed
+//! scriptPubKey, the length-prefixed redeem script (empty if none), and a
+//! CompactSize count of length-prefixed public key and signature pairs.
+//! The first 4 bytes of the double SHA-256 of everything before it follow.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::psdt::Psdt;
+//!
+//! # let (blob, redeem_script, pubkey, sign): (String, Vec<u8>, Vec<u8>, fn([u8; 32]) -> Vec<u8>) = unimplemented!();
+//! let mut psdt = Psdt::from_hex(&blob)?;
+//! psdt.set_redeem_script(0, &redeem_script)?;
+//! let signature = sign(psdt.signature_hash(0)?);
+//! psdt.add_signature(0, &pubkey, &signature)?;
+//! if psdt.is_complete() {
+//!     let signed_hex = psdt.finalize()?;
+//! }
+//! # Ok::<(), libdogecoin_rs::psdt::PsdtError>(())
+//! ```
+
+use crate::encode::{self, Reader};
+use crate::hash::{hash160, sha256d};
+use crate::hex;
+use crate::key::SigningKey;
+use crate::offline::UnsignedTransaction;
+use crate::script::{is_pubkey, push_data, OutputKind, TemplateRegistry, OP_0};
+use crate::transaction::{DecodeError, Transaction, TxOut};
+use crate::utxo::{OutPoint, Utxo};
+use std::collections::BTreeMap;
+
+const MAGIC: &[u8; 4] = b"DPST";
+const VERSION: u8 = 1;
+const FLAG_TESTNET: u8 = 0x01;
+const CHECKSUM_LEN: usize = 4;
+
+/// The only sighash type accepted.
+pub use crate::transaction::SIGHASH_ALL;
+
+/// Errors from building, signing or loading a PSDT.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PsdtError {
+    #[error("not a PSDT")]
+    BadMagic,
+
+    #[error("unsupported PSDT version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("PSDT checksum mismatch")]
+    ChecksumMismatch,
+
+    #[error("malformed PSDT: {0}")]
+    Malformed(#[from] DecodeError),
+
+    #[error("invalid raw transaction")]
+    InvalidTransaction,
+
+    #[error("no spent output given for input {0:?}")]
+    MissingPrevout(OutPoint),
+
+    #[error("no input {0}")]
+    NoSuchInput(usize),
+
+    #[error("input {0} has an unsupported script")]
+    UnsupportedScript(usize),
+
+    #[error("input {0} needs a redeem script")]
+    MissingRedeemScript(usize),
+
+    #[error("redeem script does not match input {0}")]
+    RedeemScriptMismatch(usize),
+
+    #[error("key cannot sign input {0}")]
+    UnknownKey(usize),
+
+    #[error("invalid private key")]
+    InvalidKey,
+
+    #[error("malformed signature for input {0}")]
+    InvalidSignature(usize),
+
+    #[error("input {0} is missing signatures")]
+    Incomplete(usize),
+
+    #[error("PSDTs are for different transactions")]
+    TransactionMismatch,
+}
+
+/// Signing data for one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsdtInput {
+    /// The output the input spends.
+    pub prevout: TxOut,
+    /// Redeem script, for P2SH inputs.
+    pub redeem_script: Option<Vec<u8>>,
+    /// Signatures with `SIGHASH_ALL` appended, by public key.
+    pub signatures: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl PsdtInput {
+    fn spending(prevout: TxOut) -> Self {
+        PsdtInput {
+            prevout,
+            redeem_script: None,
+            signatures: BTreeMap::new(),
+        }
+    }
+}
+
+/// A partially signed transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Psdt {
+    is_testnet: bool,
+    tx: Transaction,
+    inputs: Vec<PsdtInput>,
+}
+
+impl Psdt {
+    /// Start a PSDT.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    /// * `raw` - The unsigned transaction hex.
+    /// * `utxos` - The outputs spent by the inputs, in any order.
+    pub fn new(is_testnet: bool, raw: &str, utxos: &[Utxo]) -> Result<Self, PsdtError> {
+        let tx = Transaction::from_hex(raw).ok_or(PsdtError::InvalidTransaction)?;
+        let inputs = tx
+            .inputs
+            .iter()
+            .map(|input| {
+                utxos
+                    .iter()
+                    .find(|u| u.outpoint == input.previous_output)
+                    .map(|u| {
+                        PsdtInput::spending(TxOut {
+                            value: u.amount,
+                            script_pubkey: u.script_pubkey.clone(),
+                        })
+                    })
+                    .ok_or(PsdtError::MissingPrevout(input.previous_output))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Psdt {
+            is_testnet,
+            tx,
+            inputs,
+        })
+    }
+
+    /// Start a PSDT from an offline-signing blob.
+    pub fn from_unsigned(unsigned: &UnsignedTransaction) -> Self {
+        Psdt {
+            is_testnet: unsigned.is_testnet(),
+            tx: unsigned.transaction().clone(),
+            inputs: unsigned
+                .prevouts()
+                .iter()
+                .map(|prevout| PsdtInput::spending(prevout.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        self.is_testnet
+    }
+
+    pub fn transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    pub fn inputs(&self) -> &[PsdtInput] {
+        &self.inputs
+    }
+
+    /// Attach the redeem script of a P2SH input.
+    ///
+    /// # Returns
+    /// An error if the input is not P2SH or the script does not hash to it.
+    pub fn set_redeem_script(
+        &mut self,
+        index: usize,
+        redeem_script: &[u8],
+    ) -> Result<&mut Self, PsdtError> {
+        let input = self
+            .inputs
+            .get_mut(index)
+            .ok_or(PsdtError::NoSuchInput(index))?;
+        match classify(&input.prevout.script_pubkey) {
+            Some(OutputKind::P2sh(hash)) if hash == hash160(redeem_script) => {
+                input.redeem_script = Some(redeem_script.to_vec());
+                Ok(self)
+            }
+            Some(OutputKind::P2sh(_)) => Err(PsdtError::RedeemScriptMismatch(index)),
+            _ => Err(PsdtError::UnsupportedScript(index)),
+        }
+    }
+
+    /// Get the legacy `SIGHASH_ALL` digest an input's signers sign.
+    pub fn signature_hash(&self, index: usize) -> Result<[u8; 32], PsdtError> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or(PsdtError::NoSuchInput(index))?;
+        self.spend(index)?;
+        let script_code = input
+            .redeem_script
+            .as_ref()
+            .unwrap_or(&input.prevout.script_pubkey);
+        self.tx
+            .signature_hash(index, script_code, SIGHASH_ALL as u32)
+            .ok_or(PsdtError::NoSuchInput(index))
+    }
+
+    /// Record a signature for an input.
+    ///
+    /// # Arguments
+    /// * `index` - The input signed.
+    /// * `pubkey` - The signing public key.
+    /// * `signature` - DER signature of [`signature_hash`](Self::signature_hash)
+    ///   with the [`SIGHASH_ALL`] byte appended.
+    ///
+    /// # Returns
+    /// An error if the key cannot sign the input or the signature is
+    /// malformed.
+    pub fn add_signature(
+        &mut self,
+        index: usize,
+        pubkey: &[u8],
+        signature: &[u8],
+    ) -> Result<&mut Self, PsdtError> {
+        if !self.can_sign(index, pubkey)? {
+            return Err(PsdtError::UnknownKey(index));
+        }
+        if !is_signature(signature) {
+            return Err(PsdtError::InvalidSignature(index));
+        }
+        self.inputs[index]
+            .signatures
+            .insert(pubkey.to_vec(), signature.to_vec());
+        Ok(self)
+    }
+
+    /// Sign an input with a WIF private key and record the signature.
+    ///
+    /// P2SH inputs need their redeem script set first.
+    ///
+    /// # Returns
+    /// An error if the key is invalid, for the other network, or cannot
+    /// sign the input.
+    pub fn sign_with_privkey(
+        &mut self,
+        index: usize,
+        privkey_wif: &str,
+    ) -> Result<&mut Self, PsdtError> {
+        let key =
+            SigningKey::from_wif(privkey_wif, self.is_testnet).ok_or(PsdtError::InvalidKey)?;
+        if !self.can_sign(index, &key.pubkey)? {
+            return Err(PsdtError::UnknownKey(index));
+        }
+        self.sign_input(index, &key)
+    }
+
+    /// Sign every input a WIF private key can sign, e.g. one cosigner's
+    /// share of a multisig spend.
+    ///
+    /// # Returns
+    /// The number of inputs signed, or an error if the key is invalid or an
+    /// input's script cannot be resolved (e.g. a P2SH input without its
+    /// redeem script).
+    pub fn sign_all(&mut self, privkey_wif: &str) -> Result<usize, PsdtError> {
+        let key =
+            SigningKey::from_wif(privkey_wif, self.is_testnet).ok_or(PsdtError::InvalidKey)?;
+        let mut signed = 0;
+        for index in 0..self.inputs.len() {
+            if self.can_sign(index, &key.pubkey)? {
+                self.sign_input(index, &key)?;
+                signed += 1;
+            }
+        }
+        Ok(signed)
+    }
+
+    fn sign_input(&mut self, index: usize, key: &SigningKey) -> Result<&mut Self, PsdtError> {
+        let signature = key
+            .sign(&self.signature_hash(index)?)
+            .ok_or(PsdtError::InvalidKey)?;
+        self.add_signature(index, &key.pubkey, &signature)
+    }
+
+    /// Merge the redeem scripts and signatures of a copy signed elsewhere.
+    pub fn combine(&mut self, other: &Psdt) -> Result<&mut Self, PsdtError> {
+        if self.tx != other.tx || self.is_testnet != other.is_testnet {
+            return Err(PsdtError::TransactionMismatch);
+        }
+        for (ours, theirs) in self.inputs.iter_mut().zip(&other.inputs) {
+            if ours.redeem_script.is_none() {
+                ours.redeem_script = theirs.redeem_script.clone();
+            }
+            for (pubkey, signature) in &theirs.signatures {
+                ours.signatures
+                    .entry(pubkey.clone())
+                    .or_insert_with(|| signature.clone());
+            }
+        }
+        Ok(self)
+    }
+
+    /// Whether every input has the signatures it needs.
+    pub fn is_complete(&self) -> bool {
+        (0..self.inputs.len()).all(|index| self.script_sig(index).is_ok())
+    }
+
+    /// Build every scriptSig.
+    ///
+    /// # Returns
+    /// The signed transaction hex, or an error naming the first input that
+    /// is not fully signed.
+    pub fn finalize(&self) -> Result<String, PsdtError> {
+        let mut tx = self.tx.clone();
+        for (index, input) in tx.inputs.iter_mut().enumerate() {
+            input.script_sig = self.script_sig(index)?;
+        }
+        Ok(tx.to_hex())
+    }
+
+    /// The script an input spends, after resolving P2SH.
+    fn spend(&self, index: usize) -> Result<OutputKind, PsdtError> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or(PsdtError::NoSuchInput(index))?;
+        let kind = match classify(&input.prevout.script_pubkey) {
+            Some(OutputKind::P2sh(_)) => {
+                let redeem = input
+                    .redeem_script
+                    .as_ref()
+                    .ok_or(PsdtError::MissingRedeemScript(index))?;
+                classify(redeem).filter(|k| !matches!(k, OutputKind::P2pkh(_)))
+            }
+            kind => kind,
+        };
+        match kind {
+            Some(
+                kind @ (OutputKind::P2pkh(_) | OutputKind::P2pk(_) | OutputKind::Multisig { .. }),
+            ) => Ok(kind),
+            _ => Err(PsdtError::UnsupportedScript(index)),
+        }
+    }
+
+    /// Whether `pubkey` is one of the keys an input's script names.
+    fn can_sign(&self, index: usize, pubkey: &[u8]) -> Result<bool, PsdtError> {
+        Ok(match self.spend(index)? {
+            OutputKind::P2pkh(hash) => is_pubkey(pubkey) && hash160(pubkey) == hash,
+            OutputKind::P2pk(key) => key == pubkey,
+            OutputKind::Multisig { pubkeys, .. } => pubkeys.iter().any(|k| k == pubkey),
+            _ => false,
+        })
+    }
+
+    fn script_sig(&self, index: usize) -> Result<Vec<u8>, PsdtError> {
+        let input = &self.inputs[index];
+        let incomplete = PsdtError::Incomplete(index);
+        let mut script = Vec::new();
+        match self.spend(index)? {
+            OutputKind::P2pkh(_) => {
+                let (pubkey, signature) = input.signatures.iter().next().ok_or(incomplete)?;
+                push_data(&mut script, signature);
+                push_data(&mut script, pubkey);
+            }
+            OutputKind::P2pk(pubkey) => {
+                push_data(
+                    &mut script,
+                    input.signatures.get(&pubkey).ok_or(incomplete)?,
+                );
+            }
+            OutputKind::Multisig { required, pubkeys } => {
+                // CHECKMULTISIG pops one extra item and needs signatures in
+                // key order.
+                script.push(OP_0);
+                let signatures: Vec<&Vec<u8>> = pubkeys
+                    .iter()
+                    .filter_map(|key| input.signatures.get(key))
+                    .take(required as usize)
+                    .collect();
+                if signatures.len() < required as usize {
+                    return Err(incomplete);
+                }
+                for signature in signatures {
+                    push_data(&mut script, signature);
+                }
+            }
+            _ => unreachable!("spend only returns signable kinds"),
+        }
+        if let Some(redeem) = &input.redeem_script {
+            push_data(&mut script, redeem);
+        }
+        Ok(script)
+    }
+
+    /// Serialize to the PSDT format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(if self.is_testnet { FLAG_TESTNET } else { 0 });
+        encode::write_var_bytes(&mut out, &self.tx.to_bytes());
+        for input in &self.inputs {
+            out.extend_from_slice(&input.prevout.value.to_le_bytes());
+            encode::write_var_bytes(&mut out, &input.prevout.script_pubkey);
+            encode::write_var_bytes(&mut out, input.redeem_script.as_deref().unwrap_or(&[]));
+            encode::write_compact_size(&mut out, input.signatures.len() as u64);
+            for (pubkey, signature) in &input.signatures {
+                encode::write_var_bytes(&mut out, pubkey);
+                encode::write_var_bytes(&mut out, signature);
+            }
+        }
+        let checksum = sha256d(&out);
+        out.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+        out
+    }
+
+    /// Parse a PSDT produced by [`Psdt::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PsdtError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(PsdtError::BadMagic);
+        }
+        if bytes.len() < MAGIC.len() + 2 + CHECKSUM_LEN {
+            return Err(DecodeError::UnexpectedEnd.into());
+        }
+        let (body, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if sha256d(body)[..CHECKSUM_LEN] != *sum {
+            return Err(PsdtError::ChecksumMismatch);
+        }
+        let version = body[MAGIC.len()];
+        if version != VERSION {
+            return Err(PsdtError::UnsupportedVersion(version));
+        }
+
+        let mut reader = Reader::new(&body[MAGIC.len() + 1..]);
+        let flags = reader.u8()?;
+        let tx = Transaction::from_bytes(reader.var_bytes()?)?;
+        let mut inputs = Vec::with_capacity(tx.inputs.len());
+        for _ in &tx.inputs {
+            let prevout = TxOut {
+                value: reader.u64()?,
+                script_pubkey: reader.var_bytes()?.to_vec(),
+            };
+            let redeem_script = Some(reader.var_bytes()?.to_vec()).filter(|s| !s.is_empty());
+            let mut signatures = BTreeMap::new();
+            for _ in 0..reader.count()? {
+                signatures.insert(reader.var_bytes()?.to_vec(), reader.var_bytes()?.to_vec());
+            }
+            inputs.push(PsdtInput {
+                prevout,
+                redeem_script,
+                signatures,
+            });
+        }
+        reader.finish()?;
+        Ok(Psdt {
+            is_testnet: flags & FLAG_TESTNET != 0,
+            tx,
+            inputs,
+        })
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.to_bytes())
+    }
+
+    pub fn from_hex(psdt: &str) -> Result<Self, PsdtError> {
+        Self::from_bytes(&hex::decode(psdt).ok_or(DecodeError::InvalidHex)?)
+    }
+}
+
+fn classify(script: &[u8]) -> Option<OutputKind> {
+    TemplateRegistry::new().classify(script)
+}
+
+/// Whether `signature` is shaped like a DER signature with `SIGHASH_ALL`.
+fn is_signature(signature: &[u8]) -> bool {
+    matches!(signature, [0x30, len, .., SIGHASH_ALL]
+        if (9..=73).contains(&signature.len()) && *len as usize == signature.len() - 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::{MultisigTemplate, P2shTemplate, ScriptTemplate};
+    use crate::transaction::TxIn;
+
+    fn key(n: u8) -> Vec<u8> {
+        let mut key = vec![0x02];
+        key.extend_from_slice(&[n; 32]);
+        key
+    }
+
+    fn signature(n: u8) -> Vec<u8> {
+        let mut sig = vec![0x30, 8];
+        sig.extend_from_slice(&[n; 8]);
+        sig.push(SIGHASH_ALL);
+        sig
+    }
+
+    /// A PSDT spending a 2-of-3 P2SH multisig output.
+    fn multisig() -> (Psdt, Vec<u8>) {
+        let redeem = MultisigTemplate
+            .build(&OutputKind::Multisig {
+                required: 2,
+                pubkeys: vec![key(1), key(2), key(3)],
+            })
+            .unwrap();
+        let utxo = Utxo {
+            outpoint: OutPoint {
+                txid: [7; 32],
+                vout: 0,
+            },
+            script_pubkey: P2shTemplate
+                .build(&OutputKind::P2sh(hash160(&redeem)))
+                .unwrap(),
+            amount: 100_000,
+            height: Some(1),
+        };
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: utxo.outpoint,
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![TxOut {
+                value: 90_000,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        };
+        (Psdt::new(false, &tx.to_hex(), &[utxo]).unwrap(), redeem)
+    }
+
+    #[test]
+    fn test_multisig_signing_flow() {
+        let (mut psdt, redeem) = multisig();
+        assert_eq!(
+            psdt.signature_hash(0),
+            Err(PsdtError::MissingRedeemScript(0))
+        );
+        assert_eq!(
+            psdt.set_redeem_script(0, &[0x51]).err(),
+            Some(PsdtError::RedeemScriptMismatch(0))
+        );
+        psdt.set_redeem_script(0, &redeem).unwrap();
+        assert!(psdt.signature_hash(0).is_ok());
+
+        let mut cosigner = psdt.clone();
+        psdt.add_signature(0, &key(3), &signature(3)).unwrap();
+        assert_eq!(
+            psdt.add_signature(0, &key(4), &signature(4)).err(),
+            Some(PsdtError::UnknownKey(0))
+        );
+        assert_eq!(
+            psdt.add_signature(0, &key(1), &[0x30]).err(),
+            Some(PsdtError::InvalidSignature(0))
+        );
+        assert!(!psdt.is_complete());
+        assert_eq!(psdt.finalize(), Err(PsdtError::Incomplete(0)));
+
+        cosigner.add_signature(0, &key(1), &signature(1)).unwrap();
+        psdt.combine(&cosigner).unwrap();
+        assert!(psdt.is_complete());
+
+        let signed = Transaction::from_hex(&psdt.finalize().unwrap()).unwrap();
+        let mut expected = vec![OP_0];
+        push_data(&mut expected, &signature(1));
+        push_data(&mut expected, &signature(3));
+        push_data(&mut expected, &redeem);
+        assert_eq!(signed.inputs[0].script_sig, expected);
+    }
+
+    #[test]
+    fn test_sign_p2sh_multisig_with_wif_keys() {
+        use crate::testing::{MAINNET_WIF, TESTNET_WIF};
+        const COSIGNER_WIF: &str = "QPQmT9kzbSxpLSwstsa2vdmRxndVPMccZuct3Cko3wFD6koynPPQ";
+
+        let pubkeys = [MAINNET_WIF, COSIGNER_WIF]
+            .map(|wif| SigningKey::from_wif(wif, false).unwrap().pubkey)
+            .to_vec();
+        let redeem = MultisigTemplate
+            .build(&OutputKind::Multisig {
+                required: 2,
+                pubkeys,
+            })
+            .unwrap();
+        let prevout = TxOut {
+            value: 100_000,
+            script_pubkey: P2shTemplate
+                .build(&OutputKind::P2sh(hash160(&redeem)))
+                .unwrap(),
+        };
+        let (unsigned, _) = multisig();
+        let utxo = Utxo {
+            outpoint: unsigned.transaction().inputs[0].previous_output,
+            script_pubkey: prevout.script_pubkey.clone(),
+            amount: prevout.value,
+            height: Some(1),
+        };
+        let mut psdt = Psdt::new(false, &unsigned.transaction().to_hex(), &[utxo]).unwrap();
+
+        assert_eq!(
+            psdt.sign_all(MAINNET_WIF),
+            Err(PsdtError::MissingRedeemScript(0))
+        );
+        psdt.set_redeem_script(0, &redeem).unwrap();
+        assert_eq!(psdt.sign_all(TESTNET_WIF), Err(PsdtError::InvalidKey));
+        assert_eq!(psdt.sign_all(MAINNET_WIF), Ok(1));
+        assert!(!psdt.is_complete());
+
+        psdt.sign_with_privkey(0, COSIGNER_WIF).unwrap();
+        let signed = Transaction::from_hex(&psdt.finalize().unwrap()).unwrap();
+        assert_eq!(crate::verify::verify_input(&signed, 0, &prevout), Ok(()));
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let (mut psdt, redeem) = multisig();
+        psdt.set_redeem_script(0, &redeem)
+            .unwrap()
+            .add_signature(0, &key(2), &signature(2))
+            .unwrap();
+        assert_eq!(Psdt::from_hex(&psdt.to_hex()), Ok(psdt.clone()));
+
+        let mut bytes = psdt.to_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert_eq!(Psdt::from_bytes(&bytes), Err(PsdtError::ChecksumMismatch));
+    }
+}
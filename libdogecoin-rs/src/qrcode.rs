@@ -1,12 +1,35 @@
-//! QR Code generation for Dogecoin addresses.
+//! QR Code generation for Dogecoin addresses and arbitrary payloads.
 //!
-//! This module provides functionality to generate QR codes for addresses
-//! in various formats including console output, PNG, and JPEG files.
+//! This module provides functionality to generate QR codes for addresses,
+//! payment URIs, signed messages, extended public keys or any other string in
+//! various formats including console output, PNG, JPEG and SVG, and to
+//! decode them back into text. Payloads that exceed single-QR capacity can be
+//! split into a numbered sequence of codes and reassembled with [`QrAssembler`].
+//!
+//! The sequence framing (`DQ<index>/<total>:<checksum>:<chunk>`) is this
+//! crate's own text format, not QR structured append; other wallets will
+//! read each part as plain text.
+
+use crate::error::{Error, Result};
+use crate::ffi::{string_from_buf, sys, to_cstring};
+use std::collections::hash_map::RandomState;
+use std::fmt::Write as _;
+use std::fs::DirBuilder;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::sys;
-use std::ffi::CString;
+/// Prefix identifying a part of a multi-part QR sequence.
+const PART_PREFIX: &str = "DQ";
+/// Most parts in one sequence, which bounds what a scanned part can make
+/// [`QrAssembler`] allocate.
+pub const MAX_PARTS: usize = 999;
+/// Bit buffer size; large enough for a version 40 symbol (177x177 modules).
+const MAX_QR_SIZE: usize = 256 * 256;
+/// Light modules drawn around the symbol in rendered images.
+const QUIET_ZONE: i32 = 4;
 
-/// QR Code generator for Dogecoin addresses.
+/// QR Code generator for Dogecoin addresses and arbitrary payloads.
 ///
 /// # Example
 /// ```no_run
@@ -16,12 +39,15 @@ use std::ffi::CString;
 /// QrCode::print_console("DAddress");
 ///
 /// // Generate QR code as a string
-/// if let Some(qr_string) = QrCode::to_string("DAddress") {
+/// if let Ok(qr_string) = QrCode::to_string("DAddress") {
 ///     println!("{}", qr_string);
 /// }
 ///
 /// // Save QR code as PNG
-/// QrCode::to_png("DAddress", "address_qr.png", 4);
+/// QrCode::to_png("DAddress", "address_qr.png", 4).unwrap();
+///
+/// // Any payload works, e.g. a payment URI rendered as SVG
+/// let svg = QrCode::to_svg("dogecoin:DAddress?amount=10", 8).unwrap();
 /// ```
 pub struct QrCode;
 
@@ -29,131 +55,471 @@ impl QrCode {
     /// Generate a QR code as a text string with line breaks.
     ///
     /// # Arguments
-    /// * `address` - The Dogecoin address to encode.
+    /// * `data` - The string to encode (address, URI, message, xpub, ...).
     ///
     /// # Returns
     /// A string representation of the QR code.
-    pub fn to_string(address: &str) -> Option<String> {
-        // The QR string can be quite large, allocate enough space
-        const QR_STRING_SIZE: usize = 4096;
-        let mut out_string = vec![0u8; QR_STRING_SIZE];
-        let addr_cstr = CString::new(address).ok()?;
+    pub fn to_string(data: &str) -> Result<String> {
+        // libdogecoin writes the whole rendering into the caller's buffer, so size
+        // it from the symbol: up to 8 bytes per module plus a newline per row.
+        let (size, _) = Self::to_bits(data)?;
+        let size = size as usize;
+        let mut out_string = vec![0u8; size * (size * 8 + 2) + 64];
+        let data_cstr = to_cstring(data)?;
 
         let result = unsafe {
-            sys::qrgen_p2pkh_to_qr_string(addr_cstr.as_ptr(), out_string.as_mut_ptr() as *mut i8)
+            sys::qrgen_p2pkh_to_qr_string(data_cstr.as_ptr(), out_string.as_mut_ptr().cast())
         };
 
         if result <= 0 {
-            return None;
+            return Err(Error::Ffi {
+                function: "qrgen_p2pkh_to_qr_string",
+                code: result,
+            });
         }
 
-        // Find the null terminator
-        let len = out_string
-            .iter()
-            .position(|&x| x == 0)
-            .unwrap_or(out_string.len());
-        let qr_str = String::from_utf8_lossy(&out_string[..len]).into_owned();
-        Some(qr_str)
+        string_from_buf(&out_string)
     }
 
-    /// Print a QR code for an address to the console.
+    /// Print a QR code for a string to the console.
     ///
     /// # Arguments
-    /// * `address` - The Dogecoin address to encode.
-    pub fn print_console(address: &str) {
-        if let Ok(addr_cstr) = CString::new(address) {
-            unsafe {
-                sys::qrgen_p2pkh_consoleprint_to_qr(addr_cstr.as_ptr() as *mut i8);
-            }
+    /// * `data` - The string to encode.
+    pub fn print_console(data: &str) -> Result<()> {
+        let data_cstr = to_cstring(data)?;
+        unsafe {
+            sys::qrgen_p2pkh_consoleprint_to_qr(data_cstr.as_ptr());
         }
+        Ok(())
     }
 
     /// Generate a QR code and save as a PNG file.
     ///
     /// # Arguments
-    /// * `address` - The Dogecoin address to encode.
+    /// * `data` - The string to encode.
     /// * `filename` - The output filename.
     /// * `size_multiplier` - Size multiplier for the QR code image.
     ///
-    /// # Returns
-    /// `true` if the file was created successfully.
-    pub fn to_png(address: &str, filename: &str, size_multiplier: u8) -> bool {
-        let addr_cstr = match CString::new(address) {
-            Ok(s) => s,
-            Err(_) => return false,
-        };
-        let filename_cstr = match CString::new(filename) {
-            Ok(s) => s,
-            Err(_) => return false,
-        };
+    pub fn to_png(data: &str, filename: &str, size_multiplier: u8) -> Result<()> {
+        let data_cstr = to_cstring(data)?;
+        let filename_cstr = to_cstring(filename)?;
 
         let result = unsafe {
             sys::qrgen_string_to_qr_pngfile(
                 filename_cstr.as_ptr(),
-                addr_cstr.as_ptr(),
+                data_cstr.as_ptr(),
                 size_multiplier,
             )
         };
 
-        result == 1
+        if result != 1 {
+            return Err(Error::Ffi {
+                function: "qrgen_string_to_qr_pngfile",
+                code: result,
+            });
+        }
+        Ok(())
     }
 
-    /// Generate a QR code and save as a JPEG file.
+    /// Generate a QR code as in-memory PNG data.
+    ///
+    /// libdogecoin only writes images to files, so this renders into a
+    /// temporary directory only the current user can enter and reads the
+    /// file back. libdogecoin opens the path with a plain `fopen`, which
+    /// would follow a symlink planted at a predictable name in a shared
+    /// temporary directory.
     ///
     /// # Arguments
-    /// * `address` - The Dogecoin address to encode.
-    /// * `filename` - The output filename.
+    /// * `data` - The string to encode.
     /// * `size_multiplier` - Size multiplier for the QR code image.
     ///
     /// # Returns
-    /// `true` if the file was created successfully.
-    pub fn to_jpeg(address: &str, filename: &str, size_multiplier: u8) -> bool {
-        let addr_cstr = match CString::new(address) {
-            Ok(s) => s,
-            Err(_) => return false,
-        };
-        let filename_cstr = match CString::new(filename) {
-            Ok(s) => s,
-            Err(_) => return false,
+    /// The PNG file contents.
+    pub fn to_png_bytes(data: &str, size_multiplier: u8) -> Result<Vec<u8>> {
+        let dir = private_temp_dir()?;
+        let path = dir.join("qr.png");
+        let bytes = match path.to_str() {
+            Some(filename) => Self::to_png(data, filename, size_multiplier)
+                .and_then(|()| std::fs::read(&path).map_err(Error::from)),
+            None => Err(Error::Io("temporary directory path is not UTF-8".into())),
         };
+        let _ = std::fs::remove_dir_all(&dir);
+        bytes
+    }
+
+    /// Generate a QR code and save as a JPEG file.
+    ///
+    /// # Arguments
+    /// * `data` - The string to encode.
+    /// * `filename` - The output filename.
+    /// * `size_multiplier` - Size multiplier for the QR code image.
+    ///
+    pub fn to_jpeg(data: &str, filename: &str, size_multiplier: u8) -> Result<()> {
+        let data_cstr = to_cstring(data)?;
+        let filename_cstr = to_cstring(filename)?;
 
         let result = unsafe {
             sys::qrgen_string_to_qr_jpgfile(
                 filename_cstr.as_ptr(),
-                addr_cstr.as_ptr(),
+                data_cstr.as_ptr(),
                 size_multiplier,
             )
         };
 
-        result == 1
+        if result != 1 {
+            return Err(Error::Ffi {
+                function: "qrgen_string_to_qr_jpgfile",
+                code: result,
+            });
+        }
+        Ok(())
+    }
+
+    /// Generate a QR code as an SVG document.
+    ///
+    /// # Arguments
+    /// * `data` - The string to encode.
+    /// * `module_size` - Width/height of one module in SVG user units.
+    ///
+    /// # Returns
+    /// The SVG markup, including a light quiet zone around the symbol.
+    pub fn to_svg(data: &str, module_size: u32) -> Result<String> {
+        let (size, bits) = Self::to_bits(data)?;
+        Ok(render_svg(size, &bits, module_size.max(1)))
     }
 
     /// Get the raw QR code bits as an array.
     ///
     /// # Arguments
-    /// * `address` - The Dogecoin address to encode.
+    /// * `data` - The string to encode.
     ///
     /// # Returns
     /// A tuple of (size, data) where size is the width/height of the QR code
     /// and data contains the QR code bits.
-    pub fn to_bits(address: &str) -> Option<(i32, Vec<u8>)> {
-        // Maximum QR code size estimate
-        const MAX_QR_SIZE: usize = 256 * 256;
+    pub fn to_bits(data: &str) -> Result<(i32, Vec<u8>)> {
         let mut bits = vec![0u8; MAX_QR_SIZE];
-        let addr_cstr = CString::new(address).ok()?;
+        let data_cstr = to_cstring(data)?;
 
-        let size = unsafe { sys::qrgen_p2pkh_to_qrbits(addr_cstr.as_ptr(), bits.as_mut_ptr()) };
+        // Despite its name, the p2pkh variant encodes any text payload.
+        let size = unsafe { sys::qrgen_p2pkh_to_qrbits(data_cstr.as_ptr(), bits.as_mut_ptr()) };
 
         if size <= 0 {
-            return None;
+            return Err(Error::QrCapacity);
         }
 
         // Truncate to actual size
         let total_bytes = (size * size) as usize;
         bits.truncate(total_bytes);
 
-        Some((size, bits))
+        Ok((size, bits))
+    }
+
+    /// Split a large payload into a sequence of QR-sized parts.
+    ///
+    /// Each part is framed as `DQ<index>/<total>:<checksum>:<chunk>` so that
+    /// parts can be scanned in any order and reassembled with [`QrAssembler`].
+    /// The framing is specific to this crate. Chunks are split on UTF-8
+    /// character boundaries.
+    ///
+    /// # Arguments
+    /// * `payload` - The data to transfer (e.g. a serialized transaction).
+    /// * `max_chunk_len` - Maximum payload bytes per part, excluding the frame header.
+    ///
+    /// # Returns
+    /// The framed part strings, [`Error::ChunkTooSmall`] if
+    /// `max_chunk_len` cannot hold a single character, or
+    /// [`Error::TooManyParts`] if the payload needs more than [`MAX_PARTS`].
+    pub fn split_payload(payload: &str, max_chunk_len: usize) -> Result<Vec<String>> {
+        let mut chunks = Vec::new();
+        let mut rest = payload;
+        while !rest.is_empty() {
+            let mut end = rest.len().min(max_chunk_len);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            if end == 0 {
+                return Err(Error::ChunkTooSmall(max_chunk_len));
+            }
+            chunks.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        if chunks.is_empty() {
+            chunks.push("");
+        }
+
+        let total = chunks.len();
+        if total > MAX_PARTS {
+            return Err(Error::TooManyParts(total));
+        }
+
+        let checksum = payload_checksum(payload);
+        Ok(chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| format!("{PART_PREFIX}{}/{total}:{checksum:08x}:{chunk}", i + 1))
+            .collect())
+    }
+
+    /// Split a large payload and generate the QR code bits for every part.
+    ///
+    /// # Arguments
+    /// * `payload` - The data to transfer.
+    /// * `max_chunk_len` - Maximum payload bytes per part.
+    ///
+    /// # Returns
+    /// One `(size, data)` tuple per part, in sequence order.
+    pub fn split_to_bits(payload: &str, max_chunk_len: usize) -> Result<Vec<(i32, Vec<u8>)>> {
+        Self::split_payload(payload, max_chunk_len)?
+            .iter()
+            .map(|part| Self::to_bits(part))
+            .collect()
+    }
+
+    /// Decode QR code bits back into the encoded string.
+    ///
+    /// # Arguments
+    /// * `size` - The width/height of the QR code in modules.
+    /// * `data` - Row-major module data (non-zero for dark), as returned by [`QrCode::to_bits`].
+    ///
+    /// # Returns
+    /// The encoded address or URI.
+    pub fn decode_bits(size: i32, data: &[u8]) -> Result<String> {
+        let size = usize::try_from(size).map_err(|_| Error::QrNotFound)?;
+        crate::qrdecode::decode(size, data).ok_or(Error::QrNotFound)
+    }
+
+    /// Decode a QR code from a PNG or JPEG image file.
+    ///
+    /// The code must be upright and undistorted (e.g. a screenshot or a file
+    /// written by [`QrCode::to_png`]); camera photos need prior rectification.
+    ///
+    /// # Arguments
+    /// * `filename` - The image file to read.
+    ///
+    /// # Returns
+    /// The encoded address or URI.
+    #[cfg(feature = "image")]
+    pub fn decode_image(filename: &str) -> Result<String> {
+        let img = image::open(filename).map_err(|e| Error::Image(e.to_string()))?;
+        Self::decode_luma(&img.to_luma8())
+    }
+
+    /// Decode a QR code from in-memory PNG or JPEG image data.
+    ///
+    /// See [`QrCode::decode_image`] for the supported image layouts.
+    #[cfg(feature = "image")]
+    pub fn decode_image_bytes(bytes: &[u8]) -> Result<String> {
+        let img = image::load_from_memory(bytes).map_err(|e| Error::Image(e.to_string()))?;
+        Self::decode_luma(&img.to_luma8())
+    }
+
+    /// Sample the module grid from a greyscale image and decode it.
+    #[cfg(feature = "image")]
+    fn decode_luma(img: &image::GrayImage) -> Result<String> {
+        let (min, max) = img.pixels().fold((u8::MAX, u8::MIN), |(lo, hi), p| {
+            (lo.min(p.0[0]), hi.max(p.0[0]))
+        });
+        if max <= min {
+            return Err(Error::QrNotFound);
+        }
+        let threshold = min + (max - min) / 2;
+        let dark = |x: u32, y: u32| img.get_pixel(x, y).0[0] < threshold;
+
+        // Bounding box of all dark pixels (the quiet zone is expected to be light).
+        let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+        for (x, y, p) in img.enumerate_pixels() {
+            if p.0[0] < threshold {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x);
+                bottom = bottom.max(y);
+            }
+        }
+        if left > right || top > bottom {
+            return Err(Error::QrNotFound);
+        }
+
+        // The top-left finder pattern starts with a 7-module dark run.
+        let run = (left..=right).take_while(|&x| dark(x, top)).count() as f64;
+        let module = run / 7.0;
+        if module < 1.0 {
+            return Err(Error::QrNotFound);
+        }
+        let width = f64::from(right - left + 1);
+        let estimated = (width / module).round() as i32;
+        // Snap to the nearest valid symbol size (17 + 4 * version).
+        let size = ((estimated - 17 + 2).div_euclid(4) * 4 + 17).clamp(21, 177);
+        let module = width / f64::from(size);
+
+        let mut bits = Vec::with_capacity((size * size) as usize);
+        for row in 0..size {
+            for col in 0..size {
+                let x = left + ((f64::from(col) + 0.5) * module) as u32;
+                let y = top + ((f64::from(row) + 0.5) * module) as u32;
+                bits.push(u8::from(dark(x.min(right), y.min(bottom))));
+            }
+        }
+
+        Self::decode_bits(size, &bits)
+    }
+}
+
+/// Reassembles a payload from the parts produced by [`QrCode::split_payload`].
+///
+/// # Example
+/// ```
+/// use libdogecoin_rs::qrcode::{QrAssembler, QrCode};
+///
+/// let parts = QrCode::split_payload("a long payload", 4).unwrap();
+/// let mut assembler = QrAssembler::new();
+/// for part in parts.iter().rev() {
+///     assembler.add_part(part).unwrap();
+/// }
+/// assert_eq!(assembler.finish().unwrap(), "a long payload");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QrAssembler {
+    checksum: Option<u32>,
+    parts: Vec<Option<String>>,
+}
+
+impl QrAssembler {
+    /// Create an empty assembler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a scanned part.
+    ///
+    /// Duplicates are accepted and ignored.
+    ///
+    /// # Returns
+    /// [`Error::MalformedPart`] or [`Error::ForeignPart`] if the part was
+    /// rejected.
+    pub fn add_part(&mut self, part: &str) -> Result<()> {
+        let (index, total, checksum, chunk) = parse_part(part).ok_or(Error::MalformedPart)?;
+
+        match self.checksum {
+            Some(expected) if expected != checksum || self.parts.len() != total => {
+                return Err(Error::ForeignPart)
+            }
+            Some(_) => {}
+            None => {
+                self.checksum = Some(checksum);
+                self.parts = vec![None; total];
+            }
+        }
+
+        self.parts[index - 1].get_or_insert_with(|| chunk.to_string());
+        Ok(())
+    }
+
+    /// Number of distinct parts received so far.
+    pub fn received(&self) -> usize {
+        self.parts.iter().filter(|p| p.is_some()).count()
+    }
+
+    /// Total number of parts in the sequence, once the first part is known.
+    pub fn total(&self) -> Option<usize> {
+        self.checksum.map(|_| self.parts.len())
+    }
+
+    /// Check whether every part has been received.
+    pub fn is_complete(&self) -> bool {
+        self.checksum.is_some() && self.parts.iter().all(|p| p.is_some())
+    }
+
+    /// Reassemble the payload.
+    ///
+    /// # Returns
+    /// The original payload, or an error if parts are missing or the
+    /// reassembled data fails the checksum.
+    pub fn finish(&self) -> Result<String> {
+        let Some(checksum) = self.checksum.filter(|_| self.is_complete()) else {
+            return Err(Error::IncompleteSequence {
+                received: self.received(),
+                total: self.parts.len(),
+            });
+        };
+        let payload: String = self.parts.iter().flatten().map(String::as_str).collect();
+        if payload_checksum(&payload) != checksum {
+            return Err(Error::ChecksumMismatch);
+        }
+        Ok(payload)
+    }
+}
+
+/// Create a new directory under the system temporary directory that only the
+/// current user can enter (mode 0700 on Unix).
+///
+/// The name is random and creation fails rather than reuse an existing entry,
+/// so nothing another user prepared can be written through.
+fn private_temp_dir() -> Result<PathBuf> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    for _ in 0..8 {
+        let nonce = RandomState::new().build_hasher().finish();
+        let dir = std::env::temp_dir().join(format!(
+            "libdogecoin-rs-qr-{}-{}-{nonce:016x}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(Error::Io(
+        "could not create a private temporary directory".into(),
+    ))
+}
+
+/// Render module bits as an SVG document with one path for all dark modules.
+fn render_svg(size: i32, bits: &[u8], module_size: u32) -> String {
+    let dim = (size + 2 * QUIET_ZONE) as u32 * module_size;
+    let mut path = String::new();
+    for y in 0..size {
+        for x in 0..size {
+            if bits[(y * size + x) as usize] != 0 {
+                let _ = write!(path, "M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE);
+            }
+        }
+    }
+
+    let view = size + 2 * QUIET_ZONE;
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" \
+         width=\"{dim}\" height=\"{dim}\" viewBox=\"0 0 {view} {view}\" \
+         shape-rendering=\"crispEdges\">\
+         <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\
+         <path d=\"{path}\" fill=\"#000000\"/></svg>\n"
+    )
+}
+
+/// Parse a `DQ<index>/<total>:<checksum>:<chunk>` frame.
+fn parse_part(part: &str) -> Option<(usize, usize, u32, &str)> {
+    let rest = part.strip_prefix(PART_PREFIX)?;
+    let mut fields = rest.splitn(3, ':');
+    let (index, total) = fields.next()?.split_once('/')?;
+    let index: usize = index.parse().ok()?;
+    let total: usize = total.parse().ok()?;
+    let checksum = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let chunk = fields.next()?;
+
+    if total == 0 || total > MAX_PARTS || index == 0 || index > total {
+        return None;
     }
+    Some((index, total, checksum, chunk))
+}
+
+/// FNV-1a checksum used to bind parts of one sequence together.
+fn payload_checksum(payload: &str) -> u32 {
+    payload.bytes().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    })
 }
 
 #[cfg(test)]
@@ -167,11 +533,141 @@ mod tests {
         // QR generation may fail depending on libdogecoin version/config
         // Just test that the function doesn't crash
         let qr = QrCode::to_string(wallet.address());
-        if let Some(qr_str) = qr {
-            assert!(!qr_str.is_empty());
-            println!("QR Code generated successfully");
-        } else {
-            println!("QR generation returned None (may be expected)");
+        match qr {
+            Ok(qr_str) => {
+                assert!(!qr_str.is_empty());
+                println!("QR Code generated successfully");
+            }
+            Err(e) => println!("QR generation failed (may be expected): {e}"),
         }
     }
+
+    #[test]
+    fn test_qr_bits_roundtrip() {
+        let wallet = DogeWallet::new(false).unwrap();
+        let (size, bits) = QrCode::to_bits(wallet.address()).unwrap();
+        let decoded = QrCode::decode_bits(size, &bits);
+        assert_eq!(decoded.as_deref(), Ok(wallet.address()));
+    }
+
+    #[test]
+    fn test_decode_bits_rejects_bad_size() {
+        assert_eq!(QrCode::decode_bits(-1, &[]), Err(Error::QrNotFound));
+        assert_eq!(
+            QrCode::decode_bits(22, &[0u8; 22 * 22]),
+            Err(Error::QrNotFound)
+        );
+    }
+
+    #[test]
+    fn test_render_svg() {
+        // 21x21 matrix with only the top-left module dark.
+        let mut bits = vec![0u8; 21 * 21];
+        bits[0] = 1;
+        let svg = render_svg(21, &bits, 3);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"87\""));
+        assert!(svg.contains("viewBox=\"0 0 29 29\""));
+        assert!(svg.contains("M4,4h1v1h-1z"));
+        assert_eq!(svg.matches('M').count(), 1);
+    }
+
+    #[test]
+    fn test_qr_arbitrary_payload() {
+        let uri = "dogecoin:DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs?amount=42&message=Thanks%20for%20the%20pizza";
+        let (size, bits) = QrCode::to_bits(uri).unwrap();
+        assert_eq!(QrCode::decode_bits(size, &bits).as_deref(), Ok(uri));
+        assert!(QrCode::to_svg(uri, 4).is_ok());
+    }
+
+    #[test]
+    fn test_split_and_reassemble_out_of_order() {
+        let payload = "01000000".repeat(100) + "ünïcödé";
+        let parts = QrCode::split_payload(&payload, 64).unwrap();
+        assert!(parts.len() > 10);
+        assert!(parts[0].starts_with("DQ1/"));
+
+        let mut assembler = QrAssembler::new();
+        for part in parts.iter().rev().chain(parts.iter()) {
+            assert_eq!(assembler.add_part(part), Ok(()));
+        }
+        assert!(assembler.is_complete());
+        assert_eq!(assembler.received(), parts.len());
+        assert_eq!(assembler.finish(), Ok(payload));
+    }
+
+    #[test]
+    fn test_assembler_rejects_foreign_and_malformed_parts() {
+        let first = QrCode::split_payload("first payload", 5).unwrap();
+        let second = QrCode::split_payload("second payload", 5).unwrap();
+
+        let mut assembler = QrAssembler::new();
+        assert_eq!(assembler.add_part("not a part"), Err(Error::MalformedPart));
+        assert_eq!(
+            assembler.add_part("DQ0/3:00000000:x"),
+            Err(Error::MalformedPart)
+        );
+        // A scanned total must not size the part table unchecked.
+        for huge in ["DQ1/99999999999:00000000:x", "DQ1/1000:00000000:x"] {
+            assert_eq!(assembler.add_part(huge), Err(Error::MalformedPart));
+        }
+        assert_eq!(assembler.total(), None);
+        assert_eq!(assembler.add_part(&first[0]), Ok(()));
+        assert_eq!(assembler.add_part(&second[1]), Err(Error::ForeignPart));
+        assert_eq!(assembler.total(), Some(first.len()));
+        assert!(!assembler.is_complete());
+        assert_eq!(
+            assembler.finish(),
+            Err(Error::IncompleteSequence {
+                received: 1,
+                total: first.len()
+            })
+        );
+    }
+
+    #[test]
+    fn test_private_temp_dir() {
+        let first = private_temp_dir().unwrap();
+        let second = private_temp_dir().unwrap();
+        assert_ne!(first, second);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        std::fs::remove_dir(&first).unwrap();
+        std::fs::remove_dir(&second).unwrap();
+    }
+
+    #[test]
+    fn test_split_payload_tiny_chunk() {
+        assert_eq!(QrCode::split_payload("ü", 1), Err(Error::ChunkTooSmall(1)));
+        assert_eq!(
+            QrCode::split_payload(&"a".repeat(MAX_PARTS + 1), 1),
+            Err(Error::TooManyParts(MAX_PARTS + 1))
+        );
+        assert_eq!(QrCode::split_payload("", 10).unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_decode_image_bytes() {
+        use qrcodegen::{QrCode as Encoder, QrCodeEcc};
+
+        let text = "dogecoin:DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs?amount=1";
+        let qr = Encoder::encode_text(text, QrCodeEcc::Medium).unwrap();
+        let (scale, border) = (5, 4);
+        let side = ((qr.size() + 2 * border) * scale) as u32;
+        let img = image::GrayImage::from_fn(side, side, |x, y| {
+            let mx = x as i32 / scale - border;
+            let my = y as i32 / scale - border;
+            image::Luma([if qr.get_module(mx, my) { 0 } else { 255 }])
+        });
+
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert_eq!(QrCode::decode_image_bytes(&png).as_deref(), Ok(text));
+    }
 }
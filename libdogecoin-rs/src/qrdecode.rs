@@ -0,0 +1,666 @@
+//! QR Code symbol decoding.
+//!
+//! Decodes a module matrix (as produced by libdogecoin's `qrgen_*` functions)
+//! back into its text payload. Supports versions 1-40, all error correction
+//! levels, and numeric/alphanumeric/byte segments. Byte segments are expected
+//! to carry UTF-8 text. Reed-Solomon error correction is applied per block, so
+//! matrices sampled from slightly damaged images can still be decoded.
+
+/// Error correction codewords per block, indexed by `[ecl][version]`
+/// with `ecl` ordered Low, Medium, Quartile, High.
+const ECC_CODEWORDS_PER_BLOCK: [[u8; 41]; 4] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28,
+        30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30,
+        30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+/// Number of error correction blocks, indexed by `[ecl][version]`.
+const NUM_ERROR_CORRECTION_BLOCKS: [[u8; 41]; 4] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32,
+        35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+const ALPHANUMERIC_CHARSET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Decode a square QR module matrix into its text payload.
+///
+/// `modules` is row-major, `size * size` entries, non-zero meaning dark.
+pub(crate) fn decode(size: usize, modules: &[u8]) -> Option<String> {
+    if !(21..=177).contains(&size) || !(size - 17).is_multiple_of(4) || modules.len() < size * size
+    {
+        return None;
+    }
+    let version = (size - 17) / 4;
+    let grid = Grid { size, modules };
+
+    let (ecl, mask) = grid.read_format()?;
+    let function = function_modules(version, size);
+    let raw = grid.read_codewords(&function, mask, num_raw_data_modules(version) / 8);
+    let data = deinterleave(&raw, version, ecl)?;
+    parse_segments(&data, version)
+}
+
+struct Grid<'a> {
+    size: usize,
+    modules: &'a [u8],
+}
+
+impl Grid<'_> {
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x] != 0
+    }
+
+    /// Read both copies of the format information and return `(ecl, mask)`
+    /// for the closest valid codeword.
+    fn read_format(&self) -> Option<(usize, u8)> {
+        let size = self.size;
+        let mut first = 0u32;
+        let mut second = 0u32;
+
+        let bit = |word: &mut u32, i: u32, x: usize, y: usize| {
+            if self.get(x, y) {
+                *word |= 1 << i;
+            }
+        };
+
+        for i in 0..6 {
+            bit(&mut first, i, 8, i as usize);
+        }
+        bit(&mut first, 6, 8, 7);
+        bit(&mut first, 7, 8, 8);
+        bit(&mut first, 8, 7, 8);
+        for i in 9..15 {
+            bit(&mut first, i, 14 - i as usize, 8);
+        }
+
+        for i in 0..8 {
+            bit(&mut second, i, size - 1 - i as usize, 8);
+        }
+        for i in 8..15 {
+            bit(&mut second, i, 8, size - 15 + i as usize);
+        }
+
+        let mut best: Option<(u32, u32)> = None;
+        for data in 0..32u32 {
+            let codeword = format_codeword(data);
+            let distance = (codeword ^ first)
+                .count_ones()
+                .min((codeword ^ second).count_ones());
+            if best.is_none_or(|(d, _)| distance < d) {
+                best = Some((distance, data));
+            }
+        }
+
+        let (distance, data) = best?;
+        if distance > 3 {
+            return None;
+        }
+
+        // Format bits encode the level as L=1, M=0, Q=3, H=2.
+        let ecl = match data >> 3 {
+            1 => 0,
+            0 => 1,
+            3 => 2,
+            _ => 3,
+        };
+        Some((ecl, (data & 7) as u8))
+    }
+
+    /// Read the codeword bytes in the standard zig-zag order, removing the mask.
+    fn read_codewords(&self, function: &[bool], mask: u8, count: usize) -> Vec<u8> {
+        let size = self.size;
+        let mut out = vec![0u8; count];
+        let mut i = 0usize;
+
+        let mut right = size as isize - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right as usize - j;
+                    let upward = ((right + 1) & 2) == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if function[y * size + x] || i >= count * 8 {
+                        continue;
+                    }
+                    if self.get(x, y) ^ mask_bit(mask, x, y) {
+                        out[i >> 3] |= 1 << (7 - (i & 7));
+                    }
+                    i += 1;
+                }
+            }
+            right -= 2;
+        }
+
+        out
+    }
+}
+
+fn format_codeword(data: u32) -> u32 {
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    ((data << 10) | rem) ^ 0x5412
+}
+
+fn mask_bit(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+    }
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    // The last centre sits 7 modules from the far edge, the rest `step`
+    // apart before it.
+    let last = version * 4 + 10;
+    let mut result = vec![6; count];
+    for (i, slot) in result.iter_mut().skip(1).rev().enumerate() {
+        *slot = last - i * step;
+    }
+    result
+}
+
+/// Mark every module that belongs to a function pattern (finders, timing,
+/// alignment, format and version information).
+fn function_modules(version: usize, size: usize) -> Vec<bool> {
+    let mut function = vec![false; size * size];
+    let mut fill = |x0: usize, y0: usize, w: usize, h: usize| {
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                function[y * size + x] = true;
+            }
+        }
+    };
+
+    // Finder patterns, separators and format information.
+    fill(0, 0, 9, 9);
+    fill(size - 8, 0, 8, 9);
+    fill(0, size - 8, 9, 8);
+
+    // Timing patterns.
+    fill(6, 0, 1, size);
+    fill(0, 6, size, 1);
+
+    let positions = alignment_positions(version);
+    let last = positions.len().saturating_sub(1);
+    for (i, &ax) in positions.iter().enumerate() {
+        for (j, &ay) in positions.iter().enumerate() {
+            let corner = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+            if !corner {
+                fill(ax - 2, ay - 2, 5, 5);
+            }
+        }
+    }
+
+    if version >= 7 {
+        fill(size - 11, 0, 3, 6);
+        fill(0, size - 11, 6, 3);
+    }
+
+    function
+}
+
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let count = version / 7 + 2;
+        result -= (25 * count - 10) * count - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+/// Split the interleaved codewords into blocks, error-correct each block and
+/// return the concatenated data codewords.
+fn deinterleave(raw: &[u8], version: usize, ecl: usize) -> Option<Vec<u8>> {
+    let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[ecl][version] as usize;
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[ecl][version] as usize;
+    let num_short = num_blocks - raw.len() % num_blocks;
+    let short_data_len = raw.len() / num_blocks - ecc_len;
+    let data_len = raw.len() - ecc_len * num_blocks;
+
+    let mut result = Vec::with_capacity(data_len);
+    for i in 0..num_blocks {
+        let block_data_len = short_data_len + usize::from(i >= num_short);
+        let mut block = Vec::with_capacity(block_data_len + ecc_len);
+
+        let mut k = i;
+        for j in 0..block_data_len {
+            if j == short_data_len {
+                k -= num_short;
+            }
+            block.push(raw[k]);
+            k += num_blocks;
+        }
+        for j in 0..ecc_len {
+            block.push(raw[data_len + i + j * num_blocks]);
+        }
+
+        reed_solomon::correct(&mut block, ecc_len)?;
+        result.extend_from_slice(&block[..block_data_len]);
+    }
+
+    Some(result)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    fn read(&mut self, bits: usize) -> Option<u32> {
+        if bits > self.remaining() {
+            return None;
+        }
+        let mut value = 0u32;
+        for _ in 0..bits {
+            let bit = (self.data[self.pos >> 3] >> (7 - (self.pos & 7))) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.pos += 1;
+        }
+        Some(value)
+    }
+}
+
+fn parse_segments(data: &[u8], version: usize) -> Option<String> {
+    let group = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let mut reader = BitReader { data, pos: 0 };
+    let mut bytes = Vec::new();
+
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0b0000 => break,
+            0b0001 => {
+                let count = reader.read([10, 12, 14][group])? as usize;
+                let mut left = count;
+                while left > 0 {
+                    let digits = left.min(3);
+                    let value = reader.read([0, 4, 7, 10][digits])?;
+                    let text = format!("{:0width$}", value, width = digits);
+                    if text.len() != digits {
+                        return None;
+                    }
+                    bytes.extend_from_slice(text.as_bytes());
+                    left -= digits;
+                }
+            }
+            0b0010 => {
+                let count = reader.read([9, 11, 13][group])? as usize;
+                let mut left = count;
+                while left >= 2 {
+                    let value = reader.read(11)? as usize;
+                    bytes.push(*ALPHANUMERIC_CHARSET.get(value / 45)?);
+                    bytes.push(*ALPHANUMERIC_CHARSET.get(value % 45)?);
+                    left -= 2;
+                }
+                if left == 1 {
+                    let value = reader.read(6)? as usize;
+                    bytes.push(*ALPHANUMERIC_CHARSET.get(value)?);
+                }
+            }
+            0b0100 => {
+                let count = reader.read([8, 16, 16][group])? as usize;
+                for _ in 0..count {
+                    bytes.push(reader.read(8)? as u8);
+                }
+            }
+            0b0111 => {
+                // ECI designator: payload is still interpreted as UTF-8.
+                let first = reader.read(8)?;
+                if first & 0x80 != 0 {
+                    let extra = if first & 0x40 == 0 { 8 } else { 16 };
+                    reader.read(extra)?;
+                }
+            }
+            // Kanji, structured append, FNC1 and reserved modes are not supported.
+            _ => return None,
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Reed-Solomon decoding over GF(256) with the QR field polynomial 0x11D.
+mod reed_solomon {
+    struct Tables {
+        exp: [u8; 512],
+        log: [u8; 256],
+    }
+
+    fn tables() -> &'static Tables {
+        static TABLES: std::sync::OnceLock<Tables> = std::sync::OnceLock::new();
+        TABLES.get_or_init(|| {
+            let mut exp = [0u8; 512];
+            let mut log = [0u8; 256];
+            let mut x: u16 = 1;
+            for (i, e) in exp.iter_mut().enumerate().take(255) {
+                *e = x as u8;
+                log[x as usize] = i as u8;
+                x <<= 1;
+                if x & 0x100 != 0 {
+                    x ^= 0x11D;
+                }
+            }
+            for i in 255..512 {
+                exp[i] = exp[i - 255];
+            }
+            Tables { exp, log }
+        })
+    }
+
+    fn mul(a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        let t = tables();
+        t.exp[t.log[a as usize] as usize + t.log[b as usize] as usize]
+    }
+
+    fn div(a: u8, b: u8) -> u8 {
+        if a == 0 {
+            return 0;
+        }
+        let t = tables();
+        t.exp[t.log[a as usize] as usize + 255 - t.log[b as usize] as usize]
+    }
+
+    fn pow_alpha(power: usize) -> u8 {
+        tables().exp[power % 255]
+    }
+
+    /// Evaluate a polynomial stored lowest degree first.
+    fn eval_low_first(poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| mul(acc, x) ^ c)
+    }
+
+    /// Compute the remainder of `data` divided by the generator polynomial.
+    #[cfg(test)]
+    pub(super) fn remainder(data: &[u8], ecc_len: usize) -> Vec<u8> {
+        // Generator polynomial (x - a^0)(x - a^1)...(x - a^(n-1)), highest degree
+        // first with the leading 1 omitted.
+        let mut generator = vec![0u8; ecc_len];
+        generator[ecc_len - 1] = 1;
+        let mut root = 1u8;
+        for _ in 0..ecc_len {
+            for j in 0..ecc_len {
+                generator[j] = mul(generator[j], root);
+                if j + 1 < ecc_len {
+                    generator[j] ^= generator[j + 1];
+                }
+            }
+            root = mul(root, 2);
+        }
+
+        let mut result = vec![0u8; ecc_len];
+        for &b in data {
+            let factor = b ^ result.remove(0);
+            result.push(0);
+            for (r, &g) in result.iter_mut().zip(generator.iter()) {
+                *r ^= mul(g, factor);
+            }
+        }
+        result
+    }
+
+    /// Correct errors in a codeword block (data followed by `ecc_len` ECC bytes)
+    /// in place. Returns `None` when the block is uncorrectable.
+    pub(super) fn correct(block: &mut [u8], ecc_len: usize) -> Option<()> {
+        let n = block.len();
+
+        // Syndromes S_i = c(a^i); the first byte is the highest degree coefficient.
+        let syndromes: Vec<u8> = (0..ecc_len)
+            .map(|i| {
+                let x = pow_alpha(i);
+                block.iter().fold(0, |acc, &c| mul(acc, x) ^ c)
+            })
+            .collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(());
+        }
+
+        // Berlekamp-Massey: error locator polynomial, lowest degree first.
+        let mut locator = vec![1u8];
+        let mut previous = vec![1u8];
+        let mut degree = 0usize;
+        let mut shift = 1usize;
+        let mut last_discrepancy = 1u8;
+        for k in 0..ecc_len {
+            let mut discrepancy = syndromes[k];
+            for i in 1..=degree.min(locator.len() - 1) {
+                discrepancy ^= mul(locator[i], syndromes[k - i]);
+            }
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+
+            let scale = div(discrepancy, last_discrepancy);
+            let mut updated = locator.clone();
+            if updated.len() < previous.len() + shift {
+                updated.resize(previous.len() + shift, 0);
+            }
+            for (i, &p) in previous.iter().enumerate() {
+                updated[i + shift] ^= mul(scale, p);
+            }
+
+            if 2 * degree <= k {
+                previous = std::mem::replace(&mut locator, updated);
+                degree = k + 1 - degree;
+                last_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                locator = updated;
+                shift += 1;
+            }
+        }
+        locator.truncate(degree + 1);
+        if degree == 0 || 2 * degree > ecc_len {
+            return None;
+        }
+
+        // Chien search over every byte position.
+        let positions: Vec<usize> = (0..n)
+            .filter(|&index| {
+                let power = n - 1 - index;
+                eval_low_first(&locator, pow_alpha(255 - power % 255)) == 0
+            })
+            .collect();
+        if positions.len() != degree {
+            return None;
+        }
+
+        // Forney: error evaluator Omega(x) = S(x) * Lambda(x) mod x^ecc_len.
+        let mut evaluator = vec![0u8; ecc_len];
+        for (i, &s) in syndromes.iter().enumerate() {
+            for (j, &l) in locator.iter().enumerate() {
+                if i + j < ecc_len {
+                    evaluator[i + j] ^= mul(s, l);
+                }
+            }
+        }
+        let derivative: Vec<u8> = locator
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+            .collect();
+
+        for index in positions {
+            let power = n - 1 - index;
+            let x = pow_alpha(power);
+            let x_inv = pow_alpha(255 - power % 255);
+            let denominator = eval_low_first(&derivative, x_inv);
+            if denominator == 0 {
+                return None;
+            }
+            let magnitude = mul(x, div(eval_low_first(&evaluator, x_inv), denominator));
+            block[index] ^= magnitude;
+        }
+
+        let clean = (0..ecc_len).all(|i| {
+            let x = pow_alpha(i);
+            block.iter().fold(0, |acc, &c| mul(acc, x) ^ c) == 0
+        });
+        clean.then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcodegen::{QrCode as Encoder, QrCodeEcc};
+
+    fn matrix(qr: &Encoder) -> (usize, Vec<u8>) {
+        let size = qr.size() as usize;
+        let mut data = Vec::with_capacity(size * size);
+        for y in 0..size as i32 {
+            for x in 0..size as i32 {
+                data.push(u8::from(qr.get_module(x, y)));
+            }
+        }
+        (size, data)
+    }
+
+    #[test]
+    fn test_decode_roundtrip_levels_and_modes() {
+        let payloads = [
+            "DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs",
+            "dogecoin:DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs?amount=12.5&label=Caf%C3%A9",
+            "0123456789012345",
+            "HELLO DOGE $42",
+            "Ünïcödé 🐕",
+        ];
+        let levels = [
+            QrCodeEcc::Low,
+            QrCodeEcc::Medium,
+            QrCodeEcc::Quartile,
+            QrCodeEcc::High,
+        ];
+        for text in payloads {
+            for ecl in levels {
+                let qr = Encoder::encode_text(text, ecl).unwrap();
+                let (size, data) = matrix(&qr);
+                assert_eq!(decode(size, &data).as_deref(), Some(text));
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_large_version() {
+        let text = "x".repeat(1500);
+        let qr = Encoder::encode_text(&text, QrCodeEcc::Low).unwrap();
+        assert!(qr.version().value() >= 20);
+        let (size, data) = matrix(&qr);
+        assert_eq!(decode(size, &data), Some(text));
+    }
+
+    #[test]
+    fn test_decode_corrects_damaged_modules() {
+        let text = "DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs";
+        let qr = Encoder::encode_text(text, QrCodeEcc::Medium).unwrap();
+        let (size, mut data) = matrix(&qr);
+        // Flip a few modules in the data region (bottom-right corner).
+        for i in 0..3 {
+            let idx = (size - 1 - i) * size + (size - 1);
+            data[idx] ^= 1;
+        }
+        assert_eq!(decode(size, &data).as_deref(), Some(text));
+    }
+
+    #[test]
+    fn test_alignment_positions_match_spec() {
+        // ISO/IEC 18004 Annex E.
+        let table: [(usize, &[usize]); 7] = [
+            (1, &[]),
+            (2, &[6, 18]),
+            (7, &[6, 22, 38]),
+            (32, &[6, 34, 60, 86, 112, 138]),
+            (36, &[6, 24, 50, 76, 102, 128, 154]),
+            (39, &[6, 26, 54, 82, 110, 138, 166]),
+            (40, &[6, 30, 58, 86, 114, 142, 170]),
+        ];
+        for (version, positions) in table {
+            assert_eq!(alignment_positions(version), positions, "version {version}");
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        assert_eq!(decode(20, &[0u8; 400]), None);
+        assert_eq!(decode(21, &[0u8; 10]), None);
+        assert_eq!(decode(21, &[0u8; 21 * 21]), None);
+    }
+
+    #[test]
+    fn test_reed_solomon_corrects_up_to_half_ecc() {
+        let data: Vec<u8> = (0u8..20).map(|b| b.wrapping_mul(37)).collect();
+        let mut block = data.clone();
+        block.extend(reed_solomon::remainder(&data, 10));
+
+        let mut damaged = block.clone();
+        for i in [0, 7, 13, 21, 29] {
+            damaged[i] ^= 0x5a;
+        }
+        assert!(reed_solomon::correct(&mut damaged, 10).is_some());
+        assert_eq!(damaged, block);
+
+        let mut hopeless = block.clone();
+        for b in hopeless.iter_mut().take(8) {
+            *b ^= 0xff;
+        }
+        assert!(reed_solomon::correct(&mut hopeless, 10).is_none_or(|_| hopeless != block));
+    }
+}
@@ -0,0 +1,262 @@
+//! Fee bumping by replacing an unconfirmed transaction.
+//!
+//! A withdrawal sent with too low a fee can sit in mempools for days.
+//! [`bump_fee`] takes the stuck transaction and the outputs it spends, and
+//! builds a replacement that spends the same inputs and pays the same
+//! recipients at a higher fee rate, taking the extra fee out of change.
+//!
+//! Nodes only accept the replacement under BIP125: the original must signal
+//! replaceability (an input sequence of at most
+//! [`MAX_BIP125_RBF_SEQUENCE`]), and the replacement must pay at least the
+//! original fee plus the minimum relay fee for its own size. Both are
+//! checked here. The replacement signals too, so it can be bumped again.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::replace::bump_fee;
+//! use libdogecoin_rs::Utxo;
+//!
+//! # let (stuck_hex, utxos): (String, Vec<Utxo>) = unimplemented!();
+//! let mut tx = bump_fee(&stuck_hex, &utxos, "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas", 5_000)?;
+//! tx.sign_with_privkey(0, "private_key_wif");
+//! # Ok::<(), libdogecoin_rs::replace::ReplaceError>(())
+//! ```
+
+use crate::coinselect::DUST_LIMIT;
+use crate::koinu::Koinu;
+use crate::script::{OutputKind, TemplateRegistry};
+use crate::transaction::{DogeTransaction, StandardPolicy, Transaction, TransactionError, TxOut};
+use crate::utxo::{OutPoint, Utxo};
+
+/// Highest input sequence that signals replaceability (BIP125).
+pub const MAX_BIP125_RBF_SEQUENCE: u32 = 0xffff_fffd;
+
+/// Errors from building a replacement.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ReplaceError {
+    #[error("invalid raw transaction")]
+    InvalidRaw,
+
+    #[error("no spent output given for input {0:?}")]
+    MissingPrevout(OutPoint),
+
+    #[error("outputs exceed the inputs")]
+    Overspent,
+
+    #[error("transaction does not signal replaceability")]
+    NotReplaceable,
+
+    #[error("invalid change address {0:?}")]
+    InvalidChangeAddress(String),
+
+    #[error("no output pays the change address")]
+    NoChangeOutput,
+
+    #[error("fee of {fee} DOGE is below the replacement minimum of {required} DOGE")]
+    FeeTooLow { fee: Koinu, required: Koinu },
+
+    #[error("change of {change} DOGE cannot cover {needed} DOGE more fee")]
+    InsufficientChange { change: Koinu, needed: Koinu },
+
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+}
+
+/// Build an unsigned replacement for an unconfirmed transaction at a higher
+/// fee rate.
+///
+/// # Arguments
+/// * `raw_hex` - The transaction to replace, signed or not.
+/// * `utxos` - The outputs its inputs spend, in any order; others are
+///   ignored.
+/// * `change_address` - The address of the change output that pays the
+///   extra fee.
+/// * `fee_rate` - New fee rate in koinu per byte.
+///
+/// # Returns
+/// The replacement with the original inputs, outputs and lock time, change
+/// reduced and signatures removed. Inputs carry the outputs they spend, so
+/// the result knows its fee and checks each signature as it is made.
+pub fn bump_fee(
+    raw_hex: &str,
+    utxos: &[Utxo],
+    change_address: &str,
+    fee_rate: u64,
+) -> Result<DogeTransaction, ReplaceError> {
+    let (replacement, prevouts) = replacement(raw_hex, utxos, change_address, fee_rate)?;
+    let mut tx = DogeTransaction::from_raw(&replacement.to_hex())?;
+    for (index, prevout) in prevouts.into_iter().enumerate() {
+        tx.set_prevout(index, prevout);
+    }
+    Ok(tx)
+}
+
+/// Work out the replacement and the outputs its inputs spend.
+fn replacement(
+    raw_hex: &str,
+    utxos: &[Utxo],
+    change_address: &str,
+    fee_rate: u64,
+) -> Result<(Transaction, Vec<TxOut>), ReplaceError> {
+    let mut tx = Transaction::from_hex(raw_hex).ok_or(ReplaceError::InvalidRaw)?;
+    if !tx
+        .inputs
+        .iter()
+        .any(|input| input.sequence <= MAX_BIP125_RBF_SEQUENCE)
+    {
+        return Err(ReplaceError::NotReplaceable);
+    }
+    let prevouts = tx
+        .inputs
+        .iter()
+        .map(|input| {
+            utxos
+                .iter()
+                .find(|u| u.outpoint == input.previous_output)
+                .map(|u| TxOut {
+                    value: u.amount,
+                    script_pubkey: u.script_pubkey.clone(),
+                })
+                .ok_or(ReplaceError::MissingPrevout(input.previous_output))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let inputs = prevouts
+        .iter()
+        .try_fold(0u64, |sum, prevout| sum.checked_add(prevout.value))
+        .ok_or(ReplaceError::Overspent)?;
+    let current = tx
+        .output_value()
+        .and_then(|outputs| inputs.checked_sub(outputs))
+        .map(Koinu)
+        .ok_or(ReplaceError::Overspent)?;
+
+    // Signatures are the same size again, so the original's signed size is
+    // the replacement's.
+    let size = tx.estimate_signed_size();
+    let fee = Koinu(fee_rate.saturating_mul(size as u64));
+    let required = current
+        .checked_add(StandardPolicy::default().relay_fee(size))
+        .ok_or(ReplaceError::Overspent)?;
+    if fee < required {
+        return Err(ReplaceError::FeeTooLow { fee, required });
+    }
+
+    let change_script = OutputKind::from_address(change_address)
+        .and_then(|kind| TemplateRegistry::new().build(&kind))
+        .ok_or_else(|| ReplaceError::InvalidChangeAddress(change_address.to_string()))?;
+    let change = tx
+        .outputs
+        .iter_mut()
+        .find(|out| out.script_pubkey == change_script)
+        .ok_or(ReplaceError::NoChangeOutput)?;
+    let needed = Koinu(fee.0 - current.0);
+    match change.value.checked_sub(needed.0) {
+        Some(reduced) if Koinu(reduced) >= DUST_LIMIT => change.value = reduced,
+        _ => {
+            return Err(ReplaceError::InsufficientChange {
+                change: Koinu(change.value),
+                needed,
+            })
+        }
+    }
+
+    for input in &mut tx.inputs {
+        input.script_sig.clear();
+        input.sequence = input.sequence.min(MAX_BIP125_RBF_SEQUENCE);
+    }
+    Ok((tx, prevouts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::COIN;
+    use crate::transaction::TxIn;
+
+    const RECIPIENT: &str = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+    const CHANGE: &str = "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas";
+
+    fn script(address: &str) -> Vec<u8> {
+        TemplateRegistry::new()
+            .build(&OutputKind::from_address(address).unwrap())
+            .unwrap()
+    }
+
+    fn stuck(sequence: u32) -> (Transaction, Vec<Utxo>) {
+        let outpoint = OutPoint {
+            txid: [1; 32],
+            vout: 0,
+        };
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: vec![0xab; 106],
+                sequence,
+            }],
+            outputs: vec![
+                TxOut {
+                    value: 5 * COIN,
+                    script_pubkey: script(RECIPIENT),
+                },
+                TxOut {
+                    value: 4 * COIN,
+                    script_pubkey: script(CHANGE),
+                },
+            ],
+            lock_time: 0,
+        };
+        let utxo = Utxo {
+            outpoint,
+            script_pubkey: script(CHANGE),
+            amount: 10 * COIN,
+            height: Some(1),
+        };
+        (tx, vec![utxo])
+    }
+
+    #[test]
+    fn test_reduces_change_and_strips_signatures() {
+        let (tx, utxos) = stuck(MAX_BIP125_RBF_SEQUENCE);
+        let size = tx.estimate_signed_size() as u64;
+        let fee_rate = 2 * COIN / size;
+
+        let (bumped, prevouts) = replacement(&tx.to_hex(), &utxos, CHANGE, fee_rate).unwrap();
+        let fee = fee_rate * size;
+        assert_eq!(bumped.outputs[0], tx.outputs[0]);
+        assert_eq!(bumped.outputs[1].value, 4 * COIN - (fee - COIN));
+        assert!(bumped.inputs[0].script_sig.is_empty());
+        assert_eq!(bumped.inputs[0].sequence, MAX_BIP125_RBF_SEQUENCE);
+        assert_eq!(prevouts[0].value, 10 * COIN);
+    }
+
+    #[test]
+    fn test_replacement_rules() {
+        let (final_tx, utxos) = stuck(u32::MAX);
+        assert_eq!(
+            replacement(&final_tx.to_hex(), &utxos, CHANGE, COIN),
+            Err(ReplaceError::NotReplaceable)
+        );
+
+        let (tx, utxos) = stuck(0);
+        let raw = tx.to_hex();
+        assert_eq!(
+            replacement(&raw, &[], CHANGE, COIN),
+            Err(ReplaceError::MissingPrevout(tx.inputs[0].previous_output))
+        );
+        // The original already pays one DOGE.
+        assert!(matches!(
+            replacement(&raw, &utxos, CHANGE, 1_000),
+            Err(ReplaceError::FeeTooLow { .. })
+        ));
+        assert_eq!(
+            replacement(&raw, &utxos, "nmLFkuoGdg6R56p6d7BAPZciaLk4JEd9kd", COIN),
+            Err(ReplaceError::NoChangeOutput)
+        );
+        assert!(matches!(
+            replacement(&raw, &utxos, CHANGE, COIN),
+            Err(ReplaceError::InsufficientChange { .. })
+        ));
+    }
+}
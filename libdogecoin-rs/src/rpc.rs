@@ -2,8 +2,16 @@
 //!
 //! This module is enabled by default via the `rpc` feature.
 
+use crate::events::{Event, EventBus};
+use crate::hdwallet::{Chain, DerivationSession};
+use crate::koinu::Koinu;
+use crate::script::{self, ScriptType};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// JSON-RPC code for an unknown method.
+const RPC_METHOD_NOT_FOUND: i64 = -32601;
 
 /// A minimal JSON-RPC client (Dogecoin Core / Bitcoin Core compatible).
 #[derive(Debug, Clone)]
@@ -11,6 +19,7 @@ pub struct DogeRpcClient {
     url: String,
     auth: Option<(String, String)>,
     user_agent: String,
+    events: Option<EventBus>,
 }
 
 impl DogeRpcClient {
@@ -20,6 +29,7 @@ impl DogeRpcClient {
             url: url.into(),
             auth: None,
             user_agent: "libdogecoin-rs".to_string(),
+            events: None,
         }
     }
 
@@ -39,6 +49,15 @@ impl DogeRpcClient {
         self
     }
 
+    /// Publish broadcast results on `bus`.
+    ///
+    /// [`send_raw_transaction`](Self::send_raw_transaction) then emits
+    /// [`Event::TxSeen`] on success and [`Event::BroadcastFailed`] on error.
+    pub fn with_event_bus(mut self, bus: EventBus) -> Self {
+        self.events = Some(bus);
+        self
+    }
+
     /// Generic JSON-RPC call.
     pub fn call<T: DeserializeOwned>(
         &self,
@@ -73,11 +92,13 @@ impl DogeRpcClient {
             }
             Err(ureq::Error::Status(code, r)) => {
                 // Try to extract a JSON error body for diagnostics.
-                let body: Result<serde_json::Value, _> = r.into_json();
-                Err(RpcError::HttpStatus {
-                    code,
-                    body: body.ok(),
-                })
+                let body: Option<serde_json::Value> = r.into_json().ok();
+                // Dogecoin Core sends RPC errors with an HTTP error status
+                // and the usual response body.
+                if let Some(err) = body.as_ref().and_then(remote_error) {
+                    return Err(RpcError::Remote(err));
+                }
+                Err(RpcError::HttpStatus { code, body })
             }
             Err(e) => Err(RpcError::Transport(e)),
         }
@@ -85,7 +106,17 @@ impl DogeRpcClient {
 
     /// Broadcast a raw transaction hex.
     pub fn send_raw_transaction(&self, raw_tx_hex: &str) -> Result<String, RpcError> {
-        self.call("sendrawtransaction", serde_json::json!([raw_tx_hex]))
+        let result: Result<String, RpcError> =
+            self.call("sendrawtransaction", serde_json::json!([raw_tx_hex]));
+        if let Some(bus) = &self.events {
+            bus.emit(match &result {
+                Ok(txid) => Event::TxSeen { txid: txid.clone() },
+                Err(e) => Event::BroadcastFailed {
+                    reason: e.to_string(),
+                },
+            });
+        }
+        result
     }
 
     /// List unspent outputs (UTXOs) for the given addresses.
@@ -111,15 +142,73 @@ impl DogeRpcClient {
         self.list_unspent(min_conf, max_conf, &[address.to_string()])
     }
 
+    /// Register many scripts, addresses or keys with the node's wallet in
+    /// one `importmulti` call.
+    ///
+    /// # Arguments
+    /// * `requests` - The scripts to import.
+    /// * `rescan` - Whether to rescan the chain from the earliest request
+    ///   timestamp once all are imported.
+    ///
+    /// # Returns
+    /// One result per request, in order. A failed request does not fail the
+    /// call.
+    pub fn import_multi(
+        &self,
+        requests: &[ImportMultiRequest],
+        rescan: bool,
+    ) -> Result<Vec<ImportMultiResult>, RpcError> {
+        self.call(
+            "importmulti",
+            serde_json::json!([requests, { "rescan": rescan }]),
+        )
+    }
+
+    /// Ask the node what it knows about an address.
+    ///
+    /// Uses `getaddressinfo`, falling back to `validateaddress` on nodes
+    /// such as Dogecoin Core 1.14 that predate it.
+    pub fn get_address_info(&self, address: &str) -> Result<NodeAddressInfo, RpcError> {
+        match self.call("getaddressinfo", serde_json::json!([address])) {
+            Err(RpcError::Remote(e)) if e.code == RPC_METHOD_NOT_FOUND => {
+                self.call("validateaddress", serde_json::json!([address]))
+            }
+            result => result,
+        }
+    }
+
+    /// Compare the node's view of an address with the local wallet's.
+    ///
+    /// # Arguments
+    /// * `address` - The address to look up.
+    /// * `session` - The local wallet.
+    /// * `accounts` - Local accounts to search, in order.
+    /// * `search_depth` - Addresses to check on each chain of each account.
+    pub fn address_ownership(
+        &self,
+        address: &str,
+        session: &mut DerivationSession,
+        accounts: Range<u32>,
+        search_depth: u32,
+    ) -> Result<AddressOwnership, OwnershipError> {
+        let local = session.find_index(address, accounts, search_depth)?;
+        let node = self.get_address_info(address)?;
+        Ok(AddressOwnership { node, local })
+    }
+
     /// Convenience: compute balance from `listunspent` for one address.
     pub fn utxo_balance(
         &self,
         address: &str,
         min_conf: u32,
         max_conf: u32,
-    ) -> Result<f64, RpcError> {
+    ) -> Result<Koinu, RpcError> {
         let utxos = self.utxos_for_address(address, min_conf, max_conf)?;
-        Ok(utxos.into_iter().map(|u| u.amount).sum())
+        Ok(Koinu(
+            utxos
+                .iter()
+                .fold(0u64, |total, u| total.saturating_add(u.amount.0)),
+        ))
     }
 }
 
@@ -131,6 +220,18 @@ fn basic_auth_header(user: &str, pass: &str) -> String {
     format!("Basic {encoded}")
 }
 
+/// Get the error object from a JSON-RPC response body, if it has one.
+fn remote_error(body: &serde_json::Value) -> Option<JsonRpcErrorObject> {
+    serde_json::from_value(body.get("error")?.clone()).ok()
+}
+
+/// Deserialize a JSON-RPC DOGE amount (a decimal number) into koinu.
+fn deserialize_doge<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Koinu, D::Error> {
+    let doge = f64::deserialize(deserializer)?;
+    Koinu::from_doge_f64(doge)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid DOGE amount {doge}")))
+}
+
 /// `listunspent` response entry.
 #[derive(Debug, Clone, Deserialize)]
 pub struct ListUnspentEntry {
@@ -143,7 +244,8 @@ pub struct ListUnspentEntry {
     #[serde(rename = "scriptPubKey")]
     pub script_pub_key: String,
 
-    pub amount: f64,
+    #[serde(deserialize_with = "deserialize_doge")]
+    pub amount: Koinu,
 
     #[serde(default)]
     pub confirmations: u64,
@@ -155,6 +257,222 @@ pub struct ListUnspentEntry {
     pub solvable: Option<bool>,
 }
 
+/// `getaddressinfo` or `validateaddress` response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeAddressInfo {
+    #[serde(default)]
+    pub address: String,
+
+    /// Hex scriptPubKey; empty if the node rejected the address.
+    #[serde(rename = "scriptPubKey", default)]
+    pub script_pub_key: String,
+
+    /// The node's wallet holds the key.
+    #[serde(rename = "ismine", default)]
+    pub is_mine: bool,
+
+    #[serde(rename = "iswatchonly", default)]
+    pub is_watch_only: bool,
+
+    #[serde(rename = "isscript", default)]
+    pub is_script: bool,
+
+    /// Derivation path of the key in the node's HD wallet, in the node's
+    /// own layout.
+    #[serde(rename = "hdkeypath", default)]
+    pub hd_key_path: Option<String>,
+
+    #[serde(rename = "hdmasterkeyid", default)]
+    pub hd_master_key_id: Option<String>,
+}
+
+impl NodeAddressInfo {
+    pub fn script_type(&self) -> ScriptType {
+        script::classify(&self.script_pub_key)
+    }
+}
+
+/// The node's and the local wallet's view of one address, from
+/// [`DogeRpcClient::address_ownership`].
+#[derive(Debug, Clone)]
+pub struct AddressOwnership {
+    pub node: NodeAddressInfo,
+    /// Account, chain and index of the address in the local wallet.
+    pub local: Option<(u32, Chain, u32)>,
+}
+
+impl AddressOwnership {
+    /// Whether the node holds the key or watches the address.
+    pub fn node_tracks(&self) -> bool {
+        self.node.is_mine || self.node.is_watch_only
+    }
+
+    /// Whether the node and the local wallet agree on owning the address.
+    /// A local address the node does not track needs importing before the
+    /// node can report its funds.
+    pub fn is_consistent(&self) -> bool {
+        self.node_tracks() == self.local.is_some()
+    }
+}
+
+/// Errors from [`DogeRpcClient::address_ownership`].
+#[derive(thiserror::Error, Debug)]
+pub enum OwnershipError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+
+    #[error("local wallet lookup failed: {0}")]
+    Wallet(#[from] crate::error::Error),
+}
+
+/// What an [`ImportMultiRequest`] imports.
+///
+/// Dogecoin Core 1.14 predates output descriptors, so scripts are given by
+/// address or as raw scriptPubKey hex.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum ImportScript {
+    Address {
+        address: String,
+    },
+    /// Hex-encoded scriptPubKey.
+    Script(String),
+}
+
+/// How far back an [`ImportMultiRequest`] needs a rescan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportTimestamp {
+    /// The script is new; nothing needs rescanning.
+    Now,
+    /// Unix time of the earliest transaction that may involve the script.
+    Time(u64),
+}
+
+impl Serialize for ImportTimestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ImportTimestamp::Now => serializer.serialize_str("now"),
+            ImportTimestamp::Time(time) => serializer.serialize_u64(*time),
+        }
+    }
+}
+
+/// One `importmulti` request.
+///
+/// # Example
+/// ```
+/// use libdogecoin_rs::rpc::{ImportMultiRequest, ImportTimestamp};
+///
+/// let request = ImportMultiRequest::address(
+///     "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+///     ImportTimestamp::Time(1_704_067_200),
+/// )
+/// .with_label("donations")
+/// .watch_only();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportMultiRequest {
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: ImportScript,
+
+    pub timestamp: ImportTimestamp,
+
+    /// Hex redeem script, for P2SH scripts.
+    #[serde(rename = "redeemscript", skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<String>,
+
+    /// Hex public keys of the script.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pubkeys: Vec<String>,
+
+    /// WIF private keys of the script.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+
+    /// Treat the script as change rather than a receiving address.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub internal: bool,
+
+    /// Import without private keys, for watching only.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub watchonly: bool,
+
+    /// Label for the address; not allowed with `internal`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl ImportMultiRequest {
+    /// Import an address.
+    pub fn address(address: impl Into<String>, timestamp: ImportTimestamp) -> Self {
+        Self::new(
+            ImportScript::Address {
+                address: address.into(),
+            },
+            timestamp,
+        )
+    }
+
+    /// Import a hex-encoded scriptPubKey.
+    pub fn script(script_hex: impl Into<String>, timestamp: ImportTimestamp) -> Self {
+        Self::new(ImportScript::Script(script_hex.into()), timestamp)
+    }
+
+    fn new(script_pub_key: ImportScript, timestamp: ImportTimestamp) -> Self {
+        Self {
+            script_pub_key,
+            timestamp,
+            redeem_script: None,
+            pubkeys: Vec::new(),
+            keys: Vec::new(),
+            internal: false,
+            watchonly: false,
+            label: None,
+        }
+    }
+
+    pub fn with_redeem_script(mut self, redeem_script: impl Into<String>) -> Self {
+        self.redeem_script = Some(redeem_script.into());
+        self
+    }
+
+    pub fn with_pubkeys(mut self, pubkeys: Vec<String>) -> Self {
+        self.pubkeys = pubkeys;
+        self
+    }
+
+    pub fn with_keys(mut self, keys: Vec<String>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Mark the script as change.
+    pub fn internal(mut self) -> Self {
+        self.internal = true;
+        self
+    }
+
+    /// Import for watching only, without private keys.
+    pub fn watch_only(mut self) -> Self {
+        self.watchonly = true;
+        self
+    }
+}
+
+/// Outcome of one [`ImportMultiRequest`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportMultiResult {
+    pub success: bool,
+
+    #[serde(default)]
+    pub error: Option<JsonRpcErrorObject>,
+}
+
 #[derive(Debug, Serialize)]
 struct JsonRpcRequest<'a> {
     jsonrpc: &'a str,
@@ -211,4 +529,87 @@ mod tests {
         assert!(h.starts_with("Basic "));
         assert!(h.len() > "Basic ".len());
     }
+
+    #[test]
+    fn test_import_multi_request_json() {
+        let request = ImportMultiRequest::script("51", ImportTimestamp::Now)
+            .with_pubkeys(vec!["02aa".to_string()])
+            .internal();
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "scriptPubKey": "51",
+                "timestamp": "now",
+                "pubkeys": ["02aa"],
+                "internal": true,
+            })
+        );
+
+        let mut set = crate::WatchSet::new(crate::Network::Mainnet);
+        set.add_address(
+            "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+            Some("donations"),
+            Some(crate::birthday::Birthday::from_time(1_704_067_200)),
+        )
+        .unwrap()
+        .add_script(&[0x51], None, None);
+        assert_eq!(
+            serde_json::to_value(set.import_requests()).unwrap(),
+            set.to_importmulti()
+        );
+    }
+
+    #[test]
+    fn test_list_unspent_amount_in_koinu() {
+        let entry: ListUnspentEntry = serde_json::from_value(serde_json::json!({
+            "txid": "00",
+            "vout": 0,
+            "scriptPubKey": "",
+            "amount": 1234.56789012,
+        }))
+        .unwrap();
+        assert_eq!(entry.amount, Koinu(123_456_789_012));
+
+        let negative =
+            serde_json::json!({"txid": "00", "vout": 0, "scriptPubKey": "", "amount": -1});
+        assert!(serde_json::from_value::<ListUnspentEntry>(negative).is_err());
+    }
+
+    #[test]
+    fn test_address_info_from_either_rpc() {
+        let info: NodeAddressInfo = serde_json::from_value(serde_json::json!({
+            "address": "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+            "scriptPubKey": "76a914d8c43e6f68ca4ea1e9b93da2d1e3a95118fa4a7c88ac",
+            "ismine": true,
+            "iswatchonly": false,
+            "isscript": false,
+            "hdkeypath": "m/0'/0'/5'",
+        }))
+        .unwrap();
+        assert_eq!(info.script_type(), ScriptType::P2pkh);
+        assert_eq!(info.hd_key_path.as_deref(), Some("m/0'/0'/5'"));
+
+        // validateaddress on an address the node rejects.
+        let invalid: NodeAddressInfo =
+            serde_json::from_value(serde_json::json!({"isvalid": false})).unwrap();
+        assert_eq!(invalid.script_type(), ScriptType::Nonstandard);
+
+        let ownership = AddressOwnership {
+            node: invalid,
+            local: Some((0, Chain::External, 5)),
+        };
+        assert!(!ownership.node_tracks());
+        assert!(!ownership.is_consistent());
+
+        let body = serde_json::json!({
+            "result": null,
+            "error": {"code": -32601, "message": "Method not found"},
+            "id": "libdogecoin-rs",
+        });
+        assert_eq!(
+            remote_error(&body).map(|e| e.code),
+            Some(RPC_METHOD_NOT_FOUND)
+        );
+        assert!(remote_error(&serde_json::json!({"result": 1, "error": null})).is_none());
+    }
 }
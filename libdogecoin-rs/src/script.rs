@@ -0,0 +1,717 @@
+//! Output script templates (scriptPubKey parsing and generation).
+//!
+//! A [`TemplateRegistry`] holds a list of [`ScriptTemplate`]s and classifies
+//! raw scripts into [`OutputKind`]s, or builds scripts back from them. The
+//! standard Dogecoin forms (P2PKH, P2SH, P2PK, bare multisig and OP_RETURN)
+//! are registered by default; research tooling can register its own templates
+//! for historical or protocol-specific script shapes.
+//!
+//! For the common case of reading an output from RPC or a decoded
+//! transaction, [`classify`] and [`extract_address`] take the hex
+//! scriptPubKey directly.
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::script::{OpReturnPrefix, OutputKind, TemplateRegistry};
+//!
+//! let mut registry = TemplateRegistry::new();
+//! registry.register(OpReturnPrefix::new("memo", b"MEMO"));
+//!
+//! let script = registry
+//!     .build(&OutputKind::Custom {
+//!         template: "memo",
+//!         data: vec![b"hello".to_vec()],
+//!     })
+//!     .unwrap();
+//! assert_eq!(
+//!     registry.classify(&script),
+//!     Some(OutputKind::Custom {
+//!         template: "memo",
+//!         data: vec![b"hello".to_vec()],
+//!     })
+//! );
+//! ```
+
+use crate::address::{
+    base58check_decode, base58check_encode, P2PKH_VERSION_MAINNET, P2PKH_VERSION_TESTNET,
+    P2SH_VERSION_MAINNET, P2SH_VERSION_TESTNET,
+};
+use crate::hex;
+
+pub const OP_0: u8 = 0x00;
+pub const OP_PUSHDATA1: u8 = 0x4c;
+pub const OP_PUSHDATA2: u8 = 0x4d;
+pub const OP_PUSHDATA4: u8 = 0x4e;
+pub const OP_1: u8 = 0x51;
+pub const OP_16: u8 = 0x60;
+pub const OP_RETURN: u8 = 0x6a;
+pub const OP_DUP: u8 = 0x76;
+pub const OP_EQUAL: u8 = 0x87;
+pub const OP_EQUALVERIFY: u8 = 0x88;
+pub const OP_HASH160: u8 = 0xa9;
+pub const OP_CHECKSIG: u8 = 0xac;
+pub const OP_CHECKMULTISIG: u8 = 0xae;
+
+/// One parsed script element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    /// A data push (including `OP_0`, which pushes an empty item).
+    Push(&'a [u8]),
+    /// Any other opcode.
+    Op(u8),
+}
+
+/// Split a script into instructions.
+///
+/// # Returns
+/// `None` if a push runs past the end of the script.
+pub fn instructions(script: &[u8]) -> Option<Vec<Instruction<'_>>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < script.len() {
+        let op = script[pos];
+        pos += 1;
+        let len = match op {
+            OP_0 => 0,
+            0x01..=0x4b => op as usize,
+            OP_PUSHDATA1 => {
+                let len = *script.get(pos)? as usize;
+                pos += 1;
+                len
+            }
+            OP_PUSHDATA2 => {
+                let bytes = script.get(pos..pos + 2)?;
+                pos += 2;
+                u16::from_le_bytes([bytes[0], bytes[1]]) as usize
+            }
+            OP_PUSHDATA4 => {
+                let bytes = script.get(pos..pos + 4)?;
+                pos += 4;
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+            }
+            _ => {
+                out.push(Instruction::Op(op));
+                continue;
+            }
+        };
+        let data = script.get(pos..pos.checked_add(len)?)?;
+        pos += len;
+        out.push(Instruction::Push(data));
+    }
+    Some(out)
+}
+
+/// Append a push of `data` using the shortest encoding.
+pub fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        0 => script.push(OP_0),
+        len @ 1..=0x4b => script.push(len as u8),
+        len @ 0x4c..=0xff => script.extend_from_slice(&[OP_PUSHDATA1, len as u8]),
+        len @ 0x100..=0xffff => {
+            script.push(OP_PUSHDATA2);
+            script.extend_from_slice(&(len as u16).to_le_bytes());
+        }
+        len => {
+            script.push(OP_PUSHDATA4);
+            script.extend_from_slice(&(len as u32).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+}
+
+/// A classified output script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputKind {
+    /// `OP_DUP OP_HASH160 <hash160> OP_EQUALVERIFY OP_CHECKSIG`
+    P2pkh([u8; 20]),
+    /// `OP_HASH160 <hash160> OP_EQUAL`
+    P2sh([u8; 20]),
+    /// `<pubkey> OP_CHECKSIG`
+    P2pk(Vec<u8>),
+    /// `OP_m <pubkey>... OP_n OP_CHECKMULTISIG`
+    Multisig { required: u8, pubkeys: Vec<Vec<u8>> },
+    /// `OP_RETURN <data>...`
+    NullData(Vec<Vec<u8>>),
+    /// Output recognised by a registered custom template.
+    Custom {
+        template: &'static str,
+        data: Vec<Vec<u8>>,
+    },
+}
+
+impl OutputKind {
+    /// Classify a Base58Check address as P2PKH or P2SH.
+    ///
+    /// # Returns
+    /// `None` if the address is malformed or has an unknown version byte.
+    pub fn from_address(address: &str) -> Option<Self> {
+        let payload = base58check_decode(address)?;
+        let (&version, hash) = payload.split_first()?;
+        let hash: [u8; 20] = hash.try_into().ok()?;
+        match version {
+            P2PKH_VERSION_MAINNET | P2PKH_VERSION_TESTNET => Some(OutputKind::P2pkh(hash)),
+            P2SH_VERSION_MAINNET | P2SH_VERSION_TESTNET => Some(OutputKind::P2sh(hash)),
+            _ => None,
+        }
+    }
+
+    /// Get the address paying to this output.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    ///
+    /// # Returns
+    /// `None` for output kinds that have no address form.
+    pub fn address(&self, is_testnet: bool) -> Option<String> {
+        let (version, hash) = match (self, is_testnet) {
+            (OutputKind::P2pkh(hash), false) => (P2PKH_VERSION_MAINNET, hash),
+            (OutputKind::P2pkh(hash), true) => (P2PKH_VERSION_TESTNET, hash),
+            (OutputKind::P2sh(hash), false) => (P2SH_VERSION_MAINNET, hash),
+            (OutputKind::P2sh(hash), true) => (P2SH_VERSION_TESTNET, hash),
+            _ => return None,
+        };
+        let mut payload = Vec::with_capacity(21);
+        payload.push(version);
+        payload.extend_from_slice(hash);
+        base58check_encode(&payload)
+    }
+
+    /// Get the standard type of this output.
+    pub fn script_type(&self) -> ScriptType {
+        match self {
+            OutputKind::P2pkh(_) => ScriptType::P2pkh,
+            OutputKind::P2sh(_) => ScriptType::P2sh,
+            OutputKind::P2pk(_) => ScriptType::P2pk,
+            OutputKind::Multisig { .. } => ScriptType::Multisig,
+            OutputKind::NullData(_) => ScriptType::NullData,
+            OutputKind::Custom { .. } => ScriptType::Nonstandard,
+        }
+    }
+}
+
+/// The standard type of an output script, without its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScriptType {
+    P2pkh,
+    P2sh,
+    P2pk,
+    Multisig,
+    /// An `OP_RETURN` data output.
+    NullData,
+    /// Anything else, including scripts that fail to parse.
+    Nonstandard,
+}
+
+impl ScriptType {
+    /// Stable lowercase name: the matching standard template's
+    /// [`ScriptTemplate::name`], or `"nonstandard"`. Unlike the `Debug`
+    /// form it does not change if a variant is renamed.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptType::P2pkh => "p2pkh",
+            ScriptType::P2sh => "p2sh",
+            ScriptType::P2pk => "p2pk",
+            ScriptType::Multisig => "multisig",
+            ScriptType::NullData => "nulldata",
+            ScriptType::Nonstandard => "nonstandard",
+        }
+    }
+}
+
+/// Classify a hex-encoded scriptPubKey with the standard templates.
+///
+/// # Returns
+/// [`ScriptType::Nonstandard`] if the script matches no standard template
+/// or is not valid hex.
+pub fn classify(script_hex: &str) -> ScriptType {
+    TemplateRegistry::new()
+        .classify_hex(script_hex)
+        .map_or(ScriptType::Nonstandard, |kind| kind.script_type())
+}
+
+/// Get the address a hex-encoded scriptPubKey pays to.
+///
+/// # Arguments
+/// * `script_hex` - The scriptPubKey as hex.
+/// * `is_testnet` - Set to true for testnet, false for mainnet.
+///
+/// # Returns
+/// `None` unless the script is P2PKH or P2SH.
+pub fn extract_address(script_hex: &str, is_testnet: bool) -> Option<String> {
+    TemplateRegistry::new()
+        .classify_hex(script_hex)?
+        .address(is_testnet)
+}
+
+/// A script shape that can be recognised and generated.
+pub trait ScriptTemplate: Send + Sync {
+    /// Short identifier of this template.
+    fn name(&self) -> &'static str;
+
+    /// Recognise `script`, returning `None` if it does not match.
+    fn parse(&self, script: &[u8]) -> Option<OutputKind>;
+
+    /// Build the script for `kind`, returning `None` if `kind` is not handled.
+    fn build(&self, kind: &OutputKind) -> Option<Vec<u8>>;
+}
+
+/// Pay-to-public-key-hash template.
+pub struct P2pkhTemplate;
+
+impl ScriptTemplate for P2pkhTemplate {
+    fn name(&self) -> &'static str {
+        "p2pkh"
+    }
+
+    fn parse(&self, script: &[u8]) -> Option<OutputKind> {
+        use Instruction::{Op, Push};
+        match instructions(script)?.as_slice() {
+            [Op(OP_DUP), Op(OP_HASH160), Push(hash), Op(OP_EQUALVERIFY), Op(OP_CHECKSIG)] => {
+                Some(OutputKind::P2pkh((*hash).try_into().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    fn build(&self, kind: &OutputKind) -> Option<Vec<u8>> {
+        let OutputKind::P2pkh(hash) = kind else {
+            return None;
+        };
+        let mut script = vec![OP_DUP, OP_HASH160];
+        push_data(&mut script, hash);
+        script.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+        Some(script)
+    }
+}
+
+/// Pay-to-script-hash template.
+pub struct P2shTemplate;
+
+impl ScriptTemplate for P2shTemplate {
+    fn name(&self) -> &'static str {
+        "p2sh"
+    }
+
+    fn parse(&self, script: &[u8]) -> Option<OutputKind> {
+        match instructions(script)?.as_slice() {
+            [Instruction::Op(OP_HASH160), Instruction::Push(hash), Instruction::Op(OP_EQUAL)] => {
+                Some(OutputKind::P2sh((*hash).try_into().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    fn build(&self, kind: &OutputKind) -> Option<Vec<u8>> {
+        let OutputKind::P2sh(hash) = kind else {
+            return None;
+        };
+        let mut script = vec![OP_HASH160];
+        push_data(&mut script, hash);
+        script.push(OP_EQUAL);
+        Some(script)
+    }
+}
+
+/// Pay-to-public-key template (common in early Dogecoin coinbase outputs).
+pub struct P2pkTemplate;
+
+impl ScriptTemplate for P2pkTemplate {
+    fn name(&self) -> &'static str {
+        "p2pk"
+    }
+
+    fn parse(&self, script: &[u8]) -> Option<OutputKind> {
+        match instructions(script)?.as_slice() {
+            [Instruction::Push(pubkey), Instruction::Op(OP_CHECKSIG)] if is_pubkey(pubkey) => {
+                Some(OutputKind::P2pk(pubkey.to_vec()))
+            }
+            _ => None,
+        }
+    }
+
+    fn build(&self, kind: &OutputKind) -> Option<Vec<u8>> {
+        let OutputKind::P2pk(pubkey) = kind else {
+            return None;
+        };
+        if !is_pubkey(pubkey) {
+            return None;
+        }
+        let mut script = Vec::with_capacity(pubkey.len() + 2);
+        push_data(&mut script, pubkey);
+        script.push(OP_CHECKSIG);
+        Some(script)
+    }
+}
+
+/// Bare m-of-n multisig template (up to 16 keys).
+pub struct MultisigTemplate;
+
+impl ScriptTemplate for MultisigTemplate {
+    fn name(&self) -> &'static str {
+        "multisig"
+    }
+
+    fn parse(&self, script: &[u8]) -> Option<OutputKind> {
+        let ops = instructions(script)?;
+        let [Instruction::Op(m), keys @ .., Instruction::Op(n), Instruction::Op(OP_CHECKMULTISIG)] =
+            ops.as_slice()
+        else {
+            return None;
+        };
+        let (required, total) = (small_int(*m)?, small_int(*n)?);
+        if required == 0 || required > total || keys.len() != total as usize {
+            return None;
+        }
+        let pubkeys = keys
+            .iter()
+            .map(|key| match key {
+                Instruction::Push(key) if is_pubkey(key) => Some(key.to_vec()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(OutputKind::Multisig { required, pubkeys })
+    }
+
+    fn build(&self, kind: &OutputKind) -> Option<Vec<u8>> {
+        let OutputKind::Multisig { required, pubkeys } = kind else {
+            return None;
+        };
+        let total = u8::try_from(pubkeys.len()).ok()?;
+        if *required == 0 || *required > total || total > 16 {
+            return None;
+        }
+        if !pubkeys.iter().all(|key| is_pubkey(key)) {
+            return None;
+        }
+        let mut script = vec![OP_1 - 1 + required];
+        for key in pubkeys {
+            push_data(&mut script, key);
+        }
+        script.extend_from_slice(&[OP_1 - 1 + total, OP_CHECKMULTISIG]);
+        Some(script)
+    }
+}
+
+/// `OP_RETURN` data-carrier template.
+pub struct NullDataTemplate;
+
+impl ScriptTemplate for NullDataTemplate {
+    fn name(&self) -> &'static str {
+        "nulldata"
+    }
+
+    fn parse(&self, script: &[u8]) -> Option<OutputKind> {
+        Some(OutputKind::NullData(null_data_pushes(script)?))
+    }
+
+    fn build(&self, kind: &OutputKind) -> Option<Vec<u8>> {
+        let OutputKind::NullData(data) = kind else {
+            return None;
+        };
+        Some(null_data_script(data.iter().map(Vec::as_slice)))
+    }
+}
+
+/// `OP_RETURN` outputs whose first push starts with a protocol tag.
+///
+/// Matching scripts classify as [`OutputKind::Custom`] with the tag stripped
+/// from the first push.
+pub struct OpReturnPrefix {
+    name: &'static str,
+    prefix: Vec<u8>,
+}
+
+impl OpReturnPrefix {
+    /// Create a template named `name` matching data tagged with `prefix`.
+    pub fn new(name: &'static str, prefix: &[u8]) -> Self {
+        OpReturnPrefix {
+            name,
+            prefix: prefix.to_vec(),
+        }
+    }
+}
+
+impl ScriptTemplate for OpReturnPrefix {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn parse(&self, script: &[u8]) -> Option<OutputKind> {
+        let mut data = null_data_pushes(script)?;
+        let first = data.first_mut()?;
+        if !first.starts_with(&self.prefix) {
+            return None;
+        }
+        first.drain(..self.prefix.len());
+        Some(OutputKind::Custom {
+            template: self.name,
+            data,
+        })
+    }
+
+    fn build(&self, kind: &OutputKind) -> Option<Vec<u8>> {
+        let OutputKind::Custom { template, data } = kind else {
+            return None;
+        };
+        if *template != self.name {
+            return None;
+        }
+        let (first, rest) = data.split_first()?;
+        let tagged = [self.prefix.as_slice(), first].concat();
+        Some(null_data_script(
+            std::iter::once(tagged.as_slice()).chain(rest.iter().map(Vec::as_slice)),
+        ))
+    }
+}
+
+/// An ordered set of script templates.
+///
+/// Templates registered later take priority over earlier ones and over the
+/// built-in templates, so a custom template can refine a standard shape (for
+/// example a tagged `OP_RETURN`).
+pub struct TemplateRegistry {
+    templates: Vec<Box<dyn ScriptTemplate>>,
+}
+
+impl TemplateRegistry {
+    /// Create a registry with the standard templates.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register(NullDataTemplate)
+            .register(MultisigTemplate)
+            .register(P2pkTemplate)
+            .register(P2shTemplate)
+            .register(P2pkhTemplate);
+        registry
+    }
+
+    /// Create a registry with no templates.
+    pub fn empty() -> Self {
+        TemplateRegistry {
+            templates: Vec::new(),
+        }
+    }
+
+    /// Register a template, giving it priority over those already present.
+    pub fn register<T: ScriptTemplate + 'static>(&mut self, template: T) -> &mut Self {
+        self.templates.insert(0, Box::new(template));
+        self
+    }
+
+    /// Get the names of the registered templates, in priority order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.templates.iter().map(|t| t.name()).collect()
+    }
+
+    /// Classify a raw script.
+    ///
+    /// # Returns
+    /// The first matching template's result, or `None` if nothing matched.
+    pub fn classify(&self, script: &[u8]) -> Option<OutputKind> {
+        self.templates.iter().find_map(|t| t.parse(script))
+    }
+
+    /// Classify a hex-encoded script.
+    pub fn classify_hex(&self, script_hex: &str) -> Option<OutputKind> {
+        self.classify(&hex::decode(script_hex)?)
+    }
+
+    /// Build the script for `kind` with the first template that handles it.
+    pub fn build(&self, kind: &OutputKind) -> Option<Vec<u8>> {
+        self.templates.iter().find_map(|t| t.build(kind))
+    }
+
+    /// Build the script for `kind` as hex.
+    pub fn build_hex(&self, kind: &OutputKind) -> Option<String> {
+        self.build(kind).map(|script| hex::encode(&script))
+    }
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether `key` looks like a compressed or uncompressed SEC1 public key.
+pub(crate) fn is_pubkey(key: &[u8]) -> bool {
+    matches!(
+        (key.len(), key.first()),
+        (33, Some(0x02 | 0x03)) | (65, Some(0x04))
+    )
+}
+
+/// Decode `OP_1`..`OP_16`.
+fn small_int(op: u8) -> Option<u8> {
+    (OP_1..=OP_16).contains(&op).then(|| op - OP_1 + 1)
+}
+
+fn null_data_pushes(script: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let (&first, rest) = script.split_first()?;
+    if first != OP_RETURN {
+        return None;
+    }
+    instructions(rest)?
+        .into_iter()
+        .map(|ins| match ins {
+            Instruction::Push(data) => Some(data.to_vec()),
+            Instruction::Op(_) => None,
+        })
+        .collect()
+}
+
+fn null_data_script<'a>(pushes: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut script = vec![OP_RETURN];
+    for data in pushes {
+        push_data(&mut script, data);
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pubkey(tag: u8) -> Vec<u8> {
+        let mut key = vec![0x02];
+        key.extend_from_slice(&[tag; 32]);
+        key
+    }
+
+    #[test]
+    fn test_instructions_and_pushes() {
+        let mut script = Vec::new();
+        push_data(&mut script, &[]);
+        push_data(&mut script, &[0xaa; 3]);
+        push_data(&mut script, &[0xbb; 80]);
+        push_data(&mut script, &[0xcc; 300]);
+        script.push(OP_CHECKSIG);
+
+        assert_eq!(&script[..5], &[OP_0, 3, 0xaa, 0xaa, 0xaa]);
+        assert_eq!(&script[5..7], &[OP_PUSHDATA1, 80]);
+        assert_eq!(
+            instructions(&script).unwrap(),
+            vec![
+                Instruction::Push(&[]),
+                Instruction::Push(&[0xaa; 3]),
+                Instruction::Push(&[0xbb; 80]),
+                Instruction::Push(&[0xcc; 300]),
+                Instruction::Op(OP_CHECKSIG),
+            ]
+        );
+        assert!(instructions(&[5, 1, 2]).is_none());
+        assert!(instructions(&[OP_PUSHDATA2, 0xff]).is_none());
+    }
+
+    #[test]
+    fn test_standard_templates_roundtrip() {
+        let registry = TemplateRegistry::new();
+        let kinds = [
+            OutputKind::P2pkh([0x11; 20]),
+            OutputKind::P2sh([0x22; 20]),
+            OutputKind::P2pk(pubkey(0x33)),
+            OutputKind::Multisig {
+                required: 2,
+                pubkeys: vec![pubkey(1), pubkey(2), pubkey(3)],
+            },
+            OutputKind::NullData(vec![b"much wow".to_vec()]),
+        ];
+        for kind in kinds {
+            let script = registry.build(&kind).unwrap();
+            assert_eq!(registry.classify(&script), Some(kind));
+        }
+
+        let p2pkh = registry.build_hex(&OutputKind::P2pkh([0x11; 20])).unwrap();
+        assert_eq!(p2pkh, format!("76a914{}88ac", "11".repeat(20)));
+        assert_eq!(
+            registry.classify_hex(&p2pkh),
+            Some(OutputKind::P2pkh([0x11; 20]))
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        let registry = TemplateRegistry::new();
+        assert!(registry
+            .classify(&[OP_DUP, OP_HASH160, 1, 0, OP_EQUALVERIFY])
+            .is_none());
+        assert!(registry
+            .build(&OutputKind::Multisig {
+                required: 3,
+                pubkeys: vec![pubkey(1), pubkey(2)],
+            })
+            .is_none());
+        assert!(registry.build(&OutputKind::P2pk(vec![0x05; 33])).is_none());
+        assert!(registry.classify_hex("zz").is_none());
+    }
+
+    #[test]
+    fn test_custom_template_takes_priority() {
+        let mut registry = TemplateRegistry::new();
+        registry.register(OpReturnPrefix::new("memo", b"MEMO"));
+        assert_eq!(registry.names()[0], "memo");
+
+        let tagged = registry
+            .build(&OutputKind::NullData(vec![b"MEMOhi".to_vec()]))
+            .unwrap();
+        assert_eq!(
+            registry.classify(&tagged),
+            Some(OutputKind::Custom {
+                template: "memo",
+                data: vec![b"hi".to_vec()],
+            })
+        );
+
+        let untagged = registry
+            .build(&OutputKind::NullData(vec![b"other".to_vec()]))
+            .unwrap();
+        assert_eq!(
+            registry.classify(&untagged),
+            Some(OutputKind::NullData(vec![b"other".to_vec()]))
+        );
+    }
+
+    #[test]
+    fn test_address_roundtrip() {
+        for address in [
+            "DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViY",
+            "nmLFkuoGdg6R56p6d7BAPZciaLk4JEd9kd",
+        ] {
+            let kind = OutputKind::from_address(address).unwrap();
+            let is_testnet = address.starts_with('n');
+            assert_eq!(kind.address(is_testnet).as_deref(), Some(address));
+        }
+        assert!(OutputKind::from_address("not-an-address").is_none());
+    }
+
+    #[test]
+    fn test_classify_hex() {
+        let p2pkh = format!("76a914{}88ac", "11".repeat(20));
+        assert_eq!(classify(&p2pkh), ScriptType::P2pkh);
+        let p2sh = format!("a914{}87", "22".repeat(20));
+        assert_eq!(classify(&p2sh), ScriptType::P2sh);
+        assert_eq!(classify("6a0568656c6c6f"), ScriptType::NullData);
+        let multisig = MultisigTemplate
+            .build(&OutputKind::Multisig {
+                required: 1,
+                pubkeys: vec![pubkey(1), pubkey(2)],
+            })
+            .unwrap();
+        assert_eq!(classify(&hex::encode(&multisig)), ScriptType::Multisig);
+        assert_eq!(classify("51"), ScriptType::Nonstandard);
+        assert_eq!(classify("zz"), ScriptType::Nonstandard);
+        assert_eq!(ScriptType::P2pkh.name(), P2pkhTemplate.name());
+        assert_eq!(ScriptType::P2sh.name(), P2shTemplate.name());
+        assert_eq!(ScriptType::P2pk.name(), P2pkTemplate.name());
+        assert_eq!(ScriptType::Multisig.name(), MultisigTemplate.name());
+        assert_eq!(ScriptType::NullData.name(), NullDataTemplate.name());
+
+        let address = "DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViY";
+        let script = TemplateRegistry::new()
+            .build_hex(&OutputKind::from_address(address).unwrap())
+            .unwrap();
+        assert_eq!(extract_address(&script, false).as_deref(), Some(address));
+        assert_eq!(extract_address("6a0568656c6c6f", false), None);
+    }
+}
@@ -0,0 +1,405 @@
+//! Shamir secret sharing for seeds and mnemonics.
+//!
+//! Splits a secret into `count` shares so that any `threshold` of them
+//! reconstruct it, using Shamir's scheme over GF(256) (the same field as
+//! SLIP-39). Shares serialize either as hex or as groups of English BIP39
+//! words.
+//!
+//! This is *not* wire-compatible with SLIP-39; shares can only be combined by
+//! this module.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::shamir;
+//!
+//! let seed = [7u8; 32];
+//! let shares = shamir::split(&seed, 2, 3).unwrap();
+//! let words = shares[0].to_words().unwrap();
+//! let first = shamir::Share::from_words(&words).unwrap();
+//! let recovered = shamir::combine(&[first, shares[2].clone()]).unwrap();
+//! assert_eq!(&recovered[..], &seed[..]);
+//! ```
+
+use crate::ffi::sys;
+use crate::hex;
+use crate::mnemonic::Mnemonic;
+use crate::wordlist::{self, Language};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// Bytes before the share data: id (2), threshold, index, secret length.
+const HEADER_LEN: usize = 5;
+/// Trailing checksum length.
+const CHECKSUM_LEN: usize = 4;
+/// Bits encoded per BIP39 word.
+const BITS_PER_WORD: usize = 11;
+
+/// One share of a split secret.
+///
+/// Share data is zeroized on drop and omitted from `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    id: u16,
+    threshold: u8,
+    index: u8,
+    data: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    /// Identifier shared by all shares of one split.
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Number of shares needed to reconstruct the secret.
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Share index (1-based x coordinate).
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Serialize the share as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.to_bytes())
+    }
+
+    /// Parse a share from hex.
+    ///
+    /// # Returns
+    /// `None` if the hex is malformed or the checksum does not match.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        // Decode straight into zeroized memory.
+        let mut bytes = Zeroizing::new(vec![0u8; hex.len() / 2]);
+        hex::decode_to_slice(hex, &mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Serialize the share as space-separated English BIP39 words.
+    ///
+    /// Each word carries 11 bits; the final word is zero-padded.
+    pub fn to_words(&self) -> Option<String> {
+        let list = wordlist::words(Language::English);
+        if list.len() != wordlist::WORDLIST_LEN {
+            return None;
+        }
+
+        let bytes = self.to_bytes();
+        let mut words = Vec::new();
+        let (mut acc, mut bits) = (0u32, 0usize);
+        for &b in bytes.iter() {
+            acc = (acc << 8) | b as u32;
+            bits += 8;
+            while bits >= BITS_PER_WORD {
+                bits -= BITS_PER_WORD;
+                words.push(list[((acc >> bits) & 0x7FF) as usize]);
+            }
+        }
+        if bits > 0 {
+            words.push(list[((acc << (BITS_PER_WORD - bits)) & 0x7FF) as usize]);
+        }
+        Some(words.join(" "))
+    }
+
+    /// Parse a share from English BIP39 words.
+    ///
+    /// # Returns
+    /// `None` on unknown words or a checksum mismatch.
+    pub fn from_words(words: &str) -> Option<Self> {
+        let mut bytes = Zeroizing::new(Vec::new());
+        let (mut acc, mut bits) = (0u32, 0usize);
+        for word in words.split_whitespace() {
+            let index = wordlist::index_of(word, Language::English)?;
+            acc = (acc << BITS_PER_WORD) | index as u32;
+            bits += BITS_PER_WORD;
+            while bits >= 8 {
+                bits -= 8;
+                bytes.push((acc >> bits) as u8);
+            }
+        }
+        // The header tells us the real length; drop any zero padding byte.
+        let secret_len = *bytes.get(HEADER_LEN - 1)? as usize;
+        bytes.truncate(HEADER_LEN + secret_len + CHECKSUM_LEN);
+        Self::from_bytes(&bytes)
+    }
+
+    fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(
+            HEADER_LEN + self.data.len() + CHECKSUM_LEN,
+        ));
+        bytes.extend_from_slice(&self.id.to_be_bytes());
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.push(self.data.len() as u8);
+        bytes.extend_from_slice(&self.data);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return None;
+        }
+        let (body, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if !hex::ct_eq(&checksum(body).to_be_bytes(), sum) {
+            return None;
+        }
+
+        let secret_len = body[4] as usize;
+        let (threshold, index) = (body[2], body[3]);
+        if body.len() != HEADER_LEN + secret_len || threshold == 0 || index == 0 {
+            return None;
+        }
+
+        Some(Share {
+            id: u16::from_be_bytes([body[0], body[1]]),
+            threshold,
+            index,
+            data: Zeroizing::new(body[HEADER_LEN..].to_vec()),
+        })
+    }
+}
+
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Share")
+            .field("id", &self.id)
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Split a secret into `count` shares, any `threshold` of which recover it.
+///
+/// # Arguments
+/// * `secret` - The secret bytes (1..=255 bytes), e.g. a BIP39 seed.
+/// * `threshold` - Shares required to reconstruct (1..=count).
+/// * `count` - Total number of shares (1..=255).
+///
+/// # Returns
+/// The shares, or `None` on invalid parameters or RNG failure.
+pub fn split(secret: &[u8], threshold: u8, count: u8) -> Option<Vec<Share>> {
+    let _ecc = crate::context::ensure_ecc_started();
+    split_with(secret, threshold, count, |buf| unsafe {
+        sys::dogecoin_random_bytes(buf.as_mut_ptr(), buf.len() as u32, 0) == 1
+    })
+}
+
+/// Reconstruct a secret from at least `threshold` distinct shares.
+///
+/// # Returns
+/// The secret, or `None` if the shares are from different splits, duplicated,
+/// or too few.
+pub fn combine(shares: &[Share]) -> Option<Zeroizing<Vec<u8>>> {
+    let first = shares.first()?;
+    let threshold = first.threshold as usize;
+    let shares = &shares[..threshold.min(shares.len())];
+    if shares.len() < threshold {
+        return None;
+    }
+
+    for (i, share) in shares.iter().enumerate() {
+        if share.id != first.id
+            || share.threshold != first.threshold
+            || share.data.len() != first.data.len()
+            || shares[..i].iter().any(|s| s.index == share.index)
+        {
+            return None;
+        }
+    }
+
+    let mut secret = Zeroizing::new(vec![0u8; first.data.len()]);
+    for (i, share) in shares.iter().enumerate() {
+        // Lagrange basis polynomial for this share, evaluated at x = 0.
+        let mut basis = 1u8;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                let denom = gf_add(other.index, share.index);
+                basis = gf_mul(basis, gf_mul(other.index, gf_inv(denom)));
+            }
+        }
+        for (out, &y) in secret.iter_mut().zip(share.data.iter()) {
+            *out = gf_add(*out, gf_mul(basis, y));
+        }
+    }
+    Some(secret)
+}
+
+/// Split a mnemonic phrase into shares.
+///
+/// The phrase text itself is shared, so any wordlist language works.
+pub fn split_mnemonic(mnemonic: &Mnemonic, threshold: u8, count: u8) -> Option<Vec<Share>> {
+    split(mnemonic.phrase().as_bytes(), threshold, count)
+}
+
+/// Reconstruct a mnemonic phrase from shares made by [`split_mnemonic`].
+pub fn combine_mnemonic(shares: &[Share]) -> Option<Mnemonic> {
+    let secret = combine(shares)?;
+    let phrase = std::str::from_utf8(&secret).ok()?;
+    Some(Mnemonic::from_phrase(phrase))
+}
+
+/// Split using a caller-supplied randomness source.
+fn split_with(
+    secret: &[u8],
+    threshold: u8,
+    count: u8,
+    mut fill_random: impl FnMut(&mut [u8]) -> bool,
+) -> Option<Vec<Share>> {
+    if secret.is_empty() || secret.len() > u8::MAX as usize || threshold == 0 || threshold > count {
+        return None;
+    }
+
+    let mut id = [0u8; 2];
+    if !fill_random(&mut id) {
+        return None;
+    }
+
+    // coefficients[k] holds the degree-(k+1) coefficient for every secret byte.
+    let mut coefficients = Zeroizing::new(vec![0u8; (threshold as usize - 1) * secret.len()]);
+    if !coefficients.is_empty() && !fill_random(&mut coefficients) {
+        return None;
+    }
+
+    let shares = (1..=count)
+        .map(|x| {
+            let data = secret
+                .iter()
+                .enumerate()
+                .map(|(i, &s)| {
+                    // Horner evaluation from the highest-degree coefficient down.
+                    let mut acc = 0u8;
+                    for coeff in coefficients.chunks(secret.len()).rev() {
+                        acc = gf_add(gf_mul(acc, x), coeff[i]);
+                    }
+                    gf_add(gf_mul(acc, x), s)
+                })
+                .collect();
+            Share {
+                id: u16::from_be_bytes(id),
+                threshold,
+                index: x,
+                data: Zeroizing::new(data),
+            }
+        })
+        .collect();
+    Some(shares)
+}
+
+fn gf_add(a: u8, b: u8) -> u8 {
+    a ^ b
+}
+
+/// Multiply in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1B;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse (a^254); `gf_inv(0)` is 0.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp != 0 {
+        if exp & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+/// FNV-1a over the share body.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5u32, |hash, &b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic stand-in for the RNG.
+    fn counter_rng() -> impl FnMut(&mut [u8]) -> bool {
+        let mut next = 0x5Au8;
+        move |buf| {
+            for b in buf {
+                next = next.wrapping_mul(29).wrapping_add(71);
+                *b = next;
+            }
+            true
+        }
+    }
+
+    #[test]
+    fn test_gf_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "{a}");
+        }
+    }
+
+    #[test]
+    fn test_any_threshold_subset_recovers() {
+        let secret: Vec<u8> = (0..64).collect();
+        let shares = split_with(&secret, 3, 5, counter_rng()).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = [shares[c].clone(), shares[a].clone(), shares[b].clone()];
+                    assert_eq!(&combine(&subset).unwrap()[..], &secret[..]);
+                }
+            }
+        }
+        assert!(combine(&shares[..2]).is_none());
+        // A share is not the secret itself.
+        assert_ne!(&shares[0].data[..], &secret[..]);
+    }
+
+    #[test]
+    fn test_hex_roundtrip_and_checksum() {
+        let shares = split_with(b"correct horse", 2, 2, counter_rng()).unwrap();
+        let hex = shares[1].to_hex();
+        assert_eq!(Share::from_hex(&hex), Some(shares[1].clone()));
+
+        let mut corrupted = hex.into_bytes();
+        corrupted[12] = if corrupted[12] == b'0' { b'1' } else { b'0' };
+        assert!(Share::from_hex(std::str::from_utf8(&corrupted).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_rejects_mixed_or_duplicate_shares() {
+        let a = split_with(&[1, 2, 3], 2, 3, counter_rng()).unwrap();
+        let mut b = split_with(&[1, 2, 3], 2, 3, counter_rng()).unwrap();
+        b[1].id ^= 1;
+        assert!(combine(&[a[0].clone(), b[1].clone()]).is_none());
+        assert!(combine(&[a[0].clone(), a[0].clone()]).is_none());
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(split_with(&[], 1, 1, counter_rng()).is_none());
+        assert!(split_with(&[1], 0, 1, counter_rng()).is_none());
+        assert!(split_with(&[1], 3, 2, counter_rng()).is_none());
+        assert!(split_with(&[1], 2, 3, |_| false).is_none());
+    }
+}
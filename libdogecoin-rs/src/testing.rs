@@ -0,0 +1,410 @@
+//! Deterministic fixtures for tests (enabled by the `testing` feature).
+//!
+//! Every fixture is derived from fixed, publicly known entropy, so results are
+//! stable across runs and machines. **Never use these keys for real funds.**
+//!
+//! [`transaction_snapshot`] renders a built transaction as stable text and
+//! [`assert_golden`] compares it with a committed golden file, so a change in
+//! what libdogecoin serializes (e.g. after updating the vendored library)
+//! fails a test instead of reaching users. Run the tests with
+//! `UPDATE_GOLDEN=1` to rewrite the files after an intended change.
+//!
+//! The crate's own tests also replay the transaction vectors from the
+//! vendored libdogecoin's `test/` sources through the FFI, so they follow
+//! whichever release is vendored.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::testing;
+//!
+//! let address = testing::mnemonic().derive_address(0, 0, "", false);
+//! assert_eq!(address.as_deref(), Ok(testing::MAINNET_RECEIVE_ADDRESSES[0]));
+//! ```
+
+use crate::hex;
+use crate::script;
+use crate::transaction::{Transaction, TxOut};
+use crate::{DogeTransaction, DogeWallet, HdWallet, Mnemonic};
+use std::fs;
+use std::path::Path;
+#[cfg(test)]
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Environment variable that makes [`assert_golden`] write golden files
+/// instead of comparing against them.
+pub const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+/// 12-word mnemonic from all-zero 128-bit entropy (BIP39 test vector).
+pub const MNEMONIC_12: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+/// 24-word mnemonic from all-zero 256-bit entropy (BIP39 test vector).
+pub const MNEMONIC_24: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+     abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+     abandon abandon abandon abandon art";
+
+/// Seed of [`MNEMONIC_12`] with an empty passphrase.
+pub const SEED_12: [u8; 64] = [
+    0x5e, 0xb0, 0x0b, 0xbd, 0xdc, 0xf0, 0x69, 0x08, 0x48, 0x89, 0xa8, 0xab, 0x91, 0x55, 0x56, 0x81,
+    0x65, 0xf5, 0xc4, 0x53, 0xcc, 0xb8, 0x5e, 0x70, 0x81, 0x1a, 0xae, 0xd6, 0xf6, 0xda, 0x5f, 0xc1,
+    0x9a, 0x5a, 0xc4, 0x0b, 0x38, 0x9c, 0xd3, 0x70, 0xd0, 0x86, 0x20, 0x6d, 0xec, 0x8a, 0xa6, 0xc4,
+    0x3d, 0xae, 0xa6, 0x69, 0x0f, 0x20, 0xad, 0x3d, 0x8d, 0x48, 0xb2, 0xd2, 0xce, 0x9e, 0x38, 0xe4,
+];
+
+/// Mainnet BIP32 master key for [`SEED_12`].
+pub const MAINNET_MASTER_KEY: &str = "dgpv51eADS3spNJh98bWAfYnAW8K1gMy86HKmH1dpyT8kLsUKBqssT3jsLLFWyK4zbruL51UjejFDzrFzBcwjjA57rSv6D2978QigKG4xbCfJV6";
+
+/// Testnet BIP32 master key for [`SEED_12`].
+pub const TESTNET_MASTER_KEY: &str = "tprv8ZgxMBicQKsPe5YMU9gHen4Ez3ApihUfykaqUorj9t6FDqy3nP6eoXiAo2ssvpAjoLroQxHqr3R5nE3a5dU3DHTjTgJDd7zrbniJr6nrCzd";
+
+/// Account 0 extended public key (`m/44'/3'/0'`) of [`MAINNET_MASTER_KEY`].
+pub const MAINNET_ACCOUNT_XPUB: &str = "dgub8rUhDtD3YFGZTUphBfpBbzvFxSMKQXYLzg87Me2ta78r2SdVLmypBUkkxrrn9RTnchsyiJSkHZyLWxD13ibBiXtuFWktBoDaGaZjQUBLNLs";
+
+/// Account 0 extended public key (`m/44'/1'/0'`) of [`TESTNET_MASTER_KEY`].
+pub const TESTNET_ACCOUNT_XPUB: &str = "tpubDC5FSnBiZDMmhiuCmWAYsLwgLYrrT9rAqvTySfuCCrgsWz8wxMXUS9Tb9iVMvcRbvFcAHGkMD5Kx8koh4GquNGNTfohfk7pgjhaPCdXpoba";
+
+/// First receiving addresses (`m/44'/3'/0'/0/i`) of [`MAINNET_MASTER_KEY`].
+pub const MAINNET_RECEIVE_ADDRESSES: [&str; 2] = [
+    "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+    "DAcDAtJRztxBHyA6D6h8du1HguyTR43Mas",
+];
+
+/// First change address (`m/44'/3'/0'/1/0`) of [`MAINNET_MASTER_KEY`].
+pub const MAINNET_CHANGE_ADDRESS: &str = "D7ReBLrRv12mi9pYh5HtfFLTt1PSoeAa7e";
+
+/// First receiving addresses (`m/44'/1'/0'/0/i`) of [`TESTNET_MASTER_KEY`].
+pub const TESTNET_RECEIVE_ADDRESSES: [&str; 2] = [
+    "nZVmfmUtKPmskB9Ds4P9GUJy9eYFqPKHqH",
+    "noVoUoC3q7wgmb25McqSdLCHhUAhSREkT9",
+];
+
+/// First change address (`m/44'/1'/0'/1/0`) of [`TESTNET_MASTER_KEY`].
+pub const TESTNET_CHANGE_ADDRESS: &str = "nWozgDAFgvLeLhNPT8GVsQARcEAjzE7q9g";
+
+/// Mainnet WIF key whose secret is SHA-256("libdogecoin-rs fixture key").
+pub const MAINNET_WIF: &str = "QTqBT5zjRoo3tkBVmucZdKa1joV78VEvrTdqT9ik7JCRvaZgj6Po";
+
+/// P2PKH address of [`MAINNET_WIF`].
+pub const MAINNET_ADDRESS: &str = "DNHC2u4MhhdhC8EubHXi9A2RLUMmHA9ViY";
+
+/// Testnet WIF key for the same secret as [`MAINNET_WIF`].
+pub const TESTNET_WIF: &str = "cjxWuUuPxZqTmhHFgCNghmRSPsAFRDkGN8LetrUbs1kWJ44o4sWa";
+
+/// P2PKH address of [`TESTNET_WIF`].
+pub const TESTNET_ADDRESS: &str = "nmLFkuoGdg6R56p6d7BAPZciaLk4JEd9kd";
+
+/// Get the 12-word fixture mnemonic.
+pub fn mnemonic() -> Mnemonic {
+    Mnemonic::from_phrase(MNEMONIC_12)
+}
+
+/// Get an HD wallet for the fixture master key.
+///
+/// # Arguments
+/// * `is_testnet` - Set to true for testnet, false for mainnet.
+pub fn hd_wallet(is_testnet: bool) -> HdWallet {
+    let master_key = if is_testnet {
+        TESTNET_MASTER_KEY
+    } else {
+        MAINNET_MASTER_KEY
+    };
+    HdWallet::from_master_key(master_key, is_testnet)
+}
+
+/// Get a single-key wallet for the fixture key pair.
+///
+/// # Arguments
+/// * `is_testnet` - Set to true for testnet, false for mainnet.
+pub fn wallet(is_testnet: bool) -> DogeWallet {
+    if is_testnet {
+        DogeWallet::from_parts(TESTNET_WIF, TESTNET_ADDRESS)
+    } else {
+        DogeWallet::from_parts(MAINNET_WIF, MAINNET_ADDRESS)
+    }
+}
+
+/// Render a transaction as text for a golden file: its txid, each input
+/// with the output it spends (if known), each output with its script type,
+/// the lock time and the raw hex, one per line.
+///
+/// The slot index is left out, so the text depends only on the transaction.
+///
+/// # Returns
+/// `None` if the transaction's slot was freed.
+pub fn transaction_snapshot(tx: &DogeTransaction) -> Option<String> {
+    render(&tx.get_raw()?, |index| tx.prevout(index))
+}
+
+fn render<'a>(raw: &str, prevout: impl Fn(usize) -> Option<&'a TxOut>) -> Option<String> {
+    let tx = Transaction::from_hex(raw)?;
+    let mut lines = vec![
+        format!("txid {}", tx.txid_hex()),
+        format!("version {}", tx.version),
+    ];
+    for (index, input) in tx.inputs.iter().enumerate() {
+        let spends = prevout(index).map_or("unknown".to_string(), |prevout| {
+            format!("{} {}", prevout.value, hex::encode(&prevout.script_pubkey))
+        });
+        let script_sig = match input.script_sig.is_empty() {
+            true => "-".to_string(),
+            false => hex::encode(&input.script_sig),
+        };
+        lines.push(format!(
+            "input {index} {}:{} sequence {:08x} script_sig {script_sig} spends {spends}",
+            input.previous_output.txid_hex(),
+            input.previous_output.vout,
+            input.sequence,
+        ));
+    }
+    for (index, output) in tx.outputs.iter().enumerate() {
+        let script_hex = hex::encode(&output.script_pubkey);
+        lines.push(format!(
+            "output {index} {} {} {script_hex}",
+            output.value,
+            script::classify(&script_hex).name(),
+        ));
+    }
+    lines.push(format!("lock_time {}", tx.lock_time));
+    lines.push(format!("raw {raw}"));
+    Some(lines.join("\n") + "\n")
+}
+
+/// Compare `actual` with the golden file at `path`, or write it there when
+/// [`UPDATE_GOLDEN_VAR`] is set.
+///
+/// Relative paths resolve against the package root, where cargo runs tests.
+///
+/// # Panics
+/// If the file cannot be read or written, or differs from `actual`; the
+/// message shows the first line that differs.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .unwrap_or_else(|e| panic!("cannot create {}: {e}", dir.display()));
+        }
+        fs::write(path, actual).unwrap_or_else(|e| panic!("cannot write {}: {e}", path.display()));
+        return;
+    }
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "cannot read golden file {}: {e}; run with {UPDATE_GOLDEN_VAR}=1 to create it",
+                path.display()
+            )
+        })
+        .replace("\r\n", "\n");
+    if expected != actual {
+        let (line, want, got) = expected
+            .split('\n')
+            .map(Some)
+            .chain(std::iter::repeat(None))
+            .zip(actual.split('\n').map(Some).chain(std::iter::repeat(None)))
+            .enumerate()
+            .find(|(_, (want, got))| want != got)
+            .map(|(line, (want, got))| (line + 1, want, got))
+            .expect("texts differ");
+        panic!(
+            "{} differs at line {line}:\n expected: {want:?}\n   actual: {got:?}\n\
+             run with {UPDATE_GOLDEN_VAR}=1 to accept the change",
+            path.display()
+        );
+    }
+}
+
+/// Lock on libdogecoin's transaction table for the crate's own tests.
+///
+/// The table is shared by the whole test binary: [`active_count`] counts
+/// every live transaction and [`clear_all`] frees them all. Tests that call
+/// either hold [`own_transaction_table`]; every other test that creates a
+/// transaction holds [`share_transaction_table`].
+///
+/// [`active_count`]: crate::transaction::active_count
+/// [`clear_all`]: crate::transaction::clear_all
+#[cfg(test)]
+static TRANSACTION_TABLE: RwLock<()> = RwLock::new(());
+
+/// Take the transaction table lock alongside other tests' transactions.
+#[cfg(test)]
+pub(crate) fn share_transaction_table() -> RwLockReadGuard<'static, ()> {
+    TRANSACTION_TABLE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Take the transaction table lock for a test that counts or clears it.
+#[cfg(test)]
+pub(crate) fn own_transaction_table() -> RwLockWriteGuard<'static, ()> {
+    TRANSACTION_TABLE.write().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures_are_consistent() {
+        assert_eq!(mnemonic().to_seed(""), Ok(SEED_12));
+
+        for is_testnet in [false, true] {
+            let (xpub, receive, change) = if is_testnet {
+                (
+                    TESTNET_ACCOUNT_XPUB,
+                    TESTNET_RECEIVE_ADDRESSES,
+                    TESTNET_CHANGE_ADDRESS,
+                )
+            } else {
+                (
+                    MAINNET_ACCOUNT_XPUB,
+                    MAINNET_RECEIVE_ADDRESSES,
+                    MAINNET_CHANGE_ADDRESS,
+                )
+            };
+            let account = hd_wallet(is_testnet).account_xpub(0).unwrap();
+            assert_eq!(account.as_str(), xpub);
+
+            for (i, expected) in receive.iter().enumerate() {
+                let address = mnemonic().derive_address(0, i as u32, "", is_testnet);
+                assert_eq!(address.as_deref(), Ok(*expected));
+            }
+            let address = mnemonic().derive_change_address(0, 0, "", is_testnet);
+            assert_eq!(address.as_deref(), Ok(change));
+        }
+    }
+
+    #[test]
+    fn test_fixture_wallets() {
+        assert_eq!(wallet(false).address(), MAINNET_ADDRESS);
+        assert_eq!(wallet(true).private_key(), TESTNET_WIF);
+        assert_eq!(MNEMONIC_24.split_whitespace().count(), 24);
+    }
+
+    #[test]
+    fn test_golden_transactions() {
+        let _table = share_transaction_table();
+        let genesis = DogeTransaction::from_raw(crate::transaction::GENESIS_COINBASE_HEX).unwrap();
+        assert_golden(
+            "testdata/golden/genesis_coinbase.txt",
+            &transaction_snapshot(&genesis).unwrap(),
+        );
+
+        let script = crate::script::OutputKind::from_address(MAINNET_RECEIVE_ADDRESSES[0])
+            .and_then(|kind| crate::script::TemplateRegistry::new().build_hex(&kind))
+            .unwrap();
+        let mut tx = DogeTransaction::new();
+        assert!(tx.add_utxo_with_details(&"aa".repeat(32), 1, crate::Koinu(300_000_000), &script));
+        assert!(tx.add_output(MAINNET_CHANGE_ADDRESS, crate::Koinu(200_000_000)));
+        assert!(tx.set_locktime(5_000_000));
+        assert_golden(
+            "testdata/golden/p2pkh_unsigned.txt",
+            &transaction_snapshot(&tx).unwrap(),
+        );
+    }
+
+    /// Directory of the vendored libdogecoin's C test sources.
+    fn upstream_test_dir() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../libdogecoin-sys/vendor/libdogecoin/test")
+    }
+
+    /// String literals in a C source file, with adjacent literals joined as
+    /// the compiler would. Escapes are kept as written.
+    fn c_string_literals(source: &str) -> Vec<String> {
+        let mut literals: Vec<String> = Vec::new();
+        let mut chars = source.chars().peekable();
+        // Whether the last token was a literal, so the next one continues it.
+        let mut joining = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' | '\'' => {
+                    let mut literal = String::new();
+                    while let Some(next) = chars.next() {
+                        match next {
+                            '\\' => literal.extend([next].into_iter().chain(chars.next())),
+                            _ if next == c => break,
+                            _ => literal.push(next),
+                        }
+                    }
+                    match literals.last_mut() {
+                        Some(last) if joining && c == '"' => last.push_str(&literal),
+                        _ if c == '"' => literals.push(literal),
+                        _ => {}
+                    }
+                    joining = c == '"';
+                }
+                '/' if chars.peek() == Some(&'/') => {
+                    chars.by_ref().find(|&c| c == '\n');
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    let mut star = false;
+                    for c in chars.by_ref() {
+                        if star && c == '/' {
+                            break;
+                        }
+                        star = c == '*';
+                    }
+                }
+                _ if c.is_whitespace() => {}
+                _ => joining = false,
+            }
+        }
+        literals
+    }
+
+    #[test]
+    fn test_c_string_literals() {
+        let source = "{\"01\" /* gap */\n \"ab\", '\"', \"x\\\"y\"} // \"skipped\"\n";
+        assert_eq!(c_string_literals(source), ["01ab", "x\\\"y"]);
+    }
+
+    #[test]
+    fn test_upstream_transaction_vectors() {
+        let _table = share_transaction_table();
+        let dir = upstream_test_dir();
+        let mut vectors = Vec::new();
+        for file in ["tx_tests.c", "transaction_tests.c"] {
+            let Ok(source) = fs::read_to_string(dir.join(file)) else {
+                continue;
+            };
+            // Every literal that is a whole transaction in canonical form;
+            // scripts and hashes do not parse as one.
+            vectors.extend(
+                c_string_literals(&source)
+                    .into_iter()
+                    .map(|literal| literal.to_ascii_lowercase())
+                    .filter(|literal| {
+                        Transaction::from_hex(literal).is_some_and(|tx| tx.to_hex() == *literal)
+                    }),
+            );
+        }
+        assert!(
+            !vectors.is_empty(),
+            "no transaction vectors under {}; check out the submodule",
+            dir.display()
+        );
+
+        for raw in &vectors {
+            let tx = DogeTransaction::from_raw(raw).unwrap_or_else(|e| panic!("{raw}: {e}"));
+            assert_eq!(tx.get_raw().as_deref(), Some(raw.as_str()));
+            let txid = Transaction::from_hex(raw).unwrap().txid_hex();
+            assert_eq!(tx.txid().map(|txid| txid.to_string()), Ok(txid));
+        }
+    }
+
+    #[test]
+    fn test_assert_golden_reports_first_difference() {
+        if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+            return;
+        }
+        let path = std::env::temp_dir().join("libdogecoin-rs-golden-test.txt");
+        fs::write(&path, "txid ab\r\nversion 1\r\n").unwrap();
+        assert_golden(&path, "txid ab\nversion 1\n");
+
+        let message = std::panic::catch_unwind(|| assert_golden(&path, "txid ab\nversion 2\n"))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.contains("differs at line 2"));
+        assert!(message.contains("\"version 2\""));
+        fs::remove_file(&path).unwrap();
+    }
+}
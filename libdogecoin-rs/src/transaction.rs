@@ -1,34 +1,350 @@
 //! Transaction creation and signing for Dogecoin.
 //!
 //! This module provides a safe Rust interface to libdogecoin's transaction API.
+//!
+//! Transactions under construction live in a global table inside libdogecoin.
+//! Each [`DogeTransaction`] owns one slot and frees it on drop, and cloning
+//! one copies it into a new slot; use
+//! [`active_count`], [`set_max_active`] and [`clear_all`] to monitor and bound
+//! that table in long-running services. libdogecoin does not lock the table,
+//! so every access goes through a process-wide lock here, and transactions
+//! can be built from several threads at once.
+//!
+//! [`Transaction`] is a plain Rust view of a serialized transaction, for code
+//! that reads transactions (indexers, scanners) rather than builds them.
+//! [`DecodedTransaction`] renders one for display, with hex scripts and
+//! output addresses, to audit a transaction before signing or broadcasting.
+
+use crate::coinselect::DUST_LIMIT;
+use crate::consensus::COIN;
+use crate::encode::{self, Reader};
+use crate::ffi::{
+    checked_cstring, hex_cstring, is_base58_byte, string_from_ptr, sys, to_cstring,
+    MAX_ADDRESS_LEN, MAX_SCRIPT_HEX_LEN, MAX_WIF_LEN,
+};
+use crate::hash::{hash160, sha256d};
+use crate::hdwallet::{HdWallet, KeyPath};
+use crate::hex;
+use crate::koinu::Koinu;
+use crate::script::{
+    self, NullDataTemplate, OutputKind, P2pkhTemplate, ScriptTemplate, ScriptType, TemplateRegistry,
+};
+use crate::utxo::{OutPoint, Utxo};
+use crate::verify::{self, VerifyError};
+use std::ffi::CString;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use zeroize::Zeroizing;
+
+/// Number of live transaction slots owned by [`DogeTransaction`]s.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+/// Maximum number of live slots; 0 means unlimited.
+static MAX_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+/// Bumped by [`clear_all`] so handles from before the purge become inert.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Held for every call into libdogecoin's transaction table.
+static SLOTS: Mutex<()> = Mutex::new(());
+
+fn lock_slots() -> MutexGuard<'static, ()> {
+    SLOTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Largest standard `OP_RETURN` payload: Dogecoin Core relays data-carrier
+/// scripts of up to 83 bytes (`MAX_OP_RETURN_RELAY`), leaving 80 for data.
+pub const MAX_DATA_OUTPUT_SIZE: usize = 80;
+
+/// Errors from the transaction builder.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    #[error("too many active transactions (limit {limit})")]
+    LimitExceeded { limit: usize },
+
+    #[error("libdogecoin failed to start a transaction")]
+    StartFailed,
+
+    #[error("invalid raw transaction")]
+    InvalidRaw,
+
+    #[error("transaction slot was freed by clear_all")]
+    Freed,
+}
+
+/// Errors from [`DogeTransaction::broadcast`].
+#[cfg(feature = "rpc")]
+#[derive(thiserror::Error, Debug)]
+pub enum BroadcastError {
+    #[error("invalid raw transaction")]
+    InvalidRaw,
+
+    #[error("input {0} is not signed")]
+    Unsigned(usize),
+
+    #[error(transparent)]
+    Rpc(#[from] crate::rpc::RpcError),
+
+    #[error("node accepted the transaction as {returned:?}, expected {expected}")]
+    TxidMismatch { expected: Txid, returned: String },
+}
+
+/// A transaction ID, in the byte order RPC and explorers display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Txid(pub [u8; 32]);
+
+impl Txid {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Txid {
+    /// Formats as lower-case hex.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(&self.0))
+    }
+}
+
+impl FromStr for Txid {
+    type Err = crate::Error;
+
+    /// Parse 64 hex digits.
+    fn from_str(s: &str) -> crate::Result<Self> {
+        hex::decode_array(s)
+            .map(Txid)
+            .ok_or(crate::Error::MalformedInput("txid"))
+    }
+}
 
-use crate::sys;
-use std::ffi::{CStr, CString};
+/// Dogecoin Core's default minimum relay fee, in koinu per 1000 bytes.
+pub const DEFAULT_MIN_RELAY_FEE: Koinu = Koinu(COIN / 1000);
+
+/// Largest transaction Dogecoin Core relays (`MAX_STANDARD_TX_SIZE`).
+pub const MAX_STANDARD_TX_SIZE: usize = 100_000;
+
+/// Relay limits checked by [`Transaction::check_standard`].
+///
+/// The defaults match Dogecoin Core; nodes started with `-dustlimit` or
+/// `-minrelaytxfee` need matching values here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StandardPolicy {
+    /// Smallest value a non-data output may carry.
+    pub dust_limit: Koinu,
+    /// Minimum fee per 1000 bytes.
+    pub min_relay_fee: Koinu,
+}
+
+impl Default for StandardPolicy {
+    fn default() -> Self {
+        StandardPolicy {
+            dust_limit: DUST_LIMIT,
+            min_relay_fee: DEFAULT_MIN_RELAY_FEE,
+        }
+    }
+}
+
+impl StandardPolicy {
+    pub fn with_dust_limit(mut self, dust_limit: Koinu) -> Self {
+        self.dust_limit = dust_limit;
+        self
+    }
+
+    pub fn with_min_relay_fee(mut self, min_relay_fee: Koinu) -> Self {
+        self.min_relay_fee = min_relay_fee;
+        self
+    }
+
+    /// Get the smallest fee relayed for a transaction of `size` bytes.
+    pub fn relay_fee(&self, size: usize) -> Koinu {
+        Koinu(self.min_relay_fee.0.saturating_mul(size as u64) / 1000)
+    }
+}
+
+/// Reasons a node would refuse to relay a transaction.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum StandardError {
+    #[error("invalid raw transaction")]
+    InvalidRaw,
+
+    #[error("output {index} of {value} koinu is below the dust limit")]
+    DustOutput { index: usize, value: Koinu },
+
+    #[error("data output {0} carries more than {MAX_DATA_OUTPUT_SIZE} bytes")]
+    DataTooLarge(usize),
+
+    #[error("more than one data output")]
+    MultipleDataOutputs,
+
+    #[error("transaction of {0} bytes is over the standard size")]
+    TooLarge(usize),
+
+    #[error("fee of {fee} koinu is below the relay minimum of {required}")]
+    FeeTooLow { fee: Koinu, required: Koinu },
+}
+
+/// Errors from parsing serialized transactions and blocks.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("unexpected end of data")]
+    UnexpectedEnd,
+
+    #[error("{0} unexpected trailing bytes")]
+    TrailingData(usize),
+
+    #[error("non-canonical length prefix")]
+    NonCanonical,
+
+    #[error("invalid hex")]
+    InvalidHex,
+}
+
+/// Get the number of transactions currently held in libdogecoin's table.
+pub fn active_count() -> usize {
+    ACTIVE.load(Ordering::SeqCst)
+}
+
+/// Limit how many transactions may be alive at once.
+///
+/// Once the limit is reached, [`DogeTransaction::try_new`] fails with
+/// [`TransactionError::LimitExceeded`]. `None` removes the limit (the default).
+pub fn set_max_active(limit: Option<usize>) {
+    MAX_ACTIVE.store(limit.unwrap_or(0), Ordering::SeqCst);
+}
+
+/// Get the current limit on live transactions, if any.
+pub fn max_active() -> Option<usize> {
+    match MAX_ACTIVE.load(Ordering::SeqCst) {
+        0 => None,
+        limit => Some(limit),
+    }
+}
+
+/// Free every transaction in libdogecoin's table.
+///
+/// Existing [`DogeTransaction`] handles become inert: their methods fail and
+/// dropping them no longer touches the table, so they cannot free a slot that
+/// was since reused by a new transaction.
+pub fn clear_all() {
+    let _slots = lock_slots();
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    unsafe {
+        sys::remove_all();
+    }
+    ACTIVE.store(0, Ordering::SeqCst);
+}
 
 /// A Dogecoin transaction builder.
 ///
 /// # Example
 /// ```no_run
-/// use libdogecoin_rs::DogeTransaction;
+/// use libdogecoin_rs::{DogeTransaction, Koinu};
 ///
 /// let mut tx = DogeTransaction::new();
 /// tx.add_utxo("previous_txid_hex", 0);
-/// tx.add_output("DDestinationAddress", "10.5");
-/// let raw = tx.finalize("DDestinationAddress", "0.01", None);
+/// tx.add_output("DDestinationAddress", "10.5".parse().unwrap());
+/// let fee = tx.calculate_fee(1_000).unwrap();
+/// let raw = tx.finalize("DDestinationAddress", fee, None);
 /// tx.sign_with_privkey(0, "private_key_wif");
 /// let signed_raw = tx.get_raw();
 /// ```
 pub struct DogeTransaction {
     tx_index: i32,
+    generation: u64,
+    /// The output each input spends, when added with
+    /// [`add_utxo_with_details`](Self::add_utxo_with_details).
+    prevouts: Vec<Option<TxOut>>,
 }
 
 impl DogeTransaction {
     /// Create a new transaction.
     ///
     /// This allocates a new transaction in libdogecoin's internal memory.
+    ///
+    /// # Panics
+    /// If a limit set with [`set_max_active`] is exceeded; use
+    /// [`DogeTransaction::try_new`] to handle that case.
     pub fn new() -> Self {
+        Self::try_new().expect("failed to start transaction")
+    }
+
+    /// Create a new transaction, respecting the [`set_max_active`] limit.
+    pub fn try_new() -> Result<Self, TransactionError> {
+        ACTIVE
+            .fetch_update(
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+                |active| match max_active() {
+                    Some(limit) if active >= limit => None,
+                    _ => Some(active + 1),
+                },
+            )
+            .map_err(|_| TransactionError::LimitExceeded {
+                limit: max_active().unwrap_or(0),
+            })?;
+
+        let _slots = lock_slots();
+        let generation = GENERATION.load(Ordering::SeqCst);
         let tx_index = unsafe { sys::start_transaction() };
-        DogeTransaction { tx_index }
+        if tx_index < 0 {
+            ACTIVE.fetch_sub(1, Ordering::SeqCst);
+            return Err(TransactionError::StartFailed);
+        }
+
+        Ok(DogeTransaction {
+            tx_index,
+            generation,
+            prevouts: Vec::new(),
+        })
+    }
+
+    /// Load a serialized transaction, e.g. one built on another machine or
+    /// by Dogecoin Core's `createrawtransaction`, to inspect, edit or sign
+    /// it.
+    ///
+    /// Inputs are loaded without the outputs they spend; record those with
+    /// [`set_prevout`](Self::set_prevout) to work out the fee.
+    ///
+    /// # Arguments
+    /// * `raw` - The transaction hex.
+    #[doc(alias = "from_raw_hex")]
+    pub fn from_raw(raw: &str) -> Result<Self, TransactionError> {
+        let parsed = Transaction::from_hex(raw).ok_or(TransactionError::InvalidRaw)?;
+        let mut tx = Self::try_new()?;
+        tx.prevouts = vec![None; parsed.inputs.len()];
+        if !tx.rewrite(|slot| {
+            *slot = parsed;
+            true
+        }) {
+            return Err(TransactionError::StartFailed);
+        }
+        Ok(tx)
+    }
+
+    /// Copy this transaction into a new slot, with the outputs its inputs
+    /// spend, so the copy can be edited or signed on its own.
+    ///
+    /// # Returns
+    /// An error if the slot was freed by [`clear_all`] or the
+    /// [`set_max_active`] limit is reached.
+    pub fn try_clone(&self) -> Result<Self, TransactionError> {
+        let raw = self.get_raw().ok_or(TransactionError::Freed)?;
+        let mut tx = Self::from_raw(&raw)?;
+        tx.prevouts.clone_from(&self.prevouts);
+        Ok(tx)
+    }
+
+    /// Whether this handle's slot survived any [`clear_all`] since creation.
+    pub fn is_live(&self) -> bool {
+        self.generation == GENERATION.load(Ordering::SeqCst)
+    }
+
+    /// Run `f` on this transaction's slot while holding the table lock.
+    ///
+    /// # Returns
+    /// `None` if the slot was freed by [`clear_all`].
+    fn with_slot<T>(&self, f: impl FnOnce(i32) -> T) -> Option<T> {
+        let _slots = lock_slots();
+        self.is_live().then(|| f(self.tx_index))
     }
 
     /// Add a UTXO (Unspent Transaction Output) to this transaction.
@@ -40,37 +356,243 @@ impl DogeTransaction {
     /// # Returns
     /// `true` if the UTXO was added successfully.
     pub fn add_utxo(&mut self, txid: &str, vout: i32) -> bool {
-        let txid_cstr = CString::new(txid).expect("Invalid txid string");
-        let result = unsafe { sys::add_utxo(self.tx_index, txid_cstr.as_ptr() as *mut i8, vout) };
-        result == 1
+        if txid.len() != 64 {
+            return false;
+        }
+        let Ok(txid_cstr) = hex_cstring(txid, 64, "txid") else {
+            return false;
+        };
+        let added = self
+            .with_slot(|index| unsafe { sys::add_utxo(index, txid_cstr.as_ptr(), vout) == 1 })
+            .unwrap_or(false);
+        if added {
+            self.prevouts.push(None);
+        }
+        added
+    }
+
+    /// Add a UTXO together with the output it is, so fees and change can be
+    /// worked out and signatures checked here.
+    ///
+    /// # Arguments
+    /// * `txid` - The transaction ID of the UTXO in hexadecimal format.
+    /// * `vout` - The output index within that transaction.
+    /// * `amount` - The value of the output.
+    /// * `script_pubkey` - The output's scriptPubKey in hexadecimal format.
+    ///
+    /// # Returns
+    /// `true` if the UTXO was added successfully.
+    pub fn add_utxo_with_details(
+        &mut self,
+        txid: &str,
+        vout: i32,
+        amount: Koinu,
+        script_pubkey: &str,
+    ) -> bool {
+        let Some(script_pubkey) = (script_pubkey.len() <= MAX_SCRIPT_HEX_LEN)
+            .then(|| hex::decode(script_pubkey))
+            .flatten()
+        else {
+            return false;
+        };
+        if !self.add_utxo(txid, vout) {
+            return false;
+        }
+        if let Some(slot) = self.prevouts.last_mut() {
+            *slot = Some(TxOut {
+                value: amount.0,
+                script_pubkey,
+            });
+        }
+        true
+    }
+
+    /// Add an output listed by [`DogeRpcClient::list_unspent`], keeping its
+    /// amount and scriptPubKey as
+    /// [`add_utxo_with_details`](Self::add_utxo_with_details) does.
+    ///
+    /// [`DogeRpcClient::list_unspent`]: crate::rpc::DogeRpcClient::list_unspent
+    ///
+    /// # Returns
+    /// `true` if the UTXO was added successfully.
+    #[cfg(feature = "rpc")]
+    pub fn add_unspent(&mut self, entry: &crate::rpc::ListUnspentEntry) -> bool {
+        let Ok(vout) = i32::try_from(entry.vout) else {
+            return false;
+        };
+        self.add_utxo_with_details(&entry.txid, vout, entry.amount, &entry.script_pub_key)
+    }
+
+    /// Get the output an input spends, if it was added with
+    /// [`add_utxo_with_details`](Self::add_utxo_with_details).
+    pub fn prevout(&self, index: usize) -> Option<&TxOut> {
+        self.prevouts.get(index)?.as_ref()
+    }
+
+    /// Record the output an input spends, e.g. after
+    /// [`from_raw`](Self::from_raw), so fees can be worked out and
+    /// signatures checked here.
+    ///
+    /// # Returns
+    /// `true` if the output was recorded; `false` if there is no such input.
+    pub fn set_prevout(&mut self, index: usize, prevout: TxOut) -> bool {
+        match self.prevouts.get_mut(index) {
+            Some(slot) => {
+                *slot = Some(prevout);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the total value of the inputs; `None` unless every input was
+    /// added with [`add_utxo_with_details`](Self::add_utxo_with_details).
+    pub fn input_value(&self) -> Option<Koinu> {
+        self.prevouts
+            .iter()
+            .try_fold(0u64, |sum, prevout| {
+                sum.checked_add(prevout.as_ref()?.value)
+            })
+            .map(Koinu)
+    }
+
+    /// Get the fee the transaction pays as it stands: the inputs less the
+    /// outputs. `None` if an input amount is unknown or the outputs exceed
+    /// the inputs.
+    pub fn current_fee(&self) -> Option<Koinu> {
+        let outputs = Transaction::from_bytes(&self.to_bytes()?)
+            .ok()?
+            .output_value()?;
+        self.input_value()?.0.checked_sub(outputs).map(Koinu)
+    }
+
+    /// Get the change left after the outputs and `fee`, to pass as the
+    /// amount of a change output; `None` if the inputs do not cover them or
+    /// an input amount is unknown.
+    pub fn change_amount(&self, fee: Koinu) -> Option<Koinu> {
+        self.current_fee()?.0.checked_sub(fee.0).map(Koinu)
     }
 
     /// Add an output to this transaction.
     ///
     /// # Arguments
     /// * `address` - The destination Dogecoin address.
-    /// * `amount` - The amount in DOGE as a string (e.g., "10.5").
+    /// * `amount` - The amount to send.
     ///
     /// # Returns
     /// `true` if the output was added successfully.
-    pub fn add_output(&mut self, address: &str, amount: &str) -> bool {
-        let addr_cstr = CString::new(address).expect("Invalid address");
-        let amount_cstr = CString::new(amount).expect("Invalid amount");
-        let result = unsafe {
-            sys::add_output(
-                self.tx_index,
-                addr_cstr.as_ptr() as *mut i8,
-                amount_cstr.as_ptr() as *mut i8,
-            )
+    pub fn add_output(&mut self, address: &str, amount: Koinu) -> bool {
+        let Ok(amount) = amount.to_doge_string() else {
+            return false;
+        };
+        let (Ok(addr_cstr), Ok(amount_cstr)) = (address_cstring(address), to_cstring(&amount))
+        else {
+            return false;
+        };
+        self.with_slot(|index| unsafe {
+            sys::add_output(index, addr_cstr.as_ptr(), amount_cstr.as_ptr()) == 1
+        })
+        .unwrap_or(false)
+    }
+
+    /// Add a zero-value `OP_RETURN` output carrying `data`, e.g. a timestamp
+    /// or asset tag.
+    ///
+    /// libdogecoin only adds outputs by address, so the transaction is edited
+    /// here and stored back into a new slot; [`index`](Self::index) changes.
+    ///
+    /// # Returns
+    /// `true` if the output was added; `false` if `data` is longer than
+    /// [`MAX_DATA_OUTPUT_SIZE`] and would not be relayed.
+    pub fn add_data_output(&mut self, data: &[u8]) -> bool {
+        if data.len() > MAX_DATA_OUTPUT_SIZE {
+            return false;
+        }
+        let Some(script_pubkey) =
+            NullDataTemplate.build(&OutputKind::NullData(vec![data.to_vec()]))
+        else {
+            return false;
         };
-        result == 1
+        self.rewrite(|tx| {
+            tx.outputs.push(TxOut {
+                value: 0,
+                script_pubkey,
+            });
+            true
+        })
+    }
+
+    /// Set the transaction's nLockTime: a block height below 500,000,000,
+    /// otherwise a Unix time.
+    ///
+    /// The lock time is only enforced if some input has a non-final
+    /// sequence; see [`set_input_sequence`](Self::set_input_sequence). Set
+    /// both before signing, as signatures commit to them.
+    ///
+    /// # Returns
+    /// `true` if the lock time was set.
+    pub fn set_locktime(&mut self, lock_time: u32) -> bool {
+        self.rewrite(|tx| {
+            tx.lock_time = lock_time;
+            true
+        })
+    }
+
+    /// Set the nSequence of an input.
+    ///
+    /// Inputs are added with the final sequence `0xffffffff`; anything lower
+    /// makes the input non-final, enabling the transaction's lock time.
+    ///
+    /// # Arguments
+    /// * `index` - The input, in the order added with [`add_utxo`](Self::add_utxo).
+    /// * `sequence` - The new sequence number.
+    ///
+    /// # Returns
+    /// `true` if the sequence was set; `false` if there is no such input.
+    pub fn set_input_sequence(&mut self, index: usize, sequence: u32) -> bool {
+        self.rewrite(|tx| match tx.inputs.get_mut(index) {
+            Some(input) => {
+                input.sequence = sequence;
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Edit the transaction in Rust and swap the result into the table.
+    ///
+    /// The edited copy is stored before the old slot is freed, so on failure,
+    /// or if `edit` returns `false`, the transaction is left as it was.
+    fn rewrite(&mut self, edit: impl FnOnce(&mut Transaction) -> bool) -> bool {
+        let _slots = lock_slots();
+        if !self.is_live() {
+            return false;
+        }
+        let raw = unsafe { string_from_ptr(sys::get_raw_transaction(self.tx_index)) };
+        let Some(mut tx) = raw.ok().and_then(|raw| Transaction::from_hex(&raw)) else {
+            return false;
+        };
+        if !edit(&mut tx) {
+            return false;
+        }
+        let Ok(raw) = to_cstring(&tx.to_hex()) else {
+            return false;
+        };
+        // Slot indexes start at 1; 0 signals failure.
+        let index = unsafe { sys::store_raw_transaction(raw.as_ptr()) };
+        if index <= 0 {
+            return false;
+        }
+        unsafe { sys::clear_transaction(self.tx_index) };
+        self.tx_index = index;
+        true
     }
 
     /// Finalize the transaction.
     ///
     /// # Arguments
     /// * `destination` - The destination address (for verification).
-    /// * `fee` - The transaction fee in DOGE (will be subtracted).
+    /// * `fee` - The transaction fee (will be subtracted).
     /// * `change_address` - Optional change address. If None, change goes to first UTXO's address.
     ///
     /// # Returns
@@ -78,38 +600,33 @@ impl DogeTransaction {
     pub fn finalize(
         &self,
         destination: &str,
-        fee: &str,
+        fee: Koinu,
         change_address: Option<&str>,
     ) -> Option<String> {
-        let dest_cstr = CString::new(destination).expect("Invalid destination");
-        let fee_cstr = CString::new(fee).expect("Invalid fee");
+        let dest_cstr = address_cstring(destination).ok()?;
+        let fee_cstr = to_cstring(&fee.to_doge_string().ok()?).ok()?;
 
         // For verification amount, we use "0" as placeholder
-        let amount_cstr = CString::new("0").unwrap();
+        let amount_cstr = CString::from(c"0");
 
-        let change_cstr = change_address.map(|s| CString::new(s).expect("Invalid change address"));
+        let change_cstr = change_address.map(address_cstring).transpose().ok()?;
 
         let change_ptr = match &change_cstr {
-            Some(s) => s.as_ptr() as *mut i8,
+            Some(s) => s.as_ptr(),
             None => std::ptr::null_mut(),
         };
 
-        let result = unsafe {
-            sys::finalize_transaction(
-                self.tx_index,
-                dest_cstr.as_ptr() as *mut i8,
-                fee_cstr.as_ptr() as *mut i8,
-                amount_cstr.as_ptr() as *mut i8,
+        // The result points into the slot, so copy it before unlocking.
+        self.with_slot(|index| unsafe {
+            let result = sys::finalize_transaction(
+                index,
+                dest_cstr.as_ptr(),
+                fee_cstr.as_ptr(),
+                amount_cstr.as_ptr(),
                 change_ptr,
-            )
-        };
-
-        if result.is_null() {
-            None
-        } else {
-            let raw_str = unsafe { CStr::from_ptr(result) };
-            Some(raw_str.to_string_lossy().into_owned())
-        }
+            );
+            string_from_ptr(result).ok()
+        })?
     }
 
     /// Sign an input of the transaction.
@@ -121,16 +638,17 @@ impl DogeTransaction {
     /// # Returns
     /// `true` if signing was successful.
     pub fn sign(&mut self, script_pubkey: &str, privkey: &str) -> bool {
-        let script_cstr = CString::new(script_pubkey).expect("Invalid script");
-        let privkey_cstr = CString::new(privkey).expect("Invalid privkey");
-        let result = unsafe {
-            sys::sign_transaction(
-                self.tx_index,
-                script_cstr.as_ptr() as *mut i8,
-                privkey_cstr.as_ptr() as *mut i8,
-            )
+        let (Ok(script_cstr), Ok(privkey_cstr)) = (
+            hex_cstring(script_pubkey, MAX_SCRIPT_HEX_LEN, "script"),
+            wif_cstring(privkey),
+        ) else {
+            return false;
         };
-        result == 1
+        let _ecc = crate::context::ensure_ecc_started();
+        self.with_slot(|index| unsafe {
+            sys::sign_transaction(index, script_cstr.as_ptr(), privkey_cstr.as_ptr().cast()) == 1
+        })
+        .unwrap_or(false)
     }
 
     /// Sign an input by vout index using a private key.
@@ -141,16 +659,95 @@ impl DogeTransaction {
     ///
     /// # Returns
     /// `true` if signing was successful.
+    ///
+    /// If the input was added with
+    /// [`add_utxo_with_details`](Self::add_utxo_with_details), the new
+    /// signature is also checked against the spent scriptPubKey, so a key
+    /// for another address fails here instead of at broadcast. The input is
+    /// left signed; sign it again with the right key.
     pub fn sign_with_privkey(&mut self, vout_index: i32, privkey: &str) -> bool {
-        let privkey_cstr = CString::new(privkey).expect("Invalid privkey");
-        let result = unsafe {
-            sys::sign_transaction_w_privkey(
-                self.tx_index,
-                vout_index,
-                privkey_cstr.as_ptr() as *mut i8,
-            )
+        let Ok(privkey_cstr) = wif_cstring(privkey) else {
+            return false;
+        };
+        let _ecc = crate::context::ensure_ecc_started();
+        let signed = self
+            .with_slot(|index| unsafe {
+                sys::sign_transaction_w_privkey(index, vout_index, privkey_cstr.as_ptr().cast())
+                    == 1
+            })
+            .unwrap_or(false);
+        let prevout = usize::try_from(vout_index)
+            .ok()
+            .and_then(|index| Some((index, self.prevout(index)?)));
+        match prevout {
+            Some((index, prevout)) if signed => self.verify_input(index, prevout).is_ok(),
+            _ => signed,
+        }
+    }
+
+    /// Sign an input with a key from an HD wallet, without exporting it.
+    ///
+    /// The private key is derived, used and wiped from memory here; the
+    /// input is signed in Rust with the derived key, as a P2PKH spend with
+    /// `SIGHASH_ALL`, and checked like [`sign_with_privkey`](Self::sign_with_privkey).
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet holding the key.
+    /// * `key` - A BIP32 path, or a [`KeyPath::Bip44`] account, chain and index.
+    /// * `input_index` - The input to sign.
+    ///
+    /// # Returns
+    /// `true` if signing was successful.
+    pub fn sign_with_hd<'a>(
+        &mut self,
+        wallet: &HdWallet,
+        key: impl Into<KeyPath<'a>>,
+        input_index: usize,
+    ) -> bool {
+        let Ok(key) = wallet.signing_key(key.into()) else {
+            return false;
+        };
+        let script_code = P2pkhTemplate
+            .build(&OutputKind::P2pkh(hash160(&key.pubkey)))
+            .expect("P2PKH scripts build");
+        let signed = self.rewrite(|tx| {
+            let Some(signature) = tx
+                .signature_hash(input_index, &script_code, SIGHASH_ALL as u32)
+                .and_then(|hash| key.sign(&hash))
+            else {
+                return false;
+            };
+            let mut script_sig = Vec::new();
+            script::push_data(&mut script_sig, &signature);
+            script::push_data(&mut script_sig, &key.pubkey);
+            tx.inputs[input_index].script_sig = script_sig;
+            true
+        });
+        match self.prevout(input_index) {
+            Some(prevout) if signed => self.verify_input(input_index, prevout).is_ok(),
+            _ => signed,
+        }
+    }
+
+    /// Sign every input with one key, e.g. when all the UTXOs spent pay the
+    /// same address.
+    ///
+    /// # Arguments
+    /// * `privkey` - The private key in WIF format.
+    ///
+    /// # Returns
+    /// Whether each input was signed, in input order; empty if the
+    /// transaction cannot be read.
+    pub fn sign_all(&mut self, privkey: &str) -> Vec<bool> {
+        let Some(tx) = self
+            .to_bytes()
+            .and_then(|b| Transaction::from_bytes(&b).ok())
+        else {
+            return Vec::new();
         };
-        result == 1
+        (0..tx.inputs.len())
+            .map(|i| i32::try_from(i).is_ok_and(|i| self.sign_with_privkey(i, privkey)))
+            .collect()
     }
 
     /// Get the raw transaction hex.
@@ -158,21 +755,193 @@ impl DogeTransaction {
     /// # Returns
     /// The transaction as a hexadecimal string.
     pub fn get_raw(&self) -> Option<String> {
-        let result = unsafe { sys::get_raw_transaction(self.tx_index) };
-        if result.is_null() {
-            None
-        } else {
-            let raw_str = unsafe { CStr::from_ptr(result) };
-            Some(raw_str.to_string_lossy().into_owned())
+        self.with_slot(|index| unsafe { string_from_ptr(sys::get_raw_transaction(index)).ok() })?
+    }
+
+    /// Get the raw transaction bytes.
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        hex::decode(&self.get_raw()?)
+    }
+
+    /// Get the transaction ID: the double SHA-256 of the serialized
+    /// transaction, as hex in the byte order RPC and explorers display.
+    ///
+    /// Signing fills in the scriptSigs and so changes the txid; take it
+    /// after the last signature to track the broadcast transaction.
+    ///
+    /// # Returns
+    /// [`TransactionError::Freed`] if the slot was freed by [`clear_all`].
+    pub fn txid(&self) -> Result<Txid, TransactionError> {
+        let raw = self.get_raw().ok_or(TransactionError::Freed)?;
+        let bytes = hex::decode(&raw).ok_or(TransactionError::InvalidRaw)?;
+        let mut txid = sha256d(&bytes);
+        txid.reverse();
+        Ok(Txid(txid))
+    }
+
+    /// Send the signed transaction to a node with `sendrawtransaction`.
+    ///
+    /// # Returns
+    /// The txid, checked against the one computed here; an error if any
+    /// input is unsigned or the node rejects the transaction.
+    #[cfg(feature = "rpc")]
+    pub fn broadcast(&self, client: &crate::rpc::DogeRpcClient) -> Result<Txid, BroadcastError> {
+        let raw = self.get_raw().ok_or(BroadcastError::InvalidRaw)?;
+        let tx = Transaction::from_hex(&raw).ok_or(BroadcastError::InvalidRaw)?;
+        if let Some(index) = tx.inputs.iter().position(|i| i.script_sig.is_empty()) {
+            return Err(BroadcastError::Unsigned(index));
+        }
+        let expected = Txid(tx.txid());
+        let returned = client.send_raw_transaction(&raw)?;
+        match returned.parse::<Txid>() {
+            Ok(txid) if txid == expected => Ok(expected),
+            _ => Err(BroadcastError::TxidMismatch { expected, returned }),
         }
     }
 
+    /// Get the witness transaction ID. Dogecoin has no segregated witness,
+    /// so this is always the [`txid`](Self::txid).
+    pub fn wtxid(&self) -> Result<Txid, TransactionError> {
+        self.txid()
+    }
+
+    /// Check that an input is validly signed for the output it spends.
+    ///
+    /// # Arguments
+    /// * `index` - The input to check.
+    /// * `prevout` - The output it spends.
+    ///
+    /// # Returns
+    /// An error naming what is wrong, e.g. [`VerifyError::WrongKey`]; see
+    /// [`verify`](crate::verify) for the scripts supported.
+    pub fn verify_input(&self, index: usize, prevout: &TxOut) -> Result<(), VerifyError> {
+        verify::verify_input(&self.parsed()?, index, prevout)
+    }
+
+    /// Check that every input is validly signed, before broadcasting.
+    ///
+    /// # Arguments
+    /// * `utxos` - The outputs spent by the inputs, in any order.
+    pub fn verify_all(&self, utxos: &[Utxo]) -> Result<(), VerifyError> {
+        verify::verify_all(&self.parsed()?, utxos)
+    }
+
+    fn parsed(&self) -> Result<Transaction, VerifyError> {
+        self.to_bytes()
+            .and_then(|bytes| Transaction::from_bytes(&bytes).ok())
+            .ok_or(VerifyError::InvalidTransaction)
+    }
+
+    /// Check the transaction against the relay rules, so a wallet does not
+    /// broadcast something nodes will reject.
+    ///
+    /// Run this on the finalized transaction, after change is added. The size
+    /// limit is checked against the estimated signed size.
+    pub fn check_standard(&self, policy: &StandardPolicy) -> Result<(), StandardError> {
+        self.to_bytes()
+            .and_then(|bytes| Transaction::from_bytes(&bytes).ok())
+            .ok_or(StandardError::InvalidRaw)?
+            .check_standard(policy)
+    }
+
+    /// Check that `fee` meets the policy's minimum relay fee for the signed
+    /// transaction.
+    pub fn check_relay_fee(
+        &self,
+        fee: Koinu,
+        policy: &StandardPolicy,
+    ) -> Result<(), StandardError> {
+        let size = self.estimate_size().ok_or(StandardError::InvalidRaw)?;
+        let required = policy.relay_fee(size);
+        if fee < required {
+            return Err(StandardError::FeeTooLow { fee, required });
+        }
+        Ok(())
+    }
+
+    /// Get the size as built so far and as it will be once signed, so fees
+    /// can be worked out before signing.
+    ///
+    /// Unsigned inputs are assumed to spend P2PKH outputs; see
+    /// [`Transaction::estimate_signed_size`].
+    pub fn size_bytes(&self) -> Option<TxSize> {
+        let bytes = self.to_bytes()?;
+        let signed = Transaction::from_bytes(&bytes).ok()?.estimate_signed_size();
+        Some(TxSize {
+            current: bytes.len(),
+            signed,
+        })
+    }
+
+    /// Estimate the size in bytes once every input is signed.
+    ///
+    /// Unsigned inputs are assumed to spend P2PKH outputs; see
+    /// [`Transaction::estimate_signed_size`].
+    pub fn estimate_size(&self) -> Option<usize> {
+        self.size_bytes().map(|size| size.signed)
+    }
+
+    /// Estimate the virtual size once signed. Dogecoin has no segregated
+    /// witness, so this equals [`estimate_size`](Self::estimate_size).
+    pub fn estimate_vbytes(&self) -> Option<usize> {
+        self.estimate_size()
+    }
+
+    /// Calculate the fee for the signed transaction.
+    ///
+    /// Add outputs before calling this. [`finalize`](Self::finalize) may
+    /// append a change output, which costs
+    /// [`P2PKH_OUTPUT_SIZE`](crate::coinselect::P2PKH_OUTPUT_SIZE) more bytes.
+    ///
+    /// # Arguments
+    /// * `fee_rate_koinu_per_byte` - Fee rate, e.g. `1_000` for 0.01 DOGE/kB.
+    ///
+    /// # Returns
+    /// The fee to pass to [`finalize`](Self::finalize), or `None` if the
+    /// transaction cannot be read or the fee overflows.
+    pub fn calculate_fee(&self, fee_rate_koinu_per_byte: u64) -> Option<Koinu> {
+        let size = u64::try_from(self.estimate_vbytes()?).ok()?;
+        size.checked_mul(fee_rate_koinu_per_byte).map(Koinu)
+    }
+
     /// Get the internal transaction index.
+    ///
+    /// It changes when [`add_data_output`](Self::add_data_output),
+    /// [`set_locktime`](Self::set_locktime) or
+    /// [`set_input_sequence`](Self::set_input_sequence) store an edited copy
+    /// of the transaction.
     pub fn index(&self) -> i32 {
         self.tx_index
     }
 }
 
+/// Size of a [`DogeTransaction`], in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxSize {
+    /// Serialized size now; unsigned inputs have empty scriptSigs.
+    pub current: usize,
+    /// Estimated size once every input is signed.
+    pub signed: usize,
+}
+
+impl TxSize {
+    /// Get the signed weight. Without segregated witness every byte weighs
+    /// 4 units.
+    pub fn signed_weight(&self) -> usize {
+        self.signed * 4
+    }
+}
+
+fn address_cstring(address: &str) -> crate::Result<CString> {
+    checked_cstring(address, MAX_ADDRESS_LEN, is_base58_byte, "address")
+}
+
+/// Convert a WIF for an FFI call into a buffer that is wiped on drop.
+fn wif_cstring(privkey: &str) -> crate::Result<Zeroizing<Vec<u8>>> {
+    checked_cstring(privkey, MAX_WIF_LEN, is_base58_byte, "private key")
+        .map(|cstr| Zeroizing::new(cstr.into_bytes_with_nul()))
+}
+
 impl Default for DogeTransaction {
     fn default() -> Self {
         Self::new()
@@ -181,25 +950,951 @@ impl Default for DogeTransaction {
 
 impl Drop for DogeTransaction {
     fn drop(&mut self) {
-        unsafe {
-            sys::clear_transaction(self.tx_index);
+        let freed = self.with_slot(|index| unsafe { sys::clear_transaction(index) });
+        if freed.is_some() {
+            ACTIVE.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// A deep copy in a new slot.
+///
+/// # Panics
+/// If the slot was freed or a limit set with [`set_max_active`] is
+/// exceeded; use [`DogeTransaction::try_clone`] to handle those cases.
+impl Clone for DogeTransaction {
+    fn clone(&self) -> Self {
+        self.try_clone().expect("failed to copy transaction")
+    }
+}
+
+/// An input as shown by the `Debug` output of [`DogeTransaction`].
+struct InputSummary<'a> {
+    input: &'a TxIn,
+    prevout: Option<&'a TxOut>,
+}
+
+impl fmt::Debug for InputSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outpoint = &self.input.previous_output;
+        f.debug_struct("Input")
+            .field(
+                "outpoint",
+                &format!("{}:{}", outpoint.txid_hex(), outpoint.vout),
+            )
+            .field("amount", &self.prevout.map(|prevout| Koinu(prevout.value)))
+            .field("signed", &!self.input.script_sig.is_empty())
+            .finish()
+    }
+}
+
+/// An output as shown by the `Debug` output of [`DogeTransaction`].
+struct OutputSummary<'a>(&'a TxOut);
+
+impl fmt::Debug for OutputSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = TemplateRegistry::new()
+            .classify(&self.0.script_pubkey)
+            .map_or(ScriptType::Nonstandard, |kind| kind.script_type());
+        f.debug_struct("Output")
+            .field("amount", &Koinu(self.0.value))
+            .field("kind", &kind)
+            .field("script_pubkey", &hex::encode(&self.0.script_pubkey))
+            .finish()
+    }
+}
+
+impl fmt::Debug for DogeTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("DogeTransaction");
+        debug.field("index", &self.tx_index);
+        let Some(tx) = self.get_raw().and_then(|raw| Transaction::from_hex(&raw)) else {
+            return debug.field("live", &false).finish();
+        };
+        let inputs: Vec<InputSummary<'_>> = tx
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| InputSummary {
+                input,
+                prevout: self.prevout(index),
+            })
+            .collect();
+        let outputs: Vec<OutputSummary<'_>> = tx.outputs.iter().map(OutputSummary).collect();
+        debug
+            .field("inputs", &inputs)
+            .field("outputs", &outputs)
+            .field("lock_time", &tx.lock_time)
+            .finish()
+    }
+}
+
+/// Size of a P2PKH scriptSig: a DER signature with sighash byte (up to 72
+/// bytes) and a compressed public key, each with its push opcode.
+pub const P2PKH_SCRIPT_SIG_SIZE: usize = 107;
+
+/// Sighash type signing every input and output.
+pub const SIGHASH_ALL: u8 = 0x01;
+/// Sighash type signing the inputs but no outputs.
+pub const SIGHASH_NONE: u8 = 0x02;
+/// Sighash type signing the inputs and the output at the signed index.
+pub const SIGHASH_SINGLE: u8 = 0x03;
+/// Sighash flag signing only the signed input.
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
+/// A transaction input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxIn {
+    pub previous_output: OutPoint,
+    pub script_sig: Vec<u8>,
+    pub sequence: u32,
+}
+
+impl TxIn {
+    /// Whether this is a coinbase input (spends the null outpoint).
+    pub fn is_coinbase(&self) -> bool {
+        self.previous_output.txid == [0u8; 32] && self.previous_output.vout == u32::MAX
+    }
+}
+
+/// A transaction output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOut {
+    /// Value in koinu.
+    pub value: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+/// A parsed transaction.
+///
+/// # Example
+/// ```no_run
+/// use libdogecoin_rs::transaction::Transaction;
+///
+/// # let raw: Vec<u8> = Vec::new();
+/// let tx = Transaction::from_bytes(&raw).unwrap();
+/// for out in &tx.outputs {
+///     println!("{} koinu", out.value);
+/// }
+/// assert_eq!(tx.to_bytes(), raw);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub version: i32,
+    pub inputs: Vec<TxIn>,
+    pub outputs: Vec<TxOut>,
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// Parse a serialized transaction.
+    ///
+    /// # Returns
+    /// An error if the bytes are truncated or followed by extra data.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let tx = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    /// Parse a transaction from hex, as returned by `getrawtransaction`.
+    pub fn from_hex(raw: &str) -> Option<Self> {
+        Self::from_bytes(&hex::decode(raw)?).ok()
+    }
+
+    /// Serialize the transaction.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.size_hint());
+        self.write(&mut out);
+        out
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.to_bytes())
+    }
+
+    /// Get the transaction ID in display byte order.
+    pub fn txid(&self) -> [u8; 32] {
+        let mut txid = sha256d(&self.to_bytes());
+        txid.reverse();
+        txid
+    }
+
+    pub fn txid_hex(&self) -> String {
+        hex::encode(&self.txid())
+    }
+
+    /// Whether this is a coinbase transaction.
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.len() == 1 && self.inputs[0].is_coinbase()
+    }
+
+    /// Estimate the serialized size once every input is signed.
+    ///
+    /// Inputs whose scriptSig is shorter than [`P2PKH_SCRIPT_SIG_SIZE`] are
+    /// counted as if they carried a P2PKH signature and public key.
+    pub fn estimate_signed_size(&self) -> usize {
+        let unsigned: usize = self
+            .inputs
+            .iter()
+            .map(|input| P2PKH_SCRIPT_SIG_SIZE.saturating_sub(input.script_sig.len()))
+            .sum();
+        self.to_bytes().len() + unsigned
+    }
+
+    /// Get the sum of the output values in koinu, `None` on overflow.
+    pub fn output_value(&self) -> Option<u64> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |sum, out| sum.checked_add(out.value))
+    }
+
+    /// Check the outputs and estimated signed size against the relay rules.
+    ///
+    /// # Returns
+    /// The first rule broken, e.g. [`StandardError::DustOutput`].
+    pub fn check_standard(&self, policy: &StandardPolicy) -> Result<(), StandardError> {
+        let mut data_outputs = 0;
+        for (index, out) in self.outputs.iter().enumerate() {
+            match NullDataTemplate.parse(&out.script_pubkey) {
+                Some(OutputKind::NullData(_)) => {
+                    // The relay limit covers the whole script: OP_RETURN and
+                    // a push of up to 80 bytes.
+                    if out.script_pubkey.len() > MAX_DATA_OUTPUT_SIZE + 3 {
+                        return Err(StandardError::DataTooLarge(index));
+                    }
+                    data_outputs += 1;
+                }
+                _ if out.value < policy.dust_limit.0 => {
+                    return Err(StandardError::DustOutput {
+                        index,
+                        value: Koinu(out.value),
+                    });
+                }
+                _ => {}
+            }
+        }
+        if data_outputs > 1 {
+            return Err(StandardError::MultipleDataOutputs);
+        }
+        let size = self.estimate_signed_size();
+        if size > MAX_STANDARD_TX_SIZE {
+            return Err(StandardError::TooLarge(size));
+        }
+        Ok(())
+    }
+
+    /// Get the legacy signature hash for an input, the digest its ECDSA
+    /// signature signs.
+    ///
+    /// # Arguments
+    /// * `index` - The input signed.
+    /// * `script_code` - The script being satisfied: the spent scriptPubKey,
+    ///   or the redeem script for P2SH.
+    /// * `sighash_type` - The sighash type, e.g. [`SIGHASH_ALL`].
+    ///
+    /// # Returns
+    /// The digest in internal byte order, or `None` if there is no input at
+    /// `index`.
+    pub fn signature_hash(
+        &self,
+        index: usize,
+        script_code: &[u8],
+        sighash_type: u32,
+    ) -> Option<[u8; 32]> {
+        self.inputs.get(index)?;
+        let base = (sighash_type & 0x1f) as u8;
+        if base == SIGHASH_SINGLE && index >= self.outputs.len() {
+            // Consensus quirk: signing a missing output signs the number 1.
+            let mut one = [0u8; 32];
+            one[0] = 1;
+            return Some(one);
+        }
+
+        let mut tx = self.clone();
+        for (i, input) in tx.inputs.iter_mut().enumerate() {
+            if i == index {
+                input.script_sig = script_code.to_vec();
+            } else {
+                input.script_sig.clear();
+                if base == SIGHASH_NONE || base == SIGHASH_SINGLE {
+                    input.sequence = 0;
+                }
+            }
+        }
+        match base {
+            SIGHASH_NONE => tx.outputs.clear(),
+            SIGHASH_SINGLE => {
+                tx.outputs.truncate(index + 1);
+                for out in &mut tx.outputs[..index] {
+                    out.value = u64::MAX;
+                    out.script_pubkey.clear();
+                }
+            }
+            _ => {}
+        }
+        if sighash_type & SIGHASH_ANYONECANPAY as u32 != 0 {
+            tx.inputs = vec![tx.inputs.swap_remove(index)];
+        }
+
+        let mut preimage = tx.to_bytes();
+        preimage.extend_from_slice(&sighash_type.to_le_bytes());
+        Some(sha256d(&preimage))
+    }
+
+    pub(crate) fn read(reader: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let version = reader.i32()?;
+        let count = reader.count()?;
+        let mut inputs = Vec::with_capacity(count);
+        for _ in 0..count {
+            let txid = reader.hash()?;
+            let vout = reader.u32()?;
+            inputs.push(TxIn {
+                previous_output: OutPoint { txid, vout },
+                script_sig: reader.var_bytes()?.to_vec(),
+                sequence: reader.u32()?,
+            });
+        }
+        let count = reader.count()?;
+        let mut outputs = Vec::with_capacity(count);
+        for _ in 0..count {
+            outputs.push(TxOut {
+                value: reader.u64()?,
+                script_pubkey: reader.var_bytes()?.to_vec(),
+            });
+        }
+        Ok(Transaction {
+            version,
+            inputs,
+            outputs,
+            lock_time: reader.u32()?,
+        })
+    }
+
+    pub(crate) fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.version.to_le_bytes());
+        encode::write_compact_size(out, self.inputs.len() as u64);
+        for input in &self.inputs {
+            encode::write_hash(out, &input.previous_output.txid);
+            out.extend_from_slice(&input.previous_output.vout.to_le_bytes());
+            encode::write_var_bytes(out, &input.script_sig);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        encode::write_compact_size(out, self.outputs.len() as u64);
+        for output in &self.outputs {
+            out.extend_from_slice(&output.value.to_le_bytes());
+            encode::write_var_bytes(out, &output.script_pubkey);
+        }
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+    }
+
+    fn size_hint(&self) -> usize {
+        let inputs: usize = self
+            .inputs
+            .iter()
+            .map(|i| 41 + 9 + i.script_sig.len())
+            .sum();
+        let outputs: usize = self
+            .outputs
+            .iter()
+            .map(|o| 8 + 9 + o.script_pubkey.len())
+            .sum();
+        4 + 9 + inputs + 9 + outputs + 4
+    }
+}
+
+/// An input of a [`DecodedTransaction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInput {
+    /// Txid of the spent output, in display order.
+    pub txid: String,
+    pub vout: u32,
+    /// Hex-encoded unlocking script.
+    pub script_sig: String,
+    pub sequence: u32,
+}
+
+/// An output of a [`DecodedTransaction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedOutput {
+    /// Value in koinu.
+    pub value: u64,
+    /// Hex-encoded locking script.
+    pub script_pubkey: String,
+    /// Standard script type, if recognised.
+    pub kind: Option<OutputKind>,
+    /// Address paid, for P2PKH and P2SH outputs.
+    pub address: Option<String>,
+}
+
+/// A transaction decoded for inspection.
+///
+/// # Example
+/// ```no_run
+/// use libdogecoin_rs::transaction::DecodedTransaction;
+///
+/// # let raw = "";
+/// let tx = DecodedTransaction::from_hex(raw, false).unwrap();
+/// for out in &tx.outputs {
+///     println!("{} koinu to {:?}", out.value, out.address);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTransaction {
+    /// Transaction ID in display order.
+    pub txid: String,
+    pub version: i32,
+    pub inputs: Vec<DecodedInput>,
+    pub outputs: Vec<DecodedOutput>,
+    pub lock_time: u32,
+}
+
+impl DecodedTransaction {
+    /// Decode a raw transaction, e.g. from `getrawtransaction` or
+    /// [`DogeTransaction::get_raw`].
+    ///
+    /// # Arguments
+    /// * `raw` - The serialized transaction in hex.
+    /// * `is_testnet` - Network used to render output addresses.
+    pub fn from_hex(raw: &str, is_testnet: bool) -> Result<Self, DecodeError> {
+        let bytes = hex::decode(raw).ok_or(DecodeError::InvalidHex)?;
+        Ok(Self::from_transaction(
+            &Transaction::from_bytes(&bytes)?,
+            is_testnet,
+        ))
+    }
+
+    /// Decode an already parsed transaction.
+    pub fn from_transaction(tx: &Transaction, is_testnet: bool) -> Self {
+        let registry = TemplateRegistry::new();
+        DecodedTransaction {
+            txid: tx.txid_hex(),
+            version: tx.version,
+            inputs: tx
+                .inputs
+                .iter()
+                .map(|input| DecodedInput {
+                    txid: hex::encode(&input.previous_output.txid),
+                    vout: input.previous_output.vout,
+                    script_sig: hex::encode(&input.script_sig),
+                    sequence: input.sequence,
+                })
+                .collect(),
+            outputs: tx
+                .outputs
+                .iter()
+                .map(|output| {
+                    let kind = registry.classify(&output.script_pubkey);
+                    DecodedOutput {
+                        value: output.value,
+                        script_pubkey: hex::encode(&output.script_pubkey),
+                        address: kind.as_ref().and_then(|k| k.address(is_testnet)),
+                        kind,
+                    }
+                })
+                .collect(),
+            lock_time: tx.lock_time,
         }
     }
+
+    /// Get the sum of the output values in koinu, `None` on overflow.
+    pub fn output_value(&self) -> Option<u64> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |sum, out| sum.checked_add(out.value))
+    }
 }
 
+#[cfg(test)]
+pub(crate) const GENESIS_COINBASE_HEX: &str = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff1004ffff001d0104084e696e746f6e646fffffffff010058850c020000004341040184710fa689ad5023690c80f3a49c8f13f8d45b8c857fbcbc8bc4a8e4d3eb4b10f4d4604fa08dce601aaf0f470216fe1b51850b4acf21b179c45070ac7b03a9ac00000000";
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{own_transaction_table, share_transaction_table};
 
     #[test]
     fn test_create_transaction() {
+        let _table = share_transaction_table();
         let tx = DogeTransaction::new();
         assert!(tx.index() >= 0);
     }
 
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn test_broadcast_refuses_unsigned() {
+        let _table = share_transaction_table();
+        let mut tx = DogeTransaction::new();
+        assert!(tx.add_utxo(&"aa".repeat(32), 0));
+        assert!(tx.add_output("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", Koinu(COIN)));
+        // Nothing listens here; the check fails before a request is sent.
+        let client = crate::rpc::DogeRpcClient::new("http://127.0.0.1:1");
+        assert!(matches!(
+            tx.broadcast(&client),
+            Err(BroadcastError::Unsigned(0))
+        ));
+
+        let txid: Txid = "ab".repeat(32).parse().unwrap();
+        assert_eq!(txid.to_string(), "ab".repeat(32));
+        assert_eq!(
+            "ab".parse::<Txid>(),
+            Err(crate::Error::MalformedInput("txid"))
+        );
+    }
+
+    #[test]
+    fn test_utxo_details_give_fee_and_change() {
+        let _table = share_transaction_table();
+        let script = "76a914d8c43e6f68ca4ea1e9b93da2d1e3a95118fa4a7c88ac";
+        let mut tx = DogeTransaction::new();
+        assert!(tx.add_utxo_with_details(&"aa".repeat(32), 0, Koinu(3 * COIN), script));
+        assert!(!tx.add_utxo_with_details(&"bb".repeat(32), 0, Koinu(COIN), "zz"));
+        assert_eq!(tx.prevout(0).map(|p| p.value), Some(3 * COIN));
+        assert_eq!(tx.input_value(), Some(Koinu(3 * COIN)));
+
+        assert!(tx.add_output("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", Koinu(2 * COIN)));
+        assert_eq!(tx.current_fee(), Some(Koinu(COIN)));
+        assert_eq!(
+            tx.change_amount(Koinu(COIN / 10)),
+            Some(Koinu(9 * COIN / 10))
+        );
+        assert_eq!(tx.change_amount(Koinu(2 * COIN)), None);
+
+        assert!(tx.add_utxo(&"cc".repeat(32), 1));
+        assert_eq!(tx.prevout(1), None);
+        assert_eq!(tx.input_value(), None);
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn test_add_unspent_keeps_details() {
+        let _table = share_transaction_table();
+        let entry: crate::rpc::ListUnspentEntry = serde_json::from_value(serde_json::json!({
+            "txid": "aa".repeat(32),
+            "vout": 1,
+            "scriptPubKey": "76a914d8c43e6f68ca4ea1e9b93da2d1e3a95118fa4a7c88ac",
+            "amount": 2.5,
+            "confirmations": 6,
+        }))
+        .unwrap();
+        let mut tx = DogeTransaction::new();
+        assert!(tx.add_unspent(&entry));
+        assert_eq!(tx.input_value(), Some(Koinu(5 * COIN / 2)));
+        assert_eq!(
+            tx.prevout(0).map(|p| hex::encode(&p.script_pubkey)),
+            Some(entry.script_pub_key.clone())
+        );
+
+        let far = crate::rpc::ListUnspentEntry {
+            vout: u32::MAX,
+            ..entry
+        };
+        assert!(!tx.add_unspent(&far));
+    }
+
     #[test]
     fn test_transaction_default() {
+        let _table = share_transaction_table();
         let tx = DogeTransaction::default();
         assert!(tx.index() >= 0);
     }
+
+    #[test]
+    fn test_sign_with_hd_matches_wif_signing() {
+        use crate::hdwallet::Chain;
+        use crate::testing::{hd_wallet, MAINNET_CHANGE_ADDRESS};
+
+        let _table = share_transaction_table();
+        // WIF of m/44'/3'/0'/0/0, which owns the spent output.
+        let wif = "QPkeC1ZfHx3c9g7WTj9cQ8gnvk2iSAfAcbq1aVAWjNTwDAKfZUzx";
+        let script = "76a9144a483568665dcdfa68dd58a1f62893448a64333988ac";
+        let unsigned = || {
+            let mut tx = DogeTransaction::new();
+            assert!(tx.add_utxo_with_details(&"aa".repeat(32), 1, Koinu(3 * COIN), script));
+            assert!(tx.add_output(MAINNET_CHANGE_ADDRESS, Koinu(2 * COIN)));
+            tx
+        };
+        let wallet = hd_wallet(false);
+
+        let mut by_wif = unsigned();
+        assert!(by_wif.sign_with_privkey(0, wif));
+        let mut by_hd = unsigned();
+        let key = KeyPath::Bip44 {
+            account: 0,
+            chain: Chain::External,
+            index: 0,
+        };
+        assert!(by_hd.sign_with_hd(&wallet, key, 0));
+        // RFC 6979 nonces make both signatures identical.
+        assert_eq!(by_hd.get_raw(), by_wif.get_raw());
+
+        assert!(!unsigned().sign_with_hd(&wallet, "m/44'/3'/0'/0/1", 0));
+        assert!(!unsigned().sign_with_hd(&wallet, "m/44'/3'/0'/0/0", 1));
+    }
+
+    #[test]
+    fn test_drop_frees_slot_even_after_panic() {
+        let _table = own_transaction_table();
+        let before = active_count();
+
+        let tx = DogeTransaction::new();
+        assert_eq!(active_count(), before + 1);
+        drop(tx);
+        assert_eq!(active_count(), before);
+
+        let result = std::panic::catch_unwind(|| {
+            let _tx = DogeTransaction::new();
+            panic!("builder abandoned mid-construction");
+        });
+        assert!(result.is_err());
+        assert_eq!(active_count(), before);
+    }
+
+    #[test]
+    fn test_max_active_limit() {
+        let _table = own_transaction_table();
+        let before = active_count();
+        set_max_active(Some(before + 1));
+
+        let first = DogeTransaction::try_new().unwrap();
+        assert_eq!(
+            DogeTransaction::try_new().err(),
+            Some(TransactionError::LimitExceeded { limit: before + 1 })
+        );
+        drop(first);
+        assert!(DogeTransaction::try_new().is_ok());
+
+        set_max_active(None);
+        assert_eq!(max_active(), None);
+    }
+
+    #[test]
+    fn test_clear_all_makes_handles_inert() {
+        let _table = own_transaction_table();
+        let mut stale = DogeTransaction::new();
+        clear_all();
+        assert_eq!(active_count(), 0);
+        assert!(!stale.is_live());
+        assert!(!stale.add_output("DAddress", Koinu::ONE_DOGE));
+        assert!(stale.get_raw().is_none());
+        assert_eq!(stale.txid(), Err(TransactionError::Freed));
+
+        let fresh = DogeTransaction::new();
+        drop(stale);
+        assert!(fresh.is_live());
+        assert_eq!(active_count(), 1);
+    }
+
+    #[test]
+    fn test_from_raw_loads_external_transaction() {
+        let _table = share_transaction_table();
+        // As returned by `createrawtransaction`: unsigned, one input paying
+        // one P2PKH output.
+        let raw = concat!(
+            "0100000001",
+            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+            "0100000000ffffffff01",
+            "00c2eb0b00000000",
+            "1976a914d8c43e6f68ca4ea1e9b93da2d1e3a95118fa4a7c88ac00000000",
+        );
+        let mut tx = DogeTransaction::from_raw(raw).unwrap();
+        assert_eq!(tx.get_raw().as_deref(), Some(raw));
+        let txid = Transaction::from_hex(raw).unwrap().txid();
+        assert_eq!(tx.txid(), Ok(Txid(txid)));
+        assert_eq!(tx.wtxid(), Ok(Txid(txid)));
+        assert_eq!(tx.prevout(0), None);
+        assert!(tx.set_locktime(5_000_000));
+        assert_ne!(tx.get_raw().as_deref(), Some(raw));
+
+        assert_eq!(
+            DogeTransaction::from_raw(&raw[..raw.len() - 2]).err(),
+            Some(TransactionError::InvalidRaw)
+        );
+    }
+
+    #[test]
+    fn test_clone_is_independent() {
+        let _table = own_transaction_table();
+        let script = "76a914d8c43e6f68ca4ea1e9b93da2d1e3a95118fa4a7c88ac";
+        let mut tx = DogeTransaction::new();
+        assert!(tx.add_utxo_with_details(&"aa".repeat(32), 1, Koinu(3 * COIN), script));
+        let before = active_count();
+
+        let mut copy = tx.clone();
+        assert_eq!(active_count(), before + 1);
+        assert_ne!(copy.index(), tx.index());
+        assert_eq!(copy.get_raw(), tx.get_raw());
+        assert_eq!(copy.input_value(), Some(Koinu(3 * COIN)));
+
+        assert!(copy.add_output("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", Koinu(2 * COIN)));
+        assert_ne!(copy.get_raw(), tx.get_raw());
+        let debug = format!("{copy:?}");
+        assert!(debug.contains(&format!("{}:1", "aa".repeat(32))));
+        assert!(debug.contains("P2pkh"));
+        assert!(debug.contains("signed: false"));
+
+        drop(tx);
+        assert!(copy.get_raw().is_some());
+        clear_all();
+        assert_eq!(copy.try_clone().err(), Some(TransactionError::Freed));
+        assert!(format!("{copy:?}").contains("live: false"));
+    }
+
+    #[test]
+    fn test_concurrent_builders() {
+        let _table = share_transaction_table();
+        let workers: Vec<_> = (1..=8u64)
+            .map(|doge| {
+                std::thread::spawn(move || {
+                    let mut tx = DogeTransaction::new();
+                    for _ in 0..20 {
+                        let amount = Koinu::from_doge(doge).unwrap();
+                        assert!(tx.add_output("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", amount));
+                    }
+                    (doge, tx.get_raw().unwrap())
+                })
+            })
+            .collect();
+        for worker in workers {
+            let (doge, raw) = worker.join().unwrap();
+            let tx = Transaction::from_hex(&raw).unwrap();
+            assert_eq!(tx.outputs.len(), 20);
+            assert!(tx
+                .outputs
+                .iter()
+                .all(|out| out.value == doge * crate::consensus::COIN));
+        }
+    }
+
+    #[test]
+    fn test_parse_genesis_coinbase() {
+        let raw = hex::decode(GENESIS_COINBASE_HEX).unwrap();
+        let tx = Transaction::from_bytes(&raw).unwrap();
+        assert!(tx.is_coinbase());
+        assert_eq!(tx.version, 1);
+        assert_eq!(tx.inputs[0].script_sig.len(), 16);
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.output_value(), Some(88 * crate::consensus::COIN));
+        assert_eq!(tx.to_bytes(), raw);
+        assert_eq!(tx.to_hex(), GENESIS_COINBASE_HEX);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_and_trailing() {
+        let raw = hex::decode(GENESIS_COINBASE_HEX).unwrap();
+        assert_eq!(
+            Transaction::from_bytes(&raw[..raw.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        let mut extra = raw.clone();
+        extra.push(0);
+        assert_eq!(
+            Transaction::from_bytes(&extra),
+            Err(DecodeError::TrailingData(1))
+        );
+        assert!(Transaction::from_hex("zz").is_none());
+    }
+
+    #[test]
+    fn test_decoded_transaction() {
+        use crate::script::{P2pkhTemplate, ScriptTemplate};
+
+        let address = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+        let kind = OutputKind::from_address(address).unwrap();
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: [0xab; 32],
+                    vout: 3,
+                },
+                script_sig: vec![0x51],
+                sequence: 0xffff_fffe,
+            }],
+            outputs: vec![
+                TxOut {
+                    value: 150_000_000,
+                    script_pubkey: P2pkhTemplate.build(&kind).unwrap(),
+                },
+                TxOut {
+                    value: 0,
+                    script_pubkey: vec![crate::script::OP_RETURN, 0x01, 0x2a],
+                },
+            ],
+            lock_time: 5_000_000,
+        };
+
+        let decoded = DecodedTransaction::from_hex(&tx.to_hex(), false).unwrap();
+        assert_eq!(decoded.txid, tx.txid_hex());
+        assert_eq!(decoded.lock_time, 5_000_000);
+        assert_eq!(decoded.inputs[0].txid, "ab".repeat(32));
+        assert_eq!(decoded.inputs[0].vout, 3);
+        assert_eq!(decoded.inputs[0].script_sig, "51");
+        assert_eq!(decoded.outputs[0].address.as_deref(), Some(address));
+        assert_eq!(decoded.outputs[0].kind, Some(kind));
+        assert_eq!(decoded.outputs[1].address, None);
+        assert_eq!(decoded.outputs[1].script_pubkey, "6a012a");
+        assert_eq!(decoded.output_value(), Some(150_000_000));
+
+        assert_eq!(
+            DecodedTransaction::from_hex("0g", false),
+            Err(DecodeError::InvalidHex)
+        );
+        assert_eq!(
+            DecodedTransaction::from_hex("0100", false),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_estimate_signed_size() {
+        use crate::coinselect::{P2PKH_INPUT_SIZE, P2PKH_OUTPUT_SIZE, TX_OVERHEAD_SIZE};
+        use crate::script::{P2pkhTemplate, ScriptTemplate};
+
+        let kind = OutputKind::from_address("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC").unwrap();
+        let output = TxOut {
+            value: 1,
+            script_pubkey: P2pkhTemplate.build(&kind).unwrap(),
+        };
+        let mut tx = Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: [1; 32],
+                    vout: 0,
+                },
+                script_sig: Vec::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![output.clone(), output],
+            lock_time: 0,
+        };
+        let expected = TX_OVERHEAD_SIZE + P2PKH_INPUT_SIZE + 2 * P2PKH_OUTPUT_SIZE;
+        assert_eq!(tx.estimate_signed_size() as u64, expected);
+
+        // A signed input is counted as it is.
+        tx.inputs[0].script_sig = vec![0; P2PKH_SCRIPT_SIG_SIZE + 1];
+        assert_eq!(tx.estimate_signed_size(), tx.to_bytes().len());
+        assert_eq!(tx.estimate_signed_size() as u64, expected + 1);
+    }
+
+    #[test]
+    fn test_signature_hash_modes() {
+        let input = |n: u8| TxIn {
+            previous_output: OutPoint {
+                txid: [n; 32],
+                vout: 0,
+            },
+            script_sig: vec![n],
+            sequence: u32::MAX,
+        };
+        let output = |value| TxOut {
+            value,
+            script_pubkey: vec![0x51],
+        };
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![input(1), input(2)],
+            outputs: vec![output(5)],
+            lock_time: 0,
+        };
+        let code = [0x51];
+        let all = tx.signature_hash(0, &code, SIGHASH_ALL as u32).unwrap();
+
+        // Other inputs' scriptSigs are not signed.
+        let mut resigned = tx.clone();
+        resigned.inputs[1].script_sig = vec![9; 3];
+        assert_eq!(
+            resigned.signature_hash(0, &code, SIGHASH_ALL as u32),
+            Some(all)
+        );
+
+        let mut paid_more = tx.clone();
+        paid_more.outputs[0].value = 6;
+        assert_ne!(
+            paid_more.signature_hash(0, &code, SIGHASH_ALL as u32),
+            Some(all)
+        );
+        let none = SIGHASH_NONE as u32;
+        assert_eq!(
+            paid_more.signature_hash(0, &code, none),
+            tx.signature_hash(0, &code, none)
+        );
+
+        let anyone = (SIGHASH_ALL | SIGHASH_ANYONECANPAY) as u32;
+        let mut added = tx.clone();
+        added.inputs.push(input(3));
+        assert_eq!(
+            added.signature_hash(0, &code, anyone),
+            tx.signature_hash(0, &code, anyone)
+        );
+
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(
+            tx.signature_hash(1, &code, SIGHASH_SINGLE as u32),
+            Some(one)
+        );
+        assert_eq!(tx.signature_hash(2, &code, SIGHASH_ALL as u32), None);
+    }
+
+    #[test]
+    fn test_check_standard() {
+        let output = |value, script_pubkey| TxOut {
+            value,
+            script_pubkey,
+        };
+        let data = |len| {
+            NullDataTemplate
+                .build(&OutputKind::NullData(vec![vec![0xaa; len]]))
+                .unwrap()
+        };
+        let mut tx = Transaction {
+            version: 1,
+            inputs: Vec::new(),
+            outputs: vec![output(DUST_LIMIT.0, vec![0x51]), output(0, data(80))],
+            lock_time: 0,
+        };
+        let policy = StandardPolicy::default();
+        assert_eq!(tx.check_standard(&policy), Ok(()));
+
+        tx.outputs[0].value -= 1;
+        assert_eq!(
+            tx.check_standard(&policy),
+            Err(StandardError::DustOutput {
+                index: 0,
+                value: Koinu(DUST_LIMIT.0 - 1)
+            })
+        );
+        let lenient = policy.with_dust_limit(Koinu(COIN / 1000));
+        assert_eq!(tx.check_standard(&lenient), Ok(()));
+
+        tx.outputs[1] = output(0, data(81));
+        assert_eq!(
+            tx.check_standard(&lenient),
+            Err(StandardError::DataTooLarge(1))
+        );
+        tx.outputs[1] = output(0, data(1));
+        tx.outputs.push(output(0, data(1)));
+        assert_eq!(
+            tx.check_standard(&lenient),
+            Err(StandardError::MultipleDataOutputs)
+        );
+
+        assert_eq!(policy.relay_fee(250), Koinu(25_000));
+        assert_eq!(
+            policy.with_min_relay_fee(Koinu(COIN / 100)).relay_fee(1000),
+            Koinu(COIN / 100)
+        );
+    }
+
+    #[test]
+    fn test_genesis_coinbase_txid() {
+        let tx = Transaction::from_hex(GENESIS_COINBASE_HEX).unwrap();
+        assert_eq!(
+            tx.txid_hex(),
+            "5b2a3f53f605d62c53e62932dac6925e3d74afa5a4b459745c36d42d0ed26a69"
+        );
+    }
 }
@@ -0,0 +1,443 @@
+//! Dogecoin payment URIs (BIP21 style).
+//!
+//! Builds and parses `dogecoin:<address>?amount=..&label=..&message=..` URIs
+//! as used in payment requests and QR codes.
+//!
+//! The same request can be written for point-of-sale hardware and phones:
+//! [`PaymentUri::to_ndef`] for an NFC tag or reader, and
+//! [`PaymentUri::to_app_link`] and [`PaymentUri::to_android_intent`] for
+//! mobile deep links. Each checks the result fits where it is going.
+
+use crate::consensus::{COIN, MAX_MONEY};
+use crate::ffi::{is_base58_byte, MAX_ADDRESS_LEN};
+use crate::Koinu;
+use std::fmt;
+
+/// URI scheme for Dogecoin payment requests.
+pub const URI_SCHEME: &str = "dogecoin";
+
+/// NDEF user memory of an NTAG213, the smallest common NFC sticker.
+pub const NTAG213_CAPACITY: usize = 144;
+
+/// NDEF user memory of an NTAG215.
+pub const NTAG215_CAPACITY: usize = 504;
+
+/// NDEF user memory of an NTAG216.
+pub const NTAG216_CAPACITY: usize = 888;
+
+/// Longest deep link produced; longer URLs are truncated or refused by
+/// some browsers and messaging apps.
+pub const MAX_DEEP_LINK_LEN: usize = 2048;
+
+/// Errors from encoding a payment request for NFC or a deep link.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PayloadError {
+    #[error("payload of {len} bytes exceeds the {max} available")]
+    TooLong { len: usize, max: usize },
+
+    #[error("invalid link base {0:?}")]
+    InvalidBase(String),
+
+    #[error("invalid Android package name {0:?}")]
+    InvalidPackage(String),
+}
+
+/// A Dogecoin payment request URI.
+///
+/// # Example
+/// ```
+/// use libdogecoin_rs::{Koinu, PaymentUri};
+///
+/// let uri = PaymentUri::new("DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs")
+///     .with_amount(Koinu(1_250_000_000))
+///     .with_label("Coffee & cake");
+/// assert_eq!(
+///     uri.to_string(),
+///     "dogecoin:DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs?amount=12.5&label=Coffee%20%26%20cake"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentUri {
+    address: String,
+    amount: Option<Koinu>,
+    label: Option<String>,
+    message: Option<String>,
+}
+
+impl PaymentUri {
+    /// Create a payment URI for an address.
+    pub fn new(address: &str) -> Self {
+        PaymentUri {
+            address: address.to_string(),
+            amount: None,
+            label: None,
+            message: None,
+        }
+    }
+
+    /// Set the requested amount.
+    ///
+    /// The URI shows it in DOGE with no trailing zeros, e.g. `amount=10.5`.
+    pub fn with_amount(mut self, amount: Koinu) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// Set a label for the recipient.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Set a message describing the payment.
+    pub fn with_message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// Parse a `dogecoin:` URI.
+    ///
+    /// Unknown optional parameters are ignored; unknown `req-` parameters make
+    /// the URI invalid, as required by BIP21.
+    pub fn parse(uri: &str) -> Option<Self> {
+        let (scheme, rest) = uri.split_once(':')?;
+        if !scheme.eq_ignore_ascii_case(URI_SCHEME) {
+            return None;
+        }
+
+        let (address, query) = match rest.split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (rest, None),
+        };
+        // Addresses are plain Base58; anything else cannot be a recipient.
+        if address.is_empty()
+            || address.len() > MAX_ADDRESS_LEN
+            || !address.bytes().all(is_base58_byte)
+        {
+            return None;
+        }
+
+        let mut parsed = PaymentUri::new(address);
+        for pair in query.into_iter().flat_map(|q| q.split('&')) {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                "amount" => parsed.amount = Some(parse_amount(&value)?),
+                "label" => parsed.label = Some(value),
+                "message" => parsed.message = Some(value),
+                k if k.starts_with("req-") => return None,
+                _ => {}
+            }
+        }
+
+        Some(parsed)
+    }
+
+    /// Get the recipient address.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Get the requested amount, if any.
+    pub fn amount(&self) -> Option<Koinu> {
+        self.amount
+    }
+
+    /// Get the label, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Get the message, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Encode as an NDEF message holding one URI record, to write to an NFC
+    /// tag or emulate from a reader.
+    ///
+    /// # Arguments
+    /// * `capacity` - NDEF memory of the tag, e.g. [`NTAG213_CAPACITY`].
+    ///   The message must fit with its TLV wrapper (type, length and
+    ///   terminator bytes).
+    ///
+    /// # Returns
+    /// The NDEF message bytes, without the TLV wrapper.
+    pub fn to_ndef(&self, capacity: usize) -> Result<Vec<u8>, PayloadError> {
+        let uri = self.to_string();
+        // URI identifier code 0: no abbreviated prefix applies to dogecoin:.
+        let payload_len = uri.len() + 1;
+        let mut message = Vec::with_capacity(payload_len + 7);
+        match u8::try_from(payload_len) {
+            Ok(len) => message.extend_from_slice(&[NDEF_HEADER | NDEF_SHORT_RECORD, 1, len]),
+            Err(_) => {
+                message.extend_from_slice(&[NDEF_HEADER, 1]);
+                message.extend_from_slice(&(payload_len as u32).to_be_bytes());
+            }
+        }
+        message.extend_from_slice(&[NDEF_TYPE_URI, 0]);
+        message.extend_from_slice(uri.as_bytes());
+
+        let tlv_len = if message.len() < 0xff { 3 } else { 5 };
+        check_len(message.len() + tlv_len, capacity)?;
+        Ok(message)
+    }
+
+    /// Build an https app link (universal link) carrying the URI, for
+    /// wallets that register a web domain instead of the `dogecoin:` scheme.
+    ///
+    /// # Arguments
+    /// * `base_url` - The wallet's link handler, e.g.
+    ///   `https://wallet.example/pay`, without a query or fragment.
+    ///
+    /// # Returns
+    /// `base_url?uri=<percent-encoded URI>`.
+    pub fn to_app_link(&self, base_url: &str) -> Result<String, PayloadError> {
+        let valid = base_url
+            .strip_prefix("https://")
+            .is_some_and(|rest| !rest.is_empty() && !rest.contains(['?', '#', ' ']));
+        if !valid {
+            return Err(PayloadError::InvalidBase(base_url.to_string()));
+        }
+        let link = format!("{base_url}?uri={}", percent_encode(&self.to_string()));
+        check_len(link.len(), MAX_DEEP_LINK_LEN)?;
+        Ok(link)
+    }
+
+    /// Build an Android `intent:` link, which opens the URI in a wallet app
+    /// from a browser and can name a fallback package to install.
+    ///
+    /// # Arguments
+    /// * `package` - Optional wallet app package, e.g. `com.example.wallet`.
+    pub fn to_android_intent(&self, package: Option<&str>) -> Result<String, PayloadError> {
+        let uri = self.to_string();
+        let target = &uri[URI_SCHEME.len() + 1..];
+        let mut link = format!("intent:{target}#Intent;scheme={URI_SCHEME};");
+        if let Some(package) = package {
+            let valid = !package.is_empty()
+                && package.split('.').all(|part| {
+                    !part.is_empty() && part.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+                });
+            if !valid {
+                return Err(PayloadError::InvalidPackage(package.to_string()));
+            }
+            link.push_str(&format!("package={package};"));
+        }
+        link.push_str("end");
+        check_len(link.len(), MAX_DEEP_LINK_LEN)?;
+        Ok(link)
+    }
+}
+
+/// NDEF record header: first and last record, well-known type.
+const NDEF_HEADER: u8 = 0xc1;
+/// Header flag for a one-byte payload length.
+const NDEF_SHORT_RECORD: u8 = 0x10;
+/// Well-known record type `U` (URI).
+const NDEF_TYPE_URI: u8 = b'U';
+
+fn check_len(len: usize, max: usize) -> Result<(), PayloadError> {
+    match len <= max {
+        true => Ok(()),
+        false => Err(PayloadError::TooLong { len, max }),
+    }
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{URI_SCHEME}:{}", self.address)?;
+
+        let amount = self.amount.map(format_amount);
+        let params = [
+            ("amount", amount.as_deref()),
+            ("label", self.label.as_deref()),
+            ("message", self.message.as_deref()),
+        ];
+        let mut separator = '?';
+        for (key, value) in params {
+            if let Some(value) = value {
+                write!(f, "{separator}{key}={}", percent_encode(value))?;
+                separator = '&';
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that an amount is a plain DOGE decimal with at most 8 fractional digits.
+pub(crate) fn is_valid_amount(amount: &str) -> bool {
+    let (whole, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    !whole.is_empty()
+        && whole.bytes().all(|b| b.is_ascii_digit())
+        && frac.len() <= 8
+        && frac.bytes().all(|b| b.is_ascii_digit())
+        && !(amount.contains('.') && frac.is_empty())
+}
+
+/// Parse a DOGE decimal exactly, without going through floating point.
+///
+/// # Returns
+/// `None` if the amount is not a plain decimal or exceeds [`MAX_MONEY`].
+fn parse_amount(amount: &str) -> Option<Koinu> {
+    if !is_valid_amount(amount) {
+        return None;
+    }
+    let (whole, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    let koinu = whole
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(COIN)?
+        .checked_add(format!("{frac:0<8}").parse().ok()?)?;
+    (koinu <= MAX_MONEY).then_some(Koinu(koinu))
+}
+
+/// Format an amount in DOGE without trailing zeros, e.g. `12.5`.
+fn format_amount(amount: Koinu) -> String {
+    match amount.0 % COIN {
+        0 => (amount.0 / COIN).to_string(),
+        frac => format!("{}.{frac:08}", amount.0 / COIN)
+            .trim_end_matches('0')
+            .to_string(),
+    }
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR: &str = "DQq2RVNJdAedD1mcXR4ViGHa1mwz8sMyGs";
+
+    #[test]
+    fn test_build_plain_address() {
+        assert_eq!(
+            PaymentUri::new(ADDR).to_string(),
+            format!("dogecoin:{ADDR}")
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_all_fields() {
+        let uri = PaymentUri::new(ADDR)
+            .with_amount(Koinu(1))
+            .with_label("Café ☕")
+            .with_message("order #42 & more");
+        let text = uri.to_string();
+        assert!(text.contains("label=Caf%C3%A9%20%E2%98%95"));
+        assert_eq!(PaymentUri::parse(&text), Some(uri));
+    }
+
+    #[test]
+    fn test_amounts() {
+        for amount in [
+            "",
+            "1.",
+            ".5",
+            "1e3",
+            "-1",
+            "1.123456789",
+            "1,5",
+            "10000000001",
+        ] {
+            let uri = format!("dogecoin:{ADDR}?amount={amount}");
+            assert!(PaymentUri::parse(&uri).is_none(), "{amount}");
+        }
+        for (koinu, text) in [
+            (1, "0.00000001"),
+            (1_250_000_000, "12.5"),
+            (MAX_MONEY, "10000000000"),
+        ] {
+            let uri = PaymentUri::new(ADDR).with_amount(Koinu(koinu));
+            assert_eq!(uri.to_string(), format!("dogecoin:{ADDR}?amount={text}"));
+            assert_eq!(PaymentUri::parse(&uri.to_string()), Some(uri));
+        }
+        let parsed = PaymentUri::parse(&format!("dogecoin:{ADDR}?amount=12.50")).unwrap();
+        assert_eq!(parsed.amount(), Some(Koinu(1_250_000_000)));
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert!(PaymentUri::parse("bitcoin:abc").is_none());
+        assert!(PaymentUri::parse("dogecoin:").is_none());
+        assert!(PaymentUri::parse(&format!("dogecoin:{ADDR}?req-foo=1")).is_none());
+        assert!(PaymentUri::parse(&format!("dogecoin:{ADDR}?label=%zz")).is_none());
+        assert!(PaymentUri::parse(&format!("dogecoin:{ADDR}?label=%+f")).is_none());
+        assert!(PaymentUri::parse("dogecoin:D%3Fx").is_none());
+
+        let parsed = PaymentUri::parse(&format!("DOGECOIN:{ADDR}?foo=bar&amount=5")).unwrap();
+        assert_eq!(parsed.address(), ADDR);
+        assert_eq!(parsed.amount(), Some(Koinu(5 * COIN)));
+        assert_eq!(parsed.label(), None);
+    }
+
+    #[test]
+    fn test_nfc_and_deep_links() {
+        let uri = PaymentUri::new(ADDR).with_amount(Koinu(5 * COIN));
+        let text = uri.to_string();
+
+        let ndef = uri.to_ndef(NTAG213_CAPACITY).unwrap();
+        assert_eq!(ndef[..5], [0xd1, 0x01, text.len() as u8 + 1, b'U', 0x00]);
+        assert_eq!(&ndef[5..], text.as_bytes());
+        let long = uri.clone().with_message(&"x".repeat(300));
+        assert!(matches!(
+            long.to_ndef(NTAG213_CAPACITY),
+            Err(PayloadError::TooLong { max: 144, .. })
+        ));
+        let ndef = long.to_ndef(NTAG216_CAPACITY).unwrap();
+        assert_eq!(ndef[0], 0xc1);
+        assert_eq!(
+            ndef[2..6],
+            (long.to_string().len() as u32 + 1).to_be_bytes()
+        );
+
+        assert_eq!(
+            uri.to_app_link("https://wallet.example/pay").unwrap(),
+            format!("https://wallet.example/pay?uri=dogecoin%3A{ADDR}%3Famount%3D5")
+        );
+        assert!(uri.to_app_link("http://wallet.example").is_err());
+        assert!(uri.to_app_link("https://wallet.example/?a=1").is_err());
+
+        assert_eq!(
+            uri.to_android_intent(Some("com.example.wallet")).unwrap(),
+            format!("intent:{ADDR}?amount=5#Intent;scheme=dogecoin;package=com.example.wallet;end")
+        );
+        assert_eq!(
+            uri.to_android_intent(Some("com..wallet")),
+            Err(PayloadError::InvalidPackage("com..wallet".to_string()))
+        );
+    }
+}
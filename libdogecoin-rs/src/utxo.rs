@@ -0,0 +1,457 @@
+//! Wallet UTXO sets and compact binary snapshots of them.
+//!
+//! A [`UtxoSnapshot`] records the outputs a wallet can spend together with the
+//! chain tip it was taken at, so a service can move to a new host (or warm up
+//! a standby) and resume from that height instead of rescanning.
+//!
+//! # Format
+//! `"DUTX"`, a version byte, a flags byte (bit 0: testnet), then LEB128
+//! varints for the tip height and entry count, the entries, and a 4-byte
+//! FNV-1a checksum of everything before it. Each entry is the 32-byte txid,
+//! varint vout, amount and height (`0` = unconfirmed, otherwise height + 1),
+//! and the script: P2PKH and P2SH scripts are stored as a tag byte plus their
+//! 20-byte hash, anything else as a tag byte plus the length-prefixed script.
+
+use crate::hex;
+use crate::script::{OutputKind, P2pkhTemplate, P2shTemplate, ScriptTemplate};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"DUTX";
+const VERSION: u8 = 1;
+const FLAG_TESTNET: u8 = 0x01;
+const CHECKSUM_LEN: usize = 4;
+
+const SCRIPT_RAW: u8 = 0;
+const SCRIPT_P2PKH: u8 = 1;
+const SCRIPT_P2SH: u8 = 2;
+
+/// Errors from decoding a snapshot.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    #[error("not a UTXO snapshot")]
+    BadMagic,
+
+    #[error("unsupported snapshot version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("snapshot checksum mismatch")]
+    ChecksumMismatch,
+
+    #[error("malformed snapshot entry")]
+    Malformed,
+}
+
+/// A reference to a transaction output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OutPoint {
+    /// Transaction ID in the byte order RPC and explorers display.
+    pub txid: [u8; 32],
+    pub vout: u32,
+}
+
+impl OutPoint {
+    /// Create an outpoint from a hex txid as returned by RPC.
+    pub fn from_hex(txid: &str, vout: u32) -> Option<Self> {
+        Some(OutPoint {
+            txid: hex::decode_array(txid)?,
+            vout,
+        })
+    }
+
+    /// Get the txid as hex.
+    pub fn txid_hex(&self) -> String {
+        hex::encode(&self.txid)
+    }
+}
+
+/// An unspent output owned by the wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub script_pubkey: Vec<u8>,
+    /// Value in koinu.
+    pub amount: u64,
+    /// Height of the block that confirmed the output, `None` if unconfirmed.
+    pub height: Option<u32>,
+}
+
+impl Utxo {
+    /// Convert a `listunspent` entry.
+    ///
+    /// # Arguments
+    /// * `entry` - The RPC entry.
+    /// * `tip_height` - Node height when `listunspent` was called, used to
+    ///   turn the confirmation count into a block height.
+    #[cfg(feature = "rpc")]
+    pub fn from_list_unspent(
+        entry: &crate::rpc::ListUnspentEntry,
+        tip_height: u32,
+    ) -> Option<Self> {
+        let confirmations = u32::try_from(entry.confirmations).ok()?;
+        let height = match confirmations {
+            0 => None,
+            n => Some(tip_height.checked_sub(n - 1)?),
+        };
+        Some(Utxo {
+            outpoint: OutPoint::from_hex(&entry.txid, entry.vout)?,
+            script_pubkey: hex::decode(&entry.script_pub_key)?,
+            amount: entry.amount.0,
+            height,
+        })
+    }
+}
+
+/// A wallet's UTXO set as of a chain tip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoSnapshot {
+    is_testnet: bool,
+    tip_height: u32,
+    utxos: BTreeMap<OutPoint, Utxo>,
+}
+
+impl UtxoSnapshot {
+    /// Create an empty snapshot.
+    ///
+    /// # Arguments
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    /// * `tip_height` - Height the set is valid at.
+    pub fn new(is_testnet: bool, tip_height: u32) -> Self {
+        UtxoSnapshot {
+            is_testnet,
+            tip_height,
+            utxos: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        self.is_testnet
+    }
+
+    pub fn tip_height(&self) -> u32 {
+        self.tip_height
+    }
+
+    /// Record the height the set is now valid at.
+    pub fn set_tip_height(&mut self, tip_height: u32) {
+        self.tip_height = tip_height;
+    }
+
+    /// Add an output, replacing any entry with the same outpoint.
+    pub fn insert(&mut self, utxo: Utxo) {
+        self.utxos.insert(utxo.outpoint, utxo);
+    }
+
+    /// Remove a spent output.
+    pub fn remove(&mut self, outpoint: &OutPoint) -> Option<Utxo> {
+        self.utxos.remove(outpoint)
+    }
+
+    pub fn get(&self, outpoint: &OutPoint) -> Option<&Utxo> {
+        self.utxos.get(outpoint)
+    }
+
+    /// Iterate over the outputs in outpoint order.
+    pub fn iter(&self) -> impl Iterator<Item = &Utxo> {
+        self.utxos.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.utxos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.utxos.is_empty()
+    }
+
+    /// Get the total value in koinu.
+    pub fn balance(&self) -> u64 {
+        self.utxos.values().map(|u| u.amount).sum()
+    }
+
+    /// Serialize to the compact binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + self.utxos.len() * 64);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(if self.is_testnet { FLAG_TESTNET } else { 0 });
+        write_varint(&mut out, self.tip_height as u64);
+        write_varint(&mut out, self.utxos.len() as u64);
+
+        for utxo in self.utxos.values() {
+            out.extend_from_slice(&utxo.outpoint.txid);
+            write_varint(&mut out, utxo.outpoint.vout as u64);
+            write_varint(&mut out, utxo.amount);
+            write_varint(&mut out, utxo.height.map_or(0, |h| h as u64 + 1));
+            match P2pkhTemplate
+                .parse(&utxo.script_pubkey)
+                .or_else(|| P2shTemplate.parse(&utxo.script_pubkey))
+            {
+                Some(OutputKind::P2pkh(hash)) => {
+                    out.push(SCRIPT_P2PKH);
+                    out.extend_from_slice(&hash);
+                }
+                Some(OutputKind::P2sh(hash)) => {
+                    out.push(SCRIPT_P2SH);
+                    out.extend_from_slice(&hash);
+                }
+                _ => {
+                    out.push(SCRIPT_RAW);
+                    write_varint(&mut out, utxo.script_pubkey.len() as u64);
+                    out.extend_from_slice(&utxo.script_pubkey);
+                }
+            }
+        }
+
+        let checksum = checksum(&out);
+        out.extend_from_slice(&checksum.to_be_bytes());
+        out
+    }
+
+    /// Parse a snapshot produced by [`UtxoSnapshot::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(SnapshotError::BadMagic);
+        }
+        if bytes.len() < MAGIC.len() + 2 + CHECKSUM_LEN {
+            return Err(SnapshotError::Malformed);
+        }
+        let (body, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(body).to_be_bytes() != sum {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+        let version = body[MAGIC.len()];
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut reader = Reader {
+            bytes: body,
+            pos: MAGIC.len() + 1,
+        };
+        let flags = reader.byte()?;
+        let tip_height = reader.u32()?;
+        let count = reader.varint()?;
+
+        let mut snapshot = UtxoSnapshot::new(flags & FLAG_TESTNET != 0, tip_height);
+        for _ in 0..count {
+            let txid = reader
+                .take(32)?
+                .try_into()
+                .map_err(|_| SnapshotError::Malformed)?;
+            let vout = reader.u32()?;
+            let amount = reader.varint()?;
+            let height = match reader.u32()? {
+                0 => None,
+                h => Some(h - 1),
+            };
+            let script_pubkey = match reader.byte()? {
+                SCRIPT_P2PKH => {
+                    let hash = reader
+                        .take(20)?
+                        .try_into()
+                        .map_err(|_| SnapshotError::Malformed)?;
+                    P2pkhTemplate.build(&OutputKind::P2pkh(hash))
+                }
+                SCRIPT_P2SH => {
+                    let hash = reader
+                        .take(20)?
+                        .try_into()
+                        .map_err(|_| SnapshotError::Malformed)?;
+                    P2shTemplate.build(&OutputKind::P2sh(hash))
+                }
+                SCRIPT_RAW => {
+                    let len =
+                        usize::try_from(reader.varint()?).map_err(|_| SnapshotError::Malformed)?;
+                    Some(reader.take(len)?.to_vec())
+                }
+                _ => None,
+            }
+            .ok_or(SnapshotError::Malformed)?;
+
+            snapshot.insert(Utxo {
+                outpoint: OutPoint { txid, vout },
+                script_pubkey,
+                amount,
+                height,
+            });
+        }
+
+        if reader.pos != body.len() || snapshot.len() as u64 != count {
+            return Err(SnapshotError::Malformed);
+        }
+        Ok(snapshot)
+    }
+
+    /// Write the snapshot to `writer`.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Read a snapshot from `reader`.
+    ///
+    /// Decoding failures are reported as [`io::ErrorKind::InvalidData`].
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<'a> IntoIterator for &'a UtxoSnapshot {
+    type Item = &'a Utxo;
+    type IntoIter = std::collections::btree_map::Values<'a, OutPoint, Utxo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.utxos.values()
+    }
+}
+
+impl Extend<Utxo> for UtxoSnapshot {
+    fn extend<I: IntoIterator<Item = Utxo>>(&mut self, iter: I) {
+        for utxo in iter {
+            self.insert(utxo);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.pos.checked_add(len).ok_or(SnapshotError::Malformed)?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(SnapshotError::Malformed)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, SnapshotError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SnapshotError::Malformed)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        u32::try_from(self.varint()?).map_err(|_| SnapshotError::Malformed)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// FNV-1a over the snapshot body; guards against truncation and bit rot.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5u32, |hash, b| {
+        (hash ^ *b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> UtxoSnapshot {
+        let mut snapshot = UtxoSnapshot::new(false, 5_000_000);
+        snapshot.insert(Utxo {
+            outpoint: OutPoint {
+                txid: [0x11; 32],
+                vout: 0,
+            },
+            script_pubkey: P2pkhTemplate.build(&OutputKind::P2pkh([0xaa; 20])).unwrap(),
+            amount: 1_234_500_000,
+            height: Some(4_999_000),
+        });
+        snapshot.insert(Utxo {
+            outpoint: OutPoint {
+                txid: [0x22; 32],
+                vout: 300,
+            },
+            script_pubkey: P2shTemplate.build(&OutputKind::P2sh([0xbb; 20])).unwrap(),
+            amount: 1,
+            height: None,
+        });
+        snapshot.insert(Utxo {
+            outpoint: OutPoint {
+                txid: [0x33; 32],
+                vout: 1,
+            },
+            script_pubkey: vec![0x51],
+            amount: 0,
+            height: Some(0),
+        });
+        snapshot
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let snapshot = sample();
+        let bytes = snapshot.to_bytes();
+        assert_eq!(UtxoSnapshot::from_bytes(&bytes), Ok(snapshot.clone()));
+        assert_eq!(snapshot.balance(), 1_234_500_001);
+
+        let mut buf = Vec::new();
+        snapshot.write_to(&mut buf).unwrap();
+        assert_eq!(UtxoSnapshot::read_from(buf.as_slice()).unwrap(), snapshot);
+
+        let empty = UtxoSnapshot::new(true, 0);
+        assert_eq!(UtxoSnapshot::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+
+    #[test]
+    fn test_snapshot_is_compact() {
+        // txid + three small varints + tag + 20-byte hash.
+        let bytes = sample().to_bytes();
+        assert!(bytes.len() < 3 * 70, "{} bytes", bytes.len());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_corruption() {
+        let mut bytes = sample().to_bytes();
+        assert_eq!(
+            UtxoSnapshot::from_bytes(b"nope"),
+            Err(SnapshotError::BadMagic)
+        );
+        assert_eq!(
+            UtxoSnapshot::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::ChecksumMismatch)
+        );
+        bytes[20] ^= 0x01;
+        assert_eq!(
+            UtxoSnapshot::from_bytes(&bytes),
+            Err(SnapshotError::ChecksumMismatch)
+        );
+        let err = UtxoSnapshot::read_from(&b"DUTX"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_remove_and_outpoint_hex() {
+        let mut snapshot = sample();
+        let outpoint = OutPoint::from_hex(&"11".repeat(32), 0).unwrap();
+        assert_eq!(outpoint.txid_hex(), "11".repeat(32));
+        assert!(snapshot.remove(&outpoint).is_some());
+        assert_eq!(snapshot.len(), 2);
+        assert!(OutPoint::from_hex("abcd", 0).is_none());
+    }
+}
@@ -1,4 +1,8 @@
-use crate::sys;
+use crate::cancel::{CancellationToken, StopReason, Stopped};
+use crate::error::Error;
+use crate::ffi::{string_from_buf, sys, P2PKH_BUF_LEN, WIF_BUF_LEN};
+use crate::progress::{ProgressSink, ProgressTracker};
+use std::fmt;
 use zeroize::Zeroizing;
 
 pub struct DogeWallet {
@@ -11,35 +15,134 @@ impl DogeWallet {
     ///
     /// # Arguments
     /// * `is_testnet` - Set to true for testnet, false for mainnet.
-    pub fn new(is_testnet: bool) -> Option<Self> {
-        crate::context::ensure_ecc_started();
+    pub fn new(is_testnet: bool) -> crate::Result<Self> {
+        let _ecc = crate::context::ensure_ecc_started();
 
-        // Defined in libdogecoin.h
-        const PRIVKEYWIFLEN: usize = 53;
-        const P2PKHLEN: usize = 35;
+        let mut wif_privkey = Zeroizing::new([0u8; WIF_BUF_LEN]);
+        let mut p2pkh_pubkey = [0u8; P2PKH_BUF_LEN];
 
-        let mut wif_privkey = [0u8; PRIVKEYWIFLEN];
-        let mut p2pkh_pubkey = [0u8; P2PKHLEN];
+        let result = unsafe {
+            sys::generatePrivPubKeypair(
+                wif_privkey.as_mut_ptr().cast(),
+                p2pkh_pubkey.as_mut_ptr().cast(),
+                is_testnet,
+            )
+        };
 
-        unsafe {
-            let result = sys::generatePrivPubKeypair(
-                wif_privkey.as_mut_ptr() as *mut i8,
-                p2pkh_pubkey.as_mut_ptr() as *mut i8,
-                is_testnet as u8,
-            );
+        if result != 1 {
+            return Err(Error::Ffi {
+                function: "generatePrivPubKeypair",
+                code: result,
+            });
+        }
 
-            if result != 1 {
-                return None;
-            }
+        Ok(DogeWallet {
+            private_key: Zeroizing::new(string_from_buf(&*wif_privkey)?),
+            address: string_from_buf(&p2pkh_pubkey)?,
+        })
+    }
+
+    /// Generate many wallets in parallel.
+    ///
+    /// Work is split into contiguous chunks across the available CPU cores and
+    /// the results are concatenated in chunk order. The shared ECC context is
+    /// started before any worker thread runs; after that libdogecoin only reads
+    /// it, so concurrent key generation is safe.
+    ///
+    /// # Arguments
+    /// * `n` - Number of wallets to generate.
+    /// * `is_testnet` - Set to true for testnet, false for mainnet.
+    ///
+    /// # Returns
+    /// `n` wallets, or the first error a worker hit.
+    pub fn generate_batch(n: usize, is_testnet: bool) -> crate::Result<Vec<Self>> {
+        Self::generate_batch_inner(n, is_testnet, None, None)
+            .map_err(|(_, error)| error.unwrap_or(Error::WorkerPanicked))
+    }
+
+    /// Generate many wallets in parallel, stopping early when `token` fires.
+    ///
+    /// Like [`DogeWallet::generate_batch`], but every worker checks `token`
+    /// before each key and reports to `progress` after it.
+    ///
+    /// # Returns
+    /// All `n` wallets, or [`Stopped`] holding the wallets generated before the
+    /// token fired (or before libdogecoin failed).
+    pub fn generate_batch_cancellable(
+        n: usize,
+        is_testnet: bool,
+        token: &CancellationToken,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<Vec<Self>, Stopped<Vec<Self>>> {
+        Self::generate_batch_inner(n, is_testnet, Some(token), progress).map_err(|(s, _)| s)
+    }
+
+    /// On failure, also returns the error that stopped a worker, if any.
+    #[allow(clippy::type_complexity)]
+    fn generate_batch_inner(
+        n: usize,
+        is_testnet: bool,
+        token: Option<&CancellationToken>,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<Vec<Self>, (Stopped<Vec<Self>>, Option<Error>)> {
+        let _ecc = crate::context::ensure_ecc_started();
+
+        let tracker = ProgressTracker::new(progress, Some(n as u64));
+        let tracker = &tracker;
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |p| p.get())
+            .clamp(1, n.max(1));
+        let chunk = n.div_ceil(threads);
 
-            // Convert null-terminated C strings to Rust Strings
-            let priv_key_cstr = std::ffi::CStr::from_ptr(wif_privkey.as_ptr() as *const i8);
-            let address_cstr = std::ffi::CStr::from_ptr(p2pkh_pubkey.as_ptr() as *const i8);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|t| {
+                    let len = chunk.min(n.saturating_sub(t * chunk));
+                    scope.spawn(move || {
+                        let mut wallets = Vec::with_capacity(len);
+                        for _ in 0..len {
+                            if let Err(reason) = token.map_or(Ok(()), |t| t.check()) {
+                                return (wallets, Some((reason, None)));
+                            }
+                            match Self::new(is_testnet) {
+                                Ok(wallet) => wallets.push(wallet),
+                                Err(e) => return (wallets, Some((StopReason::Failed, Some(e)))),
+                            }
+                            tracker.advance(1);
+                        }
+                        (wallets, None)
+                    })
+                })
+                .collect();
 
-            Some(DogeWallet {
-                private_key: Zeroizing::new(priv_key_cstr.to_string_lossy().into_owned()),
-                address: address_cstr.to_string_lossy().into_owned(),
-            })
+            let mut wallets = Vec::with_capacity(n);
+            let mut stopped = None;
+            for worker in workers {
+                let (chunk, reason) = worker
+                    .join()
+                    .unwrap_or_else(|_| (Vec::new(), Some((StopReason::Failed, None))));
+                wallets.extend(chunk);
+                stopped = stopped.or(reason);
+            }
+            match stopped {
+                None => Ok(wallets),
+                Some((reason, error)) => Err((
+                    Stopped {
+                        reason,
+                        partial: wallets,
+                    },
+                    error,
+                )),
+            }
+        })
+    }
+
+    /// Build a wallet from a known WIF key and its matching address.
+    #[cfg(all(feature = "full", any(test, feature = "testing")))]
+    pub(crate) fn from_parts(private_key: &str, address: &str) -> Self {
+        DogeWallet {
+            private_key: Zeroizing::new(private_key.to_string()),
+            address: address.to_string(),
         }
     }
 
@@ -52,6 +155,15 @@ impl DogeWallet {
     }
 }
 
+impl fmt::Debug for DogeWallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DogeWallet")
+            .field("address", &self.address)
+            .field("private_key", &"<redacted>")
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,6 +178,32 @@ mod tests {
         assert!(wallet.address().starts_with("D"));
     }
 
+    #[test]
+    fn test_generate_batch() {
+        let wallets = DogeWallet::generate_batch(50, true).unwrap();
+        assert_eq!(wallets.len(), 50);
+        assert!(wallets.iter().all(|w| w.address().starts_with('n')));
+
+        let mut addresses: Vec<_> = wallets.iter().map(|w| w.address()).collect();
+        addresses.sort();
+        addresses.dedup();
+        assert_eq!(addresses.len(), 50);
+
+        assert!(DogeWallet::generate_batch(0, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_generate_batch_cancellable() {
+        let token = CancellationToken::new();
+        let wallets = DogeWallet::generate_batch_cancellable(8, false, &token, None).unwrap();
+        assert_eq!(wallets.len(), 8);
+
+        token.cancel();
+        let stopped = DogeWallet::generate_batch_cancellable(8, false, &token, None).unwrap_err();
+        assert_eq!(stopped.reason, StopReason::Cancelled);
+        assert!(stopped.partial.is_empty());
+    }
+
     #[test]
     fn test_create_wallet_testnet() {
         let wallet = DogeWallet::new(true).unwrap();
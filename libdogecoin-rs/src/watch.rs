@@ -0,0 +1,330 @@
+//! Watch-only address sets.
+//!
+//! A [`WatchSet`] lists the scripts a monitoring service follows, each with
+//! its address (when it has one), a label and a [`Birthday`] that bounds how
+//! far back to scan. With the `json` feature a set round-trips through JSON
+//! with [`WatchSet::to_json`]/[`WatchSet::from_json`], and
+//! [`WatchSet::to_importmulti`] writes the request for Dogecoin Core's
+//! `importmulti`:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "network": "mainnet",
+//!   "entries": [
+//!     {
+//!       "address": "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+//!       "script_pubkey": "76a914...88ac",
+//!       "label": "donations",
+//!       "birthday": { "time": 1704067200, "height": 5030000 }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! # Example
+//! ```
+//! use libdogecoin_rs::birthday::Birthday;
+//! use libdogecoin_rs::watch::WatchSet;
+//! use libdogecoin_rs::Network;
+//!
+//! let mut set = WatchSet::new(Network::Mainnet);
+//! set.add_address(
+//!     "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC",
+//!     Some("donations"),
+//!     Some(Birthday::from_time(1_704_067_200)),
+//! )
+//! .unwrap();
+//! assert_eq!(set.len(), 1);
+//! ```
+
+use crate::address::{AddressError, AddressUtils};
+use crate::birthday::Birthday;
+use crate::config::Network;
+use crate::hex;
+#[cfg(feature = "rpc")]
+use crate::rpc::{ImportMultiRequest, ImportTimestamp};
+use crate::script::{OutputKind, TemplateRegistry};
+
+/// Version of the JSON format written by [`WatchSet::to_json`].
+pub const WATCH_SET_VERSION: u32 = 1;
+
+/// Errors from building or loading a watch set.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum WatchError {
+    #[error("invalid address {address:?}: {source}")]
+    InvalidAddress {
+        address: String,
+        source: AddressError,
+    },
+
+    #[error("invalid script {0:?}")]
+    InvalidScript(String),
+
+    #[error("address {0} does not match its script")]
+    ScriptMismatch(String),
+
+    #[error("unsupported watch set version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("invalid watch set JSON: {0}")]
+    Json(String),
+}
+
+/// A script to watch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchEntry {
+    /// Address paid by the script, for P2PKH and P2SH scripts.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub address: Option<String>,
+    /// Hex-encoded scriptPubKey.
+    pub script_pubkey: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub label: Option<String>,
+    /// Nothing paid the script before this; `None` scans from genesis.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub birthday: Option<Birthday>,
+}
+
+/// Scripts watched on one network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchSet {
+    version: u32,
+    network: Network,
+    entries: Vec<WatchEntry>,
+}
+
+impl WatchSet {
+    pub fn new(network: Network) -> Self {
+        WatchSet {
+            version: WATCH_SET_VERSION,
+            network,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Watch an address.
+    ///
+    /// # Returns
+    /// An error if the address is invalid or for another network.
+    pub fn add_address(
+        &mut self,
+        address: &str,
+        label: Option<&str>,
+        birthday: Option<Birthday>,
+    ) -> Result<&mut Self, WatchError> {
+        let script = address_script(address, self.is_testnet())?;
+        self.entries.push(WatchEntry {
+            address: Some(address.to_string()),
+            script_pubkey: hex::encode(&script),
+            label: label.map(str::to_string),
+            birthday,
+        });
+        Ok(self)
+    }
+
+    /// Watch a raw scriptPubKey, e.g. bare multisig with no address form.
+    pub fn add_script(
+        &mut self,
+        script_pubkey: &[u8],
+        label: Option<&str>,
+        birthday: Option<Birthday>,
+    ) -> &mut Self {
+        let address = TemplateRegistry::new()
+            .classify(script_pubkey)
+            .and_then(|kind| kind.address(self.is_testnet()));
+        self.entries.push(WatchEntry {
+            address,
+            script_pubkey: hex::encode(script_pubkey),
+            label: label.map(str::to_string),
+            birthday,
+        });
+        self
+    }
+
+    pub fn entries(&self) -> &[WatchEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `script_pubkey` is watched.
+    pub fn contains_script(&self, script_pubkey: &[u8]) -> bool {
+        let script = hex::encode(script_pubkey);
+        self.entries.iter().any(|e| e.script_pubkey == script)
+    }
+
+    /// Get the earliest birthday height, where a rescan of the whole set
+    /// starts; `None` if the set is empty or any entry has no birthday.
+    pub fn scan_start_height(&self) -> Option<u32> {
+        let is_testnet = self.is_testnet();
+        self.entries
+            .iter()
+            .map(|e| e.birthday.map(|b| b.scan_start_height(is_testnet)))
+            .try_fold(u32::MAX, |min, height| Some(min.min(height?)))
+            .filter(|_| !self.entries.is_empty())
+    }
+
+    fn is_testnet(&self) -> bool {
+        self.network == Network::Testnet
+    }
+}
+
+#[cfg(feature = "json")]
+impl WatchSet {
+    /// Serialize the set as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("watch sets serialize")
+    }
+
+    /// Load a set written by [`to_json`](Self::to_json), checking that every
+    /// address matches its script.
+    pub fn from_json(json: &str) -> Result<Self, WatchError> {
+        let set: WatchSet =
+            serde_json::from_str(json).map_err(|e| WatchError::Json(e.to_string()))?;
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Check every entry, as after loading it.
+    fn validate(&self) -> Result<(), WatchError> {
+        if self.version != WATCH_SET_VERSION {
+            return Err(WatchError::UnsupportedVersion(self.version));
+        }
+        for entry in &self.entries {
+            let script = hex::decode(&entry.script_pubkey)
+                .ok_or_else(|| WatchError::InvalidScript(entry.script_pubkey.clone()))?;
+            if let Some(address) = &entry.address {
+                if address_script(address, self.is_testnet())? != script {
+                    return Err(WatchError::ScriptMismatch(address.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the request array for Dogecoin Core's `importmulti`.
+    ///
+    /// Entries are imported watch-only; each rescan starts at its birthday
+    /// time, or at genesis if unknown.
+    pub fn to_importmulti(&self) -> serde_json::Value {
+        let requests = self.entries.iter().map(|entry| {
+            let script = match &entry.address {
+                Some(address) => serde_json::json!({ "address": address }),
+                None => serde_json::json!(entry.script_pubkey),
+            };
+            let mut request = serde_json::json!({
+                "scriptPubKey": script,
+                "timestamp": entry.birthday.map_or(0, |b| b.time),
+                "watchonly": true,
+            });
+            if let Some(label) = &entry.label {
+                request["label"] = serde_json::json!(label);
+            }
+            request
+        });
+        serde_json::Value::Array(requests.collect())
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl WatchSet {
+    /// Build typed requests for [`DogeRpcClient::import_multi`], matching
+    /// [`to_importmulti`](Self::to_importmulti).
+    ///
+    /// [`DogeRpcClient::import_multi`]: crate::rpc::DogeRpcClient::import_multi
+    pub fn import_requests(&self) -> Vec<ImportMultiRequest> {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let timestamp = ImportTimestamp::Time(entry.birthday.map_or(0, |b| b.time));
+                let request = match &entry.address {
+                    Some(address) => ImportMultiRequest::address(address, timestamp),
+                    None => ImportMultiRequest::script(&entry.script_pubkey, timestamp),
+                };
+                match &entry.label {
+                    Some(label) => request.with_label(label),
+                    None => request,
+                }
+                .watch_only()
+            })
+            .collect()
+    }
+}
+
+/// The scriptPubKey paying `address`.
+fn address_script(address: &str, is_testnet: bool) -> Result<Vec<u8>, WatchError> {
+    let invalid = |source| WatchError::InvalidAddress {
+        address: address.to_string(),
+        source,
+    };
+    AddressUtils::diagnose_for(address, is_testnet).map_err(invalid)?;
+    OutputKind::from_address(address)
+        .and_then(|kind| TemplateRegistry::new().build(&kind))
+        .ok_or_else(|| WatchError::ScriptMismatch(address.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+
+    fn sample() -> WatchSet {
+        let mut set = WatchSet::new(Network::Mainnet);
+        set.add_address(
+            ADDRESS,
+            Some("donations"),
+            Some(Birthday::from_time(1_704_067_200)),
+        )
+        .unwrap()
+        .add_script(&[0x51], None, None);
+        set
+    }
+
+    #[test]
+    fn test_add_and_lookup() {
+        let set = sample();
+        assert_eq!(set.entries()[0].script_pubkey.len(), 50);
+        assert!(set.contains_script(&[0x51]));
+        assert_eq!(set.entries()[1].address, None);
+        assert_eq!(set.scan_start_height(), None);
+
+        let mut testnet = WatchSet::new(Network::Testnet);
+        assert!(matches!(
+            testnet.add_address(ADDRESS, None, None),
+            Err(WatchError::InvalidAddress { .. })
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_roundtrip_and_importmulti() {
+        let set = sample();
+        assert_eq!(WatchSet::from_json(&set.to_json()), Ok(set.clone()));
+
+        let tampered = set.to_json().replace("76a914", "76a915");
+        assert_eq!(
+            WatchSet::from_json(&tampered),
+            Err(WatchError::ScriptMismatch(ADDRESS.to_string()))
+        );
+
+        let requests = set.to_importmulti();
+        assert_eq!(requests[0]["scriptPubKey"]["address"], ADDRESS);
+        assert_eq!(requests[0]["timestamp"], 1_704_067_200);
+        assert_eq!(requests[1]["scriptPubKey"], "51");
+        assert_eq!(requests[1]["label"], serde_json::Value::Null);
+    }
+}
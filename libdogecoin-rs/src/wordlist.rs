@@ -0,0 +1,162 @@
+//! BIP39 wordlist lookup.
+//!
+//! Exposes libdogecoin's built-in BIP39 wordlists for type-ahead seed entry
+//! and per-word validation.
+//!
+//! # Example
+//! ```no_run
+//! use libdogecoin_rs::wordlist::{self, Language};
+//!
+//! assert_eq!(wordlist::suggest("aban", Language::English), vec!["abandon"]);
+//! assert!(wordlist::contains("zoo", Language::English));
+//! ```
+
+use crate::ffi::sys;
+use std::ffi::{CStr, CString};
+use std::sync::OnceLock;
+
+/// Number of words in every BIP39 wordlist.
+pub const WORDLIST_LEN: usize = 2048;
+
+/// A BIP39 wordlist language supported by libdogecoin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    #[default]
+    English,
+    SimplifiedChinese,
+    TraditionalChinese,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Spanish,
+    Portuguese,
+}
+
+impl Language {
+    /// All supported languages.
+    pub const ALL: [Language; 10] = [
+        Language::English,
+        Language::SimplifiedChinese,
+        Language::TraditionalChinese,
+        Language::Czech,
+        Language::French,
+        Language::Italian,
+        Language::Japanese,
+        Language::Korean,
+        Language::Spanish,
+        Language::Portuguese,
+    ];
+
+    /// Get libdogecoin's language code (e.g. "eng").
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "eng",
+            Language::SimplifiedChinese => "sc",
+            Language::TraditionalChinese => "tc",
+            Language::Czech => "cze",
+            Language::French => "fra",
+            Language::Italian => "ita",
+            Language::Japanese => "jpn",
+            Language::Korean => "kor",
+            Language::Spanish => "spa",
+            Language::Portuguese => "por",
+        }
+    }
+
+    /// Look up a language by its libdogecoin code.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Language::ALL.into_iter().find(|l| l.code() == code)
+    }
+
+    fn slot(self) -> usize {
+        Language::ALL.iter().position(|&l| l == self).unwrap_or(0)
+    }
+}
+
+static WORDLISTS: [OnceLock<Vec<&'static str>>; Language::ALL.len()] =
+    [const { OnceLock::new() }; Language::ALL.len()];
+
+/// Get the full wordlist for a language, in BIP39 index order.
+///
+/// # Returns
+/// The 2048 words, or an empty slice if libdogecoin has no list for the language.
+pub fn words(language: Language) -> &'static [&'static str] {
+    WORDLISTS[language.slot()].get_or_init(|| load(language).unwrap_or_default())
+}
+
+/// Suggest words starting with `prefix`, in wordlist order.
+///
+/// An empty prefix yields no suggestions.
+pub fn suggest(prefix: &str, language: Language) -> Vec<&'static str> {
+    if prefix.is_empty() {
+        return Vec::new();
+    }
+    words(language)
+        .iter()
+        .copied()
+        .filter(|w| w.starts_with(prefix))
+        .collect()
+}
+
+/// Check whether `word` is exactly a word of the list.
+pub fn contains(word: &str, language: Language) -> bool {
+    index_of(word, language).is_some()
+}
+
+/// Get the BIP39 index (0..2048) of `word`.
+pub fn index_of(word: &str, language: Language) -> Option<usize> {
+    words(language).iter().position(|&w| w == word)
+}
+
+fn load(language: Language) -> Option<Vec<&'static str>> {
+    let lang_cstr = CString::new(language.code()).ok()?;
+    let list = unsafe { sys::get_words(lang_cstr.as_ptr()) };
+    if list.is_null() {
+        return None;
+    }
+
+    (0..WORDLIST_LEN)
+        .map(|i| {
+            // The wordlists are static arrays inside libdogecoin.
+            let word = unsafe { *list.add(i) };
+            if word.is_null() {
+                return None;
+            }
+            unsafe { CStr::from_ptr(word) }.to_str().ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_codes_roundtrip() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+        assert_eq!(Language::from_code("xx"), None);
+    }
+
+    #[test]
+    fn test_english_wordlist() {
+        let list = words(Language::English);
+        assert_eq!(list.len(), WORDLIST_LEN);
+        assert_eq!(list[0], "abandon");
+        assert_eq!(list[2047], "zoo");
+        assert_eq!(index_of("zoo", Language::English), Some(2047));
+        assert!(contains("abandon", Language::English));
+        assert!(!contains("aband", Language::English));
+    }
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(suggest("aban", Language::English), vec!["abandon"]);
+        assert_eq!(suggest("zo", Language::English), vec!["zone", "zoo"]);
+        assert!(suggest("", Language::English).is_empty());
+        assert!(suggest("qqq", Language::English).is_empty());
+    }
+}
@@ -1,10 +1,159 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// libdogecoin sources behind key generation, address derivation and
+/// message signing, with everything they link against. Always compiled.
+const CORE_SOURCES: &[&str] = &[
+    "address.c",
+    "base58.c",
+    "bip32.c",
+    "bip39.c",
+    "bip44.c",
+    "buffer.c",
+    "chainparams.c",
+    "cstr.c",
+    "ecc.c",
+    "eckey.c",
+    "key.c",
+    "mem.c",
+    "random.c",
+    "rmd160.c",
+    "script.c",
+    "serialize.c",
+    "sha2.c",
+    "sign.c",
+    "utf8proc.c",
+    "utils.c",
+    "vector.c",
+];
+
+/// The rest of libdogecoin: transactions, blocks, amounts, QR codes and
+/// encrypted key storage. Compiled with the `full` feature.
+const FULL_SOURCES: &[&str] = &[
+    "aes.c",
+    "arith_uint256.c",
+    "auxpow.c",
+    "block.c",
+    "chacha20.c",
+    "ctaes.c",
+    "koinu.c",
+    "map.c",
+    "moon.c",
+    "pow.c",
+    "png.c",
+    "jpeg.c",
+    "qrengine.c",
+    "qr.c",
+    "scrypt.c",
+    "seal.c",
+    "cli/tool.c",
+    "transaction.c",
+    "tx.c",
+    "validation.c",
+];
+
+/// libdogecoin functions the safe crate calls that are defined in
+/// `CORE_SOURCES`. Nothing else is generated.
+const CORE_FUNCTIONS: &[&str] = &[
+    // libdogecoin.h
+    "dogecoin_ecc_start",
+    "dogecoin_ecc_stop",
+    "dogecoin_free",
+    "generatePrivPubKeypair",
+    "generateHDMasterPubKeypair",
+    "verifyP2pkhAddress",
+    "isMainnetFromB58Prefix",
+    "isTestnetFromB58Prefix",
+    "generateRandomEnglishMnemonic",
+    "dogecoin_seed_from_mnemonic",
+    "getDerivedHDAddressFromMnemonic",
+    "sign_message",
+    "verify_message",
+    // bip32.h
+    "dogecoin_hdnode_new",
+    "dogecoin_hdnode_copy",
+    "dogecoin_hdnode_free",
+    "dogecoin_hdnode_deserialize",
+    "dogecoin_hdnode_private_ckd",
+    "dogecoin_hdnode_public_ckd",
+    "dogecoin_hdnode_get_p2pkh_address",
+    "dogecoin_hdnode_serialize_public",
+    "dogecoin_hdnode_serialize_private",
+    // bip39.h
+    "get_words",
+    // random.h
+    "dogecoin_random_bytes",
+    // sha2.h
+    "pbkdf2_hmac_sha512",
+    // base58.h
+    "dogecoin_base58_encode_check",
+    "dogecoin_base58_decode_check",
+    // ecc.h
+    "dogecoin_ecc_get_pubkey",
+    "dogecoin_ecc_sign",
+    "dogecoin_ecc_sign_compact_recoverable",
+    "dogecoin_ecc_recover_pubkey",
+    "dogecoin_ecc_verify_sig",
+];
+
+/// libdogecoin functions the safe crate calls that are defined in
+/// `FULL_SOURCES`. Generated only with the `full` feature, so a binding never
+/// exists for a function that was not compiled.
+const FULL_FUNCTIONS: &[&str] = &[
+    // libdogecoin.h
+    "koinu_to_coins_str",
+    "coins_to_koinu_str",
+    "start_transaction",
+    "add_utxo",
+    "add_output",
+    "finalize_transaction",
+    "sign_transaction",
+    "sign_transaction_w_privkey",
+    "get_raw_transaction",
+    "clear_transaction",
+    "store_raw_transaction",
+    "remove_all",
+    "qrgen_p2pkh_to_qrbits",
+    "qrgen_p2pkh_to_qr_string",
+    "qrgen_p2pkh_consoleprint_to_qr",
+    "qrgen_string_to_qr_pngfile",
+    "qrgen_string_to_qr_jpgfile",
+];
+
+/// libdogecoin globals the safe crate reads (chainparams.h).
+const VARS: &[&str] = &["dogecoin_chainparams_main", "dogecoin_chainparams_test"];
+
+/// The vendored libdogecoin's major.minor version, from the
+/// `_PKG_VERSION_*` defines in its configure.ac.
+fn vendored_version(vendor_dir: &Path) -> String {
+    let configure =
+        fs::read_to_string(vendor_dir.join("configure.ac")).expect("Couldn't read configure.ac");
+    let component = |name: &str| {
+        let define = format!("_PKG_VERSION_{name}");
+        configure
+            .lines()
+            .filter(|line| line.trim_start().starts_with("define("))
+            .find(|line| line.contains(&define))
+            .and_then(|line| {
+                line.rsplit(|c: char| !c.is_ascii_digit())
+                    .find(|digits| !digits.is_empty())
+            })
+            .unwrap_or_else(|| panic!("configure.ac has no {define}"))
+            .to_owned()
+    };
+    format!("{}.{}", component("MAJOR"), component("MINOR"))
+}
 
 fn main() {
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let vendor_dir = PathBuf::from(&manifest_dir).join("vendor/libdogecoin");
 
+    // Exposed to dependents as DEP_DOGECOIN_VERSION, which libdogecoin-rs
+    // uses to pick its wrappers.
+    println!("cargo:rerun-if-changed=vendor/libdogecoin/configure.ac");
+    println!("cargo:version={}", vendored_version(&vendor_dir));
+
     // --- Build secp256k1 ---
     let secp_src = vendor_dir.join("src/secp256k1");
 
@@ -33,51 +182,18 @@ fn main() {
     let doge_src = vendor_dir.join("src");
     let doge_include = vendor_dir.join("include");
 
+    // Source lists follow Makefile.am.
+    let full = env::var_os("CARGO_FEATURE_FULL").is_some();
+    let sources = CORE_SOURCES
+        .iter()
+        .chain(if full { FULL_SOURCES } else { &[] });
+    for source in sources {
+        build.file(doge_src.join(source));
+    }
+
     build
         .include(&doge_include)
         .include(secp_src.join("include")) // libdogecoin needs secp256k1.h
-        // Add all source files manually from Makefile.am
-        .file(doge_src.join("address.c"))
-        .file(doge_src.join("aes.c"))
-        .file(doge_src.join("arith_uint256.c"))
-        .file(doge_src.join("auxpow.c"))
-        .file(doge_src.join("base58.c"))
-        .file(doge_src.join("bip32.c"))
-        .file(doge_src.join("bip39.c"))
-        .file(doge_src.join("bip44.c"))
-        .file(doge_src.join("block.c"))
-        .file(doge_src.join("buffer.c"))
-        .file(doge_src.join("chacha20.c"))
-        .file(doge_src.join("chainparams.c"))
-        .file(doge_src.join("cstr.c"))
-        .file(doge_src.join("ctaes.c"))
-        .file(doge_src.join("ecc.c"))
-        .file(doge_src.join("eckey.c"))
-        .file(doge_src.join("key.c"))
-        .file(doge_src.join("koinu.c"))
-        .file(doge_src.join("map.c"))
-        .file(doge_src.join("mem.c"))
-        .file(doge_src.join("moon.c"))
-        .file(doge_src.join("pow.c"))
-        .file(doge_src.join("png.c"))
-        .file(doge_src.join("jpeg.c"))
-        .file(doge_src.join("qrengine.c"))
-        .file(doge_src.join("qr.c"))
-        .file(doge_src.join("random.c"))
-        .file(doge_src.join("rmd160.c"))
-        .file(doge_src.join("script.c"))
-        .file(doge_src.join("scrypt.c"))
-        .file(doge_src.join("seal.c"))
-        .file(doge_src.join("sign.c"))
-        .file(doge_src.join("serialize.c"))
-        .file(doge_src.join("sha2.c"))
-        .file(doge_src.join("cli/tool.c"))
-        .file(doge_src.join("transaction.c"))
-        .file(doge_src.join("tx.c"))
-        .file(doge_src.join("utf8proc.c"))
-        .file(doge_src.join("utils.c"))
-        .file(doge_src.join("validation.c"))
-        .file(doge_src.join("vector.c"))
         // Flags
         .define("HAVE_STDLIB_H", None) // minimal config
         .define("HAVE_STRING_H", None)
@@ -88,21 +204,30 @@ fn main() {
     build.compile("dogecoin");
 
     // --- Generate Bindings ---
-    println!("cargo:rerun-if-changed=vendor/libdogecoin/include/dogecoin/libdogecoin.h");
+    // CargoCallbacks adds the headers wrapper.h includes.
+    println!("cargo:rerun-if-changed=wrapper.h");
 
-    let bindings = bindgen::Builder::default()
+    let mut bindings = bindgen::Builder::default()
         .header(
-            vendor_dir
-                .join("include/dogecoin/libdogecoin.h")
+            PathBuf::from(&manifest_dir)
+                .join("wrapper.h")
                 .to_str()
                 .unwrap(),
         )
         .clang_arg(format!("-I{}", vendor_dir.join("include").display()))
         // We also need secp include path for binding generation if headers refer to it
         .clang_arg(format!("-I{}", secp_src.join("include").display()))
-        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
-        .generate()
-        .expect("Unable to generate bindings");
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+    let functions = CORE_FUNCTIONS
+        .iter()
+        .chain(if full { FULL_FUNCTIONS } else { &[] });
+    for function in functions {
+        bindings = bindings.allowlist_function(function);
+    }
+    for var in VARS {
+        bindings = bindings.allowlist_var(var);
+    }
+    let bindings = bindings.generate().expect("Unable to generate bindings");
 
     let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
     bindings
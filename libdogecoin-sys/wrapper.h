@@ -0,0 +1,13 @@
+/* Headers bindgen generates bindings from.
+ *
+ * libdogecoin.h covers the high-level API; the lower-level APIs the crate
+ * also calls live in their own headers. Only the declarations build.rs
+ * allowlists are generated. */
+#include <dogecoin/libdogecoin.h>
+#include <dogecoin/bip32.h>
+#include <dogecoin/chainparams.h>
+#include <dogecoin/bip39.h>
+#include <dogecoin/random.h>
+#include <dogecoin/sha2.h>
+#include <dogecoin/base58.h>
+#include <dogecoin/ecc.h>